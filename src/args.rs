@@ -1,27 +1,58 @@
 //! Argument parsing for command-line usage.
 
+use crate::config_format::ConfigFormat;
+use crate::defaults::{ColorChoice, Defaults};
+use crate::digest::DigestFormat;
+use crate::export::OutputFormat;
+use crate::sources::{NotifyStyle, PlatformList, Priority, SourceSettings};
+use crate::util::{parse_duration, set_exact_names, set_timeout, DEFAULT_TIMEOUT_SECS};
 use chrono::{DateTime, Duration, Local, NaiveDate, NaiveDateTime, TimeZone};
 use std::path::PathBuf;
+use structopt::clap::Shell;
 use structopt::StructOpt;
 
 /// A tool for keeping you updated.
 ///
 /// Just run it with no arguments to see what you've missed
 /// and it will remember when you last ran it. The currently
-/// allowed sources are RSS, YouTube, Gmail, Anime, and Manga.
+/// allowed sources are RSS, newsletters, YouTube, Anime, Manga,
+/// Bandcamp, Mastodon, Hacker News, crates.io, GitHub, npm, PyPI,
+/// arXiv, itch.io, Gmail, Webtoon, Patreon, authors on Goodreads,
+/// Wikipedia, custom JSON APIs, scraped web pages, and changes to
+/// web pages.
 /// You can manage your sources via the subcommands shown below.
+///
+/// The flags for checking can also be given defaults in the "defaults"
+/// block of your config, like `"defaults": {"quiet": true}`. Flags given
+/// here always override those defaults, including the negative ones
+/// like --no-quiet.
 #[derive(StructOpt)]
 pub struct Args {
-    /// The location of your config.json file. If not specified,
-    /// one is managed in your system's config directory.
+    /// The location of your config.json file. If not specified, the
+    /// SITCH_CONFIG environment variable is used, and if that isn't
+    /// set either, one is managed in your system's config directory.
     #[structopt(short = "c", long = "config", parse(from_os_str))]
     pub config: Option<PathBuf>,
 
+    /// Use a profile, a separate set of sources with its own config
+    /// (`<profile>.json` in sitch's config directory) and state. The
+    /// "default" profile uses config.json. Ignored if --config is given.
+    #[structopt(short = "p", long = "profile")]
+    pub profile: Option<String>,
+
+    /// The location of your state.json file, which stores when sources
+    /// were last checked. If not specified, one is kept next to your
+    /// config.json if you specified one, and otherwise in your system's
+    /// state directory.
+    #[structopt(short = "s", long = "state", parse(from_os_str))]
+    pub state: Option<PathBuf>,
+
     /// If you want to check for updates from a specific date (and time) on
     /// instead of from the last time this was run, specify one here.
     /// Allowed formats are:
     ///
-    /// ["today", "yesterday", "MM/DD/YYYY", "HH:MM (AM|PM) MM/DD/YYYY"]
+    /// ["today", "yesterday", "MM/DD/YYYY", "HH:MM (AM|PM) MM/DD/YYYY"],
+    /// or a length of time ago, like "3d" or "12h".
     #[structopt(
         short = "t",
         long = "since-time",
@@ -30,13 +61,275 @@ pub struct Args {
     pub since_time: Option<DateTime<Local>>,
 
     /// For linux systems, send the output as clickable notifications instead.
-    #[structopt(long = "notify")]
+    #[structopt(long = "notify", overrides_with = "no_notify")]
     pub notify: bool,
 
+    /// Like --notify, but notify about every source's updates, even the
+    /// ones whose "notify" or "priority" settings say not to.
+    #[structopt(
+        long = "notify-all",
+        raw(conflicts_with_all = r#"&["no_notify", "porcelain", "output"]"#)
+    )]
+    pub notify_all: bool,
+
+    /// Don't send notifications, even if your config's defaults say to.
+    #[structopt(long = "no-notify", overrides_with = "notify")]
+    pub no_notify: bool,
+
     /// Run in quiet mode, or simplify the output.
-    #[structopt(short = "q", long = "quiet")]
+    #[structopt(short = "q", long = "quiet", overrides_with = "no_quiet")]
     pub quiet: bool,
 
+    /// Don't run in quiet mode, even if your config's defaults say to.
+    #[structopt(long = "no-quiet", overrides_with = "quiet")]
+    pub no_quiet: bool,
+
+    /// When to color the output: "auto" colors output to a terminal
+    /// unless the CLICOLOR or NO_COLOR environment variables say not to,
+    /// and "always" colors it regardless.
+    #[structopt(long = "color", raw(possible_values = "ColorChoice::VARIANTS"))]
+    pub color: Option<ColorChoice>,
+
+    /// Never color the output, the same as `--color never`.
+    #[structopt(long = "no-color", conflicts_with = "color")]
+    pub no_color: bool,
+
+    /// How many seconds to wait for each request to a source before
+    /// giving up on it. Defaults to 30.
+    #[structopt(long = "timeout")]
+    pub timeout: Option<u64>,
+
+    /// If another run of sitch is using the same config, wait up to this
+    /// many seconds for it to finish, instead of stopping right away with
+    /// exit code 12.
+    #[structopt(long = "wait-lock")]
+    pub wait_lock: Option<u64>,
+
+    /// Only find sources by their exact names, instead of guessing which
+    /// one a name means, which is safer for scripts.
+    #[structopt(long = "exact")]
+    pub exact: bool,
+
+    /// Make the config only readable and writable by you, since it can
+    /// have API keys and other secrets in it.
+    #[structopt(long = "fix-permissions")]
+    pub fix_permissions: bool,
+
+    /// Log what's checked to stderr, to find out why a source isn't
+    /// reporting what it should. Pass it twice (-vv) to also log every
+    /// request and how many of each source's items were new.
+    #[structopt(short = "v", long = "verbose", parse(from_occurrences))]
+    pub verbose: u8,
+
+    /// Append the log to this file, like for runs from cron. What's
+    /// checked is logged to it even without -v, which still decides what's
+    /// logged to stderr.
+    #[structopt(long = "log-file", parse(from_os_str))]
+    pub log_file: Option<PathBuf>,
+
+    /// Check for updates even when sitch can't connect to the host it
+    /// checks the network with (see the `network` block of the config).
+    #[structopt(long = "force-offline-check")]
+    pub force_offline_check: bool,
+
+    /// Check for updates without saving anything, including when sitch
+    /// last checked. Combine with --since-time to replay a past check.
+    #[structopt(long = "dry-run")]
+    pub dry_run: bool,
+
+    /// Report what the last check found again, like with --json or
+    /// --digest, without checking anything or saving anything.
+    #[structopt(
+        long = "replay",
+        raw(conflicts_with_all = r#"&["since_time", "interactive", "keep_unread"]"#)
+    )]
+    pub replay: bool,
+
+    /// After checking, report the slowest sources and how long each
+    /// platform took in total.
+    #[structopt(long = "timings", overrides_with = "no_timings")]
+    pub timings: bool,
+
+    /// Don't report timings, even if your config's defaults say to.
+    #[structopt(long = "no-timings", overrides_with = "timings")]
+    pub no_timings: bool,
+
+    /// Show each source's newest update instead of its oldest.
+    #[structopt(long = "newest-first", overrides_with = "no_newest_first")]
+    pub newest_first: bool,
+
+    /// Show each source's oldest update, even if your config's defaults
+    /// say to show the newest.
+    #[structopt(long = "no-newest-first", overrides_with = "newest_first")]
+    pub no_newest_first: bool,
+
+    /// How to group updates into notifications: a single summary, one
+    /// per source, or one per update.
+    #[structopt(
+        long = "notify-style",
+        default_value = "summary",
+        raw(possible_values = "NotifyStyle::VARIANTS")
+    )]
+    pub notify_style: NotifyStyle,
+
+    /// Don't wait for notifications to be clicked or dismissed before
+    /// exiting, e.g. when running from cron. This is the default when
+    /// stdin isn't a terminal.
+    #[structopt(long = "no-wait", overrides_with = "wait")]
+    pub no_wait: bool,
+
+    /// Wait for notifications to be clicked or dismissed before exiting,
+    /// even when stdin isn't a terminal.
+    #[structopt(long = "wait", overrides_with = "no_wait")]
+    pub wait: bool,
+
+    /// The most seconds to wait for notifications to be clicked or
+    /// dismissed, after which sitch exits and they can no longer be
+    /// clicked to open their updates.
+    #[structopt(long = "notify-timeout")]
+    pub notify_timeout: Option<u64>,
+
+    /// Also write the updates found into a digest document at this path,
+    /// grouped by platform and source.
+    #[structopt(long = "digest", parse(from_os_str))]
+    pub digest: Option<PathBuf>,
+
+    /// The format to write the digest in. HTML digests are single,
+    /// self-contained files with clickable links.
+    #[structopt(
+        long = "digest-format",
+        default_value = "markdown",
+        raw(possible_values = "DigestFormat::VARIANTS")
+    )]
+    pub digest_format: DigestFormat,
+
+    /// Add this check's updates to the end of the digest instead of
+    /// overwriting it.
+    #[structopt(long = "digest-append")]
+    pub digest_append: bool,
+
+    /// When there are no updates, write a dated "no updates" entry into
+    /// the digest instead of leaving it alone.
+    #[structopt(long = "digest-empty")]
+    pub digest_empty: bool,
+
+    /// Email the digest of updates found, using the email settings in
+    /// your config. Nothing is sent when there are no updates.
+    #[structopt(long = "email")]
+    pub email: bool,
+
+    /// With --email, send an email even when there are no updates.
+    #[structopt(long = "email-always")]
+    pub email_always: bool,
+
+    /// Print a line per source instead of a section per platform, as
+    /// results come in with --no-dedupe. This is always the case when the
+    /// output is piped.
+    #[structopt(long = "flat", overrides_with = "no_flat")]
+    pub flat: bool,
+
+    /// Print a section per platform, even if your config's defaults say
+    /// to print flat output.
+    #[structopt(long = "no-flat", overrides_with = "flat")]
+    pub no_flat: bool,
+
+    /// Print exactly one tab-separated line per update for scripts:
+    /// platform, source, RFC 3339 date, link, and title. Errors go to
+    /// stderr as: error, platform, source, and message. Tabs, newlines,
+    /// and backslashes in fields are escaped as \t, \n, and \\. This
+    /// format is stable, and columns will only ever be added at the end.
+    #[structopt(long = "porcelain", conflicts_with = "notify")]
+    pub porcelain: bool,
+
+    /// Only print a line summing up the updates found, like "7 updates
+    /// (YouTube 3, RSS 4)", and nothing else, not even errors, for status
+    /// bars. Sitch exits with 0 either way. Combine with --dry-run to
+    /// leave the updates to be read in a real run. With --quiet, print
+    /// the updates as usual and end with the line, along with the number
+    /// of errors and how long the check took.
+    #[structopt(
+        long = "summary",
+        raw(
+            conflicts_with_all = r#"&["notify", "notify_all", "porcelain", "output", "keep_unread"]"#
+        )
+    )]
+    pub summary: bool,
+
+    /// Like --summary, but only print the number of updates found.
+    #[structopt(
+        long = "count",
+        raw(
+            conflicts_with_all = r#"&["summary", "notify", "notify_all", "porcelain", "output", "keep_unread"]"#
+        )
+    )]
+    pub count: bool,
+
+    /// Only add the updates found to your unread updates instead of
+    /// reporting them, to read later with `sitch unread`. Errors are
+    /// still reported.
+    #[structopt(long = "keep-unread")]
+    pub keep_unread: bool,
+
+    /// Report an update for every source it's in, instead of once for the
+    /// source first in the config with the others listed. Links that only differ in
+    /// tracking parameters, scheme, or a trailing slash are the same.
+    #[structopt(long = "no-dedupe")]
+    pub no_dedupe: bool,
+
+    /// Browse the updates found in the terminal once everything is
+    /// checked: enter opens the selected update, m marks it read, p
+    /// snoozes its source for a week, and q quits and saves. Updates
+    /// left alone stay unread.
+    #[structopt(
+        long = "interactive",
+        raw(
+            conflicts_with_all = r#"&["notify", "notify_all", "porcelain", "output", "summary", "count", "format"]"#
+        )
+    )]
+    pub interactive: bool,
+
+    /// The format to print updates in, "text" if not given. "csv" prints
+    /// a row per update with the columns platform, source, title, link,
+    /// published_date, and discovered_at, and nothing else, with errors
+    /// going to stderr like --porcelain.
+    // there's no default value, since clap counts it as conflicting with --notify
+    #[structopt(
+        long = "output",
+        raw(possible_values = "OutputFormat::VARIANTS"),
+        conflicts_with = "notify"
+    )]
+    pub output: Option<OutputFormat>,
+
+    /// Print a line per update made from this template instead of one per
+    /// source, like "{platform}\t{source}\t{title}\t{link}". The other
+    /// placeholders are {also} (the second link), {date}, which takes a
+    /// format like {date:%Y-%m-%d}, {count} (the source's number of
+    /// updates), {summary}, and {took}. Write {{ and }} for braces, and \t
+    /// and \n for tabs and newlines.
+    #[structopt(
+        long = "format",
+        raw(conflicts_with_all = r#"&["porcelain", "output", "summary", "count"]"#)
+    )]
+    pub format: Option<String>,
+
+    /// Only check these platforms, named like in the config and separated
+    /// by commas, like "rss,youtube".
+    #[structopt(long = "only")]
+    pub only: Option<PlatformList>,
+
+    /// Check every platform except these, named like in the config and
+    /// separated by commas, like "anime,manga". Skipped platforms' sources
+    /// keep their last checked times.
+    #[structopt(long = "skip", conflicts_with = "only")]
+    pub skip: Option<PlatformList>,
+
+    /// Signal with the exit code whether any updates were found: sitch
+    /// exits with 0 if there were updates, 10 if there were none, and
+    /// 1 if an error occurred. When the network is down, sitch always
+    /// exits with 11.
+    #[structopt(long = "check-exit-code")]
+    pub check_exit_code: bool,
+
     /// Only output the last time sitch checked for updates.
     /// The format is "HH:MM:SS MM/DD/YY" (24 hour)
     #[structopt(short = "L", long = "last-checked")]
@@ -47,12 +340,68 @@ pub struct Args {
     pub command: Option<Command>,
 }
 
+impl Args {
+    /// Fills in the flags that weren't given with the config's defaults,
+    /// if it has any, and then applies the color choice and timeout.
+    ///
+    /// A default `notify` is ignored when another way of printing updates
+    /// is asked for, since notifications would take their place.
+    pub fn apply_defaults(&mut self, defaults: Option<&Defaults>) {
+        let empty = Defaults::default();
+        let defaults = defaults.unwrap_or(&empty);
+        let other_output = self.porcelain
+            || self.keep_unread
+            || self.interactive
+            || self.summary
+            || self.count
+            || self.output.unwrap_or_default() != OutputFormat::Text;
+
+        self.quiet = choose_flag(self.quiet, self.no_quiet, defaults.quiet);
+        self.notify = choose_flag(self.notify, self.no_notify || other_output, defaults.notify);
+        self.flat = choose_flag(self.flat, self.no_flat, defaults.flat);
+        self.newest_first = choose_flag(
+            self.newest_first,
+            self.no_newest_first,
+            defaults.newest_first,
+        );
+        self.timings = choose_flag(self.timings, self.no_timings, defaults.timings);
+        if self.no_color {
+            self.color = Some(ColorChoice::Never);
+        }
+        self.color = self.color.or(defaults.color);
+        self.timeout = self.timeout.or(defaults.timeout);
+        if !other_output {
+            self.format = self.format.take().or_else(|| defaults.format.clone());
+        }
+
+        self.color.unwrap_or_default().apply();
+        set_timeout(self.timeout.unwrap_or(DEFAULT_TIMEOUT_SECS));
+        set_exact_names(self.exact);
+    }
+}
+
+/// Whether a flag is on, given whether it or its negative form was given,
+/// and its default from the config.
+fn choose_flag(given: bool, negated: bool, default: Option<bool>) -> bool {
+    if given {
+        true
+    } else if negated {
+        false
+    } else {
+        default.unwrap_or(false)
+    }
+}
+
 #[derive(StructOpt)]
 pub enum Command {
     /// Manage your RSS feeds.
     #[structopt(name = "rss")]
     Rss(RssCommand),
 
+    /// Follow newsletters, like Substack publications, by their RSS feeds.
+    #[structopt(name = "newsletter")]
+    Newsletter(NewsletterCommand),
+
     /// Manage your Bandcamp artists.
     #[structopt(name = "bandcamp")]
     Bandcamp(BandcampCommand),
@@ -68,6 +417,327 @@ pub enum Command {
     /// Manage the anime you follow.
     #[structopt(name = "anime")]
     Anime(AnimeCommand),
+
+    /// Manage the Mastodon accounts you follow.
+    #[structopt(name = "mastodon")]
+    Mastodon(MastodonCommand),
+
+    /// Manage the Hacker News searches you follow.
+    #[structopt(name = "hn")]
+    Hn(HnCommand),
+
+    /// Manage the crates.io crates you follow.
+    #[structopt(name = "crates")]
+    Crates(CratesCommand),
+
+    /// Manage the GitHub repositories whose commits you follow.
+    #[structopt(name = "github")]
+    GitHub(GitHubCommand),
+
+    /// Manage the custom JSON API sources you follow.
+    #[structopt(name = "custom")]
+    Custom(CustomCommand),
+
+    /// Manage the web pages you follow by scraping them.
+    #[structopt(name = "scrape")]
+    Scrape(ScrapeCommand),
+
+    /// Manage the web pages you watch for changes.
+    #[structopt(name = "watch")]
+    Watch(WatchCommand),
+
+    /// Manage the npm packages you follow.
+    #[structopt(name = "npm")]
+    Npm(NpmCommand),
+
+    /// Manage the arXiv searches you follow.
+    #[structopt(name = "arxiv")]
+    Arxiv(ArxivCommand),
+
+    /// Manage the itch.io creators you follow.
+    #[structopt(name = "itch")]
+    Itch(ItchCommand),
+
+    /// Manage the Gmail searches whose new emails you follow.
+    #[structopt(name = "gmail")]
+    Gmail(GmailCommand),
+
+    /// Manage the Webtoon series you follow.
+    #[structopt(name = "webtoon")]
+    Webtoon(WebtoonCommand),
+
+    /// Manage the Patreon creators you follow.
+    #[structopt(name = "patreon")]
+    Patreon(PatreonCommand),
+
+    /// Manage the authors whose new books you follow.
+    #[structopt(name = "authors")]
+    Authors(AuthorsCommand),
+
+    /// Manage the PyPI packages you follow.
+    #[structopt(name = "pypi")]
+    Pypi(PypiCommand),
+
+    /// Manage the Wikipedia (or other wiki) articles whose edits you follow.
+    #[structopt(name = "wikipedia")]
+    Wikipedia(WikipediaCommand),
+
+    /// Merge the sources of another config into yours, like one from
+    /// another machine. Sources in both are matched by URL or id, keeping
+    /// the newer last checked time, and you're asked which name to keep
+    /// when they're named differently.
+    #[structopt(name = "merge")]
+    Merge {
+        /// The other config to merge in.
+        #[structopt(long = "from", parse(from_os_str))]
+        from: PathBuf,
+
+        /// The other config's state file. Defaults to the one next to it,
+        /// like "other-config.state.json".
+        #[structopt(long = "from-state", parse(from_os_str))]
+        from_state: Option<PathBuf>,
+
+        /// Only show what would change, without saving anything.
+        #[structopt(long = "dry-run")]
+        dry_run: bool,
+    },
+
+    /// Check that each of your sources works without checking for or
+    /// saving any updates, reporting why the broken ones fail.
+    #[structopt(name = "doctor")]
+    Doctor,
+
+    /// Report how active each platform and source has been, most active
+    /// first, from the history of the updates sitch has found.
+    #[structopt(name = "stats")]
+    Stats {
+        /// Print the stats as JSON instead of a table.
+        #[structopt(long = "json")]
+        json: bool,
+
+        /// Only list the sources without any updates in this length of
+        /// time, like "26w", and offer to remove or snooze each of them.
+        #[structopt(long = "stale", parse(try_from_str = "parse_duration"))]
+        stale: Option<Duration>,
+    },
+
+    /// Work with the history of updates sitch has found.
+    #[structopt(name = "history")]
+    History(HistoryCommand),
+
+    /// List the updates you haven't marked as read yet, oldest first,
+    /// along with their indices.
+    #[structopt(name = "unread")]
+    Unread,
+
+    /// Open a source's page in your browser, like a YouTube channel or
+    /// the site an RSS feed is for, and print its URL.
+    #[structopt(name = "open")]
+    Open {
+        /// The name of the source, on any platform (case-insensitive).
+        /// Part of a name works too, as long as only one source has it.
+        name: String,
+    },
+
+    /// Mark unread updates as read, so they're no longer listed by
+    /// `sitch unread`.
+    #[structopt(name = "read")]
+    Read {
+        /// The indices of the updates to mark as read, as listed by
+        /// `sitch unread`, or "all".
+        #[structopt(required = true)]
+        items: Vec<String>,
+    },
+
+    /// Never report an update again, like a pinned post that keeps coming
+    /// back with a new date, by its link or a pattern of links. Links
+    /// match whatever tracking parameters they have.
+    #[structopt(name = "ignore")]
+    Ignore {
+        /// The link to ignore, a glob like "example.com/pinned/*" where *
+        /// is any run of characters and ? is any one, or a regex after
+        /// "regex:".
+        pattern: Option<String>,
+
+        #[structopt(subcommand)]
+        command: Option<IgnoreCommand>,
+    },
+
+    /// Check for updates on a schedule. On Linux with systemd, this
+    /// installs and starts a user-level service and timer in
+    /// ~/.config/systemd/user, and everywhere else it prints a line to
+    /// add to your crontab. The config and state used are the ones this
+    /// would use, by their full paths.
+    #[structopt(name = "install-schedule")]
+    InstallSchedule {
+        /// How often to check, like "1h" or "30m".
+        #[structopt(long = "every", parse(try_from_str = "parse_duration"))]
+        every: Duration,
+
+        /// Send the updates as notifications.
+        #[structopt(long = "notify")]
+        notify: bool,
+
+        /// Any other flags to check with, given after "--", like
+        /// `sitch install-schedule --every 1h -- --skip anime`.
+        #[structopt(raw(last = "true"))]
+        flags: Vec<String>,
+    },
+
+    /// Stop checking for updates on the schedule installed by
+    /// install-schedule.
+    #[structopt(name = "uninstall-schedule")]
+    UninstallSchedule,
+
+    /// Manage your profiles, separate sets of sources chosen with --profile.
+    #[structopt(name = "profile")]
+    Profile(ProfileCommand),
+
+    /// Manage the config file itself.
+    #[structopt(name = "config")]
+    Config(ConfigCommand),
+
+    /// Print a completion script for your shell, e.g.
+    /// `sitch completions zsh > ~/.zfunc/_sitch`.
+    #[structopt(name = "completions")]
+    Completions {
+        /// The shell to generate completions for.
+        #[structopt(raw(possible_values = "&Shell::variants()"))]
+        shell: Shell,
+    },
+}
+
+impl Command {
+    /// The settings given for the source being added, if this adds one.
+    pub fn source_preferences(&self) -> Option<&SourcePreferences> {
+        match self {
+            Command::Rss(RssCommand::Add { preferences, .. }) => Some(preferences),
+            Command::Newsletter(NewsletterCommand::Add { preferences, .. }) => Some(preferences),
+            Command::Bandcamp(BandcampCommand::Add { preferences, .. }) => Some(preferences),
+            Command::YouTube(YouTubeCommand::Add { preferences, .. }) => Some(preferences),
+            Command::Manga(MangaCommand::Add { preferences, .. }) => Some(preferences),
+            Command::Anime(AnimeCommand::Add { preferences, .. }) => Some(preferences),
+            Command::Mastodon(MastodonCommand::Add { preferences, .. }) => Some(preferences),
+            Command::Hn(HnCommand::Add { preferences, .. }) => Some(preferences),
+            Command::Crates(CratesCommand::Add { preferences, .. }) => Some(preferences),
+            Command::GitHub(GitHubCommand::Add { preferences, .. }) => Some(preferences),
+            Command::Custom(CustomCommand::Add { preferences, .. }) => Some(preferences),
+            Command::Scrape(ScrapeCommand::Add { preferences, .. }) => Some(preferences),
+            Command::Watch(WatchCommand::Add { preferences, .. }) => Some(preferences),
+            Command::Npm(NpmCommand::Add { preferences, .. }) => Some(preferences),
+            Command::Arxiv(ArxivCommand::Add { preferences, .. }) => Some(preferences),
+            Command::Itch(ItchCommand::Add { preferences, .. }) => Some(preferences),
+            Command::Gmail(GmailCommand::Add { preferences, .. }) => Some(preferences),
+            Command::Webtoon(WebtoonCommand::Add { preferences, .. }) => Some(preferences),
+            Command::Patreon(PatreonCommand::Add { preferences, .. }) => Some(preferences),
+            Command::Authors(AuthorsCommand::Add { preferences, .. }) => Some(preferences),
+            Command::Pypi(PypiCommand::Add { preferences, .. }) => Some(preferences),
+            Command::Wikipedia(WikipediaCommand::Add { preferences, .. }) => Some(preferences),
+            _ => None,
+        }
+    }
+}
+
+/// The settings that can be given to any source when adding it, which
+/// can be changed later by editing the source.
+#[derive(StructOpt, Clone)]
+pub struct SourcePreferences {
+    /// Send notifications about this source's updates when checking with
+    /// --notify. Once any source says whether to notify about it, only
+    /// those that do are notified about, and the rest are printed.
+    #[structopt(long = "notify")]
+    pub notify: bool,
+
+    /// Never send notifications about this source's updates, even when
+    /// checking with --notify.
+    #[structopt(long = "no-notify", conflicts_with = "notify")]
+    pub no_notify: bool,
+
+    /// How important this source is. High priority sources are printed
+    /// first and highlighted, and notified about with --notify unless
+    /// --no-notify is given. Low priority sources are printed last.
+    #[structopt(long = "priority", raw(possible_values = "Priority::VARIANTS"))]
+    pub priority: Option<Priority>,
+}
+
+impl SourcePreferences {
+    /// Sets the source's settings to the ones given, leaving the rest alone.
+    pub fn apply(&self, settings: &mut SourceSettings) {
+        if self.notify {
+            settings.notify = Some(true);
+        } else if self.no_notify {
+            settings.notify = Some(false);
+        }
+        if let Some(priority) = self.priority {
+            settings.priority = priority;
+        }
+    }
+}
+
+#[derive(StructOpt)]
+pub enum HistoryCommand {
+    /// Export every update in the history of your current sources,
+    /// oldest first.
+    #[structopt(name = "export")]
+    Export {
+        /// Write the updates as CSV to this file, or to stdout if it's
+        /// "-", with the same columns as `--output csv`.
+        #[structopt(long = "csv", parse(from_os_str))]
+        csv: PathBuf,
+    },
+}
+
+#[derive(StructOpt)]
+pub enum IgnoreCommand {
+    /// List the ignored links and patterns, along with their indices.
+    #[structopt(name = "list")]
+    List,
+
+    /// Stop ignoring a link or pattern.
+    #[structopt(name = "remove")]
+    Remove {
+        /// The link or pattern, exactly as listed by `sitch ignore list`,
+        /// or its index.
+        pattern: String,
+    },
+}
+
+#[derive(StructOpt)]
+pub enum ConfigCommand {
+    /// Print a JSON Schema of the config, for editors to check it with.
+    #[structopt(name = "schema")]
+    Schema,
+
+    /// Check the config for unknown keys, values of the wrong type, and
+    /// missing fields.
+    #[structopt(name = "validate")]
+    Validate,
+
+    /// Rewrite the config in another format, keeping the old file as a
+    /// backup ending in ".bak".
+    #[structopt(name = "convert")]
+    Convert {
+        /// The format to convert to.
+        #[structopt(long = "to", raw(possible_values = "ConfigFormat::VARIANTS"))]
+        to: ConfigFormat,
+    },
+}
+
+#[derive(StructOpt)]
+pub enum ProfileCommand {
+    /// List the profiles in sitch's config directory.
+    #[structopt(name = "list")]
+    List,
+
+    /// Copy a profile's sources and state to a new profile.
+    #[structopt(name = "copy")]
+    Copy {
+        /// The profile to copy, or "default" for config.json.
+        from: String,
+
+        /// The name of the new profile.
+        to: String,
+    },
 }
 
 #[derive(StructOpt)]
@@ -85,16 +755,97 @@ pub enum RssCommand {
         /// The URL of the feed location.
         #[structopt(short = "f", long = "feed")]
         feed: Option<String>,
+
+        /// Add each feed in a file instead, one per line as either
+        /// "<feed URL>" or "<name> | <feed URL>". Pass "-" to read from stdin.
+        #[structopt(long = "from-file")]
+        from_file: Option<String>,
+
+        #[structopt(flatten)]
+        preferences: SourcePreferences,
     },
 
     /// List your RSS feeds.
     #[structopt(name = "list")]
-    List,
+    List {
+        /// Also show the index of each feed and when it was last checked.
+        #[structopt(short = "v", long = "verbose")]
+        verbose: bool,
+
+        /// Print the feeds as JSON, just as they are stored in the config.
+        #[structopt(long = "json")]
+        json: bool,
+    },
 
     /// Edit your current RSS feeds in your favorite editor. Requires
     /// the EDITOR environment variable to be set.
     #[structopt(name = "edit")]
-    Edit,
+    Edit {
+        /// Only edit the feed with this name (case-insensitive).
+        #[structopt(short = "n", long = "name")]
+        name: Option<String>,
+    },
+
+    /// Rename one of your RSS feeds, keeping everything else about it.
+    #[structopt(name = "rename")]
+    Rename {
+        /// The current name of the feed (case-insensitive).
+        #[structopt(long = "from")]
+        from: String,
+
+        /// The new name for the feed.
+        #[structopt(long = "to")]
+        to: String,
+    },
+
+    /// Stop hearing about a feed until a date, after which everything
+    /// since it was last checked is reported.
+    #[structopt(name = "snooze")]
+    Snooze {
+        /// The name of the feed (case-insensitive).
+        #[structopt(short = "n", long = "name")]
+        name: String,
+
+        /// When to hear about the feed again, in the same formats as
+        /// --since-time, except that lengths of time like "2w" are from now.
+        #[structopt(
+            long = "until",
+            parse(try_from_str = "parse_arg_until"),
+            required_unless = "clear"
+        )]
+        until: Option<DateTime<Local>>,
+
+        /// Hear about the feed again right away.
+        #[structopt(long = "clear", conflicts_with = "until")]
+        clear: bool,
+    },
+
+    /// Stop following an RSS feed.
+    #[structopt(name = "remove")]
+    Remove {
+        /// The name of the feed (case-insensitive).
+        #[structopt(short = "n", long = "name")]
+        name: String,
+    },
+}
+
+#[derive(StructOpt)]
+pub enum NewsletterCommand {
+    /// Add a newsletter to sitch by the URL of its publication, like
+    /// https://name.substack.com. Its feed is found and checked, then
+    /// kept with your RSS feeds.
+    #[structopt(name = "add")]
+    Add {
+        /// The URL of the publication.
+        url: String,
+
+        /// Your name for the newsletter. Defaults to the feed's title.
+        #[structopt(short = "n", long = "name")]
+        name: Option<String>,
+
+        #[structopt(flatten)]
+        preferences: SourcePreferences,
+    },
 }
 
 #[derive(StructOpt)]
@@ -112,16 +863,83 @@ pub enum BandcampCommand {
         /// The URL of the bandcamp page.
         #[structopt(short = "u", long = "url")]
         url: Option<String>,
+
+        /// Add each artist in a file instead, one per line as either
+        /// "<page URL>" or "<name> | <page URL>". Pass "-" to read from stdin.
+        #[structopt(long = "from-file")]
+        from_file: Option<String>,
+
+        #[structopt(flatten)]
+        preferences: SourcePreferences,
     },
 
     /// List your Bandcamp artists.
     #[structopt(name = "list")]
-    List,
+    List {
+        /// Also show the index of each artist and when it was last checked.
+        #[structopt(short = "v", long = "verbose")]
+        verbose: bool,
+
+        /// Print the artists as JSON, just as they are stored in the config.
+        #[structopt(long = "json")]
+        json: bool,
+    },
 
     /// Edit your current Bandcamp artists in your favorite editor.
     /// Requires the EDITOR environment variable to be set.
     #[structopt(name = "edit")]
-    Edit,
+    Edit {
+        /// Only edit the artist with this name (case-insensitive).
+        #[structopt(short = "n", long = "name")]
+        name: Option<String>,
+    },
+
+    /// Rename one of your Bandcamp artists, keeping everything else about it.
+    #[structopt(name = "rename")]
+    Rename {
+        /// The current name of the artist (case-insensitive).
+        #[structopt(long = "from")]
+        from: String,
+
+        /// The new name for the artist.
+        #[structopt(long = "to")]
+        to: String,
+    },
+
+    /// Stop hearing about a artist until a date, after which everything
+    /// since it was last checked is reported.
+    #[structopt(name = "snooze")]
+    Snooze {
+        /// The name of the artist (case-insensitive).
+        #[structopt(short = "n", long = "name")]
+        name: String,
+
+        /// When to hear about the artist again, in the same formats as
+        /// --since-time, except that lengths of time like "2w" are from now.
+        #[structopt(
+            long = "until",
+            parse(try_from_str = "parse_arg_until"),
+            required_unless = "clear"
+        )]
+        until: Option<DateTime<Local>>,
+
+        /// Hear about the artist again right away.
+        #[structopt(long = "clear", conflicts_with = "until")]
+        clear: bool,
+    },
+
+    /// Stop following a Bandcamp artist.
+    #[structopt(name = "remove")]
+    Remove {
+        /// The name of the artist (case-insensitive).
+        #[structopt(short = "n", long = "name")]
+        name: String,
+    },
+
+    /// Interactively search for artists on Bandcamp and add the artist
+    /// you want correctly to sitch without needing a web browser.
+    #[structopt(name = "search")]
+    Search,
 }
 
 #[derive(StructOpt)]
@@ -139,22 +957,97 @@ pub enum YouTubeCommand {
         /// The channel ID as found on each channel's home page in the URL.
         #[structopt(short = "i", long = "id")]
         channel_id: Option<String>,
+
+        /// Add each channel in a file instead, one per line as either
+        /// "<channel ID or URL>" or "<name> | <channel ID or URL>". Pass "-" to read from stdin.
+        #[structopt(long = "from-file")]
+        from_file: Option<String>,
+
+        #[structopt(flatten)]
+        preferences: SourcePreferences,
     },
 
     /// List your YouTube channels.
     #[structopt(name = "list")]
-    List,
+    List {
+        /// Also show the index of each channel and when it was last checked.
+        #[structopt(short = "v", long = "verbose")]
+        verbose: bool,
+
+        /// Print the channels as JSON, just as they are stored in the config.
+        #[structopt(long = "json")]
+        json: bool,
+    },
 
     /// Edit your current YouTube channels in your favorite editor. Requires
     /// the EDITOR environment variable to be set.
     #[structopt(name = "edit")]
-    Edit,
+    Edit {
+        /// Only edit the channel with this name (case-insensitive).
+        #[structopt(short = "n", long = "name")]
+        name: Option<String>,
+    },
+
+    /// Rename one of your YouTube channels, keeping everything else about it.
+    #[structopt(name = "rename")]
+    Rename {
+        /// The current name of the channel (case-insensitive).
+        #[structopt(long = "from")]
+        from: String,
+
+        /// The new name for the channel.
+        #[structopt(long = "to")]
+        to: String,
+    },
+
+    /// Stop hearing about a channel until a date, after which everything
+    /// since it was last checked is reported.
+    #[structopt(name = "snooze")]
+    Snooze {
+        /// The name of the channel (case-insensitive).
+        #[structopt(short = "n", long = "name")]
+        name: String,
+
+        /// When to hear about the channel again, in the same formats as
+        /// --since-time, except that lengths of time like "2w" are from now.
+        #[structopt(
+            long = "until",
+            parse(try_from_str = "parse_arg_until"),
+            required_unless = "clear"
+        )]
+        until: Option<DateTime<Local>>,
+
+        /// Hear about the channel again right away.
+        #[structopt(long = "clear", conflicts_with = "until")]
+        clear: bool,
+    },
+
+    /// Stop following a YouTube channel.
+    #[structopt(name = "remove")]
+    Remove {
+        /// The name of the channel (case-insensitive).
+        #[structopt(short = "n", long = "name")]
+        name: String,
+    },
 
     /// Interactively search for YouTube channels and add the channel
     /// you want correctly to sitch without needing a web browser.
     #[structopt(name = "search")]
     Search,
 
+    /// Import your YouTube subscriptions from a Google Takeout export,
+    /// skipping the channels you already follow.
+    #[structopt(name = "import")]
+    Import {
+        /// The `subscriptions.csv` (or older OPML file) from Takeout.
+        #[structopt(long = "takeout", parse(from_os_str))]
+        takeout: PathBuf,
+
+        /// Only import channels whose titles contain this (case-insensitive).
+        #[structopt(long = "filter")]
+        filter: Option<String>,
+    },
+
     /// Manage the YouTube API key (required for sitch to access the YouTube API).
     /// If the key is set, sitch will check the channels for recent videos. If it
     /// is never set or it is cleared, then sitch will ignore the YouTube feature.
@@ -172,15 +1065,24 @@ pub enum YouTubeApiCommand {
         /// The new API key to use for checking YouTube.
         #[structopt(short = "k", long = "key")]
         new_key: String,
+        /// Store the key in your system's keyring instead of in your config.
+        #[structopt(long = "keyring")]
+        keyring: bool,
     },
 
     /// Clear the existing key (if you want sitch to ignore YouTube channels).
+    /// A key in your system's keyring is deleted from it as well.
     #[structopt(name = "clear")]
     Clear,
 
     /// Show your current key if it is set (prints nothing if no key is set).
+    /// The key is masked unless --reveal is given.
     #[structopt(name = "show")]
-    Show,
+    Show {
+        /// Print the actual key instead of a masked version.
+        #[structopt(long = "reveal")]
+        reveal: bool,
+    },
 }
 
 #[derive(StructOpt)]
@@ -190,19 +1092,80 @@ pub enum GmailCommand {
     /// https://support.google.com/mail/answer/7190?hl=en
     #[structopt(name = "add")]
     Add {
+        /// Your name for the filter. Defaults to the filter itself.
+        #[structopt(short = "n", long = "name")]
+        name: Option<String>,
+
         /// The filter to search with.
         #[structopt(short = "f", long = "filter")]
         filter: String,
+
+        #[structopt(flatten)]
+        preferences: SourcePreferences,
     },
 
-    /// List your Gmail filters.
+    /// List the Gmail filters you follow.
     #[structopt(name = "list")]
-    List,
+    List {
+        /// Also show the index of each filter and when it was last checked.
+        #[structopt(short = "v", long = "verbose")]
+        verbose: bool,
 
-    /// Edit your current Gmail filters in your favorite editor. Requires
-    /// the EDITOR environment variable to be set.
+        /// Print the filters as JSON, just as they are stored in the config.
+        #[structopt(long = "json")]
+        json: bool,
+    },
+
+    /// Edit the Gmail filters you follow in your favorite editor.
+    /// Requires the EDITOR environment variable to be set.
     #[structopt(name = "edit")]
-    Edit,
+    Edit {
+        /// Only edit the filter with this name (case-insensitive).
+        #[structopt(short = "n", long = "name")]
+        name: Option<String>,
+    },
+
+    /// Rename a Gmail filter you follow, keeping everything else about it.
+    #[structopt(name = "rename")]
+    Rename {
+        /// The current name of the filter (case-insensitive).
+        #[structopt(long = "from")]
+        from: String,
+
+        /// The new name for the filter.
+        #[structopt(long = "to")]
+        to: String,
+    },
+
+    /// Stop hearing about a filter until a date, after which everything
+    /// since it was last checked is reported.
+    #[structopt(name = "snooze")]
+    Snooze {
+        /// The name of the filter (case-insensitive).
+        #[structopt(short = "n", long = "name")]
+        name: String,
+
+        /// When to hear about the filter again, in the same formats as
+        /// --since-time, except that lengths of time like "2w" are from now.
+        #[structopt(
+            long = "until",
+            parse(try_from_str = "parse_arg_until"),
+            required_unless = "clear"
+        )]
+        until: Option<DateTime<Local>>,
+
+        /// Hear about the filter again right away.
+        #[structopt(long = "clear", conflicts_with = "until")]
+        clear: bool,
+    },
+
+    /// Stop following a Gmail filter.
+    #[structopt(name = "remove")]
+    Remove {
+        /// The name of the filter (case-insensitive).
+        #[structopt(short = "n", long = "name")]
+        name: String,
+    },
 
     /// Manage the Gmail API Oauth (required for sitch to access the
     /// Gmail API). If the client ID is set, sitch will check each of the
@@ -224,6 +1187,11 @@ pub enum GmailOauthCommand {
         location: Option<PathBuf>,
     },
 
+    /// Sign in to Gmail with the client ID you set, for when you set it
+    /// through stdin or your sign-in has expired.
+    #[structopt(name = "login")]
+    Login,
+
     /// Clear the existing Oauth (if you want sitch to ignore your Gmail).
     #[structopt(name = "clear")]
     Clear,
@@ -252,21 +1220,107 @@ pub enum AnimeCommand {
         /// The id of the anime as found on "myanimelist.net".
         #[structopt(short = "i", long = "id")]
         id: Option<String>,
+
+        /// Add each anime in a file instead, one per line as either
+        /// "<id or URL>" or "<name> | <id or URL>". Pass "-" to read from stdin.
+        #[structopt(long = "from-file")]
+        from_file: Option<String>,
+
+        #[structopt(flatten)]
+        preferences: SourcePreferences,
     },
 
     /// List the anime you follow.
     #[structopt(name = "list")]
-    List,
+    List {
+        /// Also show the index of each anime and when it was last checked.
+        #[structopt(short = "v", long = "verbose")]
+        verbose: bool,
+
+        /// Print the anime as JSON, just as they are stored in the config.
+        #[structopt(long = "json")]
+        json: bool,
+    },
 
     /// Edit your currently followed anime in your favorite editor. Requires
     /// the EDITOR environment variable to be set.
     #[structopt(name = "edit")]
-    Edit,
+    Edit {
+        /// Only edit the anime with this name (case-insensitive).
+        #[structopt(short = "n", long = "name")]
+        name: Option<String>,
+    },
+
+    /// Rename an anime you follow, keeping everything else about it.
+    #[structopt(name = "rename")]
+    Rename {
+        /// The current name of the anime (case-insensitive).
+        #[structopt(long = "from")]
+        from: String,
+
+        /// The new name for the anime.
+        #[structopt(long = "to")]
+        to: String,
+    },
+
+    /// Stop hearing about a anime until a date, after which everything
+    /// since it was last checked is reported.
+    #[structopt(name = "snooze")]
+    Snooze {
+        /// The name of the anime (case-insensitive).
+        #[structopt(short = "n", long = "name")]
+        name: String,
+
+        /// When to hear about the anime again, in the same formats as
+        /// --since-time, except that lengths of time like "2w" are from now.
+        #[structopt(
+            long = "until",
+            parse(try_from_str = "parse_arg_until"),
+            required_unless = "clear"
+        )]
+        until: Option<DateTime<Local>>,
+
+        /// Hear about the anime again right away.
+        #[structopt(long = "clear", conflicts_with = "until")]
+        clear: bool,
+    },
+
+    /// Stop following an anime.
+    #[structopt(name = "remove")]
+    Remove {
+        /// The name of the anime (case-insensitive).
+        #[structopt(short = "n", long = "name")]
+        name: String,
+    },
 
     /// Interactively search for anime on "myanimelist.net" and add the
     /// anime you want correctly to sitch without needing a web browser.
     #[structopt(name = "search")]
     Search,
+
+    /// Show a week view of when the anime you follow air, in your
+    /// local time. Anime that have finished airing are marked.
+    #[structopt(name = "schedule")]
+    Schedule {
+        /// Offer to remove the anime that have finished airing,
+        /// since they will never update again.
+        #[structopt(long = "prune")]
+        prune: bool,
+    },
+
+    /// Write an iCalendar file with an event for each upcoming episode of
+    /// the anime you follow that are airing, to subscribe to from a
+    /// calendar app. Regenerating the file updates the same events.
+    #[structopt(name = "calendar")]
+    Calendar {
+        /// Where to write the calendar, like "anime.ics".
+        #[structopt(long = "out", parse(from_os_str))]
+        out: PathBuf,
+
+        /// How many weeks of episodes to include.
+        #[structopt(long = "weeks", default_value = "4")]
+        weeks: u32,
+    },
 }
 
 #[derive(StructOpt)]
@@ -288,16 +1342,78 @@ pub enum MangaCommand {
         /// The id of the manga as found on "mangaeden.com".
         #[structopt(short = "i", long = "id")]
         id: Option<String>,
+
+        /// Add each manga in a file instead, one per line as either
+        /// "<id>" or "<name> | <id>". Pass "-" to read from stdin.
+        #[structopt(long = "from-file")]
+        from_file: Option<String>,
+
+        #[structopt(flatten)]
+        preferences: SourcePreferences,
     },
 
     /// List the manga you follow.
     #[structopt(name = "list")]
-    List,
+    List {
+        /// Also show the index of each manga and when it was last checked.
+        #[structopt(short = "v", long = "verbose")]
+        verbose: bool,
+
+        /// Print the manga as JSON, just as they are stored in the config.
+        #[structopt(long = "json")]
+        json: bool,
+    },
 
     /// Edit your currently followed manga in your favorite editor. Requires
     /// the EDITOR environment variable to be set.
     #[structopt(name = "edit")]
-    Edit,
+    Edit {
+        /// Only edit the manga with this name (case-insensitive).
+        #[structopt(short = "n", long = "name")]
+        name: Option<String>,
+    },
+
+    /// Rename a manga you follow, keeping everything else about it.
+    #[structopt(name = "rename")]
+    Rename {
+        /// The current name of the manga (case-insensitive).
+        #[structopt(long = "from")]
+        from: String,
+
+        /// The new name for the manga.
+        #[structopt(long = "to")]
+        to: String,
+    },
+
+    /// Stop hearing about a manga until a date, after which everything
+    /// since it was last checked is reported.
+    #[structopt(name = "snooze")]
+    Snooze {
+        /// The name of the manga (case-insensitive).
+        #[structopt(short = "n", long = "name")]
+        name: String,
+
+        /// When to hear about the manga again, in the same formats as
+        /// --since-time, except that lengths of time like "2w" are from now.
+        #[structopt(
+            long = "until",
+            parse(try_from_str = "parse_arg_until"),
+            required_unless = "clear"
+        )]
+        until: Option<DateTime<Local>>,
+
+        /// Hear about the manga again right away.
+        #[structopt(long = "clear", conflicts_with = "until")]
+        clear: bool,
+    },
+
+    /// Stop following a manga.
+    #[structopt(name = "remove")]
+    Remove {
+        /// The name of the manga (case-insensitive).
+        #[structopt(short = "n", long = "name")]
+        name: String,
+    },
 
     /// Interactively search for manga on "mangaeden.com" and add the
     /// manga you read correctly to sitch without needing a web browser.
@@ -305,6 +1421,1385 @@ pub enum MangaCommand {
     Search,
 }
 
+#[derive(StructOpt)]
+pub enum MastodonCommand {
+    /// Add a Mastodon account to sitch. You can provide all, none,
+    /// or some of the arguments for the given type, sitch will
+    /// open your preferred editor to fill in the rest of a JSON
+    /// object if you missed any required fields.
+    #[structopt(name = "add")]
+    Add {
+        /// Your name for the account.
+        #[structopt(short = "n", long = "name")]
+        name: Option<String>,
+
+        /// The account's profile URL (https://mastodon.social/@user)
+        /// or handle (@user@mastodon.social).
+        #[structopt(short = "a", long = "account")]
+        account: Option<String>,
+
+        /// Leave out posts that the account boosts.
+        #[structopt(long = "exclude-boosts")]
+        exclude_boosts: bool,
+
+        #[structopt(flatten)]
+        preferences: SourcePreferences,
+    },
+
+    /// List the Mastodon accounts you follow.
+    #[structopt(name = "list")]
+    List {
+        /// Also show the index of each account and when it was last checked.
+        #[structopt(short = "v", long = "verbose")]
+        verbose: bool,
+
+        /// Print the accounts as JSON, just as they are stored in the config.
+        #[structopt(long = "json")]
+        json: bool,
+    },
+
+    /// Edit the Mastodon accounts you follow in your favorite editor.
+    /// Requires the EDITOR environment variable to be set.
+    #[structopt(name = "edit")]
+    Edit {
+        /// Only edit the account with this name (case-insensitive).
+        #[structopt(short = "n", long = "name")]
+        name: Option<String>,
+    },
+
+    /// Rename a Mastodon account you follow, keeping everything else about it.
+    #[structopt(name = "rename")]
+    Rename {
+        /// The current name of the account (case-insensitive).
+        #[structopt(long = "from")]
+        from: String,
+
+        /// The new name for the account.
+        #[structopt(long = "to")]
+        to: String,
+    },
+
+    /// Stop hearing about a account until a date, after which everything
+    /// since it was last checked is reported.
+    #[structopt(name = "snooze")]
+    Snooze {
+        /// The name of the account (case-insensitive).
+        #[structopt(short = "n", long = "name")]
+        name: String,
+
+        /// When to hear about the account again, in the same formats as
+        /// --since-time, except that lengths of time like "2w" are from now.
+        #[structopt(
+            long = "until",
+            parse(try_from_str = "parse_arg_until"),
+            required_unless = "clear"
+        )]
+        until: Option<DateTime<Local>>,
+
+        /// Hear about the account again right away.
+        #[structopt(long = "clear", conflicts_with = "until")]
+        clear: bool,
+    },
+
+    /// Stop following a Mastodon account.
+    #[structopt(name = "remove")]
+    Remove {
+        /// The name of the account (case-insensitive).
+        #[structopt(short = "n", long = "name")]
+        name: String,
+    },
+}
+
+#[derive(StructOpt)]
+pub enum HnCommand {
+    /// Add a Hacker News watch to sitch. You can provide all, none,
+    /// or some of the arguments for the given type, sitch will
+    /// open your preferred editor to fill in the rest of a JSON
+    /// object if you missed any required fields.
+    #[structopt(name = "add")]
+    Add {
+        /// Your name for the watch.
+        #[structopt(short = "n", long = "name")]
+        name: Option<String>,
+
+        /// The search terms that stories must match.
+        #[structopt(long = "query")]
+        query: Option<String>,
+
+        /// The fewest points a story needs to be reported.
+        #[structopt(short = "p", long = "min-points", default_value = "0")]
+        min_points: u64,
+
+        #[structopt(flatten)]
+        preferences: SourcePreferences,
+    },
+
+    /// List the Hacker News watches you follow.
+    #[structopt(name = "list")]
+    List {
+        /// Also show the index of each watch and when it was last checked.
+        #[structopt(short = "v", long = "verbose")]
+        verbose: bool,
+
+        /// Print the watches as JSON, just as they are stored in the config.
+        #[structopt(long = "json")]
+        json: bool,
+    },
+
+    /// Edit the Hacker News watches you follow in your favorite editor.
+    /// Requires the EDITOR environment variable to be set.
+    #[structopt(name = "edit")]
+    Edit {
+        /// Only edit the watch with this name (case-insensitive).
+        #[structopt(short = "n", long = "name")]
+        name: Option<String>,
+    },
+
+    /// Rename a Hacker News watch you follow, keeping everything else about it.
+    #[structopt(name = "rename")]
+    Rename {
+        /// The current name of the watch (case-insensitive).
+        #[structopt(long = "from")]
+        from: String,
+
+        /// The new name for the watch.
+        #[structopt(long = "to")]
+        to: String,
+    },
+
+    /// Stop hearing about a watch until a date, after which everything
+    /// since it was last checked is reported.
+    #[structopt(name = "snooze")]
+    Snooze {
+        /// The name of the watch (case-insensitive).
+        #[structopt(short = "n", long = "name")]
+        name: String,
+
+        /// When to hear about the watch again, in the same formats as
+        /// --since-time, except that lengths of time like "2w" are from now.
+        #[structopt(
+            long = "until",
+            parse(try_from_str = "parse_arg_until"),
+            required_unless = "clear"
+        )]
+        until: Option<DateTime<Local>>,
+
+        /// Hear about the watch again right away.
+        #[structopt(long = "clear", conflicts_with = "until")]
+        clear: bool,
+    },
+
+    /// Stop following a Hacker News watch.
+    #[structopt(name = "remove")]
+    Remove {
+        /// The name of the watch (case-insensitive).
+        #[structopt(short = "n", long = "name")]
+        name: String,
+    },
+}
+
+#[derive(StructOpt)]
+pub enum CratesCommand {
+    /// Add a crate from crates.io to sitch. You can provide all, none,
+    /// or some of the arguments for the given type, sitch will
+    /// open your preferred editor to fill in the rest of a JSON
+    /// object if you missed any required fields.
+    #[structopt(name = "add")]
+    Add {
+        /// Your name for the crate.
+        #[structopt(short = "n", long = "name")]
+        name: Option<String>,
+
+        /// The crate's name on crates.io.
+        #[structopt(short = "k", long = "crate")]
+        crate_name: Option<String>,
+
+        /// Leave out pre-release versions, like "1.0.0-beta.1".
+        #[structopt(long = "exclude-prereleases")]
+        exclude_prereleases: bool,
+
+        #[structopt(flatten)]
+        preferences: SourcePreferences,
+    },
+
+    /// List the crates you follow.
+    #[structopt(name = "list")]
+    List {
+        /// Also show the index of each crate and when it was last checked.
+        #[structopt(short = "v", long = "verbose")]
+        verbose: bool,
+
+        /// Print the crates as JSON, just as they are stored in the config.
+        #[structopt(long = "json")]
+        json: bool,
+    },
+
+    /// Edit the crates you follow in your favorite editor.
+    /// Requires the EDITOR environment variable to be set.
+    #[structopt(name = "edit")]
+    Edit {
+        /// Only edit the crate with this name (case-insensitive).
+        #[structopt(short = "n", long = "name")]
+        name: Option<String>,
+    },
+
+    /// Rename a crate you follow, keeping everything else about it.
+    #[structopt(name = "rename")]
+    Rename {
+        /// The current name of the crate (case-insensitive).
+        #[structopt(long = "from")]
+        from: String,
+
+        /// The new name for the crate.
+        #[structopt(long = "to")]
+        to: String,
+    },
+
+    /// Stop hearing about a crate until a date, after which everything
+    /// since it was last checked is reported.
+    #[structopt(name = "snooze")]
+    Snooze {
+        /// The name of the crate (case-insensitive).
+        #[structopt(short = "n", long = "name")]
+        name: String,
+
+        /// When to hear about the crate again, in the same formats as
+        /// --since-time, except that lengths of time like "2w" are from now.
+        #[structopt(
+            long = "until",
+            parse(try_from_str = "parse_arg_until"),
+            required_unless = "clear"
+        )]
+        until: Option<DateTime<Local>>,
+
+        /// Hear about the crate again right away.
+        #[structopt(long = "clear", conflicts_with = "until")]
+        clear: bool,
+    },
+
+    /// Stop following a crate.
+    #[structopt(name = "remove")]
+    Remove {
+        /// The name of the crate (case-insensitive).
+        #[structopt(short = "n", long = "name")]
+        name: String,
+    },
+}
+
+#[derive(StructOpt)]
+pub enum GitHubCommand {
+    /// Add a GitHub repository to sitch. You can provide all, none,
+    /// or some of the arguments for the given type, sitch will
+    /// open your preferred editor to fill in the rest of a JSON
+    /// object if you missed any required fields.
+    #[structopt(name = "add")]
+    Add {
+        /// Your name for the repository.
+        #[structopt(short = "n", long = "name")]
+        name: Option<String>,
+
+        /// The user or organization that owns the repository.
+        #[structopt(short = "o", long = "owner")]
+        owner: Option<String>,
+
+        /// The repository's name.
+        #[structopt(short = "r", long = "repo")]
+        repo: Option<String>,
+
+        /// The branch to follow, if not the default branch.
+        #[structopt(short = "b", long = "branch")]
+        branch: Option<String>,
+
+        /// Report all of a check's new commits as a single update.
+        #[structopt(long = "collapse")]
+        collapse: bool,
+
+        #[structopt(flatten)]
+        preferences: SourcePreferences,
+    },
+
+    /// List the GitHub repositories you follow.
+    #[structopt(name = "list")]
+    List {
+        /// Also show the index of each repository and when it was last checked.
+        #[structopt(short = "v", long = "verbose")]
+        verbose: bool,
+
+        /// Print the repositories as JSON, just as they are stored in the config.
+        #[structopt(long = "json")]
+        json: bool,
+    },
+
+    /// Edit the GitHub repositories you follow in your favorite editor.
+    /// Requires the EDITOR environment variable to be set.
+    #[structopt(name = "edit")]
+    Edit {
+        /// Only edit the repository with this name (case-insensitive).
+        #[structopt(short = "n", long = "name")]
+        name: Option<String>,
+    },
+
+    /// Rename a GitHub repository you follow, keeping everything else about it.
+    #[structopt(name = "rename")]
+    Rename {
+        /// The current name of the repository (case-insensitive).
+        #[structopt(long = "from")]
+        from: String,
+
+        /// The new name for the repository.
+        #[structopt(long = "to")]
+        to: String,
+    },
+
+    /// Stop hearing about a repository until a date, after which everything
+    /// since it was last checked is reported.
+    #[structopt(name = "snooze")]
+    Snooze {
+        /// The name of the repository (case-insensitive).
+        #[structopt(short = "n", long = "name")]
+        name: String,
+
+        /// When to hear about the repository again, in the same formats as
+        /// --since-time, except that lengths of time like "2w" are from now.
+        #[structopt(
+            long = "until",
+            parse(try_from_str = "parse_arg_until"),
+            required_unless = "clear"
+        )]
+        until: Option<DateTime<Local>>,
+
+        /// Hear about the repository again right away.
+        #[structopt(long = "clear", conflicts_with = "until")]
+        clear: bool,
+    },
+
+    /// Stop following a GitHub repository.
+    #[structopt(name = "remove")]
+    Remove {
+        /// The name of the repository (case-insensitive).
+        #[structopt(short = "n", long = "name")]
+        name: String,
+    },
+}
+
+#[derive(StructOpt)]
+pub enum CustomCommand {
+    /// Add a JSON API to sitch. You can provide all, none,
+    /// or some of the arguments for the given type, sitch will
+    /// open your preferred editor to fill in the rest of a JSON
+    /// object if you missed any required fields.
+    ///
+    /// The pointers are JSON pointers, like "/data/events". The title,
+    /// link, and date pointers are relative to each item.
+    #[structopt(name = "add")]
+    Add {
+        /// Your name for the source.
+        #[structopt(short = "n", long = "name")]
+        name: Option<String>,
+
+        /// The URL of the JSON to check.
+        #[structopt(short = "u", long = "url")]
+        url: Option<String>,
+
+        /// Where the array of items is in the JSON.
+        #[structopt(long = "items")]
+        items_pointer: Option<String>,
+
+        /// Where each item's title is.
+        #[structopt(long = "title")]
+        title_pointer: Option<String>,
+
+        /// Where each item's link is.
+        #[structopt(long = "link")]
+        link_pointer: Option<String>,
+
+        /// Where each item's date is.
+        #[structopt(long = "date")]
+        date_pointer: Option<String>,
+
+        /// How dates are formatted: a chrono format string like
+        /// "%Y-%m-%d %H:%M", or "epoch" for seconds since the Unix epoch.
+        /// RFC 3339 and RFC 2822 dates are read if not set.
+        #[structopt(long = "date-format")]
+        date_format: Option<String>,
+
+        #[structopt(flatten)]
+        preferences: SourcePreferences,
+    },
+
+    /// List the custom sources you follow.
+    #[structopt(name = "list")]
+    List {
+        /// Also show the index of each source and when it was last checked.
+        #[structopt(short = "v", long = "verbose")]
+        verbose: bool,
+
+        /// Print the sources as JSON, just as they are stored in the config.
+        #[structopt(long = "json")]
+        json: bool,
+    },
+
+    /// Edit the custom sources you follow in your favorite editor.
+    /// Requires the EDITOR environment variable to be set.
+    #[structopt(name = "edit")]
+    Edit {
+        /// Only edit the source with this name (case-insensitive).
+        #[structopt(short = "n", long = "name")]
+        name: Option<String>,
+    },
+
+    /// Rename a custom source you follow, keeping everything else about it.
+    #[structopt(name = "rename")]
+    Rename {
+        /// The current name of the source (case-insensitive).
+        #[structopt(long = "from")]
+        from: String,
+
+        /// The new name for the source.
+        #[structopt(long = "to")]
+        to: String,
+    },
+
+    /// Stop hearing about a source until a date, after which everything
+    /// since it was last checked is reported.
+    #[structopt(name = "snooze")]
+    Snooze {
+        /// The name of the source (case-insensitive).
+        #[structopt(short = "n", long = "name")]
+        name: String,
+
+        /// When to hear about the source again, in the same formats as
+        /// --since-time, except that lengths of time like "2w" are from now.
+        #[structopt(
+            long = "until",
+            parse(try_from_str = "parse_arg_until"),
+            required_unless = "clear"
+        )]
+        until: Option<DateTime<Local>>,
+
+        /// Hear about the source again right away.
+        #[structopt(long = "clear", conflicts_with = "until")]
+        clear: bool,
+    },
+
+    /// Stop following a custom source.
+    #[structopt(name = "remove")]
+    Remove {
+        /// The name of the source (case-insensitive).
+        #[structopt(short = "n", long = "name")]
+        name: String,
+    },
+}
+
+#[derive(StructOpt)]
+pub enum ScrapeCommand {
+    /// Add a web page to scrape to sitch. You can provide all, none,
+    /// or some of the arguments for the given type, sitch will
+    /// open your preferred editor to fill in the rest of a JSON
+    /// object if you missed any required fields.
+    ///
+    /// The title, link, and date selectors are relative to each item.
+    #[structopt(name = "add")]
+    Add {
+        /// Your name for the page.
+        #[structopt(short = "n", long = "name")]
+        name: Option<String>,
+
+        /// The URL of the page to scrape.
+        #[structopt(short = "u", long = "url")]
+        url: Option<String>,
+
+        /// The CSS selector for each item on the page.
+        #[structopt(long = "items")]
+        item_selector: Option<String>,
+
+        /// The CSS selector for each item's title, if not all of its text.
+        #[structopt(long = "title")]
+        title_selector: Option<String>,
+
+        /// The CSS selector for the link in each item, if not its first link.
+        #[structopt(long = "link")]
+        link_selector: Option<String>,
+
+        /// The CSS selector for each item's date, if it has one.
+        #[structopt(long = "date")]
+        date_selector: Option<String>,
+
+        /// How dates are formatted: a chrono format string like
+        /// "%Y-%m-%d %H:%M", or "epoch" for seconds since the Unix epoch.
+        /// RFC 3339 and RFC 2822 dates are read if not set.
+        #[structopt(long = "date-format")]
+        date_format: Option<String>,
+
+        #[structopt(flatten)]
+        preferences: SourcePreferences,
+    },
+
+    /// List the scraped pages you follow.
+    #[structopt(name = "list")]
+    List {
+        /// Also show the index of each page and when it was last checked.
+        #[structopt(short = "v", long = "verbose")]
+        verbose: bool,
+
+        /// Print the pages as JSON, just as they are stored in the config.
+        #[structopt(long = "json")]
+        json: bool,
+    },
+
+    /// Edit the scraped pages you follow in your favorite editor.
+    /// Requires the EDITOR environment variable to be set.
+    #[structopt(name = "edit")]
+    Edit {
+        /// Only edit the page with this name (case-insensitive).
+        #[structopt(short = "n", long = "name")]
+        name: Option<String>,
+    },
+
+    /// Rename a scraped page you follow, keeping everything else about it.
+    #[structopt(name = "rename")]
+    Rename {
+        /// The current name of the page (case-insensitive).
+        #[structopt(long = "from")]
+        from: String,
+
+        /// The new name for the page.
+        #[structopt(long = "to")]
+        to: String,
+    },
+
+    /// Stop hearing about a page until a date, after which everything
+    /// since it was last checked is reported.
+    #[structopt(name = "snooze")]
+    Snooze {
+        /// The name of the page (case-insensitive).
+        #[structopt(short = "n", long = "name")]
+        name: String,
+
+        /// When to hear about the page again, in the same formats as
+        /// --since-time, except that lengths of time like "2w" are from now.
+        #[structopt(
+            long = "until",
+            parse(try_from_str = "parse_arg_until"),
+            required_unless = "clear"
+        )]
+        until: Option<DateTime<Local>>,
+
+        /// Hear about the page again right away.
+        #[structopt(long = "clear", conflicts_with = "until")]
+        clear: bool,
+    },
+
+    /// Stop following a scraped page.
+    #[structopt(name = "remove")]
+    Remove {
+        /// The name of the page (case-insensitive).
+        #[structopt(short = "n", long = "name")]
+        name: String,
+    },
+
+    /// Print what would currently be found on a page, without saving
+    /// anything, to try out its selectors.
+    #[structopt(name = "test")]
+    Test {
+        /// The name of the page (case-insensitive).
+        name: String,
+    },
+}
+
+#[derive(StructOpt)]
+pub enum WatchCommand {
+    /// Add a web page to watch for changes to sitch. You can provide
+    /// all, none, or some of the arguments for the given type, sitch will
+    /// open your preferred editor to fill in the rest of a JSON
+    /// object if you missed any required fields.
+    #[structopt(name = "add")]
+    Add {
+        /// Your name for the page.
+        #[structopt(short = "n", long = "name")]
+        name: Option<String>,
+
+        /// The URL of the page to watch.
+        #[structopt(short = "u", long = "url")]
+        url: Option<String>,
+
+        /// A CSS selector for the part of the page to watch, if not all of it.
+        #[structopt(short = "s", long = "selector")]
+        selector: Option<String>,
+
+        #[structopt(flatten)]
+        preferences: SourcePreferences,
+    },
+
+    /// List the watched pages you follow.
+    #[structopt(name = "list")]
+    List {
+        /// Also show the index of each page and when it was last checked.
+        #[structopt(short = "v", long = "verbose")]
+        verbose: bool,
+
+        /// Print the pages as JSON, just as they are stored in the config.
+        #[structopt(long = "json")]
+        json: bool,
+    },
+
+    /// Edit the watched pages you follow in your favorite editor.
+    /// Requires the EDITOR environment variable to be set.
+    #[structopt(name = "edit")]
+    Edit {
+        /// Only edit the page with this name (case-insensitive).
+        #[structopt(short = "n", long = "name")]
+        name: Option<String>,
+    },
+
+    /// Rename a watched page you follow, keeping everything else about it.
+    #[structopt(name = "rename")]
+    Rename {
+        /// The current name of the page (case-insensitive).
+        #[structopt(long = "from")]
+        from: String,
+
+        /// The new name for the page.
+        #[structopt(long = "to")]
+        to: String,
+    },
+
+    /// Stop hearing about a page until a date, after which everything
+    /// since it was last checked is reported.
+    #[structopt(name = "snooze")]
+    Snooze {
+        /// The name of the page (case-insensitive).
+        #[structopt(short = "n", long = "name")]
+        name: String,
+
+        /// When to hear about the page again, in the same formats as
+        /// --since-time, except that lengths of time like "2w" are from now.
+        #[structopt(
+            long = "until",
+            parse(try_from_str = "parse_arg_until"),
+            required_unless = "clear"
+        )]
+        until: Option<DateTime<Local>>,
+
+        /// Hear about the page again right away.
+        #[structopt(long = "clear", conflicts_with = "until")]
+        clear: bool,
+    },
+
+    /// Stop following a watched page.
+    #[structopt(name = "remove")]
+    Remove {
+        /// The name of the page (case-insensitive).
+        #[structopt(short = "n", long = "name")]
+        name: String,
+    },
+}
+
+#[derive(StructOpt)]
+pub enum NpmCommand {
+    /// Add a package from npm to sitch. You can provide all, none,
+    /// or some of the arguments for the given type, sitch will
+    /// open your preferred editor to fill in the rest of a JSON
+    /// object if you missed any required fields.
+    #[structopt(name = "add")]
+    Add {
+        /// Your name for the package.
+        #[structopt(short = "n", long = "name")]
+        name: Option<String>,
+
+        /// The package's name on npm, like "left-pad" or "@org/pkg".
+        #[structopt(short = "p", long = "package")]
+        package: Option<String>,
+
+        /// Leave out versions with this dist-tag, like "next". Can be
+        /// given more than once.
+        #[structopt(long = "exclude-tag")]
+        exclude_tags: Vec<String>,
+
+        #[structopt(flatten)]
+        preferences: SourcePreferences,
+    },
+
+    /// List the npm packages you follow.
+    #[structopt(name = "list")]
+    List {
+        /// Also show the index of each package and when it was last checked.
+        #[structopt(short = "v", long = "verbose")]
+        verbose: bool,
+
+        /// Print the packages as JSON, just as they are stored in the config.
+        #[structopt(long = "json")]
+        json: bool,
+    },
+
+    /// Edit the npm packages you follow in your favorite editor.
+    /// Requires the EDITOR environment variable to be set.
+    #[structopt(name = "edit")]
+    Edit {
+        /// Only edit the package with this name (case-insensitive).
+        #[structopt(short = "n", long = "name")]
+        name: Option<String>,
+    },
+
+    /// Rename a npm package you follow, keeping everything else about it.
+    #[structopt(name = "rename")]
+    Rename {
+        /// The current name of the package (case-insensitive).
+        #[structopt(long = "from")]
+        from: String,
+
+        /// The new name for the package.
+        #[structopt(long = "to")]
+        to: String,
+    },
+
+    /// Stop hearing about a package until a date, after which everything
+    /// since it was last checked is reported.
+    #[structopt(name = "snooze")]
+    Snooze {
+        /// The name of the package (case-insensitive).
+        #[structopt(short = "n", long = "name")]
+        name: String,
+
+        /// When to hear about the package again, in the same formats as
+        /// --since-time, except that lengths of time like "2w" are from now.
+        #[structopt(
+            long = "until",
+            parse(try_from_str = "parse_arg_until"),
+            required_unless = "clear"
+        )]
+        until: Option<DateTime<Local>>,
+
+        /// Hear about the package again right away.
+        #[structopt(long = "clear", conflicts_with = "until")]
+        clear: bool,
+    },
+
+    /// Stop following a npm package.
+    #[structopt(name = "remove")]
+    Remove {
+        /// The name of the package (case-insensitive).
+        #[structopt(short = "n", long = "name")]
+        name: String,
+    },
+}
+
+#[derive(StructOpt)]
+pub enum ArxivCommand {
+    /// Add an arXiv search to sitch. You can provide all, none,
+    /// or some of the arguments for the given type, sitch will
+    /// open your preferred editor to fill in the rest of a JSON
+    /// object if you missed any required fields.
+    #[structopt(name = "add")]
+    Add {
+        /// Your name for the search.
+        #[structopt(short = "n", long = "name")]
+        name: Option<String>,
+
+        /// The search in arXiv's query syntax, like "au:hinton AND cat:cs.LG".
+        #[structopt(long = "query")]
+        query: Option<String>,
+
+        /// The most new papers to load per check.
+        #[structopt(short = "m", long = "max-results")]
+        max_results: Option<usize>,
+
+        #[structopt(flatten)]
+        preferences: SourcePreferences,
+    },
+
+    /// List the arXiv queries you follow.
+    #[structopt(name = "list")]
+    List {
+        /// Also show the index of each query and when it was last checked.
+        #[structopt(short = "v", long = "verbose")]
+        verbose: bool,
+
+        /// Print the queries as JSON, just as they are stored in the config.
+        #[structopt(long = "json")]
+        json: bool,
+    },
+
+    /// Edit the arXiv queries you follow in your favorite editor.
+    /// Requires the EDITOR environment variable to be set.
+    #[structopt(name = "edit")]
+    Edit {
+        /// Only edit the query with this name (case-insensitive).
+        #[structopt(short = "n", long = "name")]
+        name: Option<String>,
+    },
+
+    /// Rename a arXiv query you follow, keeping everything else about it.
+    #[structopt(name = "rename")]
+    Rename {
+        /// The current name of the query (case-insensitive).
+        #[structopt(long = "from")]
+        from: String,
+
+        /// The new name for the query.
+        #[structopt(long = "to")]
+        to: String,
+    },
+
+    /// Stop hearing about a query until a date, after which everything
+    /// since it was last checked is reported.
+    #[structopt(name = "snooze")]
+    Snooze {
+        /// The name of the query (case-insensitive).
+        #[structopt(short = "n", long = "name")]
+        name: String,
+
+        /// When to hear about the query again, in the same formats as
+        /// --since-time, except that lengths of time like "2w" are from now.
+        #[structopt(
+            long = "until",
+            parse(try_from_str = "parse_arg_until"),
+            required_unless = "clear"
+        )]
+        until: Option<DateTime<Local>>,
+
+        /// Hear about the query again right away.
+        #[structopt(long = "clear", conflicts_with = "until")]
+        clear: bool,
+    },
+
+    /// Stop following a arXiv query.
+    #[structopt(name = "remove")]
+    Remove {
+        /// The name of the query (case-insensitive).
+        #[structopt(short = "n", long = "name")]
+        name: String,
+    },
+}
+
+#[derive(StructOpt)]
+pub enum ItchCommand {
+    /// Add an itch.io creator to sitch. You can provide all, none,
+    /// or some of the arguments for the given type, sitch will
+    /// open your preferred editor to fill in the rest of a JSON
+    /// object if you missed any required fields.
+    #[structopt(name = "add")]
+    Add {
+        /// Your name for the creator.
+        #[structopt(short = "n", long = "name")]
+        name: Option<String>,
+
+        /// The URL of the creator's page, like https://creator.itch.io.
+        #[structopt(short = "u", long = "url")]
+        url: Option<String>,
+
+        #[structopt(flatten)]
+        preferences: SourcePreferences,
+    },
+
+    /// List the itch.io creators you follow.
+    #[structopt(name = "list")]
+    List {
+        /// Also show the index of each creator and when it was last checked.
+        #[structopt(short = "v", long = "verbose")]
+        verbose: bool,
+
+        /// Print the creators as JSON, just as they are stored in the config.
+        #[structopt(long = "json")]
+        json: bool,
+    },
+
+    /// Edit the itch.io creators you follow in your favorite editor.
+    /// Requires the EDITOR environment variable to be set.
+    #[structopt(name = "edit")]
+    Edit {
+        /// Only edit the creator with this name (case-insensitive).
+        #[structopt(short = "n", long = "name")]
+        name: Option<String>,
+    },
+
+    /// Rename a itch.io creator you follow, keeping everything else about it.
+    #[structopt(name = "rename")]
+    Rename {
+        /// The current name of the creator (case-insensitive).
+        #[structopt(long = "from")]
+        from: String,
+
+        /// The new name for the creator.
+        #[structopt(long = "to")]
+        to: String,
+    },
+
+    /// Stop hearing about a creator until a date, after which everything
+    /// since it was last checked is reported.
+    #[structopt(name = "snooze")]
+    Snooze {
+        /// The name of the creator (case-insensitive).
+        #[structopt(short = "n", long = "name")]
+        name: String,
+
+        /// When to hear about the creator again, in the same formats as
+        /// --since-time, except that lengths of time like "2w" are from now.
+        #[structopt(
+            long = "until",
+            parse(try_from_str = "parse_arg_until"),
+            required_unless = "clear"
+        )]
+        until: Option<DateTime<Local>>,
+
+        /// Hear about the creator again right away.
+        #[structopt(long = "clear", conflicts_with = "until")]
+        clear: bool,
+    },
+
+    /// Stop following a itch.io creator.
+    #[structopt(name = "remove")]
+    Remove {
+        /// The name of the creator (case-insensitive).
+        #[structopt(short = "n", long = "name")]
+        name: String,
+    },
+}
+
+#[derive(StructOpt)]
+pub enum WebtoonCommand {
+    /// Add a Webtoon series to sitch by its URL, like
+    /// https://www.webtoons.com/en/fantasy/tower-of-god/list?title_no=95.
+    /// The series is checked before it is added.
+    #[structopt(name = "add")]
+    Add {
+        /// Your name for the series. Defaults to the series' title.
+        #[structopt(short = "n", long = "name")]
+        name: Option<String>,
+
+        /// The URL of the series' page.
+        #[structopt(short = "u", long = "url")]
+        url: String,
+
+        #[structopt(flatten)]
+        preferences: SourcePreferences,
+    },
+
+    /// List the Webtoon series you follow.
+    #[structopt(name = "list")]
+    List {
+        /// Also show the index of each series and when it was last checked.
+        #[structopt(short = "v", long = "verbose")]
+        verbose: bool,
+
+        /// Print the series as JSON, just as they are stored in the config.
+        #[structopt(long = "json")]
+        json: bool,
+    },
+
+    /// Edit the Webtoon series you follow in your favorite editor.
+    /// Requires the EDITOR environment variable to be set.
+    #[structopt(name = "edit")]
+    Edit {
+        /// Only edit the series with this name (case-insensitive).
+        #[structopt(short = "n", long = "name")]
+        name: Option<String>,
+    },
+
+    /// Rename a Webtoon series you follow, keeping everything else about it.
+    #[structopt(name = "rename")]
+    Rename {
+        /// The current name of the series (case-insensitive).
+        #[structopt(long = "from")]
+        from: String,
+
+        /// The new name for the series.
+        #[structopt(long = "to")]
+        to: String,
+    },
+
+    /// Stop hearing about a series until a date, after which everything
+    /// since it was last checked is reported.
+    #[structopt(name = "snooze")]
+    Snooze {
+        /// The name of the series (case-insensitive).
+        #[structopt(short = "n", long = "name")]
+        name: String,
+
+        /// When to hear about the series again, in the same formats as
+        /// --since-time, except that lengths of time like "2w" are from now.
+        #[structopt(
+            long = "until",
+            parse(try_from_str = "parse_arg_until"),
+            required_unless = "clear"
+        )]
+        until: Option<DateTime<Local>>,
+
+        /// Hear about the series again right away.
+        #[structopt(long = "clear", conflicts_with = "until")]
+        clear: bool,
+    },
+
+    /// Stop following a Webtoon series.
+    #[structopt(name = "remove")]
+    Remove {
+        /// The name of the series (case-insensitive).
+        #[structopt(short = "n", long = "name")]
+        name: String,
+    },
+}
+
+#[derive(StructOpt)]
+pub enum PatreonCommand {
+    /// Add a Patreon creator to sitch by their page's URL, like
+    /// https://www.patreon.com/creator. Their campaign is found on the
+    /// page before they are added.
+    #[structopt(name = "add")]
+    Add {
+        /// Your name for the creator. Defaults to the end of the URL.
+        #[structopt(short = "n", long = "name")]
+        name: Option<String>,
+
+        /// The URL of the creator's page.
+        #[structopt(short = "u", long = "url")]
+        url: String,
+
+        #[structopt(flatten)]
+        preferences: SourcePreferences,
+    },
+
+    /// List the Patreon creators you follow.
+    #[structopt(name = "list")]
+    List {
+        /// Also show the index of each creator and when it was last checked.
+        #[structopt(short = "v", long = "verbose")]
+        verbose: bool,
+
+        /// Print the creators as JSON, just as they are stored in the config.
+        #[structopt(long = "json")]
+        json: bool,
+    },
+
+    /// Edit the Patreon creators you follow in your favorite editor.
+    /// Requires the EDITOR environment variable to be set.
+    #[structopt(name = "edit")]
+    Edit {
+        /// Only edit the creator with this name (case-insensitive).
+        #[structopt(short = "n", long = "name")]
+        name: Option<String>,
+    },
+
+    /// Rename a Patreon creator you follow, keeping everything else about it.
+    #[structopt(name = "rename")]
+    Rename {
+        /// The current name of the creator (case-insensitive).
+        #[structopt(long = "from")]
+        from: String,
+
+        /// The new name for the creator.
+        #[structopt(long = "to")]
+        to: String,
+    },
+
+    /// Stop hearing about a creator until a date, after which everything
+    /// since it was last checked is reported.
+    #[structopt(name = "snooze")]
+    Snooze {
+        /// The name of the creator (case-insensitive).
+        #[structopt(short = "n", long = "name")]
+        name: String,
+
+        /// When to hear about the creator again, in the same formats as
+        /// --since-time, except that lengths of time like "2w" are from now.
+        #[structopt(
+            long = "until",
+            parse(try_from_str = "parse_arg_until"),
+            required_unless = "clear"
+        )]
+        until: Option<DateTime<Local>>,
+
+        /// Hear about the creator again right away.
+        #[structopt(long = "clear", conflicts_with = "until")]
+        clear: bool,
+    },
+
+    /// Stop following a Patreon creator.
+    #[structopt(name = "remove")]
+    Remove {
+        /// The name of the creator (case-insensitive).
+        #[structopt(short = "n", long = "name")]
+        name: String,
+    },
+}
+
+#[derive(StructOpt)]
+pub enum AuthorsCommand {
+    /// Add an author to sitch by their Goodreads id or the URL of their
+    /// Goodreads page, like https://www.goodreads.com/author/show/1234.Some_Author.
+    #[structopt(name = "add")]
+    Add {
+        /// Your name for the author. Defaults to the name in the URL.
+        #[structopt(short = "n", long = "name")]
+        name: Option<String>,
+
+        /// The author's Goodreads id or page URL.
+        #[structopt(short = "a", long = "author")]
+        author: String,
+
+        #[structopt(flatten)]
+        preferences: SourcePreferences,
+    },
+
+    /// List the authors you follow.
+    #[structopt(name = "list")]
+    List {
+        /// Also show the index of each author and when it was last checked.
+        #[structopt(short = "v", long = "verbose")]
+        verbose: bool,
+
+        /// Print the authors as JSON, just as they are stored in the config.
+        #[structopt(long = "json")]
+        json: bool,
+    },
+
+    /// Edit the authors you follow in your favorite editor.
+    /// Requires the EDITOR environment variable to be set.
+    #[structopt(name = "edit")]
+    Edit {
+        /// Only edit the author with this name (case-insensitive).
+        #[structopt(short = "n", long = "name")]
+        name: Option<String>,
+    },
+
+    /// Rename a author you follow, keeping everything else about it.
+    #[structopt(name = "rename")]
+    Rename {
+        /// The current name of the author (case-insensitive).
+        #[structopt(long = "from")]
+        from: String,
+
+        /// The new name for the author.
+        #[structopt(long = "to")]
+        to: String,
+    },
+
+    /// Stop hearing about a author until a date, after which everything
+    /// since it was last checked is reported.
+    #[structopt(name = "snooze")]
+    Snooze {
+        /// The name of the author (case-insensitive).
+        #[structopt(short = "n", long = "name")]
+        name: String,
+
+        /// When to hear about the author again, in the same formats as
+        /// --since-time, except that lengths of time like "2w" are from now.
+        #[structopt(
+            long = "until",
+            parse(try_from_str = "parse_arg_until"),
+            required_unless = "clear"
+        )]
+        until: Option<DateTime<Local>>,
+
+        /// Hear about the author again right away.
+        #[structopt(long = "clear", conflicts_with = "until")]
+        clear: bool,
+    },
+
+    /// Stop following a author.
+    #[structopt(name = "remove")]
+    Remove {
+        /// The name of the author (case-insensitive).
+        #[structopt(short = "n", long = "name")]
+        name: String,
+    },
+}
+
+#[derive(StructOpt)]
+pub enum PypiCommand {
+    /// Add a package from PyPI to sitch. You can provide all, none,
+    /// or some of the arguments for the given type, sitch will
+    /// open your preferred editor to fill in the rest of a JSON
+    /// object if you missed any required fields.
+    #[structopt(name = "add")]
+    Add {
+        /// Your name for the package.
+        #[structopt(short = "n", long = "name")]
+        name: Option<String>,
+
+        /// The package's name on PyPI, like "requests".
+        #[structopt(short = "p", long = "package")]
+        package: Option<String>,
+
+        /// Leave out pre-releases and dev releases, like "2.0.0rc1".
+        #[structopt(long = "exclude-prereleases")]
+        exclude_prereleases: bool,
+
+        #[structopt(flatten)]
+        preferences: SourcePreferences,
+    },
+
+    /// List the PyPI packages you follow.
+    #[structopt(name = "list")]
+    List {
+        /// Also show the index of each package and when it was last checked.
+        #[structopt(short = "v", long = "verbose")]
+        verbose: bool,
+
+        /// Print the packages as JSON, just as they are stored in the config.
+        #[structopt(long = "json")]
+        json: bool,
+    },
+
+    /// Edit the PyPI packages you follow in your favorite editor.
+    /// Requires the EDITOR environment variable to be set.
+    #[structopt(name = "edit")]
+    Edit {
+        /// Only edit the package with this name (case-insensitive).
+        #[structopt(short = "n", long = "name")]
+        name: Option<String>,
+    },
+
+    /// Rename a PyPI package you follow, keeping everything else about it.
+    #[structopt(name = "rename")]
+    Rename {
+        /// The current name of the package (case-insensitive).
+        #[structopt(long = "from")]
+        from: String,
+
+        /// The new name for the package.
+        #[structopt(long = "to")]
+        to: String,
+    },
+
+    /// Stop hearing about a package until a date, after which everything
+    /// since it was last checked is reported.
+    #[structopt(name = "snooze")]
+    Snooze {
+        /// The name of the package (case-insensitive).
+        #[structopt(short = "n", long = "name")]
+        name: String,
+
+        /// When to hear about the package again, in the same formats as
+        /// --since-time, except that lengths of time like "2w" are from now.
+        #[structopt(
+            long = "until",
+            parse(try_from_str = "parse_arg_until"),
+            required_unless = "clear"
+        )]
+        until: Option<DateTime<Local>>,
+
+        /// Hear about the package again right away.
+        #[structopt(long = "clear", conflicts_with = "until")]
+        clear: bool,
+    },
+
+    /// Stop following a PyPI package.
+    #[structopt(name = "remove")]
+    Remove {
+        /// The name of the package (case-insensitive).
+        #[structopt(short = "n", long = "name")]
+        name: String,
+    },
+}
+
+#[derive(StructOpt)]
+pub enum WikipediaCommand {
+    /// Add a wiki article to sitch to hear about its edits.
+    #[structopt(name = "add")]
+    Add {
+        /// Your name for the article. Defaults to its title.
+        #[structopt(short = "n", long = "name")]
+        name: Option<String>,
+
+        /// The article's title, like "Rust (programming language)".
+        #[structopt(short = "t", long = "title")]
+        title: String,
+
+        /// The wiki's domain, like "de.wikipedia.org". Defaults to
+        /// English Wikipedia.
+        #[structopt(short = "w", long = "wiki")]
+        wiki: Option<String>,
+
+        /// Report all of a check's new edits as a single update.
+        #[structopt(long = "collapse")]
+        collapse: bool,
+
+        /// Leave out edits marked as minor.
+        #[structopt(long = "exclude-minor")]
+        exclude_minor: bool,
+
+        /// Leave out edits made by bots.
+        #[structopt(long = "exclude-bots")]
+        exclude_bots: bool,
+
+        #[structopt(flatten)]
+        preferences: SourcePreferences,
+    },
+
+    /// List the wiki articles you follow.
+    #[structopt(name = "list")]
+    List {
+        /// Also show the index of each article and when it was last checked.
+        #[structopt(short = "v", long = "verbose")]
+        verbose: bool,
+
+        /// Print the articles as JSON, just as they are stored in the config.
+        #[structopt(long = "json")]
+        json: bool,
+    },
+
+    /// Edit the wiki articles you follow in your favorite editor.
+    /// Requires the EDITOR environment variable to be set.
+    #[structopt(name = "edit")]
+    Edit {
+        /// Only edit the article with this name (case-insensitive).
+        #[structopt(short = "n", long = "name")]
+        name: Option<String>,
+    },
+
+    /// Rename a wiki article you follow, keeping everything else about it.
+    #[structopt(name = "rename")]
+    Rename {
+        /// The current name of the article (case-insensitive).
+        #[structopt(long = "from")]
+        from: String,
+
+        /// The new name for the article.
+        #[structopt(long = "to")]
+        to: String,
+    },
+
+    /// Stop hearing about a article until a date, after which everything
+    /// since it was last checked is reported.
+    #[structopt(name = "snooze")]
+    Snooze {
+        /// The name of the article (case-insensitive).
+        #[structopt(short = "n", long = "name")]
+        name: String,
+
+        /// When to hear about the article again, in the same formats as
+        /// --since-time, except that lengths of time like "2w" are from now.
+        #[structopt(
+            long = "until",
+            parse(try_from_str = "parse_arg_until"),
+            required_unless = "clear"
+        )]
+        until: Option<DateTime<Local>>,
+
+        /// Hear about the article again right away.
+        #[structopt(long = "clear", conflicts_with = "until")]
+        clear: bool,
+    },
+
+    /// Stop following a wiki article.
+    #[structopt(name = "remove")]
+    Remove {
+        /// The name of the article (case-insensitive).
+        #[structopt(short = "n", long = "name")]
+        name: String,
+    },
+}
+
+/// Attempts to parse the `until` argument of the `snooze` commands, which
+/// takes the same formats as `since_time` except that lengths of time are
+/// counted forward from now instead of back.
+pub fn parse_arg_until(date_str: &str) -> Result<DateTime<Local>, String> {
+    match parse_duration(date_str) {
+        Ok(duration) => Ok(Local::now() + duration),
+        Err(_err) => parse_arg_time(date_str),
+    }
+}
+
 /// Attempts to parse the `since_time` command-line argument.
 ///
 /// If the date/time can be interpretted by one of the below
@@ -315,6 +2810,7 @@ pub enum MangaCommand {
 /// - The literal strings "today" or "yesterday"
 /// - A date in the format "MM/DD/YYYY"
 /// - A date and time in the format "HH:MM (AM|PM) MM/DD/YYYY"
+/// - A length of time ago, like "3d" or "12h" (see `parse_duration`)
 fn parse_arg_time(date_str: &str) -> Result<DateTime<Local>, String> {
     if date_str == "today" {
         Ok(Local::today().and_hms(0, 0, 0))
@@ -332,6 +2828,8 @@ fn parse_arg_time(date_str: &str) -> Result<DateTime<Local>, String> {
             .from_local_datetime(&naive_datetime)
             .earliest()
             .expect("Couldn't find timezone"))
+    } else if let Ok(duration) = parse_duration(date_str) {
+        Ok(Local::now() - duration)
     } else {
         Err("Could not parse the provided time. \
              Make sure it is one of the allowed formats."
@@ -0,0 +1,209 @@
+//! The formats sitch's config can be written in, JSON or TOML.
+//!
+//! Either way, the config is handled as a JSON `Value` once it's loaded,
+//! so the format only matters when reading and writing the file.
+
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+
+/// The format of a config file.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ConfigFormat {
+    Json,
+    Toml,
+}
+
+impl ConfigFormat {
+    /// The names of the formats as given on the command line.
+    pub const VARIANTS: &'static [&'static str] = &["json", "toml"];
+
+    /// The name of the format, like "TOML".
+    pub fn name(self) -> &'static str {
+        match self {
+            ConfigFormat::Json => "JSON",
+            ConfigFormat::Toml => "TOML",
+        }
+    }
+
+    /// The file extension of the format, like "toml".
+    pub fn extension(self) -> &'static str {
+        match self {
+            ConfigFormat::Json => "json",
+            ConfigFormat::Toml => "toml",
+        }
+    }
+
+    /// Figures out the format of a config from its path's extension, or
+    /// for other extensions, from whether its contents look like JSON.
+    pub fn detect(path: &Path, contents: &str) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("json") => ConfigFormat::Json,
+            Some("toml") => ConfigFormat::Toml,
+            // an empty file is an empty config in either format
+            _ if contents.trim().is_empty() || contents.trim_start().starts_with('{') => {
+                ConfigFormat::Json
+            }
+            _ => ConfigFormat::Toml,
+        }
+    }
+
+    /// Parses text in this format, failing with where the text went wrong
+    /// and the line it went wrong on.
+    pub fn parse(self, contents: &str) -> Result<Value, String> {
+        match self {
+            // serde_json counts lines from 1, and toml counts both from 0
+            ConfigFormat::Json => serde_json::from_str(contents).map_err(|err| {
+                with_snippet(&err, contents, err.line().saturating_sub(1), err.column())
+            }),
+            ConfigFormat::Toml => {
+                toml::from_str(contents).map_err(|err: toml::de::Error| match err.line_col() {
+                    Some((line, column)) => with_snippet(&err, contents, line, column + 1),
+                    None => err.to_string(),
+                })
+            }
+        }
+    }
+
+    /// Writes a value out in this format, ending with a newline.
+    ///
+    /// TOML has no null, so nulls are left out of TOML entirely, and its
+    /// documents are tables, so anything else can't be written as TOML.
+    pub fn to_string<T: Serialize + ?Sized>(self, value: &T) -> Result<String, String> {
+        let contents = match self {
+            ConfigFormat::Json => serde_json::to_string_pretty(value).unwrap(),
+            ConfigFormat::Toml => {
+                toml::Value::try_from(without_nulls(&serde_json::to_value(value).unwrap()))
+                    .and_then(|value| toml::to_string_pretty(&value))
+                    .map_err(|err| format!("Couldn't write the config as TOML: {}", err))?
+            }
+        };
+
+        Ok(format!("{}\n", contents.trim_end()))
+    }
+}
+
+impl Default for ConfigFormat {
+    fn default() -> Self {
+        ConfigFormat::Json
+    }
+}
+
+impl FromStr for ConfigFormat {
+    type Err = String;
+
+    fn from_str(format: &str) -> Result<Self, Self::Err> {
+        match format {
+            "json" => Ok(ConfigFormat::Json),
+            "toml" => Ok(ConfigFormat::Toml),
+            _ => Err(format!(
+                "Unknown config format \"{}\", expected one of: {}",
+                format,
+                Self::VARIANTS.join(", ")
+            )),
+        }
+    }
+}
+
+/// How many characters of a line are shown on each side of where it
+/// went wrong, so that a config written on one long line still fits.
+const SNIPPET_CONTEXT: usize = 40;
+
+/// A parse error, followed by the line of the text it's on (counted from
+/// 0) with its number, and a caret under the column (counted from 1) that
+/// it's at. Errors past the last line are left as they are.
+fn with_snippet(err: &dyn fmt::Display, contents: &str, line: usize, column: usize) -> String {
+    let text = match contents.lines().nth(line) {
+        Some(text) => text,
+        None => return err.to_string(),
+    };
+    let chars: Vec<char> = text.chars().collect();
+    let at = column.saturating_sub(1).min(chars.len());
+    let start = at.saturating_sub(SNIPPET_CONTEXT);
+    let end = (at + SNIPPET_CONTEXT).min(chars.len());
+
+    let mut shown = String::new();
+    if start > 0 {
+        shown.push('…');
+    }
+    shown.extend(
+        chars[start..end]
+            .iter()
+            .map(|&c| if c == '\t' { ' ' } else { c }),
+    );
+    if end < chars.len() {
+        shown.push('…');
+    }
+
+    let gutter = format!("{} | ", line + 1);
+    let caret_at = gutter.chars().count() + at - start + usize::from(start > 0);
+    format!("{}\n{}{}\n{}^", err, gutter, shown, " ".repeat(caret_at))
+}
+
+/// A copy of a value with all of its nulls removed, including the ones in
+/// objects and arrays.
+fn without_nulls(value: &Value) -> Value {
+    match value {
+        Value::Object(object) => Value::Object(
+            object
+                .iter()
+                .filter(|(_key, value)| !value.is_null())
+                .map(|(key, value)| (key.clone(), without_nulls(value)))
+                .collect::<Map<String, Value>>(),
+        ),
+        Value::Array(array) => Value::Array(
+            array
+                .iter()
+                .filter(|value| !value.is_null())
+                .map(without_nulls)
+                .collect(),
+        ),
+        value => value.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn errors_point_at_where_they_are() {
+        let err = ConfigFormat::Json
+            .parse("{\n  \"rss\": [],\n  \"youtube\" []\n}")
+            .unwrap_err();
+
+        let lines = err.lines().collect::<Vec<_>>();
+        assert_eq!(lines[1], "3 |   \"youtube\" []");
+        assert_eq!(lines[2], "                ^");
+    }
+
+    #[test]
+    fn long_lines_are_shown_around_the_error() {
+        let contents = format!(
+            "{{\"name\": \"{}\" \"oops\", \"more\": \"{}\"}}",
+            "x".repeat(100),
+            "y".repeat(100)
+        );
+        let err = ConfigFormat::Json.parse(&contents).unwrap_err();
+
+        let lines = err.lines().collect::<Vec<_>>();
+        assert!(lines[1].starts_with("1 | …"), "{}", lines[1]);
+        assert!(lines[1].ends_with('…'), "{}", lines[1]);
+        assert!(lines[1].chars().count() <= "1 | ".len() + 2 * SNIPPET_CONTEXT + 2);
+        // the caret is under the stray string
+        let caret = lines[2].chars().count() - 1;
+        let from_caret = lines[1].chars().skip(caret).collect::<String>();
+        assert!(from_caret.starts_with("\"oops\""), "{}", from_caret);
+    }
+
+    #[test]
+    fn toml_errors_point_at_where_they_are_too() {
+        let err = ConfigFormat::Toml
+            .parse("title_width = 80\nrss = [1,,2]\n")
+            .unwrap_err();
+
+        assert!(err.contains("\n2 | rss = [1,,2]\n"), "{}", err);
+    }
+}
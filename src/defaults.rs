@@ -0,0 +1,94 @@
+//! Defaults for command-line flags, from the `defaults` block of the config.
+//!
+//! These only fill in flags that weren't given, so that a flag given on
+//! the command line, including the negative forms like `--no-quiet`,
+//! always wins (see `Args::apply_defaults`).
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::str::FromStr;
+
+/// When to color sitch's output.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ColorChoice {
+    /// Color output to a terminal, unless the environment says not to.
+    Auto,
+    /// Color output to a terminal, even if the environment says not to.
+    Always,
+    /// Never color output.
+    Never,
+}
+
+impl ColorChoice {
+    /// The names of the choices as given on the command line.
+    pub const VARIANTS: &'static [&'static str] = &["auto", "always", "never"];
+
+    /// Makes all colored output follow this choice.
+    pub fn apply(self) {
+        match self {
+            ColorChoice::Auto => colored::control::unset_override(),
+            ColorChoice::Always => colored::control::set_override(true),
+            ColorChoice::Never => colored::control::set_override(false),
+        }
+    }
+}
+
+impl Default for ColorChoice {
+    fn default() -> Self {
+        ColorChoice::Auto
+    }
+}
+
+impl FromStr for ColorChoice {
+    type Err = String;
+
+    fn from_str(choice: &str) -> Result<Self, Self::Err> {
+        match choice {
+            "auto" => Ok(ColorChoice::Auto),
+            "always" => Ok(ColorChoice::Always),
+            "never" => Ok(ColorChoice::Never),
+            _ => Err(format!(
+                "Unknown color choice \"{}\", expected one of: {}",
+                choice,
+                Self::VARIANTS.join(", ")
+            )),
+        }
+    }
+}
+
+/// The `defaults` block of the config, with a field for each flag that
+/// can be given a default.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, JsonSchema)]
+pub struct Defaults {
+    /// Like `--quiet`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quiet: Option<bool>,
+    /// Like `--notify`. This doesn't apply when another way of printing
+    /// updates, like `--porcelain`, is given.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notify: Option<bool>,
+    /// Like `--flat`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub flat: Option<bool>,
+    /// Like `--newest-first`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub newest_first: Option<bool>,
+    /// Like `--timings`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timings: Option<bool>,
+    /// Like `--color`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<ColorChoice>,
+    /// Like `--timeout`, in seconds.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<u64>,
+    /// Like `--format`. This doesn't apply when another way of printing
+    /// updates, like `--porcelain`, is given.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub format: Option<String>,
+    /// Any fields sitch doesn't know about, kept so they aren't lost.
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
@@ -0,0 +1,321 @@
+//! Writing the updates found by a check into a digest document.
+//!
+//! A digest groups updates with a heading per platform and a subheading
+//! per source, followed by a list of the source's updates. Each check
+//! renders as one dated stanza, so that digests can be appended to
+//! across runs.
+
+use crate::sources::SourceUpdate;
+use crate::util::expand_tilde;
+use chrono::{DateTime, Local};
+use std::fs::{read_to_string, write};
+use std::path::PathBuf;
+use std::str::FromStr;
+
+/// The format used when writing dates in digests.
+const DATETIME_FORMAT: &str = "%B %-e, %Y at %-l:%M %p";
+
+/// The styling for HTML digests, inlined so they're self-contained.
+const HTML_STYLE: &str = "body { font-family: sans-serif; max-width: 48em; \
+                          margin: 2em auto; padding: 0 1em; line-height: 1.5; } \
+                          h2 { border-bottom: 1px solid #ccc; } \
+                          .date { color: #666; }";
+
+/// The formats that digests can be written in.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DigestFormat {
+    Markdown,
+    Html,
+}
+
+impl DigestFormat {
+    /// The names of the formats as given on the command line.
+    pub const VARIANTS: &'static [&'static str] = &["markdown", "html"];
+}
+
+impl Default for DigestFormat {
+    fn default() -> Self {
+        DigestFormat::Markdown
+    }
+}
+
+impl FromStr for DigestFormat {
+    type Err = String;
+
+    fn from_str(format: &str) -> Result<Self, Self::Err> {
+        match format {
+            "markdown" => Ok(DigestFormat::Markdown),
+            "html" => Ok(DigestFormat::Html),
+            _ => Err(format!(
+                "Unknown digest format \"{}\", expected one of: {}",
+                format,
+                Self::VARIANTS.join(", ")
+            )),
+        }
+    }
+}
+
+/// All of the updates found by a single check, grouped by platform
+/// and then by source.
+#[derive(Clone)]
+pub struct Digest {
+    /// When the check looked for updates since, if ever.
+    pub since: Option<DateTime<Local>>,
+    /// When the check was run.
+    pub checked_at: DateTime<Local>,
+    /// The platforms with updates, each with its sources and their updates.
+    pub platforms: Vec<(&'static str, Vec<(String, Vec<SourceUpdate>)>)>,
+    /// Whether none of the sources could be reached because the network
+    /// was down (see `Failure::all_offline`).
+    pub offline: bool,
+}
+
+impl Digest {
+    /// Groups the updates found for each source by platform, with the
+    /// platforms in the given order and their sources sorted by name.
+    /// Sources without any updates are left out.
+    pub fn new(
+        since: Option<DateTime<Local>>,
+        platform_order: &[&'static str],
+        mut found: Vec<(&'static str, String, Vec<SourceUpdate>)>,
+    ) -> Self {
+        found.retain(|(_type_name, _source_name, updates)| !updates.is_empty());
+        found.sort_by_key(|(type_name, source_name, _updates)| {
+            (
+                platform_order.iter().position(|name| name == type_name),
+                source_name.clone(),
+            )
+        });
+
+        let mut platforms: Vec<(&'static str, Vec<(String, Vec<SourceUpdate>)>)> = Vec::new();
+        for (type_name, source_name, updates) in found {
+            match platforms.last_mut() {
+                Some((name, sources)) if *name == type_name => sources.push((source_name, updates)),
+                _ => platforms.push((type_name, vec![(source_name, updates)])),
+            }
+        }
+
+        Digest {
+            since,
+            checked_at: Local::now(),
+            platforms,
+            offline: false,
+        }
+    }
+
+    /// Whether no updates were found.
+    pub fn is_empty(&self) -> bool {
+        self.platforms.is_empty()
+    }
+
+    /// The total number of updates found.
+    pub fn num_updates(&self) -> usize {
+        self.platforms
+            .iter()
+            .flat_map(|(_type_name, sources)| sources.iter())
+            .map(|(_source_name, updates)| updates.len())
+            .sum()
+    }
+
+    /// Sums up the digest in a line, like "7 updates (YouTube 3, RSS 4)",
+    /// for status bars.
+    pub fn summary(&self) -> String {
+        let num_updates = self.num_updates();
+        let total = format!(
+            "{} update{}",
+            num_updates,
+            if num_updates == 1 { "" } else { "s" }
+        );
+        if self.is_empty() {
+            return total;
+        }
+
+        let platforms = self
+            .platforms
+            .iter()
+            .map(|(type_name, sources)| {
+                let count: usize = sources
+                    .iter()
+                    .map(|(_source_name, updates)| updates.len())
+                    .sum();
+                format!("{} {}", type_name, count)
+            })
+            .collect::<Vec<String>>();
+        format!("{} ({})", total, platforms.join(", "))
+    }
+
+    /// Renders the digest as a complete document in the given format.
+    pub fn render(&self, format: DigestFormat) -> String {
+        match format {
+            DigestFormat::Markdown => self.render_markdown(),
+            DigestFormat::Html => format!(
+                "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n\
+                 <title>Sitch updates</title>\n<style>{}</style>\n</head>\n\
+                 <body>\n{}</body>\n</html>\n",
+                HTML_STYLE,
+                self.render_html()
+            ),
+        }
+    }
+
+    /// Writes the digest to a file, either replacing it or adding this
+    /// check's stanza to the end of it.
+    ///
+    /// If no updates were found, the file is left alone unless
+    /// `write_empty` is set, in which case a "no updates" stanza is
+    /// written instead.
+    pub fn write(
+        &self,
+        path: PathBuf,
+        format: DigestFormat,
+        append: bool,
+        write_empty: bool,
+    ) -> Result<(), String> {
+        if self.is_empty() && !write_empty {
+            return Ok(());
+        }
+
+        let path = expand_tilde(path);
+        let existing = if append {
+            read_to_string(&path).ok()
+        } else {
+            None
+        };
+        let contents = match (existing, format) {
+            (Some(existing), DigestFormat::Markdown) => {
+                format!("{}\n\n{}", existing.trim_end(), self.render_markdown())
+            }
+            // new stanzas go at the end of the body to keep the document whole
+            (Some(existing), DigestFormat::Html) => match existing.rfind("</body>") {
+                Some(body_end) => format!(
+                    "{}{}{}",
+                    &existing[..body_end],
+                    self.render_html(),
+                    &existing[body_end..]
+                ),
+                None => self.render(format),
+            },
+            (None, format) => self.render(format),
+        };
+
+        write(&path, contents)
+            .map_err(|err| format!("Couldn't write digest to {}: {}", path.display(), err))
+    }
+
+    /// A description of the time period that the check covered.
+    fn period(&self) -> String {
+        match self.since {
+            Some(since) => format!(
+                "{} to {}",
+                since.format(DATETIME_FORMAT),
+                self.checked_at.format(DATETIME_FORMAT)
+            ),
+            None => format!("Up to {}", self.checked_at.format(DATETIME_FORMAT)),
+        }
+    }
+
+    fn render_markdown(&self) -> String {
+        let mut markdown = format!(
+            "## Sitch updates from {}\n\n",
+            self.checked_at.format(DATETIME_FORMAT)
+        );
+        markdown.push_str(&format!("_{}_\n\n", self.period()));
+        if self.is_empty() {
+            markdown.push_str("No updates.\n");
+        }
+
+        for (type_name, sources) in &self.platforms {
+            markdown.push_str(&format!("### {}\n\n", type_name));
+            for (source_name, updates) in sources {
+                markdown.push_str(&format!("#### {}\n\n", escape_markdown(source_name)));
+                for update in updates {
+                    markdown.push_str(&format!(
+                        "- [{}](<{}>) — {}{}{}\n",
+                        escape_markdown(&update.title),
+                        update.link,
+                        update.published_date.format(DATETIME_FORMAT),
+                        update
+                            .secondary_link
+                            .as_ref()
+                            .map(|link| format!(" ([also](<{}>))", link))
+                            .unwrap_or_default(),
+                        also_in(&update.also_in, escape_markdown)
+                    ));
+                }
+                markdown.push('\n');
+            }
+        }
+
+        markdown
+    }
+
+    fn render_html(&self) -> String {
+        let mut html = format!(
+            "<h1>Sitch updates from {}</h1>\n<p><em>{}</em></p>\n",
+            self.checked_at.format(DATETIME_FORMAT),
+            self.period()
+        );
+        if self.is_empty() {
+            html.push_str("<p>No updates.</p>\n");
+        }
+
+        for (type_name, sources) in &self.platforms {
+            html.push_str(&format!("<h2>{}</h2>\n", escape_html(type_name)));
+            for (source_name, updates) in sources {
+                html.push_str(&format!("<h3>{}</h3>\n<ul>\n", escape_html(source_name)));
+                for update in updates {
+                    html.push_str(&format!(
+                        "<li><a href=\"{}\">{}</a> <span class=\"date\">— {}</span>{}{}</li>\n",
+                        escape_html(&update.link),
+                        escape_html(&update.title),
+                        update.published_date.format(DATETIME_FORMAT),
+                        update
+                            .secondary_link
+                            .as_ref()
+                            .map(|link| format!(" (<a href=\"{}\">also</a>)", escape_html(link)))
+                            .unwrap_or_default(),
+                        also_in(&update.also_in, escape_html)
+                    ));
+                }
+                html.push_str("</ul>\n");
+            }
+        }
+
+        html
+    }
+}
+
+/// Lists the other sources that had an update, escaped for the digest's
+/// format, or nothing if none did.
+fn also_in(sources: &[String], escape: fn(&str) -> String) -> String {
+    if sources.is_empty() {
+        String::new()
+    } else {
+        let sources = sources
+            .iter()
+            .map(|source| escape(source))
+            .collect::<Vec<String>>();
+        format!(" (also in {})", sources.join(", "))
+    }
+}
+
+/// Escapes the characters in text that Markdown would treat as formatting.
+fn escape_markdown(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if "\\`*_[]<>#".contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+
+    escaped
+}
+
+/// Escapes text for use in HTML, including in attribute values.
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,150 @@
+//! Sending the digest of found updates by email over SMTP.
+
+use crate::digest::{Digest, DigestFormat};
+use lettre::smtp::authentication::Credentials;
+use lettre::smtp::error::Error as SmtpError;
+use lettre::{ClientSecurity, ClientTlsParameters, SmtpClient, Transport};
+use lettre_email::EmailBuilder;
+use native_tls::TlsConnector;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::process::Command;
+
+/// The port for SMTP over an implicit TLS connection. Other ports
+/// are expected to upgrade to TLS with `STARTTLS`.
+const SMTPS_PORT: u16 = 465;
+
+/// The SMTP reply code for rejected credentials.
+const AUTH_FAILED_CODE: &str = "535";
+
+/// How to send the digest by email, from the `email` block of the config.
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+pub struct EmailConfig {
+    /// The SMTP server to send through, like "smtp.gmail.com".
+    pub host: String,
+    #[serde(default = "EmailConfig::default_port")]
+    pub port: u16,
+    /// The username to log in with, if the server requires logging in.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub password: Option<String>,
+    /// A shell command that prints the password, so it doesn't
+    /// have to be stored in the config.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub password_cmd: Option<String>,
+    pub from: String,
+    pub to: Vec<String>,
+    /// Any fields sitch doesn't know about, kept so they aren't lost.
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl EmailConfig {
+    fn default_port() -> u16 {
+        587
+    }
+
+    /// Sends the digest to every recipient, as HTML with a Markdown
+    /// fallback for plain text mail clients.
+    pub fn send(&self, digest: &Digest) -> Result<(), String> {
+        if self.to.is_empty() {
+            return Err("No recipients are listed under email.to in your config.".to_owned());
+        }
+
+        let subject = match digest.num_updates() {
+            0 => "Sitch: no updates".to_owned(),
+            1 => "Sitch: 1 update".to_owned(),
+            num_updates => format!("Sitch: {} updates", num_updates),
+        };
+        let mut builder = EmailBuilder::new()
+            .from(self.from.as_str())
+            .subject(subject)
+            .alternative(
+                digest.render(DigestFormat::Html),
+                digest.render(DigestFormat::Markdown),
+            );
+        for recipient in &self.to {
+            builder = builder.to(recipient.as_str());
+        }
+        let email = builder
+            .build()
+            .map_err(|err| format!("Couldn't build the update email: {}", err))?;
+
+        let tls_connector = TlsConnector::new()
+            .map_err(|err| format!("Couldn't set up TLS for sending email: {}", err))?;
+        let tls_parameters = ClientTlsParameters::new(self.host.clone(), tls_connector);
+        let security = if self.port == SMTPS_PORT {
+            ClientSecurity::Wrapper(tls_parameters)
+        } else {
+            ClientSecurity::Required(tls_parameters)
+        };
+        let mut client = SmtpClient::new((self.host.as_str(), self.port), security)
+            .map_err(|err| self.describe_error(err))?;
+        if let Some(username) = &self.username {
+            client = client.credentials(Credentials::new(username.clone(), self.password()?));
+        }
+
+        client
+            .transport()
+            .send(email.into())
+            .map(|_response| ())
+            .map_err(|err| self.describe_error(err))
+    }
+
+    /// The password to log in with, either from the config or by
+    /// running `password_cmd` and taking the first line it prints.
+    fn password(&self) -> Result<String, String> {
+        if let Some(password) = &self.password {
+            return Ok(password.clone());
+        }
+        let password_cmd = self
+            .password_cmd
+            .as_ref()
+            .ok_or("Set either email.password or email.password_cmd in your config.")?;
+
+        let output = if cfg!(windows) {
+            Command::new("cmd").args(&["/C", password_cmd]).output()
+        } else {
+            Command::new("sh").args(&["-c", password_cmd]).output()
+        }
+        .map_err(|err| format!("Couldn't run email.password_cmd: {}", err))?;
+        if !output.status.success() {
+            return Err(format!(
+                "email.password_cmd failed ({}): {}",
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+
+        Ok(String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .next()
+            .unwrap_or("")
+            .to_owned())
+    }
+
+    /// Explains why sending the email failed, singling out the common
+    /// causes of rejected credentials and unreachable servers.
+    fn describe_error(&self, err: SmtpError) -> String {
+        let server = format!("{}:{}", self.host, self.port);
+        match err {
+            SmtpError::Permanent(ref response) if response.code.to_string() == AUTH_FAILED_CODE => {
+                format!(
+                    "The email server {} rejected the username or password.",
+                    server
+                )
+            }
+            SmtpError::Resolution => format!("Couldn't find the email server {}.", server),
+            SmtpError::Io(err) => {
+                format!("Couldn't connect to the email server {}: {}", server, err)
+            }
+            SmtpError::Tls(err) => format!(
+                "Couldn't make a secure connection to the email server {}: {}",
+                server, err
+            ),
+            err => format!("Couldn't send email through {}: {}", server, err),
+        }
+    }
+}
@@ -0,0 +1,229 @@
+//! Exporting updates as CSV, for spreadsheets and the like.
+//!
+//! The same writer is used for the updates found by a check
+//! (`--output csv`) and for the updates in the history
+//! (`sitch history export`), so both have the same columns.
+
+use crate::digest::Digest;
+use crate::state::HistoryEntry;
+use crate::stats::SourceStats;
+use chrono::{DateTime, Local};
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+/// The columns of exported CSV, in order.
+const CSV_HEADER: &[&str] = &[
+    "platform",
+    "source",
+    "title",
+    "link",
+    "published_date",
+    "discovered_at",
+];
+
+/// The formats that the updates found by a check can be printed in.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum OutputFormat {
+    /// Messages meant to be read, as configured by the other options.
+    Text,
+    /// A row of CSV per update, and nothing else.
+    Csv,
+}
+
+impl OutputFormat {
+    /// The names of the formats as given on the command line.
+    pub const VARIANTS: &'static [&'static str] = &["text", "csv"];
+}
+
+impl Default for OutputFormat {
+    fn default() -> Self {
+        OutputFormat::Text
+    }
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(format: &str) -> Result<Self, Self::Err> {
+        match format {
+            "text" => Ok(OutputFormat::Text),
+            "csv" => Ok(OutputFormat::Csv),
+            _ => Err(format!(
+                "Unknown output format \"{}\", expected one of: {}",
+                format,
+                Self::VARIANTS.join(", ")
+            )),
+        }
+    }
+}
+
+/// An update as it's exported, along with where it's from and when
+/// sitch found it.
+#[derive(Clone, Debug)]
+pub struct ExportedUpdate {
+    /// The name of the platform the update is from, like "RSS".
+    pub platform: String,
+    /// The name of the source the update is from.
+    pub source: String,
+    /// The title of the update.
+    pub title: String,
+    /// A link to the update.
+    pub link: String,
+    /// When the update was published.
+    pub published_date: DateTime<Local>,
+    /// When sitch found the update.
+    pub discovered_at: DateTime<Local>,
+}
+
+/// The updates found by a check, in the digest's order.
+pub fn from_digest(digest: &Digest) -> Vec<ExportedUpdate> {
+    digest
+        .platforms
+        .iter()
+        .flat_map(|(type_name, sources)| {
+            sources.iter().flat_map(move |(source_name, updates)| {
+                updates.iter().map(move |update| ExportedUpdate {
+                    platform: type_name.to_string(),
+                    source: source_name.clone(),
+                    title: update.title.clone(),
+                    link: update.link.clone(),
+                    published_date: update.published_date.clone(),
+                    discovered_at: digest.checked_at.clone(),
+                })
+            })
+        })
+        .collect()
+}
+
+/// The updates in the history of each of the given sources, oldest
+/// first. Sources that are no longer configured are left out.
+pub fn from_history(
+    sources: &[SourceStats],
+    history: &BTreeMap<String, Vec<HistoryEntry>>,
+) -> Vec<ExportedUpdate> {
+    let mut updates = sources
+        .iter()
+        .flat_map(|source| {
+            history
+                .get(&source.key)
+                .into_iter()
+                .flatten()
+                .map(move |entry| ExportedUpdate {
+                    platform: source.platform.to_owned(),
+                    source: source.name.clone(),
+                    title: entry.title.clone(),
+                    link: entry.link.clone(),
+                    published_date: entry.published_date.clone(),
+                    discovered_at: entry.discovered_at.clone(),
+                })
+        })
+        .collect::<Vec<ExportedUpdate>>();
+    updates.sort_by_key(|update| update.published_date);
+
+    updates
+}
+
+/// Writes updates as CSV with a header row, following RFC 4180: rows end
+/// with CRLF, and fields are quoted as needed.
+pub fn to_csv(updates: &[ExportedUpdate]) -> String {
+    let mut csv = csv_row(CSV_HEADER);
+    for update in updates {
+        let published_date = update.published_date.to_rfc3339();
+        let discovered_at = update.discovered_at.to_rfc3339();
+        csv += &csv_row(&[
+            &update.platform,
+            &update.source,
+            &update.title,
+            &update.link,
+            &published_date,
+            &discovered_at,
+        ]);
+    }
+
+    csv
+}
+
+/// Writes a single row of CSV, ending with CRLF.
+fn csv_row(fields: &[&str]) -> String {
+    let fields = fields
+        .iter()
+        .map(|field| csv_field(field))
+        .collect::<Vec<Cow<'_, str>>>();
+
+    format!("{}\r\n", fields.join(","))
+}
+
+/// Quotes a field if it has a comma, quote, or line break in it, or
+/// spaces at either end that spreadsheets would trim, doubling any quotes.
+fn csv_field(field: &str) -> Cow<'_, str> {
+    let needs_quotes =
+        field.contains(|c| c == ',' || c == '"' || c == '\n' || c == '\r') || field.trim() != field;
+    if needs_quotes {
+        Cow::Owned(format!("\"{}\"", field.replace('"', "\"\"")))
+    } else {
+        Cow::Borrowed(field)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn update(title: &str) -> ExportedUpdate {
+        ExportedUpdate {
+            platform: "RSS".to_owned(),
+            source: "Blog".to_owned(),
+            title: title.to_owned(),
+            link: "https://example.com/post".to_owned(),
+            published_date: Local.ymd(2024, 3, 1).and_hms(9, 0, 0),
+            discovered_at: Local.ymd(2024, 3, 1).and_hms(10, 0, 0),
+        }
+    }
+
+    #[test]
+    fn plain_fields_arent_quoted() {
+        assert_eq!(csv_row(&["RSS", "Blog", "A post"]), "RSS,Blog,A post\r\n");
+    }
+
+    #[test]
+    fn fields_with_commas_are_quoted() {
+        assert_eq!(csv_field("Cats, dogs"), "\"Cats, dogs\"");
+    }
+
+    #[test]
+    fn quotes_are_doubled() {
+        assert_eq!(csv_field("The \"best\" post"), "\"The \"\"best\"\" post\"");
+    }
+
+    #[test]
+    fn line_breaks_are_quoted() {
+        assert_eq!(csv_field("Part one\nPart two"), "\"Part one\nPart two\"");
+        assert_eq!(
+            csv_field("Part one\r\nPart two"),
+            "\"Part one\r\nPart two\""
+        );
+    }
+
+    #[test]
+    fn padded_fields_are_quoted() {
+        assert_eq!(csv_field(" Padded "), "\" Padded \"");
+    }
+
+    #[test]
+    fn updates_get_a_row_each_after_the_header() {
+        let csv = to_csv(&[update("Hello, \"world\"\n")]);
+        let published_date = Local.ymd(2024, 3, 1).and_hms(9, 0, 0).to_rfc3339();
+        let discovered_at = Local.ymd(2024, 3, 1).and_hms(10, 0, 0).to_rfc3339();
+
+        assert_eq!(
+            csv,
+            format!(
+                "platform,source,title,link,published_date,discovered_at\r\n\
+                 RSS,Blog,\"Hello, \"\"world\"\"\n\",https://example.com/post,{},{}\r\n",
+                published_date, discovered_at
+            )
+        );
+    }
+}
@@ -0,0 +1,149 @@
+//! Writing iCalendar (`.ics`) files, for calendar apps to subscribe to.
+//!
+//! Only what sitch needs is supported: a single calendar of events with
+//! times in UTC, written following RFC 5545's escaping and line folding.
+
+use chrono::{DateTime, Utc};
+
+/// The format of UTC times in iCalendar, like "20190406T160000Z".
+const TIME_FORMAT: &str = "%Y%m%dT%H%M%SZ";
+
+/// The longest a line can be in octets, not counting its line break,
+/// before it's folded onto the next.
+const MAX_LINE_LENGTH: usize = 75;
+
+/// An event on a calendar.
+#[derive(Clone, Debug)]
+pub struct CalendarEvent {
+    /// What identifies the event across versions of the calendar, so that
+    /// calendar apps update the event instead of adding it again.
+    pub uid: String,
+    /// The title of the event.
+    pub summary: String,
+    /// A link to more about the event, if there is one.
+    pub url: Option<String>,
+    /// When the event starts.
+    pub start: DateTime<Utc>,
+    /// When the event ends.
+    pub end: DateTime<Utc>,
+}
+
+/// Renders a calendar with the given name and events.
+pub fn render_calendar(name: &str, events: &[CalendarEvent]) -> String {
+    let stamp = Utc::now().format(TIME_FORMAT).to_string();
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_owned(),
+        "VERSION:2.0".to_owned(),
+        "PRODID:-//sitch//sitch//EN".to_owned(),
+        "CALSCALE:GREGORIAN".to_owned(),
+        format!("X-WR-CALNAME:{}", escape_text(name)),
+    ];
+    for event in events {
+        lines.push("BEGIN:VEVENT".to_owned());
+        lines.push(format!("UID:{}", escape_text(&event.uid)));
+        lines.push(format!("DTSTAMP:{}", stamp));
+        lines.push(format!("DTSTART:{}", event.start.format(TIME_FORMAT)));
+        lines.push(format!("DTEND:{}", event.end.format(TIME_FORMAT)));
+        lines.push(format!("SUMMARY:{}", escape_text(&event.summary)));
+        if let Some(url) = &event.url {
+            // URLs are written as they are, since they're not text
+            lines.push(format!("URL:{}", url));
+        }
+        lines.push("END:VEVENT".to_owned());
+    }
+    lines.push("END:VCALENDAR".to_owned());
+
+    lines.iter().map(|line| fold_line(line)).collect()
+}
+
+/// Escapes text for a property value: backslashes, semicolons, and
+/// commas get a backslash, and line breaks become "\n".
+fn escape_text(text: &str) -> String {
+    text.replace("\r\n", "\n")
+        .chars()
+        .fold(String::new(), |mut escaped, c| {
+            match c {
+                '\\' => escaped.push_str("\\\\"),
+                ';' => escaped.push_str("\\;"),
+                ',' => escaped.push_str("\\,"),
+                '\n' | '\r' => escaped.push_str("\\n"),
+                c => escaped.push(c),
+            }
+            escaped
+        })
+}
+
+/// Ends a line with CRLF, first folding it onto more lines that start
+/// with a space if it's too long. Lines are only split between
+/// characters, so multi-byte characters stay whole.
+fn fold_line(line: &str) -> String {
+    let mut folded = String::new();
+    let mut length = 0;
+    for c in line.chars() {
+        if length + c.len_utf8() > MAX_LINE_LENGTH {
+            folded.push_str("\r\n ");
+            // the space starting the new line counts towards its length
+            length = 1;
+        }
+        folded.push(c);
+        length += c.len_utf8();
+    }
+
+    folded + "\r\n"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn text_is_escaped() {
+        assert_eq!(
+            escape_text("Show; Part 1, \\ok\r\nnext"),
+            "Show\\; Part 1\\, \\\\ok\\nnext"
+        );
+    }
+
+    #[test]
+    fn long_lines_are_folded_between_characters() {
+        let line = format!("SUMMARY:{}", "é".repeat(40));
+        let folded = fold_line(&line);
+
+        assert!(folded.ends_with("\r\n"));
+        let lines = folded
+            .trim_end_matches("\r\n")
+            .split("\r\n")
+            .collect::<Vec<_>>();
+        assert_eq!(lines.len(), 2);
+        assert!(lines.iter().all(|line| line.len() <= MAX_LINE_LENGTH));
+        assert!(lines[1].starts_with(' '));
+        assert_eq!(lines.concat().replacen(' ', "", 1), line);
+        assert_eq!(fold_line("END:VEVENT"), "END:VEVENT\r\n");
+    }
+
+    #[test]
+    fn events_are_written_in_utc() {
+        let start = Utc.ymd(2024, 3, 1).and_hms(16, 0, 0);
+        let event = CalendarEvent {
+            uid: "anime-1-episode-2@sitch".to_owned(),
+            summary: "Show - Episode 2".to_owned(),
+            url: Some("https://myanimelist.net/anime/1".to_owned()),
+            start,
+            end: start + chrono::Duration::minutes(24),
+        };
+        let calendar = render_calendar("sitch, anime", &[event]);
+
+        assert!(calendar.starts_with("BEGIN:VCALENDAR\r\n"));
+        assert!(calendar.ends_with("END:VCALENDAR\r\n"));
+        for line in &[
+            "X-WR-CALNAME:sitch\\, anime\r\n",
+            "UID:anime-1-episode-2@sitch\r\n",
+            "DTSTART:20240301T160000Z\r\n",
+            "DTEND:20240301T162400Z\r\n",
+            "URL:https://myanimelist.net/anime/1\r\n",
+        ] {
+            assert!(calendar.contains(line), "{}", line);
+        }
+    }
+}
@@ -0,0 +1,129 @@
+//! Updates that are never reported, like a pinned announcement that keeps
+//! coming back with a refreshed date, listed in the config's `ignore`
+//! list with `sitch ignore`.
+//!
+//! Each entry is either a link, a glob like `example.com/pinned/*` where
+//! `*` stands for any run of characters and `?` for any one, or a regex
+//! after "regex:". Links are compared once normalized (see
+//! `links::normalize`), and patterns are tried on both the link without
+//! its tracking (see `links::clean`) and its normalized form, so links
+//! that only differ in tracking parameters or scheme are ignored alike.
+
+use crate::links;
+use regex::Regex;
+
+/// What starts an entry that's a regex instead of a link or glob.
+const REGEX_PREFIX: &str = "regex:";
+
+/// The parsed entries of an ignore list.
+#[derive(Clone, Debug, Default)]
+pub struct IgnoreList {
+    entries: Vec<Entry>,
+}
+
+/// One entry of an ignore list.
+#[derive(Clone, Debug)]
+enum Entry {
+    /// A single link, normalized.
+    Link(String),
+    /// A pattern with `*` and `?` in it.
+    Glob(String),
+    /// A pattern given after "regex:".
+    Regex(Regex),
+}
+
+impl IgnoreList {
+    /// Parses the entries of an ignore list, failing on the first that
+    /// isn't valid.
+    pub fn parse(entries: &[String]) -> Result<Self, String> {
+        let entries = entries
+            .iter()
+            .map(|entry| Entry::parse(entry))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(IgnoreList { entries })
+    }
+
+    /// Checks that an entry can be added to an ignore list, returning it
+    /// as it should be saved.
+    pub fn check_entry(entry: &str) -> Result<String, String> {
+        let entry = entry.trim();
+        Entry::parse(entry)?;
+
+        Ok(entry.to_owned())
+    }
+
+    /// Whether an update with this link should never be reported.
+    pub fn matches(&self, link: &str) -> bool {
+        if self.entries.is_empty() {
+            return false;
+        }
+
+        let cleaned = links::clean(link);
+        let normalized = links::normalize(link);
+        self.entries.iter().any(|entry| match entry {
+            Entry::Link(ignored) => *ignored == normalized,
+            Entry::Glob(glob) => glob_matches(glob, &cleaned) || glob_matches(glob, &normalized),
+            Entry::Regex(regex) => regex.is_match(&cleaned) || regex.is_match(&normalized),
+        })
+    }
+}
+
+impl Entry {
+    /// Parses an entry, telling what kind it is by its syntax.
+    fn parse(entry: &str) -> Result<Self, String> {
+        let entry = entry.trim();
+        if entry.is_empty() {
+            return Err("An ignored link or pattern can't be empty.".to_owned());
+        }
+
+        if entry.starts_with(REGEX_PREFIX) {
+            let pattern = &entry[REGEX_PREFIX.len()..];
+            Regex::new(pattern)
+                .map(Entry::Regex)
+                .map_err(|err| format!("\"{}\" isn't a valid regex: {}", pattern, err))
+        } else if entry.contains('*') || entry.contains('?') {
+            if entry.chars().all(|c| c == '*' || c == '?') {
+                return Err(format!("\"{}\" would ignore every update.", entry));
+            }
+            Ok(Entry::Glob(entry.to_owned()))
+        } else {
+            Ok(Entry::Link(links::normalize(entry)))
+        }
+    }
+}
+
+/// Whether all of `text` matches a glob, where `*` matches any run of
+/// characters, including none, and `?` matches any one.
+fn glob_matches(glob: &str, text: &str) -> bool {
+    let glob = glob.chars().collect::<Vec<_>>();
+    let text = text.chars().collect::<Vec<_>>();
+    let (mut g, mut t) = (0, 0);
+    // where the last `*` was, and where in the text it started matching,
+    // to go back to when the rest of the glob doesn't match
+    let mut star: Option<(usize, usize)> = None;
+
+    while t < text.len() {
+        match glob.get(g) {
+            Some('*') => {
+                star = Some((g, t));
+                g += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                g += 1;
+                t += 1;
+            }
+            _ => match star {
+                // let the last `*` take one more character
+                Some((star_g, star_t)) => {
+                    star = Some((star_g, star_t + 1));
+                    g = star_g + 1;
+                    t = star_t + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+
+    glob[g..].iter().all(|&c| c == '*')
+}
@@ -0,0 +1,199 @@
+//! Cleaning up the links updates are reported with, and telling when two
+//! links point to the same page, like a post that's in both a site's own
+//! feed and an aggregator's, which link to it with different tracking
+//! parameters or schemes.
+//!
+//! Tracking parameters are known by name, so that parameters that pick
+//! what a page shows, like YouTube's `v` and `t`, are never touched.
+
+use reqwest::Url;
+
+/// Query parameters that only track where a visit came from, and never
+/// change what a page shows.
+const TRACKING_PARAMS: &[&str] = &[
+    "fbclid",
+    "gclid",
+    "gclsrc",
+    "dclid",
+    "msclkid",
+    "yclid",
+    "twclid",
+    "igshid",
+    "mc_cid",
+    "mc_eid",
+    "mkt_tok",
+    "_hsenc",
+    "_hsmi",
+    "vero_id",
+    "oly_anon_id",
+    "oly_enc_id",
+    "ref",
+    "ref_src",
+    "ref_url",
+    "xtor",
+    "echobox",
+    "_ga",
+    "_gl",
+    "ncid",
+    "cmpid",
+];
+
+/// The starts of the names of families of tracking parameters, like
+/// `utm_source` and `utm_medium`.
+const TRACKING_PREFIXES: &[&str] = &["utm_"];
+
+/// Whether a query parameter, by its name, only tracks where a visit came
+/// from.
+fn is_tracking_param(name: &str) -> bool {
+    let name = name.to_lowercase();
+    TRACKING_PARAMS.contains(&name.as_str())
+        || TRACKING_PREFIXES
+            .iter()
+            .any(|prefix| name.starts_with(prefix))
+}
+
+/// Whether a link's fragment only tracks where a visit came from, like
+/// `#xtor=RSS-1`, instead of pointing to a part of the page.
+fn is_tracking_fragment(fragment: &str) -> bool {
+    !fragment.is_empty()
+        && fragment.split('&').all(|param| {
+            let mut parts = param.splitn(2, '=');
+            match (parts.next(), parts.next()) {
+                (Some(name), Some(_value)) => is_tracking_param(name),
+                _ => false,
+            }
+        })
+}
+
+/// A query string without its tracking parameters, which is empty if it
+/// only had those. The rest are kept in order, exactly as they were.
+fn without_tracking(query: &str) -> String {
+    query
+        .split('&')
+        .filter(|param| {
+            let name = param.split('=').next().unwrap_or("");
+            !name.is_empty() && !is_tracking_param(name)
+        })
+        .collect::<Vec<&str>>()
+        .join("&")
+}
+
+/// Takes the tracking parameters out of a link, along with its fragment
+/// if that's only tracking too.
+///
+/// Links without any tracking are returned exactly as they were, and so
+/// are links that aren't to a web page, like magnet links.
+pub fn clean(link: &str) -> String {
+    let mut url = match Url::parse(link.trim()) {
+        Ok(url) if url.scheme() == "http" || url.scheme() == "https" => url,
+        _ => return link.to_owned(),
+    };
+
+    let mut changed = false;
+    if let Some(query) = url.query().map(str::to_owned) {
+        let kept = without_tracking(&query);
+        if kept != query {
+            url.set_query(if kept.is_empty() { None } else { Some(&kept) });
+            changed = true;
+        }
+    }
+    if url.fragment().is_some_and(is_tracking_fragment) {
+        url.set_fragment(None);
+        changed = true;
+    }
+
+    if changed {
+        url.to_string()
+    } else {
+        link.to_owned()
+    }
+}
+
+/// Reduces a link to the parts that decide which page it's for, so that
+/// links to the same page are equal: the scheme, a leading "www.", a
+/// trailing slash, and any tracking (see `clean`) are left out.
+///
+/// Every other query parameter is kept as is, since many of them (like
+/// YouTube's `v`) are the page. Links that aren't to a web page, like
+/// magnet links, are only trimmed.
+pub fn normalize(link: &str) -> String {
+    let link = link.trim();
+    let url = match Url::parse(link) {
+        Ok(url) if url.scheme() == "http" || url.scheme() == "https" => url,
+        _ => return link.to_owned(),
+    };
+
+    let host = url.host_str().unwrap_or("").to_lowercase();
+    let host = host.trim_start_matches("www.");
+    let port = url
+        .port()
+        .map(|port| format!(":{}", port))
+        .unwrap_or_default();
+    let path = url.path().trim_end_matches('/');
+    let query = without_tracking(url.query().unwrap_or(""));
+    let fragment = url
+        .fragment()
+        .filter(|fragment| !is_tracking_fragment(fragment))
+        .unwrap_or("");
+
+    let mut normalized = format!("{}{}{}", host, port, path);
+    if !query.is_empty() {
+        normalized.push('?');
+        normalized.push_str(&query);
+    }
+    if !fragment.is_empty() {
+        normalized.push('#');
+        normalized.push_str(fragment);
+    }
+
+    normalized
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tracking_parameters_are_taken_out() {
+        assert_eq!(
+            clean("https://example.com/post?id=5&utm_source=rss&UTM_Medium=feed&fbclid=abc"),
+            "https://example.com/post?id=5"
+        );
+        assert_eq!(
+            clean("https://example.com/post?utm_source=rss#xtor=RSS-1"),
+            "https://example.com/post"
+        );
+        assert_eq!(
+            clean("https://example.com/post?utm_source=rss#comments"),
+            "https://example.com/post#comments"
+        );
+    }
+
+    #[test]
+    fn other_links_are_left_exactly_as_they_were() {
+        for link in &[
+            "https://www.youtube.com/watch?v=abc&t=42s",
+            "https://example.com/search?q=a%20b&page=2",
+            "https://example.com/post?reference=1",
+            "magnet:?xt=urn:btih:abc&utm_source=rss",
+            "not a link",
+        ] {
+            assert_eq!(clean(link), *link);
+        }
+    }
+
+    #[test]
+    fn links_to_the_same_page_are_normalized_alike() {
+        let normalized = normalize("https://example.com/post?id=5");
+
+        for link in &[
+            "http://www.example.com/post/?id=5",
+            " https://EXAMPLE.com/post?id=5&utm_campaign=x ",
+            "https://example.com/post?id=5#xtor=RSS-1",
+        ] {
+            assert_eq!(normalize(link), normalized, "{}", link);
+        }
+        assert_ne!(normalize("https://example.com/post?id=6"), normalized);
+        assert_ne!(normalize("https://example.com:8080/post?id=5"), normalized);
+    }
+}
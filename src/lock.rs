@@ -0,0 +1,141 @@
+//! Keeping sitch from running twice on the same config at once, like when
+//! a timer fires while the last run is still checking. Both runs would
+//! save, and whichever saved last would undo the other's changes.
+//!
+//! The lock is held by the operating system on a file next to the state,
+//! so it's let go of as soon as the run holding it ends, even if it
+//! crashes. The file itself is left behind, and means nothing on its own.
+
+use std::fs::{create_dir_all, File, OpenOptions};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How long to wait between tries while another run has the lock.
+const RETRY_INTERVAL: Duration = Duration::from_millis(100);
+
+/// The lock on a config and its state, which is held until dropped.
+pub struct RunLock {
+    _file: File,
+}
+
+impl RunLock {
+    /// Where the lock is kept for the state at `state_path`.
+    pub fn path(state_path: &Path) -> PathBuf {
+        state_path.with_extension("lock")
+    }
+
+    /// Takes the lock for the state at `state_path`, waiting up to `wait`
+    /// for another run to let go of it. Returns `None` if it didn't.
+    pub fn acquire(state_path: &Path, wait: Duration) -> Result<Option<Self>, String> {
+        let path = Self::path(state_path);
+        if let Some(parent) = path.parent() {
+            create_dir_all(parent).ok();
+        }
+
+        let deadline = Instant::now() + wait;
+        loop {
+            let locked = try_lock(&path).map_err(|err| {
+                format!(
+                    "Couldn't lock {} to make sure sitch isn't already running: {}",
+                    path.to_string_lossy(),
+                    err
+                )
+            })?;
+            match locked {
+                Some(file) => return Ok(Some(RunLock { _file: file })),
+                None if Instant::now() >= deadline => return Ok(None),
+                None => thread::sleep(RETRY_INTERVAL),
+            }
+        }
+    }
+}
+
+/// Opens the lock file and locks it, unless another process has it locked.
+#[cfg(unix)]
+fn try_lock(path: &Path) -> io::Result<Option<File>> {
+    use std::os::unix::io::AsRawFd;
+
+    let file = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(path)?;
+    // flock is let go of when the file is closed, including by the process
+    // ending
+    if unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX | libc::LOCK_NB) } == 0 {
+        return Ok(Some(file));
+    }
+
+    let err = io::Error::last_os_error();
+    match err.kind() {
+        io::ErrorKind::WouldBlock => Ok(None),
+        _ => Err(err),
+    }
+}
+
+/// Opens the lock file without sharing it, unless another process has it
+/// open.
+#[cfg(windows)]
+fn try_lock(path: &Path) -> io::Result<Option<File>> {
+    use std::os::windows::fs::OpenOptionsExt;
+
+    /// The error Windows gives for a file another process has open.
+    const ERROR_SHARING_VIOLATION: i32 = 32;
+
+    let opened = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .share_mode(0)
+        .open(path);
+    match opened {
+        Ok(file) => Ok(Some(file)),
+        Err(err) if err.raw_os_error() == Some(ERROR_SHARING_VIOLATION) => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::test_dir;
+
+    #[test]
+    fn only_one_run_has_the_lock() {
+        let state_path = test_dir("run-lock-held").join("state.json");
+        let first = RunLock::acquire(&state_path, Duration::from_secs(0)).unwrap();
+        assert!(first.is_some());
+
+        let second = RunLock::acquire(&state_path, Duration::from_secs(0)).unwrap();
+        assert!(second.is_none());
+    }
+
+    #[test]
+    fn the_lock_is_let_go_of_when_dropped() {
+        let state_path = test_dir("run-lock-dropped").join("state.json");
+        let first = RunLock::acquire(&state_path, Duration::from_secs(0)).unwrap();
+        assert!(first.is_some());
+        drop(first);
+
+        let second = RunLock::acquire(&state_path, Duration::from_secs(0)).unwrap();
+        assert!(second.is_some());
+    }
+
+    #[test]
+    fn waiting_gets_the_lock_once_its_let_go_of() {
+        let state_path = test_dir("run-lock-wait").join("state.json");
+        let first = RunLock::acquire(&state_path, Duration::from_secs(0))
+            .unwrap()
+            .unwrap();
+        let holder = thread::spawn(move || {
+            thread::sleep(3 * RETRY_INTERVAL);
+            drop(first);
+        });
+
+        let second = RunLock::acquire(&state_path, Duration::from_secs(10)).unwrap();
+        assert!(second.is_some());
+        holder.join().unwrap();
+    }
+}
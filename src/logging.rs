@@ -0,0 +1,121 @@
+//! Logging what sitch does while checking, for finding out why a source
+//! isn't reporting what it should.
+//!
+//! Nothing is logged unless it's asked for, so that sitch's usual output
+//! stays the same. Only sitch's own logs are shown, not its dependencies'.
+
+use chrono::Local;
+use log::{LevelFilter, Log, Metadata, Record};
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use std::sync::Mutex;
+
+/// Logs to stderr, and to a file too if there is one.
+struct Logger {
+    /// The most detailed level to log anywhere at.
+    level: LevelFilter,
+    /// The most detailed level to log to stderr at, which is `Off` when
+    /// only logging to the file.
+    stderr_level: LevelFilter,
+    /// The file to also append the log to, with the most detailed level
+    /// to log to it at.
+    file: Option<(Mutex<File>, LevelFilter)>,
+}
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.level && metadata.target().starts_with("sitch")
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        let now = Local::now();
+        if record.level() <= self.stderr_level {
+            eprintln!(
+                "[{} {}] {}",
+                now.format("%T"),
+                record.level(),
+                record.args()
+            );
+        }
+        if let Some((file, file_level)) = &self.file {
+            if record.level() <= *file_level {
+                // a log that can't be written shouldn't stop the check
+                writeln!(
+                    file.lock().unwrap(),
+                    "[{} {}] {}",
+                    now.to_rfc3339(),
+                    record.level(),
+                    record.args()
+                )
+                .ok();
+            }
+        }
+    }
+
+    fn flush(&self) {
+        if let Some((file, _file_level)) = &self.file {
+            file.lock().unwrap().flush().ok();
+        }
+    }
+}
+
+/// The most detailed level to log at for a verbosity, where 0 logs
+/// nothing, 1 logs what's checked (info), and 2 or more logs every
+/// request too (debug).
+fn level_for(verbosity: u8) -> LevelFilter {
+    match verbosity {
+        0 => LevelFilter::Off,
+        1 => LevelFilter::Info,
+        _ => LevelFilter::Debug,
+    }
+}
+
+/// Starts logging to stderr at a verbosity (see `level_for`).
+///
+/// Logs are also appended to `log_file` if one is given, which gets at
+/// least what's checked even when nothing is logged to stderr, so that
+/// sitch's usual output stays the same.
+pub fn init(verbosity: u8, log_file: Option<&Path>) -> Result<(), String> {
+    let stderr_level = level_for(verbosity);
+    let file = match log_file {
+        Some(path) => Some((
+            Mutex::new(
+                OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(path)
+                    .map_err(|err| {
+                        format!(
+                            "Couldn't open the log file {}: {}",
+                            path.to_string_lossy(),
+                            err
+                        )
+                    })?,
+            ),
+            level_for(verbosity.max(1)),
+        )),
+        None => None,
+    };
+    let level = match &file {
+        Some((_file, file_level)) => stderr_level.max(*file_level),
+        None => stderr_level,
+    };
+    if level == LevelFilter::Off {
+        return Ok(());
+    }
+
+    log::set_boxed_logger(Box::new(Logger {
+        level,
+        stderr_level,
+        file,
+    }))
+    .map_err(|err| format!("Couldn't start logging: {}", err))?;
+    log::set_max_level(level);
+
+    Ok(())
+}
@@ -2,9 +2,26 @@
 //! supports the following sources:
 //! - YouTube channels
 //! - RSS feeds
+//! - Newsletters, like Substack publications
 //! - Anime (myanimelist.net via Jikan)
 //! - Manga (mangaeden.net API)
 //! - Bandcamp artists
+//! - Mastodon accounts
+//! - Hacker News searches
+//! - Rust crates (crates.io releases)
+//! - GitHub repository commits
+//! - Any JSON API, using JSON pointers
+//! - Any web page, using CSS selectors
+//! - Changes to any web page
+//! - npm packages (releases)
+//! - arXiv papers by author or keyword
+//! - itch.io creators
+//! - Gmail searches
+//! - Webtoon series
+//! - Patreon creators
+//! - Authors (new books, via Goodreads)
+//! - PyPI packages (releases)
+//! - Wikipedia articles (edits)
 //!
 //! Read more on the [sitch repository](https://www.github.com/smores56/sitch).
 
@@ -12,44 +29,286 @@ extern crate atty;
 extern crate chrono;
 extern crate colored;
 extern crate dirs;
+extern crate keyring;
+extern crate lettre;
+extern crate lettre_email;
+#[cfg(unix)]
+extern crate libc;
+extern crate log;
+extern crate native_tls;
+#[cfg(not(windows))]
 extern crate notify_rust;
 extern crate rayon;
+extern crate regex;
 extern crate reqwest;
 extern crate rss;
+extern crate schemars;
 extern crate select;
 extern crate serde;
 extern crate serde_json;
 extern crate structopt;
+extern crate toml;
+extern crate unicode_segmentation;
 extern crate webbrowser;
+#[cfg(windows)]
+extern crate winrt_notification;
 
 pub mod args;
+pub mod config_format;
+pub mod defaults;
+pub mod digest;
+pub mod email;
+pub mod export;
+pub mod ical;
+pub mod ignore;
+pub mod links;
+pub mod lock;
+pub mod logging;
+pub mod migrations;
+pub mod network;
+pub mod notification;
+pub mod schedule;
+pub mod schema;
+pub mod selector;
 pub mod sources;
+pub mod state;
+pub mod stats;
+pub mod style;
+pub mod template;
+pub mod tui;
 pub mod util;
 
 use chrono::{DateTime, Local};
-use colored::Colorize;
-use serde::Deserialize;
+use log::info;
+use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
 use serde_json::json;
+use std::fs::{read_to_string, write};
+use std::io::Read;
+use std::iter;
 use std::process;
+use std::time::Duration;
 use structopt::StructOpt;
-use util::edit_as_json;
+use style::{Role, Stylize};
+use template::Template;
+use util::{
+    edit_as, expand_tilde, find_by_name, readline, set_title_width, shorten_title, ReadlineResult,
+    DEFAULT_TITLE_WIDTH,
+};
 
 use args::{
-    AnimeCommand, Args, BandcampCommand, Command, MangaCommand, RssCommand, YouTubeApiCommand,
+    parse_arg_until, AnimeCommand, Args, ArxivCommand, AuthorsCommand, BandcampCommand, Command,
+    ConfigCommand, CratesCommand, CustomCommand, GitHubCommand, GmailCommand, GmailOauthCommand,
+    HistoryCommand, HnCommand, IgnoreCommand, ItchCommand, MangaCommand, MastodonCommand,
+    NewsletterCommand, NpmCommand, PatreonCommand, ProfileCommand, PypiCommand, RssCommand,
+    ScrapeCommand, WatchCommand, WebtoonCommand, WikipediaCommand, YouTubeApiCommand,
     YouTubeCommand,
 };
+use config_format::ConfigFormat;
+use lock::RunLock;
+use schedule::Schedule;
 use sources::anime::Anime;
+use sources::arxiv::ArxivQuery;
+use sources::authors::Author;
 use sources::bandcamp::BandcampArtist;
+use sources::crates::RustCrate;
+use sources::custom::CustomSource;
+use sources::github::GitHubRepo;
+use sources::gmail::{GmailFilter, GmailOauth};
+use sources::hn::HnWatch;
+use sources::itch::ItchCreator;
 use sources::manga::Manga;
+use sources::mastodon::MastodonAccount;
+use sources::npm::NpmPackage;
+use sources::patreon::PatreonCreator;
+use sources::pypi::PypiPackage;
 use sources::rss::RssSource;
-use sources::youtube::YouTubeChannel;
-use sources::Sources;
+use sources::scrape::ScrapeSource;
+use sources::takeout::read_subscriptions;
+use sources::watch::WatchedPage;
+use sources::webtoon::WebtoonSeries;
+use sources::wikipedia::WikipediaArticle;
+use sources::youtube::{ApiKey, YouTubeChannel};
+use sources::{
+    deserialize_sources, keep_last_checked, validate_name, validate_sources, CheckOptions,
+    NamedSource, PlatformFilter, Sources, DEFAULT_PROFILE,
+};
+use state::{LastRun, UnreadUpdate};
+use stats::{
+    describe_most_recent, is_stale, platform_totals, render_table, sort_by_activity, SourceStats,
+    StaleAction,
+};
+
+/// The exit code used with `--check-exit-code` when no updates were found.
+const NO_UPDATES_EXIT_CODE: i32 = 10;
+
+/// The exit code used when the network is down, so nothing was checked,
+/// or none of the sources could be reached.
+const OFFLINE_EXIT_CODE: i32 = 11;
+
+/// The exit code used when another run of sitch has the config locked.
+const LOCKED_EXIT_CODE: i32 = 12;
 
 fn run() -> Result<(), String> {
     // parse arguments
-    let args = Args::from_args();
+    let mut args = Args::from_args();
+    let log_file = args.log_file.clone().map(expand_tilde);
+    logging::init(args.verbose, log_file.as_deref())?;
+    // completions don't need any config, so print them right away
+    if let Some(Command::Completions { shell }) = args.command {
+        Args::clap().gen_completions_to("sitch", shell, &mut std::io::stdout());
+        return Ok(());
+    }
+    // profiles are just files, so they're managed without loading one
+    if let Some(Command::Profile(profile_command)) = &args.command {
+        match profile_command {
+            ProfileCommand::List => {
+                let current = args.profile.clone().unwrap_or(DEFAULT_PROFILE.to_owned());
+                for profile in Sources::profiles()? {
+                    if profile == current {
+                        println!("{} (current)", profile);
+                    } else {
+                        println!("{}", profile);
+                    }
+                }
+            }
+            ProfileCommand::Copy { from, to } => {
+                Sources::copy_profile(from, to)?;
+                println!("Copied the profile \"{}\" to \"{}\".", from, to);
+            }
+        }
+        return Ok(());
+    }
+    // schedules only need to know where the config and state are, so
+    // they're managed without loading them
+    let schedule_name = schedule::unit_name(
+        args.profile
+            .as_ref()
+            .map(String::as_str)
+            .filter(|profile| *profile != DEFAULT_PROFILE),
+    );
+    match &args.command {
+        Some(Command::InstallSchedule {
+            every,
+            notify,
+            flags,
+        }) => {
+            let (config_path, state_path) = Sources::paths(
+                args.config.clone(),
+                args.state.clone(),
+                args.profile.clone(),
+            )?;
+            let mut flags = flags.clone();
+            if *notify {
+                flags.insert(0, "--notify".to_owned());
+            }
+            let schedule = Schedule::new(schedule_name, *every, &config_path, &state_path, &flags)?;
+            // make sure the flags can actually be checked with
+            let scheduled_args = Args::from_iter_safe(
+                iter::once("sitch").chain(schedule.command[1..].iter().map(String::as_str)),
+            )
+            .map_err(|err| {
+                let message = err.message.lines().next().unwrap_or_default();
+                format!(
+                    "The flags to check with are invalid: {}",
+                    message.trim_start_matches("error: ")
+                )
+            })?;
+            if scheduled_args.command.is_some() {
+                return Err("Only flags for checking can be given, not a subcommand.".to_owned());
+            }
+            schedule::install(&schedule)?;
+            return Ok(());
+        }
+        Some(Command::UninstallSchedule) => {
+            schedule::uninstall(&schedule_name)?;
+            return Ok(());
+        }
+        _ => {}
+    }
+    // the config is checked without loading it, since loading fails on
+    // some of the problems it finds
+    match &args.command {
+        Some(Command::Config(ConfigCommand::Schema)) => {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&schema::config_schema()).unwrap()
+            );
+            return Ok(());
+        }
+        Some(Command::Config(ConfigCommand::Validate)) => {
+            let (path, problems) =
+                Sources::validate_config(args.config.clone(), args.profile.clone())?;
+            if problems.is_empty() {
+                println!("The config at {} is valid.", path.to_string_lossy());
+                return Ok(());
+            }
+            for problem in &problems {
+                println!("{}", problem);
+            }
+            return Err(format!(
+                "Found {} problem{} in the config at {}.",
+                problems.len(),
+                if problems.len() == 1 { "" } else { "s" },
+                path.to_string_lossy()
+            ));
+        }
+        _ => {}
+    }
+    // runs that save lock the config before loading it, so that another
+    // run can't save over what this one saves
+    let _run_lock = if !args.dry_run && !args.replay && !args.last_checked {
+        let (_config_path, state_path) = Sources::paths(
+            args.config.clone(),
+            args.state.clone(),
+            args.profile.clone(),
+        )?;
+        let wait = Duration::from_secs(args.wait_lock.unwrap_or(0));
+        match RunLock::acquire(&state_path, wait)? {
+            Some(run_lock) => Some(run_lock),
+            None => {
+                eprintln!(
+                    "Another sitch instance is running with this config, so this one is stopping. \
+                     Pass --wait-lock to wait for it to finish instead."
+                );
+                process::exit(LOCKED_EXIT_CODE);
+            }
+        }
+    } else {
+        None
+    };
+    // the config is made private before it's loaded, so it isn't
+    // warned about
+    if args.fix_permissions {
+        let (path, _state_path) = Sources::paths(
+            args.config.clone(),
+            args.state.clone(),
+            args.profile.clone(),
+        )?;
+        Sources::fix_permissions(&path)?;
+        println!("Made the config at {} private.", path.to_string_lossy());
+    }
     // load source configuration file
-    let mut sources = Sources::load(args.config.clone())?;
+    let mut sources = Sources::load(
+        args.config.clone(),
+        args.state.clone(),
+        args.profile.clone(),
+    )?;
+    // flags that weren't given fall back on the config's defaults
+    args.apply_defaults(sources.defaults.as_ref());
+    // requests go through the config's proxy from here on, if it has one
+    if let Some(network) = &sources.network {
+        network.apply()?;
+    }
+    // output is colored with the config's theme from here on
+    if let Some(theme) = &sources.theme {
+        theme.apply()?;
+    }
+    // and titles are cut down to the config's width
+    set_title_width(sources.title_width.unwrap_or(DEFAULT_TITLE_WIDTH));
+    // sources are edited in the same format as the config
+    let format = sources.format;
     // if just checking the last time it was run,
     if args.last_checked {
         if let Some(last_checked) = sources.last_checked {
@@ -67,303 +326,2421 @@ fn run() -> Result<(), String> {
         sources.last_checked = Some(since_time);
     }
 
+    // whether to exit signaling that no updates were found
+    let mut no_updates = false;
+    // how many updates a check found, which would be reported again if
+    // they couldn't be saved
+    let mut found_updates = 0;
+    // whether to exit signaling that none of the sources could be reached
+    let mut offline = false;
+    // settings given to a source being added are applied once it's added
+    let source_counts = sources.source_counts();
+    let preferences = args
+        .command
+        .as_ref()
+        .and_then(Command::source_preferences)
+        .cloned();
+
     if let Some(command) = args.command {
         match command {
             Command::Rss(rss_command) => match rss_command {
-                RssCommand::Add { name, feed } => {
+                RssCommand::Add {
+                    from_file: Some(path),
+                    ..
+                } => {
+                    let feeds = read_sources_from_file(&path, "feeds", RssSource::from_feed)?;
+                    sources
+                        .rss
+                        .0
+                        .extend(feeds.into_iter().map(|feed| (feed, None)));
+                }
+                RssCommand::Add {
+                    name,
+                    feed,
+                    from_file: None,
+                    ..
+                } => {
                     // if both name and feed url are provided,
                     if name.is_some() && feed.is_some() {
                         // add the new rss source to sitch
                         sources.rss.0.push((
-                            RssSource {
+                            validated(RssSource {
                                 name: name.unwrap(),
                                 feed: feed.unwrap(),
-                            },
+                                ..Default::default()
+                            })?,
                             None,
                         ));
                     } else {
                         // otherwise, let the user edit a JSON object in their
                         // preferred editor and attempt to save the edited JSON as
                         // an new rss source
-                        edit_as_json(&json!({ "name": name, "feed": feed }), |edited| {
-                            let source = RssSource::deserialize(edited).map_err(|err| {
-                                format!("The edited object could not be parsed: {}.", err)
-                            })?;
-                            sources.rss.0.push((source, None));
-                            Ok(())
-                        })?;
+                        add_in_editor(
+                            &mut sources.rss.0,
+                            &json!({ "name": name, "feed": feed }),
+                            format,
+                        )?;
                     }
                     println!("Added a new RSS feed.");
                 }
-                RssCommand::List => {
-                    for (source, _last_checked) in &sources.rss.0 {
-                        // only print color if the output isn't piped
-                        if atty::is(atty::Stream::Stdout) {
-                            println!("{}: {}", source.name.green(), source.feed.bright_blue());
-                        } else {
-                            println!("{}: {}", source.name, source.feed);
-                        }
-                    }
-                }
-                RssCommand::Edit => {
-                    // attempt to edit all of the user's rss sources in their
-                    // preferred editor, and save if the edit was successful
-                    edit_as_json(&sources.rss.clone(), |edited| {
-                        let rss = Vec::<(RssSource, Option<DateTime<Local>>)>::deserialize(edited)
-                            .map_err(|err| {
-                                format!("The edited RSS sources could not be parsed: {}.", err)
-                            })?;
-                        sources.rss.0 = rss;
-                        Ok(())
-                    })?;
-                }
+                RssCommand::List { verbose, json } => run_source_command(
+                    SourceCommand::List { verbose, json },
+                    &mut sources.rss.0,
+                    ("RSS feed", "RSS feeds"),
+                    format,
+                )?,
+                RssCommand::Rename { from, to } => run_source_command(
+                    SourceCommand::Rename { from, to },
+                    &mut sources.rss.0,
+                    ("RSS feed", "RSS feeds"),
+                    format,
+                )?,
+                RssCommand::Snooze { name, until, .. } => run_source_command(
+                    SourceCommand::Snooze { name, until },
+                    &mut sources.rss.0,
+                    ("RSS feed", "RSS feeds"),
+                    format,
+                )?,
+                RssCommand::Remove { name } => run_source_command(
+                    SourceCommand::Remove { name },
+                    &mut sources.rss.0,
+                    ("RSS feed", "RSS feeds"),
+                    format,
+                )?,
+                RssCommand::Edit { name } => run_source_command(
+                    SourceCommand::Edit { name },
+                    &mut sources.rss.0,
+                    ("RSS feed", "RSS feeds"),
+                    format,
+                )?,
             },
+            Command::Newsletter(NewsletterCommand::Add { url, name, .. }) => {
+                // find and check the newsletter's feed before adding it
+                let source = validated(RssSource::newsletter(&url, name)?)?;
+                println!(
+                    "Added the newsletter \"{}\" to your RSS feeds.",
+                    source.name
+                );
+                sources.rss.0.push((source, None));
+            }
             Command::Bandcamp(bandcamp_command) => match bandcamp_command {
-                BandcampCommand::Add { name, url } => {
-                    // if both name and artist url are provided,
-                    if name.is_some() && url.is_some() {
-                        // add the new bandcamp artist to sitch
-                        sources.bandcamp.0.push((
-                            BandcampArtist {
-                                name: name.unwrap(),
-                                url: url.unwrap(),
-                            },
-                            None,
-                        ));
-                    } else {
+                BandcampCommand::Add {
+                    from_file: Some(path),
+                    ..
+                } => {
+                    let artists =
+                        read_sources_from_file(&path, "artists", BandcampArtist::from_url)?;
+                    sources
+                        .bandcamp
+                        .0
+                        .extend(artists.into_iter().map(|artist| (artist, None)));
+                }
+                BandcampCommand::Add {
+                    name,
+                    url,
+                    from_file: None,
+                    ..
+                } => {
+                    match (name, url) {
+                        // if both name and artist url are provided, add the
+                        // new bandcamp artist to sitch once its page checks out
+                        (Some(name), Some(url)) => {
+                            let url = BandcampArtist::offer_canonical_url(&url)?;
+                            let artist = validated(BandcampArtist::from_url(&url, Some(name))?)?;
+                            sources.bandcamp.0.push((artist, None));
+                        }
                         // otherwise, let the user edit a JSON object in their
                         // preferred editor and attempt to save the edited JSON as
                         // an new bandcamp artist
-                        edit_as_json(&json!({ "name": name, "url": url }), |edited| {
-                            let source = BandcampArtist::deserialize(edited).map_err(|err| {
-                                format!("The edited object could not be parsed: {}.", err)
+                        (name, url) => {
+                            edit_as(format, &json!({ "name": name, "url": url }), |edited| {
+                                let mut source =
+                                    BandcampArtist::deserialize(edited).map_err(|err| {
+                                        format!("The edited object could not be parsed: {}.", err)
+                                    })?;
+                                source.validate()?;
+                                source.url = BandcampArtist::offer_canonical_url(&source.url)?;
+                                source.diagnose()?;
+                                sources.bandcamp.0.push((source, None));
+                                Ok(())
                             })?;
-                            sources.bandcamp.0.push((source, None));
-                            Ok(())
-                        })?;
+                        }
                     }
                     println!("Added a new Bandcamp artist.");
                 }
-                BandcampCommand::List => {
-                    for (source, _last_checked) in &sources.bandcamp.0 {
-                        // only print color if the output isn't piped
-                        if atty::is(atty::Stream::Stdout) {
-                            println!("{}: {}", source.name.green(), source.url.bright_blue());
-                        } else {
-                            println!("{}: {}", source.name, source.url);
-                        }
+                BandcampCommand::List { verbose, json } => run_source_command(
+                    SourceCommand::List { verbose, json },
+                    &mut sources.bandcamp.0,
+                    ("Bandcamp artist", "Bandcamp artists"),
+                    format,
+                )?,
+                BandcampCommand::Rename { from, to } => run_source_command(
+                    SourceCommand::Rename { from, to },
+                    &mut sources.bandcamp.0,
+                    ("Bandcamp artist", "Bandcamp artists"),
+                    format,
+                )?,
+                BandcampCommand::Snooze { name, until, .. } => run_source_command(
+                    SourceCommand::Snooze { name, until },
+                    &mut sources.bandcamp.0,
+                    ("Bandcamp artist", "Bandcamp artists"),
+                    format,
+                )?,
+                BandcampCommand::Remove { name } => run_source_command(
+                    SourceCommand::Remove { name },
+                    &mut sources.bandcamp.0,
+                    ("Bandcamp artist", "Bandcamp artists"),
+                    format,
+                )?,
+                BandcampCommand::Edit { name } => run_source_command(
+                    SourceCommand::Edit { name },
+                    &mut sources.bandcamp.0,
+                    ("Bandcamp artist", "Bandcamp artists"),
+                    format,
+                )?,
+                BandcampCommand::Search => {
+                    // search for artists, and if one is found and selected,
+                    // add it to their config file
+                    if let Some(new_artist) = BandcampArtist::interactive_search()? {
+                        sources.bandcamp.0.push((new_artist, None));
+                        println!("Added a new artist.");
                     }
                 }
-                BandcampCommand::Edit => {
-                    // attempt to edit all of the user's bandcamp artists in their
-                    // preferred editor, and save if the edit was successful
-                    edit_as_json(&sources.bandcamp.clone(), |edited| {
-                        let artists =
-                            Vec::<(BandcampArtist, Option<DateTime<Local>>)>::deserialize(edited)
-                                .map_err(|err| {
-                                format!("The edited bandcamp artists could not be parsed: {}.", err)
-                            })?;
-                        sources.bandcamp.0 = artists;
-                        Ok(())
-                    })?;
-                }
             },
             Command::YouTube(youtube_command) => match youtube_command {
                 // if both name and channel id are provided,
-                YouTubeCommand::Add { name, channel_id } => {
+                YouTubeCommand::Add {
+                    from_file: Some(path),
+                    ..
+                } => {
+                    let youtube = &sources.youtube;
+                    let channels = read_sources_from_file(&path, "channels", |id_or_url, name| {
+                        youtube.resolve_channel(id_or_url, name)
+                    })?;
+                    sources
+                        .youtube
+                        .channels
+                        .extend(channels.into_iter().map(|channel| (channel, None)));
+                }
+                YouTubeCommand::Add {
+                    name,
+                    channel_id,
+                    from_file: None,
+                    ..
+                } => {
                     // then add the new YouTube channel to sitch
                     if name.is_some() && channel_id.is_some() {
                         sources.youtube.channels.push((
-                            YouTubeChannel {
+                            validated(YouTubeChannel {
                                 name: name.unwrap(),
                                 channel_id: channel_id.unwrap(),
-                            },
+                                ..Default::default()
+                            })?,
                             None,
                         ));
                     } else {
                         // otherwise, let the user edit a JSON object in their
                         // preferred editor and attempt to save the edited JSON as
                         // an new YouTube channel
-                        edit_as_json(
+                        add_in_editor(
+                            &mut sources.youtube.channels,
                             &json!({ "name": name, "channel_id": channel_id }),
-                            |edited| {
-                                let channel =
-                                    YouTubeChannel::deserialize(edited).map_err(|err| {
-                                        format!("The edited object could not be parsed: {}.", err)
-                                    })?;
-                                sources.youtube.channels.push((channel, None));
-                                Ok(())
-                            },
+                            format,
                         )?;
                     }
                     println!("Added a new YouTube channel.");
                 }
-                YouTubeCommand::List => {
-                    for (channel, _last_checked) in &sources.youtube.channels {
-                        // only print color if the output isn't piped
-                        if atty::is(atty::Stream::Stdout) {
-                            println!("{}: {}", channel.name.green(), channel.channel_id);
-                        } else {
-                            println!("{}: {}", channel.name, channel.channel_id);
-                        }
-                    }
-                }
-                YouTubeCommand::Edit => {
-                    // attempt to edit all of the user's YouTube channels in their
-                    // preferred editor, and save if the edit was successful
-                    edit_as_json(&sources.youtube.channels.clone(), |edited| {
-                        let channels =
-                            Vec::<(YouTubeChannel, Option<DateTime<Local>>)>::deserialize(edited)
-                                .map_err(|err| {
-                                format!("The edited channels could not be parsed: {}.", err)
-                            })?;
-                        sources.youtube.channels = channels;
-                        Ok(())
-                    })?;
-                }
-                YouTubeCommand::Search => match sources.youtube.interactive_search() {
+                YouTubeCommand::List { verbose, json } => run_source_command(
+                    SourceCommand::List { verbose, json },
+                    &mut sources.youtube.channels,
+                    ("YouTube channel", "YouTube channels"),
+                    format,
+                )?,
+                YouTubeCommand::Rename { from, to } => run_source_command(
+                    SourceCommand::Rename { from, to },
+                    &mut sources.youtube.channels,
+                    ("YouTube channel", "YouTube channels"),
+                    format,
+                )?,
+                YouTubeCommand::Snooze { name, until, .. } => run_source_command(
+                    SourceCommand::Snooze { name, until },
+                    &mut sources.youtube.channels,
+                    ("YouTube channel", "YouTube channels"),
+                    format,
+                )?,
+                YouTubeCommand::Remove { name } => run_source_command(
+                    SourceCommand::Remove { name },
+                    &mut sources.youtube.channels,
+                    ("YouTube channel", "YouTube channels"),
+                    format,
+                )?,
+                YouTubeCommand::Edit { name } => run_source_command(
+                    SourceCommand::Edit { name },
+                    &mut sources.youtube.channels,
+                    ("YouTube channel", "YouTube channels"),
+                    format,
+                )?,
+                YouTubeCommand::Search => {
                     // search for channels, and if one is found and selected,
                     // add it to their config file
-                    Ok(new_channel) => {
+                    if let Some(new_channel) = sources.youtube.interactive_search()? {
                         sources.youtube.channels.push((new_channel, None));
                         println!("Added a new channel.");
                     }
-                    // otherwise, print the returned error message
-                    Err(err) => eprintln!("{}", err),
-                },
+                }
+                YouTubeCommand::Import { takeout, filter } => {
+                    let export = read_to_string(&takeout).map_err(|err| {
+                        format!("Couldn't read {}: {}", takeout.to_string_lossy(), err)
+                    })?;
+                    let filter = filter.map(|filter| filter.to_lowercase());
+
+                    let mut new_channels: Vec<YouTubeChannel> = Vec::new();
+                    let mut skipped = 0;
+                    let mut invalid = Vec::new();
+                    for row in read_subscriptions(&export) {
+                        let channel = match row {
+                            Ok(channel) => channel,
+                            Err(err) => {
+                                invalid.push(err);
+                                continue;
+                            }
+                        };
+                        let matches_filter = filter
+                            .as_ref()
+                            .map(|filter| channel.name.to_lowercase().contains(filter))
+                            .unwrap_or(true);
+                        // channels are the same if their ids are
+                        let already_followed = sources
+                            .youtube
+                            .channels
+                            .iter()
+                            .map(|(followed, _last_checked)| followed)
+                            .chain(new_channels.iter())
+                            .any(|followed| followed.channel_id == channel.channel_id);
+                        if matches_filter && !already_followed {
+                            new_channels.push(channel);
+                        } else {
+                            skipped += 1;
+                        }
+                    }
+
+                    println!(
+                        "Found {} new channels to import in {}.",
+                        new_channels.len(),
+                        takeout.to_string_lossy()
+                    );
+                    // give people a chance to back out of a big import
+                    if new_channels.len() > 0 && atty::is(atty::Stream::Stdin) {
+                        let should_import =
+                            readline("Import them? [Y/n] ", |input| match input.as_str() {
+                                "" | "y" | "Y" | "yes" => Ok(true),
+                                "n" | "N" | "no" => Ok(false),
+                                _ => Err("Please respond with a yes or no.".to_owned()),
+                            })?;
+                        if should_import != ReadlineResult::Value(true) {
+                            return Ok(());
+                        }
+                    }
+
+                    let imported = new_channels.len();
+                    sources
+                        .youtube
+                        .channels
+                        .extend(new_channels.into_iter().map(|channel| (channel, None)));
+                    println!(
+                        "Imported {} channels, skipped {}, and found {} invalid rows.",
+                        imported,
+                        skipped,
+                        invalid.len()
+                    );
+                    for err in invalid {
+                        eprintln!("{}", err);
+                    }
+                }
                 YouTubeCommand::ApiKey(api_command) => match api_command {
                     // set or update the required API key for YouTube channel updates
-                    YouTubeApiCommand::Set { new_key } => sources.youtube.api_key = Some(new_key),
-                    // clear the key
-                    YouTubeApiCommand::Clear => sources.youtube.api_key = None,
-                    // if a key exists, print it
-                    YouTubeApiCommand::Show => {
-                        if let Some(key) = &sources.youtube.api_key {
-                            println!("{}", key);
+                    YouTubeApiCommand::Set { new_key, keyring } => {
+                        let api_key = if keyring {
+                            ApiKey::store_in_keyring(&new_key)?
+                        } else {
+                            ApiKey::Plaintext(new_key)
+                        };
+                        // don't leave an old key behind in the keyring
+                        if let Some(ApiKey::Keyring { .. }) = &sources.youtube.api_key {
+                            if !keyring {
+                                ApiKey::Keyring { keyring: true }.clear().ok();
+                            }
+                        }
+                        sources.youtube.api_key = Some(api_key);
+                    }
+                    // clear the key, including from the keyring
+                    YouTubeApiCommand::Clear => {
+                        if let Some(api_key) = sources.youtube.api_key.take() {
+                            api_key.clear()?;
+                        }
+                    }
+                    // if a key exists, print it (masked unless revealing it)
+                    YouTubeApiCommand::Show { reveal } => {
+                        if let Some(api_key) = &sources.youtube.api_key {
+                            if reveal {
+                                println!("{}", api_key.resolve()?);
+                            } else {
+                                println!("{}", api_key.masked());
+                            }
                         }
                     }
                 },
             },
             Command::Anime(anime_command) => match anime_command {
                 // if both a name and anime id were provided,
-                AnimeCommand::Add { name, id } => {
+                AnimeCommand::Add {
+                    from_file: Some(path),
+                    ..
+                } => {
+                    let anime = read_sources_from_file(&path, "anime", Anime::from_id_or_url)?;
+                    sources
+                        .anime
+                        .0
+                        .extend(anime.into_iter().map(|anime| (anime, None)));
+                }
+                AnimeCommand::Add {
+                    name,
+                    id,
+                    from_file: None,
+                    ..
+                } => {
                     if name.is_some() && id.is_some() {
                         // add the new anime to sitch
                         sources.anime.0.push((
-                            Anime {
+                            validated(Anime {
                                 name: name.unwrap(),
                                 id: id.unwrap(),
-                            },
+                                ..Default::default()
+                            })?,
                             None,
                         ));
                     } else {
                         // otherwise, let the user edit a JSON object in their
                         // preferred editor and attempt to save the edited JSON as
                         // an new anime
-                        edit_as_json(&json!({ "name": name, "id": id }), |edited| {
-                            let anime = Anime::deserialize(edited).map_err(|err| {
-                                format!("The edited object could not be parsed: {}.", err)
-                            })?;
-                            sources.anime.0.push((anime, None));
-                            Ok(())
-                        })?;
+                        add_in_editor(
+                            &mut sources.anime.0,
+                            &json!({ "name": name, "id": id }),
+                            format,
+                        )?;
                         println!("Added a new anime.");
                     }
                 }
-                AnimeCommand::List => {
-                    for (anime, _last_checked) in &sources.anime.0 {
-                        println!("{}", anime.name);
-                    }
-                }
-                AnimeCommand::Edit => {
-                    // attempt to edit all of the user's anime in their
-                    // preferred editor, and save if the edit was successful
-                    edit_as_json(&sources.anime.clone(), |edited| {
-                        let anime = Vec::<(Anime, Option<DateTime<Local>>)>::deserialize(edited)
-                            .map_err(|err| {
-                                format!("The edited anime could not be parsed: {}.", err)
-                            })?;
-                        sources.anime.0 = anime;
-                        Ok(())
-                    })?;
-                }
-                AnimeCommand::Search => match Anime::interactive_search() {
+                AnimeCommand::List { verbose, json } => run_source_command(
+                    SourceCommand::List { verbose, json },
+                    &mut sources.anime.0,
+                    ("anime", "anime"),
+                    format,
+                )?,
+                AnimeCommand::Rename { from, to } => run_source_command(
+                    SourceCommand::Rename { from, to },
+                    &mut sources.anime.0,
+                    ("anime", "anime"),
+                    format,
+                )?,
+                AnimeCommand::Snooze { name, until, .. } => run_source_command(
+                    SourceCommand::Snooze { name, until },
+                    &mut sources.anime.0,
+                    ("anime", "anime"),
+                    format,
+                )?,
+                AnimeCommand::Remove { name } => run_source_command(
+                    SourceCommand::Remove { name },
+                    &mut sources.anime.0,
+                    ("anime", "anime"),
+                    format,
+                )?,
+                AnimeCommand::Edit { name } => run_source_command(
+                    SourceCommand::Edit { name },
+                    &mut sources.anime.0,
+                    ("anime", "anime"),
+                    format,
+                )?,
+                AnimeCommand::Search => {
                     // search for anime, and if one is found and selected,
                     // add it to their config file
-                    Ok(new_anime) => {
+                    if let Some(new_anime) = Anime::interactive_search()? {
                         sources.anime.0.push((new_anime, None));
                         println!("Added a new anime.");
                     }
-                    // otherwise, print the returned error message
-                    Err(err) => eprintln!("{}", err),
-                },
+                }
+                AnimeCommand::Schedule { prune } => {
+                    let finished = sources.anime.print_schedule();
+                    if prune {
+                        // ask about each finished anime before removing any
+                        let mut to_remove = Vec::new();
+                        for index in finished {
+                            let prompt = format!(
+                                "\"{}\" has finished airing. Remove it? [y/N] ",
+                                sources.anime.0[index].0.name
+                            );
+                            match readline(&prompt, |input| match input.as_str() {
+                                "y" | "Y" | "yes" => Ok(true),
+                                "" | "n" | "N" | "no" => Ok(false),
+                                _ => Err("Please respond with a yes or no.".to_owned()),
+                            })? {
+                                ReadlineResult::Value(true) => to_remove.push(index),
+                                ReadlineResult::Value(false) => {}
+                                // stop asking, but keep the answers given so far
+                                ReadlineResult::Quit => break,
+                            }
+                        }
+                        // remove from the back so the other indices stay valid
+                        for &index in to_remove.iter().rev() {
+                            sources.anime.0.remove(index);
+                        }
+                        if to_remove.len() > 0 {
+                            println!("Removed {} finished anime.", to_remove.len());
+                        }
+                    }
+                }
+                AnimeCommand::Calendar { out, weeks } => {
+                    let mut events = Vec::new();
+                    for (anime, anime_events) in sources.anime.calendar_events(weeks) {
+                        match anime_events {
+                            Ok(anime_events) => events.extend(anime_events),
+                            Err(reason) => eprintln!("Skipped \"{}\": {}.", anime.name, reason),
+                        }
+                    }
+                    events.sort_by_key(|event| event.start);
+
+                    let out = expand_tilde(out);
+                    write(&out, ical::render_calendar("Anime", &events)).map_err(|err| {
+                        format!("Couldn't write the calendar to {}: {}", out.display(), err)
+                    })?;
+                    println!(
+                        "Wrote {} episode{} to {}.",
+                        events.len(),
+                        if events.len() == 1 { "" } else { "s" },
+                        out.display()
+                    );
+                }
             },
             Command::Manga(manga_command) => match manga_command {
                 // if both a name and manga id were provided,
-                MangaCommand::Add { name, id } => {
+                MangaCommand::Add {
+                    from_file: Some(path),
+                    ..
+                } => {
+                    let manga = read_sources_from_file(&path, "manga", Manga::from_id)?;
+                    sources
+                        .manga
+                        .0
+                        .extend(manga.into_iter().map(|manga| (manga, None)));
+                }
+                MangaCommand::Add {
+                    name,
+                    id,
+                    from_file: None,
+                    ..
+                } => {
                     if name.is_some() && id.is_some() {
                         // add the new manga to sitch
                         sources.manga.0.push((
-                            Manga {
+                            validated(Manga {
                                 name: name.unwrap(),
                                 id: id.unwrap(),
-                            },
+                                ..Default::default()
+                            })?,
                             None,
                         ));
                     } else {
                         // otherwise, let the user edit a JSON object in their
                         // preferred editor and attempt to save the edited JSON as
                         // an new manga
-                        edit_as_json(&json!({ "name": name, "id": id }), |edited| {
-                            let manga = Manga::deserialize(edited).map_err(|err| {
-                                format!("The edited object could not be parsed: {}.", err)
-                            })?;
-                            sources.manga.0.push((manga, None));
-                            Ok(())
-                        })?;
+                        add_in_editor(
+                            &mut sources.manga.0,
+                            &json!({ "name": name, "id": id }),
+                            format,
+                        )?;
                         println!("Added a new manga.");
                     }
                 }
-                MangaCommand::List => {
-                    for (manga, _last_checked) in &sources.manga.0 {
-                        println!("{}", manga.name);
-                    }
-                }
-                MangaCommand::Edit => {
-                    // attempt to edit all of the user's manga in their
-                    // preferred editor, and save if the edit was successful
-                    edit_as_json(&sources.manga.clone(), |edited| {
-                        let manga = Vec::<(Manga, Option<DateTime<Local>>)>::deserialize(edited)
-                            .map_err(|err| {
-                                format!("The edited manga could not be parsed: {}.", err)
-                            })?;
-                        sources.manga.0 = manga;
-                        Ok(())
-                    })?;
-                }
-                MangaCommand::Search => match Manga::interactive_search() {
-                    // search for anime, and if one is found and selected,
+                MangaCommand::List { verbose, json } => run_source_command(
+                    SourceCommand::List { verbose, json },
+                    &mut sources.manga.0,
+                    ("manga", "manga"),
+                    format,
+                )?,
+                MangaCommand::Rename { from, to } => run_source_command(
+                    SourceCommand::Rename { from, to },
+                    &mut sources.manga.0,
+                    ("manga", "manga"),
+                    format,
+                )?,
+                MangaCommand::Snooze { name, until, .. } => run_source_command(
+                    SourceCommand::Snooze { name, until },
+                    &mut sources.manga.0,
+                    ("manga", "manga"),
+                    format,
+                )?,
+                MangaCommand::Remove { name } => run_source_command(
+                    SourceCommand::Remove { name },
+                    &mut sources.manga.0,
+                    ("manga", "manga"),
+                    format,
+                )?,
+                MangaCommand::Edit { name } => run_source_command(
+                    SourceCommand::Edit { name },
+                    &mut sources.manga.0,
+                    ("manga", "manga"),
+                    format,
+                )?,
+                MangaCommand::Search => {
+                    // search for manga, and if one is found and selected,
                     // add it to their config file
-                    Ok(new_manga) => {
+                    if let Some(new_manga) = Manga::interactive_search()? {
                         sources.manga.0.push((new_manga, None));
                         println!("Added a new manga.");
                     }
-                    // otherwise, print the returned error message
-                    Err(err) => eprintln!("{}", err),
+                }
+            },
+            Command::Mastodon(mastodon_command) => match mastodon_command {
+                MastodonCommand::Add {
+                    name,
+                    account,
+                    exclude_boosts,
+                    ..
+                } => {
+                    let account = match account {
+                        Some(account) => Some(MastodonAccount::parse_account(&account)?),
+                        None => None,
+                    };
+                    // if both name and account are provided,
+                    if let (Some(name), Some((instance, username))) = (&name, &account) {
+                        // add the new mastodon account to sitch
+                        sources.mastodon.0.push((
+                            validated(MastodonAccount {
+                                name: name.clone(),
+                                instance: instance.clone(),
+                                username: username.clone(),
+                                exclude_boosts,
+                                ..Default::default()
+                            })?,
+                            None,
+                        ));
+                    } else {
+                        // otherwise, let the user edit a JSON object in their
+                        // preferred editor and attempt to save the edited JSON as
+                        // an new mastodon account
+                        let (instance, username) = match account {
+                            Some((instance, username)) => (Some(instance), Some(username)),
+                            None => (None, None),
+                        };
+                        add_in_editor(
+                            &mut sources.mastodon.0,
+                            &json!({
+                                "name": name,
+                                "instance": instance,
+                                "username": username,
+                                "exclude_boosts": exclude_boosts,
+                            }),
+                            format,
+                        )?;
+                    }
+                    println!("Added a new Mastodon account.");
+                }
+                MastodonCommand::List { verbose, json } => run_source_command(
+                    SourceCommand::List { verbose, json },
+                    &mut sources.mastodon.0,
+                    ("Mastodon account", "Mastodon accounts"),
+                    format,
+                )?,
+                MastodonCommand::Rename { from, to } => run_source_command(
+                    SourceCommand::Rename { from, to },
+                    &mut sources.mastodon.0,
+                    ("Mastodon account", "Mastodon accounts"),
+                    format,
+                )?,
+                MastodonCommand::Snooze { name, until, .. } => run_source_command(
+                    SourceCommand::Snooze { name, until },
+                    &mut sources.mastodon.0,
+                    ("Mastodon account", "Mastodon accounts"),
+                    format,
+                )?,
+                MastodonCommand::Remove { name } => run_source_command(
+                    SourceCommand::Remove { name },
+                    &mut sources.mastodon.0,
+                    ("Mastodon account", "Mastodon accounts"),
+                    format,
+                )?,
+                MastodonCommand::Edit { name } => run_source_command(
+                    SourceCommand::Edit { name },
+                    &mut sources.mastodon.0,
+                    ("Mastodon account", "Mastodon accounts"),
+                    format,
+                )?,
+            },
+            Command::Hn(hn_command) => match hn_command {
+                HnCommand::Add {
+                    name,
+                    query,
+                    min_points,
+                    ..
+                } => {
+                    // if both name and query are provided,
+                    if let (Some(name), Some(query)) = (&name, &query) {
+                        // add the new watch to sitch
+                        sources.hn.0.push((
+                            validated(HnWatch {
+                                name: name.clone(),
+                                query: query.clone(),
+                                min_points,
+                                ..Default::default()
+                            })?,
+                            None,
+                        ));
+                    } else {
+                        // otherwise, let the user edit a JSON object in their
+                        // preferred editor and attempt to save the edited JSON as
+                        // an new watch
+                        add_in_editor(
+                            &mut sources.hn.0,
+                            &json!({
+                                "name": name,
+                                "query": query,
+                                "min_points": min_points,
+                            }),
+                            format,
+                        )?;
+                    }
+                    println!("Added a new Hacker News watch.");
+                }
+                HnCommand::List { verbose, json } => run_source_command(
+                    SourceCommand::List { verbose, json },
+                    &mut sources.hn.0,
+                    ("Hacker News watch", "Hacker News watches"),
+                    format,
+                )?,
+                HnCommand::Rename { from, to } => run_source_command(
+                    SourceCommand::Rename { from, to },
+                    &mut sources.hn.0,
+                    ("Hacker News watch", "Hacker News watches"),
+                    format,
+                )?,
+                HnCommand::Snooze { name, until, .. } => run_source_command(
+                    SourceCommand::Snooze { name, until },
+                    &mut sources.hn.0,
+                    ("Hacker News watch", "Hacker News watches"),
+                    format,
+                )?,
+                HnCommand::Remove { name } => run_source_command(
+                    SourceCommand::Remove { name },
+                    &mut sources.hn.0,
+                    ("Hacker News watch", "Hacker News watches"),
+                    format,
+                )?,
+                HnCommand::Edit { name } => run_source_command(
+                    SourceCommand::Edit { name },
+                    &mut sources.hn.0,
+                    ("Hacker News watch", "Hacker News watches"),
+                    format,
+                )?,
+            },
+            Command::Crates(crates_command) => match crates_command {
+                CratesCommand::Add {
+                    name,
+                    crate_name,
+                    exclude_prereleases,
+                    ..
+                } => {
+                    // if both name and crate name are provided,
+                    if let (Some(name), Some(crate_name)) = (&name, &crate_name) {
+                        // add the new crate to sitch
+                        sources.crates.0.push((
+                            validated(RustCrate {
+                                name: name.clone(),
+                                crate_name: crate_name.clone(),
+                                exclude_prereleases,
+                                ..Default::default()
+                            })?,
+                            None,
+                        ));
+                    } else {
+                        // otherwise, let the user edit a JSON object in their
+                        // preferred editor and attempt to save the edited JSON as
+                        // an new crate
+                        add_in_editor(
+                            &mut sources.crates.0,
+                            &json!({
+                                "name": name,
+                                "crate_name": crate_name,
+                                "exclude_prereleases": exclude_prereleases,
+                            }),
+                            format,
+                        )?;
+                    }
+                    println!("Added a new crate.");
+                }
+                CratesCommand::List { verbose, json } => run_source_command(
+                    SourceCommand::List { verbose, json },
+                    &mut sources.crates.0,
+                    ("crate", "crates"),
+                    format,
+                )?,
+                CratesCommand::Rename { from, to } => run_source_command(
+                    SourceCommand::Rename { from, to },
+                    &mut sources.crates.0,
+                    ("crate", "crates"),
+                    format,
+                )?,
+                CratesCommand::Snooze { name, until, .. } => run_source_command(
+                    SourceCommand::Snooze { name, until },
+                    &mut sources.crates.0,
+                    ("crate", "crates"),
+                    format,
+                )?,
+                CratesCommand::Remove { name } => run_source_command(
+                    SourceCommand::Remove { name },
+                    &mut sources.crates.0,
+                    ("crate", "crates"),
+                    format,
+                )?,
+                CratesCommand::Edit { name } => run_source_command(
+                    SourceCommand::Edit { name },
+                    &mut sources.crates.0,
+                    ("crate", "crates"),
+                    format,
+                )?,
+            },
+            Command::GitHub(github_command) => match github_command {
+                GitHubCommand::Add {
+                    name,
+                    owner,
+                    repo,
+                    branch,
+                    collapse,
+                    ..
+                } => {
+                    // if the name, owner, and repo are all provided,
+                    if let (Some(name), Some(owner), Some(repo)) = (&name, &owner, &repo) {
+                        // add the new repository to sitch
+                        sources.github.repos.push((
+                            validated(GitHubRepo {
+                                name: name.clone(),
+                                owner: owner.clone(),
+                                repo: repo.clone(),
+                                branch,
+                                collapse,
+                                ..Default::default()
+                            })?,
+                            None,
+                        ));
+                    } else {
+                        // otherwise, let the user edit a JSON object in their
+                        // preferred editor and attempt to save the edited JSON as
+                        // an new repository
+                        add_in_editor(
+                            &mut sources.github.repos,
+                            &json!({
+                                "name": name,
+                                "owner": owner,
+                                "repo": repo,
+                                "branch": branch,
+                                "collapse": collapse,
+                            }),
+                            format,
+                        )?;
+                    }
+                    println!("Added a new GitHub repository.");
+                }
+                GitHubCommand::List { verbose, json } => run_source_command(
+                    SourceCommand::List { verbose, json },
+                    &mut sources.github.repos,
+                    ("GitHub repository", "GitHub repositories"),
+                    format,
+                )?,
+                GitHubCommand::Rename { from, to } => run_source_command(
+                    SourceCommand::Rename { from, to },
+                    &mut sources.github.repos,
+                    ("GitHub repository", "GitHub repositories"),
+                    format,
+                )?,
+                GitHubCommand::Snooze { name, until, .. } => run_source_command(
+                    SourceCommand::Snooze { name, until },
+                    &mut sources.github.repos,
+                    ("GitHub repository", "GitHub repositories"),
+                    format,
+                )?,
+                GitHubCommand::Remove { name } => run_source_command(
+                    SourceCommand::Remove { name },
+                    &mut sources.github.repos,
+                    ("GitHub repository", "GitHub repositories"),
+                    format,
+                )?,
+                GitHubCommand::Edit { name } => run_source_command(
+                    SourceCommand::Edit { name },
+                    &mut sources.github.repos,
+                    ("GitHub repository", "GitHub repositories"),
+                    format,
+                )?,
+            },
+            Command::Custom(custom_command) => match custom_command {
+                CustomCommand::Add {
+                    name,
+                    url,
+                    items_pointer,
+                    title_pointer,
+                    link_pointer,
+                    date_pointer,
+                    date_format,
+                    ..
+                } => {
+                    // if all of the required fields are provided,
+                    if let (
+                        Some(name),
+                        Some(url),
+                        Some(items_pointer),
+                        Some(title_pointer),
+                        Some(link_pointer),
+                        Some(date_pointer),
+                    ) = (
+                        &name,
+                        &url,
+                        &items_pointer,
+                        &title_pointer,
+                        &link_pointer,
+                        &date_pointer,
+                    ) {
+                        // add the new source to sitch
+                        sources.custom.0.push((
+                            validated(CustomSource {
+                                name: name.clone(),
+                                url: url.clone(),
+                                items_pointer: items_pointer.clone(),
+                                title_pointer: title_pointer.clone(),
+                                link_pointer: link_pointer.clone(),
+                                date_pointer: date_pointer.clone(),
+                                date_format,
+                                ..Default::default()
+                            })?,
+                            None,
+                        ));
+                    } else {
+                        // otherwise, let the user edit a JSON object in their
+                        // preferred editor and attempt to save the edited JSON as
+                        // an new source
+                        add_in_editor(
+                            &mut sources.custom.0,
+                            &json!({
+                                "name": name,
+                                "url": url,
+                                "items_pointer": items_pointer,
+                                "title_pointer": title_pointer,
+                                "link_pointer": link_pointer,
+                                "date_pointer": date_pointer,
+                                "date_format": date_format,
+                            }),
+                            format,
+                        )?;
+                    }
+                    println!("Added a new custom source.");
+                }
+                CustomCommand::List { verbose, json } => run_source_command(
+                    SourceCommand::List { verbose, json },
+                    &mut sources.custom.0,
+                    ("custom source", "custom sources"),
+                    format,
+                )?,
+                CustomCommand::Rename { from, to } => run_source_command(
+                    SourceCommand::Rename { from, to },
+                    &mut sources.custom.0,
+                    ("custom source", "custom sources"),
+                    format,
+                )?,
+                CustomCommand::Snooze { name, until, .. } => run_source_command(
+                    SourceCommand::Snooze { name, until },
+                    &mut sources.custom.0,
+                    ("custom source", "custom sources"),
+                    format,
+                )?,
+                CustomCommand::Remove { name } => run_source_command(
+                    SourceCommand::Remove { name },
+                    &mut sources.custom.0,
+                    ("custom source", "custom sources"),
+                    format,
+                )?,
+                CustomCommand::Edit { name } => run_source_command(
+                    SourceCommand::Edit { name },
+                    &mut sources.custom.0,
+                    ("custom source", "custom sources"),
+                    format,
+                )?,
+            },
+            Command::Scrape(scrape_command) => match scrape_command {
+                ScrapeCommand::Add {
+                    name,
+                    url,
+                    item_selector,
+                    title_selector,
+                    link_selector,
+                    date_selector,
+                    date_format,
+                    ..
+                } => {
+                    // if the name, URL, and item selector are provided,
+                    if let (Some(name), Some(url), Some(item_selector)) =
+                        (&name, &url, &item_selector)
+                    {
+                        // add the new page to sitch
+                        sources.scrape.0.push((
+                            validated(ScrapeSource {
+                                name: name.clone(),
+                                url: url.clone(),
+                                item_selector: item_selector.clone(),
+                                title_selector,
+                                link_selector,
+                                date_selector,
+                                date_format,
+                                ..Default::default()
+                            })?,
+                            None,
+                        ));
+                    } else {
+                        // otherwise, let the user edit a JSON object in their
+                        // preferred editor and attempt to save the edited JSON as
+                        // an new page
+                        add_in_editor(
+                            &mut sources.scrape.0,
+                            &json!({
+                                "name": name,
+                                "url": url,
+                                "item_selector": item_selector,
+                                "title_selector": title_selector,
+                                "link_selector": link_selector,
+                                "date_selector": date_selector,
+                                "date_format": date_format,
+                            }),
+                            format,
+                        )?;
+                    }
+                    println!("Added a new scraped page.");
+                }
+                ScrapeCommand::List { verbose, json } => run_source_command(
+                    SourceCommand::List { verbose, json },
+                    &mut sources.scrape.0,
+                    ("scraped page", "scraped pages"),
+                    format,
+                )?,
+                ScrapeCommand::Rename { from, to } => run_source_command(
+                    SourceCommand::Rename { from, to },
+                    &mut sources.scrape.0,
+                    ("scraped page", "scraped pages"),
+                    format,
+                )?,
+                ScrapeCommand::Snooze { name, until, .. } => run_source_command(
+                    SourceCommand::Snooze { name, until },
+                    &mut sources.scrape.0,
+                    ("scraped page", "scraped pages"),
+                    format,
+                )?,
+                ScrapeCommand::Remove { name } => run_source_command(
+                    SourceCommand::Remove { name },
+                    &mut sources.scrape.0,
+                    ("scraped page", "scraped pages"),
+                    format,
+                )?,
+                ScrapeCommand::Edit { name } => run_source_command(
+                    SourceCommand::Edit { name },
+                    &mut sources.scrape.0,
+                    ("scraped page", "scraped pages"),
+                    format,
+                )?,
+                ScrapeCommand::Test { name } => {
+                    let index = find_source(&sources.scrape.0, &name)?;
+                    let items = sources.scrape.0[index].0.extract()?;
+                    if items.is_empty() {
+                        println!("The item selector didn't match anything.");
+                    }
+                    for (index, item) in items.iter().enumerate() {
+                        let date = match item.date {
+                            Some(date) => date.format("%B %-e, %Y at %-l:%M %p").to_string(),
+                            None => "no date (new if the link wasn't seen last time)".to_owned(),
+                        };
+                        println!("{}: {}", index + 1, item.title);
+                        println!("   {}", item.link);
+                        println!("   {}", date);
+                    }
+                }
+            },
+            Command::Watch(watch_command) => match watch_command {
+                WatchCommand::Add {
+                    name,
+                    url,
+                    selector,
+                    ..
+                } => {
+                    // if both name and URL are provided,
+                    if let (Some(name), Some(url)) = (&name, &url) {
+                        // add the new page to sitch
+                        sources.watch.0.push((
+                            validated(WatchedPage {
+                                name: name.clone(),
+                                url: url.clone(),
+                                selector,
+                                ..Default::default()
+                            })?,
+                            None,
+                        ));
+                    } else {
+                        // otherwise, let the user edit a JSON object in their
+                        // preferred editor and attempt to save the edited JSON as
+                        // an new page
+                        add_in_editor(
+                            &mut sources.watch.0,
+                            &json!({
+                                "name": name,
+                                "url": url,
+                                "selector": selector,
+                            }),
+                            format,
+                        )?;
+                    }
+                    println!("Added a new watched page.");
+                }
+                WatchCommand::List { verbose, json } => run_source_command(
+                    SourceCommand::List { verbose, json },
+                    &mut sources.watch.0,
+                    ("watched page", "watched pages"),
+                    format,
+                )?,
+                WatchCommand::Rename { from, to } => run_source_command(
+                    SourceCommand::Rename { from, to },
+                    &mut sources.watch.0,
+                    ("watched page", "watched pages"),
+                    format,
+                )?,
+                WatchCommand::Snooze { name, until, .. } => run_source_command(
+                    SourceCommand::Snooze { name, until },
+                    &mut sources.watch.0,
+                    ("watched page", "watched pages"),
+                    format,
+                )?,
+                WatchCommand::Remove { name } => run_source_command(
+                    SourceCommand::Remove { name },
+                    &mut sources.watch.0,
+                    ("watched page", "watched pages"),
+                    format,
+                )?,
+                WatchCommand::Edit { name } => run_source_command(
+                    SourceCommand::Edit { name },
+                    &mut sources.watch.0,
+                    ("watched page", "watched pages"),
+                    format,
+                )?,
+            },
+            Command::Npm(npm_command) => match npm_command {
+                NpmCommand::Add {
+                    name,
+                    package,
+                    exclude_tags,
+                    ..
+                } => {
+                    // if both name and package are provided,
+                    if let (Some(name), Some(package)) = (&name, &package) {
+                        // add the new package to sitch
+                        sources.npm.0.push((
+                            validated(NpmPackage {
+                                name: name.clone(),
+                                package: package.clone(),
+                                exclude_tags,
+                                ..Default::default()
+                            })?,
+                            None,
+                        ));
+                    } else {
+                        // otherwise, let the user edit a JSON object in their
+                        // preferred editor and attempt to save the edited JSON as
+                        // an new package
+                        add_in_editor(
+                            &mut sources.npm.0,
+                            &json!({
+                                "name": name,
+                                "package": package,
+                                "exclude_tags": exclude_tags,
+                            }),
+                            format,
+                        )?;
+                    }
+                    println!("Added a new npm package.");
+                }
+                NpmCommand::List { verbose, json } => run_source_command(
+                    SourceCommand::List { verbose, json },
+                    &mut sources.npm.0,
+                    ("npm package", "npm packages"),
+                    format,
+                )?,
+                NpmCommand::Rename { from, to } => run_source_command(
+                    SourceCommand::Rename { from, to },
+                    &mut sources.npm.0,
+                    ("npm package", "npm packages"),
+                    format,
+                )?,
+                NpmCommand::Snooze { name, until, .. } => run_source_command(
+                    SourceCommand::Snooze { name, until },
+                    &mut sources.npm.0,
+                    ("npm package", "npm packages"),
+                    format,
+                )?,
+                NpmCommand::Remove { name } => run_source_command(
+                    SourceCommand::Remove { name },
+                    &mut sources.npm.0,
+                    ("npm package", "npm packages"),
+                    format,
+                )?,
+                NpmCommand::Edit { name } => run_source_command(
+                    SourceCommand::Edit { name },
+                    &mut sources.npm.0,
+                    ("npm package", "npm packages"),
+                    format,
+                )?,
+            },
+            Command::Arxiv(arxiv_command) => match arxiv_command {
+                ArxivCommand::Add {
+                    name,
+                    query,
+                    max_results,
+                    ..
+                } => {
+                    // if both name and query are provided,
+                    if let (Some(name), Some(query)) = (&name, &query) {
+                        // add the new query to sitch
+                        sources.arxiv.0.push((
+                            validated(ArxivQuery {
+                                name: name.clone(),
+                                query: query.clone(),
+                                max_results,
+                                ..Default::default()
+                            })?,
+                            None,
+                        ));
+                    } else {
+                        // otherwise, let the user edit a JSON object in their
+                        // preferred editor and attempt to save the edited JSON as
+                        // an new query
+                        add_in_editor(
+                            &mut sources.arxiv.0,
+                            &json!({
+                                "name": name,
+                                "query": query,
+                                "max_results": max_results,
+                            }),
+                            format,
+                        )?;
+                    }
+                    println!("Added a new arXiv query.");
+                }
+                ArxivCommand::List { verbose, json } => run_source_command(
+                    SourceCommand::List { verbose, json },
+                    &mut sources.arxiv.0,
+                    ("arXiv query", "arXiv queries"),
+                    format,
+                )?,
+                ArxivCommand::Rename { from, to } => run_source_command(
+                    SourceCommand::Rename { from, to },
+                    &mut sources.arxiv.0,
+                    ("arXiv query", "arXiv queries"),
+                    format,
+                )?,
+                ArxivCommand::Snooze { name, until, .. } => run_source_command(
+                    SourceCommand::Snooze { name, until },
+                    &mut sources.arxiv.0,
+                    ("arXiv query", "arXiv queries"),
+                    format,
+                )?,
+                ArxivCommand::Remove { name } => run_source_command(
+                    SourceCommand::Remove { name },
+                    &mut sources.arxiv.0,
+                    ("arXiv query", "arXiv queries"),
+                    format,
+                )?,
+                ArxivCommand::Edit { name } => run_source_command(
+                    SourceCommand::Edit { name },
+                    &mut sources.arxiv.0,
+                    ("arXiv query", "arXiv queries"),
+                    format,
+                )?,
+            },
+            Command::Itch(itch_command) => match itch_command {
+                ItchCommand::Add { name, url, .. } => {
+                    // if both name and URL are provided,
+                    if let (Some(name), Some(url)) = (&name, &url) {
+                        // add the new creator to sitch
+                        sources.itch.0.push((
+                            validated(ItchCreator {
+                                name: name.clone(),
+                                url: url.clone(),
+                                ..Default::default()
+                            })?,
+                            None,
+                        ));
+                    } else {
+                        // otherwise, let the user edit a JSON object in their
+                        // preferred editor and attempt to save the edited JSON as
+                        // an new creator
+                        add_in_editor(
+                            &mut sources.itch.0,
+                            &json!({ "name": name, "url": url }),
+                            format,
+                        )?;
+                    }
+                    println!("Added a new itch.io creator.");
+                }
+                ItchCommand::List { verbose, json } => run_source_command(
+                    SourceCommand::List { verbose, json },
+                    &mut sources.itch.0,
+                    ("itch.io creator", "itch.io creators"),
+                    format,
+                )?,
+                ItchCommand::Rename { from, to } => run_source_command(
+                    SourceCommand::Rename { from, to },
+                    &mut sources.itch.0,
+                    ("itch.io creator", "itch.io creators"),
+                    format,
+                )?,
+                ItchCommand::Snooze { name, until, .. } => run_source_command(
+                    SourceCommand::Snooze { name, until },
+                    &mut sources.itch.0,
+                    ("itch.io creator", "itch.io creators"),
+                    format,
+                )?,
+                ItchCommand::Remove { name } => run_source_command(
+                    SourceCommand::Remove { name },
+                    &mut sources.itch.0,
+                    ("itch.io creator", "itch.io creators"),
+                    format,
+                )?,
+                ItchCommand::Edit { name } => run_source_command(
+                    SourceCommand::Edit { name },
+                    &mut sources.itch.0,
+                    ("itch.io creator", "itch.io creators"),
+                    format,
+                )?,
+            },
+            Command::Gmail(gmail_command) => match gmail_command {
+                GmailCommand::Add { name, filter, .. } => {
+                    sources.gmail.filters.push((
+                        validated(GmailFilter {
+                            name: name.unwrap_or(filter.clone()),
+                            filter,
+                            ..Default::default()
+                        })?,
+                        None,
+                    ));
+                    println!("Added a new Gmail filter.");
+                }
+                GmailCommand::List { verbose, json } => run_source_command(
+                    SourceCommand::List { verbose, json },
+                    &mut sources.gmail.filters,
+                    ("Gmail filter", "Gmail filters"),
+                    format,
+                )?,
+                GmailCommand::Rename { from, to } => run_source_command(
+                    SourceCommand::Rename { from, to },
+                    &mut sources.gmail.filters,
+                    ("Gmail filter", "Gmail filters"),
+                    format,
+                )?,
+                GmailCommand::Snooze { name, until, .. } => run_source_command(
+                    SourceCommand::Snooze { name, until },
+                    &mut sources.gmail.filters,
+                    ("Gmail filter", "Gmail filters"),
+                    format,
+                )?,
+                GmailCommand::Remove { name } => run_source_command(
+                    SourceCommand::Remove { name },
+                    &mut sources.gmail.filters,
+                    ("Gmail filter", "Gmail filters"),
+                    format,
+                )?,
+                GmailCommand::Edit { name } => run_source_command(
+                    SourceCommand::Edit { name },
+                    &mut sources.gmail.filters,
+                    ("Gmail filter", "Gmail filters"),
+                    format,
+                )?,
+                GmailCommand::ApiKey(oauth_command) => match oauth_command {
+                    // set the client ID from the file Google gives for it,
+                    // signing in right away if possible
+                    GmailOauthCommand::Set { location } => {
+                        let client_json = match &location {
+                            Some(location) => read_to_string(location).map_err(|err| {
+                                format!("Couldn't read {}: {}", location.to_string_lossy(), err)
+                            })?,
+                            None => {
+                                let mut client_json = String::new();
+                                std::io::stdin()
+                                    .read_to_string(&mut client_json)
+                                    .map_err(|err| format!("Couldn't read stdin: {}", err))?;
+                                client_json
+                            }
+                        };
+                        let mut oauth = GmailOauth::from_client_json(&client_json)?;
+                        // stdin can't be read again after piping the file in
+                        if location.is_some() && atty::is(atty::Stream::Stdin) {
+                            oauth.sign_in()?;
+                            println!("Signed in to Gmail.");
+                        } else {
+                            println!(
+                                "Set the client ID. Run `sitch gmail apikey login` to sign in."
+                            );
+                        }
+                        sources.gmail.oauth = Some(oauth);
+                    }
+                    GmailOauthCommand::Login => {
+                        let oauth = sources.gmail.oauth.as_mut().ok_or(
+                            "No client ID is set. Set one with `sitch gmail apikey set` first.",
+                        )?;
+                        oauth.sign_in()?;
+                        println!("Signed in to Gmail.");
+                    }
+                    GmailOauthCommand::Clear => {
+                        sources.gmail.oauth = None;
+                    }
+                    // show the client ID, but never the secret or token
+                    GmailOauthCommand::Show => {
+                        if let Some(oauth) = &sources.gmail.oauth {
+                            println!(
+                                "{} ({})",
+                                oauth.client_id,
+                                if oauth.is_signed_in() {
+                                    "signed in"
+                                } else {
+                                    "not signed in"
+                                }
+                            );
+                        }
+                    }
                 },
             },
+            Command::Webtoon(webtoon_command) => match webtoon_command {
+                WebtoonCommand::Add { name, url, .. } => {
+                    // read the series from its URL and make sure it loads
+                    let mut series = WebtoonSeries::from_url(&url)?;
+                    let channel = series.fetch_channel()?;
+                    series.name = name.unwrap_or(channel.title().trim().to_owned());
+                    series.validate()?;
+                    println!("Added the Webtoon series \"{}\".", series.name);
+                    sources.webtoon.0.push((series, None));
+                }
+                WebtoonCommand::List { verbose, json } => run_source_command(
+                    SourceCommand::List { verbose, json },
+                    &mut sources.webtoon.0,
+                    ("Webtoon series", "Webtoon series"),
+                    format,
+                )?,
+                WebtoonCommand::Rename { from, to } => run_source_command(
+                    SourceCommand::Rename { from, to },
+                    &mut sources.webtoon.0,
+                    ("Webtoon series", "Webtoon series"),
+                    format,
+                )?,
+                WebtoonCommand::Snooze { name, until, .. } => run_source_command(
+                    SourceCommand::Snooze { name, until },
+                    &mut sources.webtoon.0,
+                    ("Webtoon series", "Webtoon series"),
+                    format,
+                )?,
+                WebtoonCommand::Remove { name } => run_source_command(
+                    SourceCommand::Remove { name },
+                    &mut sources.webtoon.0,
+                    ("Webtoon series", "Webtoon series"),
+                    format,
+                )?,
+                WebtoonCommand::Edit { name } => run_source_command(
+                    SourceCommand::Edit { name },
+                    &mut sources.webtoon.0,
+                    ("Webtoon series", "Webtoon series"),
+                    format,
+                )?,
+            },
+            Command::Patreon(patreon_command) => match patreon_command {
+                PatreonCommand::Add { name, url, .. } => {
+                    // find the creator's campaign before adding them
+                    let creator = validated(PatreonCreator::from_url(&url, name)?)?;
+                    println!("Added the Patreon creator \"{}\".", creator.name);
+                    sources.patreon.0.push((creator, None));
+                }
+                PatreonCommand::List { verbose, json } => run_source_command(
+                    SourceCommand::List { verbose, json },
+                    &mut sources.patreon.0,
+                    ("Patreon creator", "Patreon creators"),
+                    format,
+                )?,
+                PatreonCommand::Rename { from, to } => run_source_command(
+                    SourceCommand::Rename { from, to },
+                    &mut sources.patreon.0,
+                    ("Patreon creator", "Patreon creators"),
+                    format,
+                )?,
+                PatreonCommand::Snooze { name, until, .. } => run_source_command(
+                    SourceCommand::Snooze { name, until },
+                    &mut sources.patreon.0,
+                    ("Patreon creator", "Patreon creators"),
+                    format,
+                )?,
+                PatreonCommand::Remove { name } => run_source_command(
+                    SourceCommand::Remove { name },
+                    &mut sources.patreon.0,
+                    ("Patreon creator", "Patreon creators"),
+                    format,
+                )?,
+                PatreonCommand::Edit { name } => run_source_command(
+                    SourceCommand::Edit { name },
+                    &mut sources.patreon.0,
+                    ("Patreon creator", "Patreon creators"),
+                    format,
+                )?,
+            },
+            Command::Authors(authors_command) => match authors_command {
+                AuthorsCommand::Add { name, author, .. } => {
+                    let author = validated(Author::from_id_or_url(&author, name)?)?;
+                    println!("Added the author \"{}\".", author.name);
+                    sources.authors.0.push((author, None));
+                }
+                AuthorsCommand::List { verbose, json } => run_source_command(
+                    SourceCommand::List { verbose, json },
+                    &mut sources.authors.0,
+                    ("author", "authors"),
+                    format,
+                )?,
+                AuthorsCommand::Rename { from, to } => run_source_command(
+                    SourceCommand::Rename { from, to },
+                    &mut sources.authors.0,
+                    ("author", "authors"),
+                    format,
+                )?,
+                AuthorsCommand::Snooze { name, until, .. } => run_source_command(
+                    SourceCommand::Snooze { name, until },
+                    &mut sources.authors.0,
+                    ("author", "authors"),
+                    format,
+                )?,
+                AuthorsCommand::Remove { name } => run_source_command(
+                    SourceCommand::Remove { name },
+                    &mut sources.authors.0,
+                    ("author", "authors"),
+                    format,
+                )?,
+                AuthorsCommand::Edit { name } => run_source_command(
+                    SourceCommand::Edit { name },
+                    &mut sources.authors.0,
+                    ("author", "authors"),
+                    format,
+                )?,
+            },
+            Command::Pypi(pypi_command) => match pypi_command {
+                PypiCommand::Add {
+                    name,
+                    package,
+                    exclude_prereleases,
+                    ..
+                } => {
+                    // if both name and package are provided,
+                    if let (Some(name), Some(package)) = (&name, &package) {
+                        // add the new package to sitch
+                        sources.pypi.0.push((
+                            validated(PypiPackage {
+                                name: name.clone(),
+                                package: package.clone(),
+                                exclude_prereleases,
+                                ..Default::default()
+                            })?,
+                            None,
+                        ));
+                    } else {
+                        // otherwise, let the user edit a JSON object in their
+                        // preferred editor and attempt to save the edited JSON as
+                        // an new package
+                        add_in_editor(
+                            &mut sources.pypi.0,
+                            &json!({
+                                "name": name,
+                                "package": package,
+                                "exclude_prereleases": exclude_prereleases,
+                            }),
+                            format,
+                        )?;
+                    }
+                    println!("Added a new PyPI package.");
+                }
+                PypiCommand::List { verbose, json } => run_source_command(
+                    SourceCommand::List { verbose, json },
+                    &mut sources.pypi.0,
+                    ("PyPI package", "PyPI packages"),
+                    format,
+                )?,
+                PypiCommand::Rename { from, to } => run_source_command(
+                    SourceCommand::Rename { from, to },
+                    &mut sources.pypi.0,
+                    ("PyPI package", "PyPI packages"),
+                    format,
+                )?,
+                PypiCommand::Snooze { name, until, .. } => run_source_command(
+                    SourceCommand::Snooze { name, until },
+                    &mut sources.pypi.0,
+                    ("PyPI package", "PyPI packages"),
+                    format,
+                )?,
+                PypiCommand::Remove { name } => run_source_command(
+                    SourceCommand::Remove { name },
+                    &mut sources.pypi.0,
+                    ("PyPI package", "PyPI packages"),
+                    format,
+                )?,
+                PypiCommand::Edit { name } => run_source_command(
+                    SourceCommand::Edit { name },
+                    &mut sources.pypi.0,
+                    ("PyPI package", "PyPI packages"),
+                    format,
+                )?,
+            },
+            Command::Wikipedia(wikipedia_command) => match wikipedia_command {
+                WikipediaCommand::Add {
+                    name,
+                    title,
+                    wiki,
+                    collapse,
+                    exclude_minor,
+                    exclude_bots,
+                    ..
+                } => {
+                    let article = validated(WikipediaArticle {
+                        name: name.unwrap_or(title.clone()),
+                        title,
+                        wiki,
+                        collapse,
+                        exclude_minor,
+                        exclude_bots,
+                        ..Default::default()
+                    })?;
+                    println!("Added the article \"{}\".", article.name);
+                    sources.wikipedia.0.push((article, None));
+                }
+                WikipediaCommand::List { verbose, json } => run_source_command(
+                    SourceCommand::List { verbose, json },
+                    &mut sources.wikipedia.0,
+                    ("wiki article", "wiki articles"),
+                    format,
+                )?,
+                WikipediaCommand::Rename { from, to } => run_source_command(
+                    SourceCommand::Rename { from, to },
+                    &mut sources.wikipedia.0,
+                    ("wiki article", "wiki articles"),
+                    format,
+                )?,
+                WikipediaCommand::Snooze { name, until, .. } => run_source_command(
+                    SourceCommand::Snooze { name, until },
+                    &mut sources.wikipedia.0,
+                    ("wiki article", "wiki articles"),
+                    format,
+                )?,
+                WikipediaCommand::Remove { name } => run_source_command(
+                    SourceCommand::Remove { name },
+                    &mut sources.wikipedia.0,
+                    ("wiki article", "wiki articles"),
+                    format,
+                )?,
+                WikipediaCommand::Edit { name } => run_source_command(
+                    SourceCommand::Edit { name },
+                    &mut sources.wikipedia.0,
+                    ("wiki article", "wiki articles"),
+                    format,
+                )?,
+            },
+            Command::Merge {
+                from,
+                from_state,
+                dry_run,
+            } => {
+                let other = Sources::load_other(&from, from_state)?;
+                let interactive = atty::is(atty::Stream::Stdin);
+                let changes = sources.merge(other, |location, ours, theirs| {
+                    // keep this config's name unless the user picks the other
+                    if dry_run || !interactive {
+                        println!(
+                            "! {} is named \"{}\" here and \"{}\" in {}, keeping \"{}\"",
+                            location,
+                            ours,
+                            theirs,
+                            from.to_string_lossy(),
+                            ours
+                        );
+                        return Ok(ours.to_owned());
+                    }
+                    println!(
+                        "{} is named \"{}\" here and \"{}\" in {}.",
+                        location,
+                        ours,
+                        theirs,
+                        from.to_string_lossy()
+                    );
+                    readline(
+                        "Keep which name? [1 = here, 2 = other] ",
+                        |input| match input.trim() {
+                            "1" => Ok(ours.to_owned()),
+                            "2" => Ok(theirs.to_owned()),
+                            _ => Err("Please respond with 1 or 2.".to_owned()),
+                        },
+                    )?
+                    .value()
+                    .ok_or("Stopped without picking a name.".to_owned())
+                })?;
+
+                for change in &changes {
+                    // only print color if the output isn't piped
+                    if atty::is(atty::Stream::Stdout) && change.starts_with('+') {
+                        println!("{}", change.style(Role::Success));
+                    } else if atty::is(atty::Stream::Stdout) {
+                        println!("{}", change.style(Role::Highlight));
+                    } else {
+                        println!("{}", change);
+                    }
+                }
+                if changes.is_empty() {
+                    println!("Nothing to merge from {}.", from.to_string_lossy());
+                }
+                // don't save anything when only showing the changes
+                if dry_run {
+                    return Ok(());
+                }
+            }
+            Command::Doctor => {
+                // diagnose sources without saving anything
+                if sources.diagnose() {
+                    return Ok(());
+                } else {
+                    process::exit(1);
+                }
+            }
+            Command::Stats { json, stale } => {
+                let mut stats = sources.stats();
+                sort_by_activity(&mut stats);
+                if let Some(window) = stale {
+                    stats.retain(|source| is_stale(source, window));
+                }
+
+                if json {
+                    let report = json!({
+                        "platforms": platform_totals(&stats),
+                        "sources": stats,
+                    });
+                    println!("{}", serde_json::to_string_pretty(&report).unwrap());
+                } else if stale.is_some() {
+                    if stats.is_empty() {
+                        println!("Every source has updated since then.");
+                    } else {
+                        print!("{}", render_table(&[], &stats));
+                        // only offer to prune the sources when someone's there to answer
+                        if atty::is(atty::Stream::Stdin) {
+                            eprintln!("");
+                            prune_stale(&mut sources, &stats)?;
+                        }
+                    }
+                } else if stats.is_empty() {
+                    println!("No sources are configured.");
+                } else {
+                    print!("{}", render_table(&platform_totals(&stats), &stats));
+                }
+            }
+            Command::History(HistoryCommand::Export { csv }) => {
+                let updates = export::from_history(&sources.stats(), &sources.history);
+                let contents = export::to_csv(&updates);
+                if csv.to_str() == Some("-") {
+                    print!("{}", contents);
+                } else {
+                    let csv = expand_tilde(csv);
+                    write(&csv, contents).map_err(|err| {
+                        format!("Couldn't write the history to {}: {}", csv.display(), err)
+                    })?;
+                    eprintln!(
+                        "Exported {} update{} to {}.",
+                        updates.len(),
+                        if updates.len() == 1 { "" } else { "s" },
+                        csv.display()
+                    );
+                }
+            }
+            Command::Unread => {
+                print_unread(&sources.unread);
+            }
+            Command::Open { name } => {
+                let (type_name, source_name) = find_source_anywhere(&sources, &name)?;
+                let page = sources
+                    .find_named(type_name, &source_name)
+                    .ok_or(format!("No source is named \"{}\".", name))?
+                    .page()?;
+                println!("{}", page.style(Role::Link));
+                webbrowser::open(&page)
+                    .map_err(|err| format!("Couldn't open {} in your browser: {}", page, err))?;
+            }
+            Command::Ignore {
+                pattern: None,
+                command: Some(IgnoreCommand::List),
+            } => {
+                print_ignored(sources.ignore.as_deref().unwrap_or(&[]));
+            }
+            Command::Ignore {
+                pattern: None,
+                command: Some(IgnoreCommand::Remove { pattern }),
+            } => {
+                let removed = sources.remove_ignored(&pattern)?;
+                println!("Stopped ignoring \"{}\".", removed);
+            }
+            Command::Ignore {
+                pattern: Some(pattern),
+                command: None,
+            } => match sources.add_ignored(&pattern)? {
+                Some(0) => println!("Ignoring \"{}\" from now on.", pattern.trim()),
+                Some(count) => println!(
+                    "Ignoring \"{}\" from now on, and marked {} unread update{} it matches as read.",
+                    pattern.trim(),
+                    count,
+                    if count == 1 { "" } else { "s" }
+                ),
+                None => println!("\"{}\" is already ignored.", pattern.trim()),
+            },
+            Command::Ignore { .. } => {
+                return Err(
+                    "Give a link or pattern to ignore, or `list` or `remove` one.".to_owned(),
+                )
+            }
+            Command::Read { items } => {
+                let count = sources.mark_read(&items)?;
+                println!(
+                    "Marked {} update{} as read, {} left unread.",
+                    count,
+                    if count == 1 { "" } else { "s" },
+                    sources.unread.len()
+                );
+            }
+            Command::Config(ConfigCommand::Convert { to }) => {
+                let (new_path, backup_path) =
+                    sources.convert(args.config.clone(), args.profile.clone(), to)?;
+                println!(
+                    "Wrote the config to {}, and moved the old one to {}.",
+                    new_path.to_string_lossy(),
+                    backup_path.to_string_lossy()
+                );
+                // saving now would write the old config back
+                return Ok(());
+            }
+            Command::Config(ConfigCommand::Schema)
+            | Command::Config(ConfigCommand::Validate)
+            | Command::Profile(_)
+            | Command::InstallSchedule { .. }
+            | Command::UninstallSchedule
+            | Command::Completions { .. } => unreachable!(),
         }
     } else {
+        // don't bother every source when the network is down, and leave
+        // when each was last checked alone so nothing is missed
+        let network = sources.network.clone().unwrap_or_default();
+        if !args.force_offline_check && !args.replay && network.skip_probe != Some(true) {
+            if let Err(err) = network.probe(&sources.probe_urls()) {
+                info!("The network probe failed: {}", err);
+                // status bars get nothing new instead of an error
+                if args.count {
+                    println!("0");
+                    return Ok(());
+                } else if args.summary {
+                    println!("0 updates");
+                    return Ok(());
+                } else if !args.quiet {
+                    eprintln!("No network connection, skipping the update check.");
+                }
+                process::exit(OFFLINE_EXIT_CODE);
+            }
+        }
+        // updates can only be browsed in a terminal, which is known
+        // before anything is checked, like a bad template
+        if args.interactive {
+            tui::require_terminal()?;
+        }
+        let format = match &args.format {
+            Some(format) => Some(
+                Template::parse(format)
+                    .map_err(|err| format!("The format \"{}\" is invalid: {}", format, err))?,
+            ),
+            None => None,
+        };
+        // a replay reports the last check instead of checking
+        let replay = if args.replay {
+            let (_config_path, state_path) = Sources::paths(
+                args.config.clone(),
+                args.state.clone(),
+                args.profile.clone(),
+            )?;
+            Some(LastRun::load(&state_path)?)
+        } else {
+            None
+        };
         // if no subcommand was provided, check for updates
-        sources.check_for_updates(args.quiet, args.notify);
+        let digest = sources.check_for_updates(&CheckOptions {
+            quiet: args.quiet,
+            notify: args.notify || args.notify_all,
+            notify_all: args.notify_all,
+            dry_run: args.dry_run,
+            since_time: args.since_time.is_some(),
+            timings: args.timings,
+            newest_first: args.newest_first,
+            notify_style: args.notify_style,
+            // nobody's there to click notifications from a timer or cron
+            no_wait: args.no_wait || (!args.wait && !atty::is(atty::Stream::Stdin)),
+            flat: args.flat,
+            porcelain: args.porcelain,
+            max_age: sources.max_age()?,
+            check_interval: sources.check_interval()?,
+            // browsing takes the place of printing the updates
+            keep_unread: args.keep_unread || args.interactive,
+            // quiet mode prints its own summary after its lines
+            silent: (args.summary && !args.quiet) || args.count,
+            output: args.output.unwrap_or_default(),
+            platforms: match (args.only, args.skip) {
+                (Some(only), _) => PlatformFilter::Only(only.0),
+                (None, Some(skip)) => PlatformFilter::Skip(skip.0),
+                (None, None) => PlatformFilter::All,
+            },
+            format,
+            no_dedupe: args.no_dedupe,
+            clean_links: sources.clean_links.unwrap_or(true),
+            future_items: sources.future_items.unwrap_or_default(),
+            replay,
+            summary: args.summary,
+            ignore: sources.ignore_list()?,
+        });
+        if args.count {
+            println!("{}", digest.num_updates());
+        } else if args.summary && !args.quiet {
+            println!("{}", digest.summary());
+        }
+        if args.interactive {
+            tui::browse(&mut sources, &digest)?;
+        } else if args.keep_unread && !args.dry_run && !digest.is_empty() {
+            println!(
+                "Found {} update{}, see {} with `sitch unread`.",
+                digest.num_updates(),
+                if digest.num_updates() == 1 { "" } else { "s" },
+                if digest.num_updates() == 1 {
+                    "it"
+                } else {
+                    "them"
+                }
+            );
+        }
+        if let Some(digest_path) = args.digest {
+            digest.write(
+                digest_path,
+                args.digest_format,
+                args.digest_append,
+                args.digest_empty,
+            )?;
+        }
+        if args.email && (!digest.is_empty() || args.email_always) {
+            sources
+                .email
+                .as_ref()
+                .ok_or("Add an email block to your config to send updates by email.")?
+                .send(&digest)?;
+        }
+        // status bars always get an exit code of 0, so they show the summary
+        no_updates = args.check_exit_code && digest.is_empty() && !args.summary && !args.count;
+        found_updates = digest.num_updates();
+        offline = digest.offline;
+    }
+    if let Some(preferences) = preferences {
+        sources.apply_to_new_sources(&source_counts, |settings| preferences.apply(settings));
+    }
+
+    // if an error hasn't occured yet and this isn't a dry run or a
+    // replay, save potential changes
+    let saving = !args.dry_run && !args.replay;
+    if saving {
+        sources
+            .save(
+                args.config.clone(),
+                args.state.clone(),
+                args.profile.clone(),
+            )
+            .map_err(|err| unsaved_check_warning(err, found_updates))?;
+    }
+    // notifications are waited for after saving, since they can stay up
+    // for as long as they aren't dismissed
+    let notify_timeout = args.notify_timeout.map(Duration::from_secs);
+    if sources.wait_for_notifications(notify_timeout) && saving {
+        sources.save(args.config, args.state, args.profile)?;
     }
 
-    // if an error hasn't occured yet, save potential changes
-    sources.save(args.config)?;
+    if offline {
+        process::exit(OFFLINE_EXIT_CODE);
+    } else if no_updates {
+        process::exit(NO_UPDATES_EXIT_CODE);
+    }
 
     Ok(())
 }
 
+/// Adds a warning to an error saving the config or state after a check
+/// found updates, since they'll be reported again on the next check.
+fn unsaved_check_warning(err: String, found_updates: usize) -> String {
+    if found_updates == 0 {
+        return err;
+    }
+
+    format!(
+        "{}\n{} The {} found won't be remembered, so the next check will report {} again.",
+        err,
+        "Warning: sitch couldn't save this check."
+            .style(Role::Error)
+            .bold(),
+        if found_updates == 1 {
+            "update".to_owned()
+        } else {
+            format!("{} updates", found_updates)
+        },
+        if found_updates == 1 { "it" } else { "them" }
+    )
+}
+
+/// One of the subcommands that every platform has for its sources
+/// besides adding them, which are all run by `run_source_command`.
+enum SourceCommand {
+    List {
+        verbose: bool,
+        json: bool,
+    },
+    Edit {
+        name: Option<String>,
+    },
+    Rename {
+        from: String,
+        to: String,
+    },
+    Snooze {
+        name: String,
+        until: Option<DateTime<Local>>,
+    },
+    Remove {
+        name: String,
+    },
+}
+
+/// Runs one of the subcommands that every platform has on a platform's
+/// sources, which are called `noun` and `plural` in what's printed,
+/// like "RSS feed" and "RSS feeds".
+fn run_source_command<T>(
+    command: SourceCommand,
+    sources: &mut Vec<(T, Option<DateTime<Local>>)>,
+    (noun, plural): (&str, &str),
+    format: ConfigFormat,
+) -> Result<(), String>
+where
+    T: NamedSource + Clone + Serialize + DeserializeOwned,
+{
+    match command {
+        SourceCommand::List { verbose, json } => {
+            if json {
+                println!("{}", serde_json::to_string_pretty(sources).unwrap());
+            } else if verbose {
+                list_verbose(sources, noun, plural);
+            } else {
+                let tty = atty::is(atty::Stream::Stdout);
+                for (source, _last_checked) in sources.iter() {
+                    // only print color if the output isn't piped
+                    if tty {
+                        println!(
+                            "{}: {}",
+                            source.name().style(Role::Name),
+                            source.location().style(Role::Link)
+                        );
+                    } else {
+                        println!("{}: {}", source.name(), source.location());
+                    }
+                    print_snoozed(source);
+                }
+            }
+        }
+        SourceCommand::Edit { name: Some(name) } => {
+            // attempt to edit just the named source in the user's
+            // preferred editor, keeping its last checked time
+            let index = find_source(sources, &name)?;
+            let source = sources[index].0.clone();
+            edit_as(format, &source, |edited| {
+                let mut source = T::deserialize(edited)
+                    .map_err(|err| format!("The edited {} could not be parsed: {}.", noun, err))?;
+                source.validate()?;
+                sources[index].0 = source;
+                Ok(())
+            })?;
+        }
+        SourceCommand::Edit { name: None } => {
+            // attempt to edit all of the platform's sources in the user's
+            // preferred editor, and save if the edit was successful
+            let unpaired = sources
+                .iter()
+                .map(|(source, _last_checked)| source.clone())
+                .collect::<Vec<T>>();
+            edit_as(format, &unpaired, |edited| {
+                let mut edited: Vec<(T, Option<DateTime<Local>>)> = deserialize_sources(edited)
+                    .map_err(|err| {
+                        format!("The edited {} could not be parsed: {}.", plural, err)
+                    })?;
+                validate_sources(&mut edited)?;
+                // keep the last checked times of unchanged sources
+                keep_last_checked(&mut edited, sources);
+                *sources = edited;
+                Ok(())
+            })?;
+        }
+        SourceCommand::Rename { from, mut to } => {
+            // only change the name, keeping the rest of the source
+            validate_name(&mut to)?;
+            let index = find_source(sources, &from)?;
+            *sources[index].0.name_mut() = to;
+            println!("Renamed the {}.", noun);
+        }
+        SourceCommand::Snooze { name, until } => {
+            snooze_source(sources, &name, until, noun)?;
+        }
+        SourceCommand::Remove { name } => {
+            let index = find_source(sources, &name)?;
+            sources.remove(index);
+            println!("Removed the {}.", noun);
+        }
+    }
+
+    Ok(())
+}
+
+/// Lets the user fill in the rest of a new source in their preferred
+/// editor, starting from the fields they gave, and adds it if it's valid.
+fn add_in_editor<T>(
+    sources: &mut Vec<(T, Option<DateTime<Local>>)>,
+    fields: &serde_json::Value,
+    format: ConfigFormat,
+) -> Result<(), String>
+where
+    T: NamedSource + DeserializeOwned,
+{
+    edit_as(format, fields, |edited| {
+        let mut source = T::deserialize(edited)
+            .map_err(|err| format!("The edited object could not be parsed: {}.", err))?;
+        source.validate()?;
+        sources.push((source, None));
+        Ok(())
+    })
+}
+
+/// Prints each of a platform's sources along with its index and when
+/// it was last checked, followed by a summary of the sources.
+fn list_verbose<T: NamedSource>(
+    sources: &[(T, Option<DateTime<Local>>)],
+    singular: &str,
+    plural: &str,
+) {
+    let tty = atty::is(atty::Stream::Stdout);
+    for (index, (source, last_checked)) in sources.iter().enumerate() {
+        // use the same format as the `--last-checked` flag
+        let mut last_checked = last_checked
+            .map(|last_checked| format!("last checked {}", last_checked.format("%T %D")))
+            .unwrap_or("never checked".to_owned());
+        if source.settings().is_snoozed() {
+            if let Some(snoozed_until) = source.settings().snoozed_until {
+                last_checked += &format!(", snoozed until {}", snoozed_until.format("%T %D"));
+            }
+        }
+        // only print color if the output isn't piped
+        if tty {
+            println!(
+                "{}: {}: {} {}",
+                (index + 1).style(Role::Index),
+                source.name().style(Role::Name),
+                source.location().style(Role::Link),
+                format!("[{}]", last_checked).style(Role::Timing)
+            );
+        } else {
+            println!(
+                "{}: {}: {} [{}]",
+                index + 1,
+                source.name(),
+                source.location(),
+                last_checked
+            );
+        }
+    }
+
+    let never_checked = sources
+        .iter()
+        .filter(|(_source, last_checked)| last_checked.is_none())
+        .count();
+    println!(
+        "{} {}, {} never checked",
+        sources.len(),
+        if sources.len() == 1 { singular } else { plural },
+        never_checked
+    );
+}
+
+/// Offers to remove or snooze each of the stale sources in turn, until
+/// all of them are answered or the prompt is quit.
+fn prune_stale(sources: &mut Sources, stale: &[SourceStats]) -> Result<(), String> {
+    for source in stale {
+        let prompt = format!(
+            "{} - \"{}\" (latest update: {}). Remove it, snooze it, or keep it? [r/s/K] ",
+            source.platform,
+            source.name,
+            describe_most_recent(source.most_recent)
+        );
+        let choice = match readline(&prompt, |input| match input.as_str() {
+            "r" | "R" | "remove" => Ok('r'),
+            "s" | "S" | "snooze" => Ok('s'),
+            "" | "k" | "K" | "keep" => Ok('k'),
+            _ => Err("Please respond with r, s, or k.".to_owned()),
+        })? {
+            ReadlineResult::Value(choice) => choice,
+            // stop asking, but keep the answers given so far
+            ReadlineResult::Quit => break,
+        };
+
+        match choice {
+            'r' => {
+                sources.act_on_stale(&source.key, StaleAction::Remove);
+                println!("Removed the source \"{}\".", source.name);
+            }
+            's' => {
+                let until = readline("Snooze until? (like 03/01/2027 or 26w) ", |input| {
+                    parse_arg_until(&input)
+                })?
+                .value()
+                .ok_or("Stopped without picking a date.".to_owned())?;
+                sources.act_on_stale(&source.key, StaleAction::Snooze(until));
+                println!(
+                    "Snoozed the source \"{}\" until {}.",
+                    source.name,
+                    until.format("%T %D")
+                );
+            }
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
+
+/// Prints each ignored link and pattern along with its index, for
+/// removing it with `sitch ignore remove`.
+fn print_ignored(ignored: &[String]) {
+    if ignored.is_empty() {
+        println!("Nothing is ignored.");
+        return;
+    }
+
+    let tty = atty::is(atty::Stream::Stdout);
+    for (index, pattern) in ignored.iter().enumerate() {
+        // only print color if the output isn't piped
+        if tty {
+            println!("{}: {}", (index + 1).style(Role::Index), pattern);
+        } else {
+            println!("{}: {}", index + 1, pattern);
+        }
+    }
+}
+
+/// Prints each unread update along with its index, for marking it as
+/// read with `sitch read`.
+fn print_unread(unread: &[UnreadUpdate]) {
+    if unread.is_empty() {
+        println!("No unread updates.");
+        return;
+    }
+
+    let tty = atty::is(atty::Stream::Stdout);
+    for (index, update) in unread.iter().enumerate() {
+        let published = format!("published {}", update.published_date.format("%T %D"));
+        // only print color if the output isn't piped
+        if tty {
+            println!(
+                "{}: {} - {}: \"{}\" {} {}",
+                (index + 1).style(Role::Index),
+                update.platform.style(Role::Name),
+                update.source.style(Role::Name),
+                shorten_title(&update.title),
+                update.link.style(Role::Link),
+                format!("[{}]", published).style(Role::Timing)
+            );
+        } else {
+            println!(
+                "{}: {} - {}: \"{}\" {} [{}]",
+                index + 1,
+                update.platform,
+                update.source,
+                shorten_title(&update.title),
+                update.link,
+                published
+            );
+        }
+        if let Some(secondary_link) = &update.secondary_link {
+            println!("    {}", secondary_link);
+        }
+    }
+}
+
+/// Prints when a source in a list is snoozed until, under it, if it's
+/// snoozed.
+fn print_snoozed<T: NamedSource>(source: &T) {
+    if source.settings().is_snoozed() {
+        if let Some(snoozed_until) = source.settings().snoozed_until {
+            println!("    snoozed until {}", snoozed_until.format("%T %D"));
+        }
+    }
+}
+
+/// Snoozes the named source until a date, or wakes it up right away if
+/// there's no date.
+fn snooze_source<T: NamedSource>(
+    sources: &mut [(T, Option<DateTime<Local>>)],
+    name: &str,
+    until: Option<DateTime<Local>>,
+    noun: &str,
+) -> Result<(), String> {
+    if let Some(until) = until.filter(|until| *until <= Local::now()) {
+        return Err(format!(
+            "{} has already passed, so there's nothing to snooze until.",
+            until.format("%T %D")
+        ));
+    }
+
+    let index = find_source(sources, name)?;
+    sources[index].0.settings_mut().snoozed_until = until;
+    match until {
+        Some(until) => println!("Snoozed the {} until {}.", noun, until.format("%T %D")),
+        None => println!("Unsnoozed the {}.", noun),
+    }
+
+    Ok(())
+}
+
+/// Reads sources to add from a file, or from stdin if the path is "-",
+/// and prints how many of them could be added.
+///
+/// Each line is either "<value>" or "<name> | <value>", with blank lines
+/// and lines starting with "#" skipped. Lines are turned into sources by
+/// `make_source` in parallel, and the ones that fail are reported at the
+/// end without stopping the rest from being added.
+fn read_sources_from_file<T, F>(path: &str, plural: &str, make_source: F) -> Result<Vec<T>, String>
+where
+    T: NamedSource + Send,
+    F: Fn(&str, Option<String>) -> Result<T, String> + Sync,
+{
+    let text = if path == "-" {
+        let mut text = String::new();
+        std::io::stdin()
+            .read_to_string(&mut text)
+            .map_err(|err| format!("Couldn't read stdin: {}", err))?;
+        text
+    } else {
+        read_to_string(path).map_err(|err| format!("Couldn't read {}: {}", path, err))?
+    };
+
+    // (line number, name, value) for each line with a source on it
+    let lines = text
+        .lines()
+        .enumerate()
+        .map(|(index, line)| (index + 1, line.trim()))
+        .filter(|(_number, line)| !line.is_empty() && !line.starts_with('#'))
+        .map(|(number, line)| match line.find('|') {
+            Some(index) => (
+                number,
+                Some(line[..index].trim().to_owned()).filter(|name| !name.is_empty()),
+                line[index + 1..].trim(),
+            ),
+            None => (number, None, line),
+        })
+        .collect::<Vec<(usize, Option<String>, &str)>>();
+    let results = lines
+        .par_iter()
+        .map(|(number, name, value)| {
+            (
+                *number,
+                make_source(value, name.clone()).and_then(validated),
+            )
+        })
+        .collect::<Vec<(usize, Result<T, String>)>>();
+
+    let total = results.len();
+    let mut added = Vec::new();
+    let mut failures = Vec::new();
+    for (number, result) in results {
+        match result {
+            Ok(source) => added.push(source),
+            Err(err) => failures.push((number, err)),
+        }
+    }
+
+    let tty = atty::is(atty::Stream::Stdout);
+    for source in &added {
+        // only print color if the output isn't piped
+        if tty {
+            println!("Added \"{}\".", source.name().style(Role::Name));
+        } else {
+            println!("Added \"{}\".", source.name());
+        }
+    }
+    println!("Added {} of {} {}.", added.len(), total, plural);
+    if !failures.is_empty() {
+        eprintln!("These lines couldn't be added:");
+        for (number, err) in failures {
+            eprintln!("line {}: {}", number, err);
+        }
+    }
+
+    Ok(added)
+}
+
+/// Validates a source before adding it (see `NamedSource::validate`).
+fn validated<T: NamedSource>(mut source: T) -> Result<T, String> {
+    source.validate()?;
+    Ok(source)
+}
+
+/// Finds the index of the source with the given name (see
+/// `find_by_name`), showing where each source checks if the user has to
+/// pick between them.
+fn find_source<T: NamedSource>(
+    sources: &[(T, Option<DateTime<Local>>)],
+    name: &str,
+) -> Result<usize, String> {
+    let locations = sources
+        .iter()
+        .map(|(source, _last_checked)| source.location())
+        .collect::<Vec<_>>();
+    let candidates = sources
+        .iter()
+        .zip(&locations)
+        .map(|((source, _last_checked), location)| (location.as_ref(), source.name()))
+        .collect::<Vec<(&str, &str)>>();
+
+    find_by_name(name, &candidates)
+}
+
+/// Finds the platform (named like "RSS") and name of the source with
+/// the given name on any platform (see `find_by_name`).
+fn find_source_anywhere(sources: &Sources, name: &str) -> Result<(&'static str, String), String> {
+    let mut names = sources.source_names();
+    let candidates = names
+        .iter()
+        .map(|(type_name, source_name)| (*type_name, source_name.as_str()))
+        .collect::<Vec<(&str, &str)>>();
+    let index = find_by_name(name, &candidates)?;
+
+    Ok(names.remove(index))
+}
+
 fn main() {
     // handle errors above gracefully
     if let Err(error) = run() {
@@ -371,3 +2748,47 @@ fn main() {
         process::exit(1);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn feed(name: &str, feed: &str) -> (RssSource, Option<DateTime<Local>>) {
+        let source = RssSource {
+            name: name.to_owned(),
+            feed: feed.to_owned(),
+            ..Default::default()
+        };
+        (source, Some(Local::now()))
+    }
+
+    #[test]
+    fn shared_subcommands_work_on_any_platform() {
+        let mut feeds = vec![
+            feed("Blog", "https://example.com/feed.xml"),
+            feed("News", "https://example.org/feed.xml"),
+        ];
+        let nouns = ("RSS feed", "RSS feeds");
+
+        let rename = SourceCommand::Rename {
+            from: "blog".to_owned(),
+            to: " Some Blog ".to_owned(),
+        };
+        run_source_command(rename, &mut feeds, nouns, ConfigFormat::Json).unwrap();
+        assert_eq!(feeds[0].0.name, "Some Blog");
+        assert!(feeds[0].1.is_some());
+
+        let remove = SourceCommand::Remove {
+            name: "News".to_owned(),
+        };
+        run_source_command(remove, &mut feeds, nouns, ConfigFormat::Json).unwrap();
+        assert_eq!(feeds.len(), 1);
+        assert_eq!(feeds[0].0.name, "Some Blog");
+
+        let missing = SourceCommand::Remove {
+            name: "Nothing Like It".to_owned(),
+        };
+        assert!(run_source_command(missing, &mut feeds, nouns, ConfigFormat::Json).is_err());
+        assert_eq!(feeds.len(), 1);
+    }
+}
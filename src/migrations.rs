@@ -0,0 +1,105 @@
+//! Upgrades configs written by older versions of sitch.
+//!
+//! The root of config.json has a `version` field, which is missing from
+//! configs written before it was added (those are version 0). Each
+//! migration transforms the raw JSON of a config from one version to the
+//! next, so that renames and restructures don't lose any data.
+
+use serde_json::{json, Value};
+
+/// The config version that this version of sitch reads and writes.
+pub const CONFIG_VERSION: u32 = 1;
+
+/// A transformation of a config from one version to the next.
+type Migration = fn(&mut Value) -> Result<(), String>;
+
+/// The migrations in order, where the one at index `n` upgrades
+/// a config from version `n` to version `n + 1`.
+const MIGRATIONS: [Migration; CONFIG_VERSION as usize] = [v0_to_v1];
+
+/// The version of a config, which is 0 if it has none.
+pub fn config_version(config: &Value) -> Result<u32, String> {
+    match config.get("version") {
+        None => Ok(0),
+        Some(version) => version
+            .as_u64()
+            .map(|version| version as u32)
+            .ok_or(format!("The config version {} isn't a number", version)),
+    }
+}
+
+/// Runs all pending migrations on a config, returning the version the
+/// config was at beforehand.
+///
+/// Configs from newer versions of sitch are refused rather than loaded,
+/// since this version would silently drop whatever it doesn't understand.
+pub fn migrate(config: &mut Value) -> Result<u32, String> {
+    let version = config_version(config)?;
+    if version > CONFIG_VERSION {
+        return Err(format!(
+            "Your config is version {}, but this version of sitch only understands \
+             up to version {}. Please upgrade sitch.",
+            version, CONFIG_VERSION
+        ));
+    }
+
+    for (from, migration) in MIGRATIONS.iter().enumerate().skip(version as usize) {
+        migration(config).map_err(|err| {
+            format!(
+                "Couldn't upgrade your config from version {} to {}: {}",
+                from,
+                from + 1,
+                err
+            )
+        })?;
+        config["version"] = json!(from + 1);
+    }
+
+    Ok(version)
+}
+
+/// Version 1 only adds the `version` field, so the config must be an object.
+fn v0_to_v1(config: &mut Value) -> Result<(), String> {
+    if config.is_object() {
+        Ok(())
+    } else {
+        Err("the config isn't a JSON object".to_owned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn configs_without_a_version_are_upgraded() {
+        let mut config = json!({"rss": []});
+
+        assert_eq!(migrate(&mut config), Ok(0));
+        assert_eq!(config, json!({"rss": [], "version": CONFIG_VERSION}));
+    }
+
+    #[test]
+    fn current_configs_are_left_alone() {
+        let mut config = json!({"version": CONFIG_VERSION, "rss": []});
+        let original = config.clone();
+
+        assert_eq!(migrate(&mut config), Ok(CONFIG_VERSION));
+        assert_eq!(config, original);
+    }
+
+    #[test]
+    fn newer_configs_are_refused() {
+        let mut config = json!({"version": CONFIG_VERSION + 1});
+
+        assert!(migrate(&mut config)
+            .unwrap_err()
+            .contains("Please upgrade sitch"));
+    }
+
+    #[test]
+    fn broken_configs_are_refused() {
+        assert!(migrate(&mut json!({"version": "one"})).is_err());
+        assert!(migrate(&mut json!([])).is_err());
+    }
+}
@@ -0,0 +1,170 @@
+//! Showing desktop notifications on each platform.
+//!
+//! notify-rust shows them on Linux, the BSDs, and macOS. Windows gets
+//! toast notifications instead, which stay in the action center, but
+//! can't be waited on to open updates from. Only Linux and the BSDs get
+//! buttons besides "Open in Browser", since macOS shows just one.
+
+use crate::style::{Role, Stylize};
+#[cfg(not(windows))]
+use notify_rust::Notification;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+#[cfg(windows)]
+use winrt_notification::{Duration, Toast};
+
+/// Whether a notification has failed to show yet, so that the warning
+/// about it is only printed once.
+static FAILED: AtomicBool = AtomicBool::new(false);
+
+/// A button on a notification for an update.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    /// Open the update in the browser, which marks it as read.
+    Open,
+    /// Mark the update as read without opening it.
+    MarkRead,
+    /// Snooze the update's source, so it isn't checked for a while.
+    IgnoreSource,
+}
+
+#[cfg(not(windows))]
+impl Action {
+    /// The buttons shown on notifications on this platform.
+    #[cfg(all(unix, not(target_os = "macos")))]
+    const SHOWN: &'static [Action] = &[Action::Open, Action::MarkRead, Action::IgnoreSource];
+    /// The buttons shown on notifications on this platform.
+    #[cfg(target_os = "macos")]
+    const SHOWN: &'static [Action] = &[Action::Open];
+
+    /// The identifier the notification server reports the button with.
+    fn id(self) -> &'static str {
+        match self {
+            Action::Open => "open",
+            Action::MarkRead => "mark-read",
+            Action::IgnoreSource => "ignore-source",
+        }
+    }
+
+    /// The button's label.
+    fn label(self) -> &'static str {
+        match self {
+            Action::Open => "Open in Browser",
+            Action::MarkRead => "Mark Read",
+            Action::IgnoreSource => "Ignore Source",
+        }
+    }
+
+    /// The button with the identifier the notification server reported.
+    fn from_id(id: &str) -> Option<Self> {
+        [Action::Open, Action::MarkRead, Action::IgnoreSource]
+            .iter()
+            .cloned()
+            .find(|action| action.id() == id)
+    }
+}
+
+/// Warns that notifications can't be shown and are printed instead, the
+/// first time one fails.
+pub fn warn_failed(err: &str) {
+    if !FAILED.swap(true, Ordering::Relaxed) {
+        eprintln!(
+            "{} {}",
+            "Notifications aren't working, so they're printed instead.".style(Role::Error),
+            err
+        );
+    }
+}
+
+/// Shows a notification that goes away on its own.
+pub fn show(summary: &str, body: &str) -> Result<(), String> {
+    #[cfg(not(windows))]
+    let shown = Notification::new()
+        .summary(summary)
+        .body(body)
+        .show()
+        .map(|_handle| ())
+        .map_err(|err| err.to_string());
+    #[cfg(windows)]
+    let shown = Toast::new(Toast::POWERSHELL_APP_ID)
+        .title(summary)
+        .text1(body)
+        .show()
+        .map_err(|err| err.to_string());
+
+    shown.map_err(|err| format!("Couldn't show a notification: {}", err))
+}
+
+/// Shows a notification for an update that stays until it's dismissed,
+/// with buttons for what to do with the update (see `Action`) and a
+/// thumbnail if there is one, then waits for it to be dismissed,
+/// returning the button that was clicked, if any. Clicking "Open in
+/// Browser" opens the update's link, and only counts if it opened.
+///
+/// Windows can't wait on notifications, so there it's shown without the
+/// buttons or thumbnail, and this returns right away.
+#[cfg(not(windows))]
+pub fn show_and_wait(
+    summary: &str,
+    body: &str,
+    thumbnail: Option<&Path>,
+    link: &str,
+) -> Result<Option<Action>, String> {
+    let mut notification = Notification::new();
+    notification.summary(summary).body(body);
+    #[cfg(all(unix, not(target_os = "macos")))]
+    {
+        if let Some(thumbnail) = thumbnail {
+            notification.image_path(&thumbnail.to_string_lossy());
+        }
+    }
+
+    for action in Action::SHOWN {
+        notification.action(action.id(), action.label());
+    }
+
+    let mut clicked = None;
+    notification
+        .timeout(0)
+        .show()
+        .map_err(|err| format!("Couldn't show a notification: {}", err))?
+        .wait_for_action(|id| {
+            clicked = match Action::from_id(id) {
+                Some(Action::Open) if webbrowser::open(link).is_err() => None,
+                action => action,
+            };
+        });
+
+    Ok(clicked)
+}
+
+/// See the other platforms' `show_and_wait`.
+#[cfg(windows)]
+pub fn show_and_wait(
+    summary: &str,
+    body: &str,
+    _thumbnail: Option<&Path>,
+    _link: &str,
+) -> Result<Option<Action>, String> {
+    Toast::new(Toast::POWERSHELL_APP_ID)
+        .title(summary)
+        .text1(body)
+        .duration(Duration::Long)
+        .show()
+        .map_err(|err| format!("Couldn't show a notification: {}", err))?;
+
+    Ok(None)
+}
+
+#[cfg(all(test, not(windows)))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn buttons_are_known_by_their_ids() {
+        for action in &[Action::Open, Action::MarkRead, Action::IgnoreSource] {
+            assert_eq!(Action::from_id(action.id()), Some(*action));
+        }
+        assert_eq!(Action::from_id("default"), None);
+    }
+}
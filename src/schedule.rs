@@ -0,0 +1,523 @@
+//! Running sitch on a schedule.
+//!
+//! On Linux with systemd, a user-level service and timer are installed
+//! and enabled, and on Windows, a scheduled task is created. Everywhere
+//! else, a crontab line is printed for the user to add themselves, since
+//! editing someone's crontab for them is rude.
+
+use crate::style::{Role, Stylize};
+use crate::util::{readline, ReadlineResult};
+use chrono::Duration;
+use dirs::config_dir;
+use std::env;
+use std::fs::{create_dir_all, read_to_string, remove_file, write};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// The longest command line that a scheduled task can run.
+#[cfg(windows)]
+const MAX_TASK_COMMAND_LENGTH: usize = 261;
+
+/// A check for updates to run on a schedule.
+#[derive(Clone, Debug)]
+pub struct Schedule {
+    /// The name of the schedule's units, like "sitch", or "sitch-work"
+    /// for the "work" profile.
+    pub name: String,
+    /// How often to check.
+    pub every: Duration,
+    /// The command to run, starting with the absolute path of sitch.
+    pub command: Vec<String>,
+}
+
+impl Schedule {
+    /// A schedule that runs the current sitch binary with the given config,
+    /// state, and flags for checking.
+    ///
+    /// Every path is made absolute, so that the schedule keeps working
+    /// no matter what environment it's run in.
+    pub fn new(
+        name: String,
+        every: Duration,
+        config_path: &Path,
+        state_path: &Path,
+        flags: &[String],
+    ) -> Result<Self, String> {
+        if every < Duration::minutes(1) {
+            return Err("Checking more than once a minute isn't supported.".to_owned());
+        }
+
+        let exe = env::current_exe()
+            .and_then(|exe| exe.canonicalize())
+            .map_err(|err| format!("Couldn't find where sitch is installed: {}", err))?;
+        let mut command = vec![
+            exe.to_string_lossy().into_owned(),
+            "--config".to_owned(),
+            absolute(config_path)?.to_string_lossy().into_owned(),
+            "--state".to_owned(),
+            absolute(state_path)?.to_string_lossy().into_owned(),
+        ];
+        command.extend(flags.iter().cloned());
+
+        Ok(Schedule {
+            name,
+            every,
+            command,
+        })
+    }
+
+    /// The schedule's systemd service, which checks once.
+    pub fn service_unit(&self) -> String {
+        let exec_start = self
+            .command
+            .iter()
+            .map(|arg| systemd_quote(arg))
+            .collect::<Vec<String>>()
+            .join(" ");
+
+        format!(
+            "[Unit]\n\
+             Description=Check for updates with sitch\n\
+             \n\
+             [Service]\n\
+             Type=oneshot\n\
+             ExecStart={}\n",
+            exec_start
+        )
+    }
+
+    /// The schedule's systemd timer, which starts the service a minute
+    /// after booting and then every so often.
+    pub fn timer_unit(&self) -> String {
+        format!(
+            "[Unit]\n\
+             Description=Check for updates with sitch every {}\n\
+             \n\
+             [Timer]\n\
+             OnBootSec=1min\n\
+             OnUnitActiveSec={}s\n\
+             Unit={}.service\n\
+             \n\
+             [Install]\n\
+             WantedBy=timers.target\n",
+            describe_every(self.every),
+            self.every.num_seconds(),
+            self.name
+        )
+    }
+
+    /// The arguments to `schtasks /create` for how often a scheduled task
+    /// runs, which has to be a whole number of days if it's longer than
+    /// a day.
+    pub fn task_timing(&self) -> Result<Vec<String>, String> {
+        let minutes = self.every.num_minutes();
+        let (unit, amount) = if minutes % (60 * 24) == 0 && self.every.num_days() <= 365 {
+            ("daily", self.every.num_days())
+        } else if minutes % 60 == 0 && self.every.num_hours() < 24 {
+            ("hourly", self.every.num_hours())
+        } else if minutes < 60 * 24 {
+            ("minute", minutes)
+        } else {
+            return Err(format!(
+                "Scheduled tasks can't run every {}, so try a whole number \
+                 of days up to a year, or a length of time under a day.",
+                describe_every(self.every)
+            ));
+        };
+
+        Ok(vec![
+            "/sc".to_owned(),
+            unit.to_owned(),
+            "/mo".to_owned(),
+            amount.to_string(),
+        ])
+    }
+
+    /// The command line that a scheduled task runs, quoted like Windows
+    /// programs expect.
+    pub fn task_command(&self) -> String {
+        self.command
+            .iter()
+            .map(|arg| {
+                if arg.is_empty() || arg.contains(|c: char| c == ' ' || c == '\t' || c == '"') {
+                    format!("\"{}\"", arg.replace('"', "\\\""))
+                } else {
+                    arg.to_owned()
+                }
+            })
+            .collect::<Vec<String>>()
+            .join(" ")
+    }
+
+    /// A line for a crontab that runs the schedule's command.
+    ///
+    /// Cron can only run something at set minutes, hours, and days, so
+    /// the schedule has to evenly divide an hour or a day.
+    pub fn cron_line(&self) -> Result<String, String> {
+        let minutes = self.every.num_minutes();
+        let hours = self.every.num_hours();
+        let days = self.every.num_days();
+        let timing = if minutes == 1 {
+            "* * * * *".to_owned()
+        } else if minutes < 60 && 60 % minutes == 0 {
+            format!("*/{} * * * *", minutes)
+        } else if minutes == 60 {
+            "0 * * * *".to_owned()
+        } else if minutes % 60 == 0 && hours < 24 && 24 % hours == 0 {
+            format!("0 */{} * * *", hours)
+        } else if minutes % (60 * 24) == 0 && days == 1 {
+            "0 0 * * *".to_owned()
+        } else if minutes % (60 * 24) == 0 && days <= 31 {
+            format!("0 0 */{} * *", days)
+        } else {
+            return Err(format!(
+                "Cron can't run something every {}, so try a length of time \
+                 that evenly divides an hour or a day, like 15m or 6h.",
+                describe_every(self.every)
+            ));
+        };
+        let command = self
+            .command
+            .iter()
+            .map(|arg| shell_quote(arg))
+            .collect::<Vec<String>>()
+            .join(" ");
+
+        // an unescaped "%" ends the command in a crontab
+        Ok(format!("{} {}", timing, command.replace('%', "\\%")))
+    }
+}
+
+/// The name of the schedule's units for a profile, if there is one.
+pub fn unit_name(profile: Option<&str>) -> String {
+    match profile {
+        Some(profile) => format!("sitch-{}", profile),
+        None => "sitch".to_owned(),
+    }
+}
+
+/// Installs and starts a schedule's systemd units, or prints a crontab
+/// line for it if there's no systemd.
+///
+/// If the units already exist and are different, what would change is
+/// shown, and they're only overwritten if the user says so.
+pub fn install(schedule: &Schedule) -> Result<(), String> {
+    if cfg!(windows) {
+        return install_task(schedule);
+    } else if !has_systemd() {
+        println!("There's no systemd here, so add this line to your crontab with `crontab -e`:");
+        println!("{}", schedule.cron_line()?);
+        return Ok(());
+    }
+
+    let dir = units_dir()?;
+    let timer = format!("{}.timer", schedule.name);
+    let units = vec![
+        (
+            dir.join(format!("{}.service", schedule.name)),
+            schedule.service_unit(),
+        ),
+        (dir.join(&timer), schedule.timer_unit()),
+    ];
+    let changed = units
+        .iter()
+        .filter_map(|(path, contents)| {
+            let existing = read_to_string(path).ok()?;
+            Some((path, existing, contents))
+                .filter(|(_path, existing, contents)| existing != *contents)
+        })
+        .collect::<Vec<_>>();
+    if !changed.is_empty() {
+        for (path, existing, contents) in &changed {
+            println!("{}", path.to_string_lossy().style(Role::Header));
+            for line in diff_lines(existing, contents) {
+                if line.starts_with('-') {
+                    println!("{}", line.style(Role::Error));
+                } else if line.starts_with('+') {
+                    println!("{}", line.style(Role::Success));
+                } else {
+                    println!("{}", line);
+                }
+            }
+        }
+        let overwrite = readline(
+            "A schedule is already installed. Overwrite it? [y/N] ",
+            |input| match input.to_lowercase().as_str() {
+                "y" | "yes" => Ok(true),
+                "" | "n" | "no" => Ok(false),
+                _ => Err("Please enter \"y\" or \"n\".".to_owned()),
+            },
+        )?;
+        if overwrite != ReadlineResult::Value(true) {
+            println!("Left the installed schedule alone.");
+            return Ok(());
+        }
+    }
+
+    create_dir_all(&dir)
+        .map_err(|err| format!("Couldn't create {}: {}", dir.to_string_lossy(), err))?;
+    for (path, contents) in &units {
+        write(path, contents)
+            .map_err(|err| format!("Couldn't write {}: {}", path.to_string_lossy(), err))?;
+    }
+    systemctl(&["daemon-reload"])?;
+    systemctl(&["enable", "--now", &timer])?;
+
+    println!(
+        "Sitch will now check for updates every {}. See when it's next \
+         run with `systemctl --user list-timers {}`.",
+        describe_every(schedule.every),
+        timer
+    );
+
+    Ok(())
+}
+
+/// Stops and removes the systemd units installed by `install`, or says
+/// how to remove the crontab line if there's no systemd.
+pub fn uninstall(name: &str) -> Result<(), String> {
+    if cfg!(windows) {
+        return uninstall_task(name);
+    } else if !has_systemd() {
+        println!("There's no systemd here, so remove the line running sitch from your crontab with `crontab -e`.");
+        return Ok(());
+    }
+
+    let dir = units_dir()?;
+    let timer = format!("{}.timer", name);
+    let paths = [dir.join(format!("{}.service", name)), dir.join(&timer)];
+    if !paths.iter().any(|path| path.exists()) {
+        return Err(format!(
+            "There's no schedule installed at {}.",
+            paths[1].to_string_lossy()
+        ));
+    }
+
+    systemctl(&["disable", "--now", &timer])?;
+    for path in paths.iter().filter(|path| path.exists()) {
+        remove_file(path)
+            .map_err(|err| format!("Couldn't remove {}: {}", path.to_string_lossy(), err))?;
+    }
+    systemctl(&["daemon-reload"])?;
+    println!("Sitch will no longer check for updates on a schedule.");
+
+    Ok(())
+}
+
+/// Creates a scheduled task that runs in the user's session, so that its
+/// notifications can be seen, asking before replacing one that exists.
+#[cfg(windows)]
+fn install_task(schedule: &Schedule) -> Result<(), String> {
+    let command = schedule.task_command();
+    if command.chars().count() > MAX_TASK_COMMAND_LENGTH {
+        return Err(format!(
+            "The command to run is {} characters long, but scheduled tasks \
+             can only run up to {}. Try moving your config somewhere with \
+             a shorter path.",
+            command.chars().count(),
+            MAX_TASK_COMMAND_LENGTH
+        ));
+    }
+
+    if schtasks(&["/query", "/tn", &schedule.name]).is_ok() {
+        println!("The scheduled task \"{}\" will now run:", schedule.name);
+        println!("{}", command.style(Role::Success));
+        let overwrite = readline(
+            "A schedule is already installed. Overwrite it? [y/N] ",
+            |input| match input.to_lowercase().as_str() {
+                "y" | "yes" => Ok(true),
+                "" | "n" | "no" => Ok(false),
+                _ => Err("Please enter \"y\" or \"n\".".to_owned()),
+            },
+        )?;
+        if overwrite != ReadlineResult::Value(true) {
+            println!("Left the installed schedule alone.");
+            return Ok(());
+        }
+    }
+
+    let timing = schedule.task_timing()?;
+    let mut args = vec!["/create", "/tn", &schedule.name, "/tr", &command];
+    args.extend(timing.iter().map(String::as_str));
+    // only run while the user is logged on, where notifications are seen
+    args.extend(&["/it", "/f"]);
+    schtasks(&args)?;
+
+    println!(
+        "Sitch will now check for updates every {}. See the task with \
+         `schtasks /query /tn {}`.",
+        describe_every(schedule.every),
+        schedule.name
+    );
+
+    Ok(())
+}
+
+#[cfg(not(windows))]
+fn install_task(_schedule: &Schedule) -> Result<(), String> {
+    Err("Scheduled tasks are only supported on Windows.".to_owned())
+}
+
+/// Deletes the scheduled task created by `install_task`.
+#[cfg(windows)]
+fn uninstall_task(name: &str) -> Result<(), String> {
+    if schtasks(&["/query", "/tn", name]).is_err() {
+        return Err(format!("There's no scheduled task named \"{}\".", name));
+    }
+
+    schtasks(&["/delete", "/tn", name, "/f"])?;
+    println!("Sitch will no longer check for updates on a schedule.");
+
+    Ok(())
+}
+
+#[cfg(not(windows))]
+fn uninstall_task(_name: &str) -> Result<(), String> {
+    Err("Scheduled tasks are only supported on Windows.".to_owned())
+}
+
+/// Runs `schtasks` with some arguments, returning what it printed, or
+/// what it printed to stderr if it failed.
+#[cfg(windows)]
+fn schtasks(args: &[&str]) -> Result<String, String> {
+    let output = Command::new("schtasks")
+        .args(args)
+        .output()
+        .map_err(|err| format!("Couldn't run schtasks: {}", err))?;
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    } else {
+        Err(format!(
+            "`schtasks {}` failed: {}",
+            args[0],
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))
+    }
+}
+
+/// Whether the system was booted with systemd, which is what systemd
+/// itself checks for.
+fn has_systemd() -> bool {
+    cfg!(target_os = "linux") && Path::new("/run/systemd/system").exists()
+}
+
+/// The directory that the user's own systemd units are kept in,
+/// `$CONFIG_DIR/systemd/user`.
+fn units_dir() -> Result<PathBuf, String> {
+    config_dir()
+        .map(|dir| dir.join("systemd").join("user"))
+        .ok_or(
+            "Could not find your system's config directory, which systemd units are kept in."
+                .to_owned(),
+        )
+}
+
+/// Runs `systemctl --user` with some arguments, failing if it does.
+fn systemctl(args: &[&str]) -> Result<(), String> {
+    let status = Command::new("systemctl")
+        .arg("--user")
+        .args(args)
+        .status()
+        .map_err(|err| format!("Couldn't run systemctl: {}", err))?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!("`systemctl --user {}` failed.", args.join(" ")))
+    }
+}
+
+/// Makes a path absolute by joining it onto the current directory.
+fn absolute(path: &Path) -> Result<PathBuf, String> {
+    if path.is_absolute() {
+        return Ok(path.to_owned());
+    }
+
+    env::current_dir()
+        .map(|dir| dir.join(path))
+        .map_err(|err| format!("Couldn't find the current directory: {}", err))
+}
+
+/// Describes a length of time in the largest unit that it's a whole
+/// number of, like "hour" or "90 minutes", to follow "every".
+fn describe_every(every: Duration) -> String {
+    let minutes = every.num_minutes();
+    let (amount, unit) = if minutes % (60 * 24 * 7) == 0 {
+        (every.num_weeks(), "week")
+    } else if minutes % (60 * 24) == 0 {
+        (every.num_days(), "day")
+    } else if minutes % 60 == 0 {
+        (every.num_hours(), "hour")
+    } else {
+        (minutes, "minute")
+    };
+
+    if amount == 1 {
+        unit.to_owned()
+    } else {
+        format!("{} {}s", amount, unit)
+    }
+}
+
+/// Quotes an argument for a unit's `ExecStart`, escaping what systemd
+/// would otherwise expand, like "%" specifiers and "$" variables.
+fn systemd_quote(arg: &str) -> String {
+    let escaped = arg
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('%', "%%")
+        .replace('$', "$$");
+    if arg.is_empty() || arg.contains(|c: char| c.is_whitespace() || c == '\'' || c == '"') {
+        format!("\"{}\"", escaped)
+    } else {
+        escaped
+    }
+}
+
+/// Quotes an argument for a POSIX shell, leaving it alone if it's safe.
+fn shell_quote(arg: &str) -> String {
+    let is_safe = !arg.is_empty()
+        && arg
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./:=,+@".contains(c));
+    if is_safe {
+        arg.to_owned()
+    } else {
+        format!("'{}'", arg.replace('\'', "'\\''"))
+    }
+}
+
+/// A line-by-line diff between two texts, where removed lines start with
+/// "-", added lines with "+", and unchanged lines with a space.
+fn diff_lines(old: &str, new: &str) -> Vec<String> {
+    let old = old.lines().collect::<Vec<&str>>();
+    let new = new.lines().collect::<Vec<&str>>();
+    // the length of the longest common subsequence of each pair of suffixes
+    let mut common = vec![vec![0; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            common[i][j] = if old[i] == new[j] {
+                common[i + 1][j + 1] + 1
+            } else {
+                common[i + 1][j].max(common[i][j + 1])
+            };
+        }
+    }
+
+    let mut lines = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            lines.push(format!(" {}", old[i]));
+            i += 1;
+            j += 1;
+        } else if i < old.len() && (j == new.len() || common[i + 1][j] >= common[i][j + 1]) {
+            lines.push(format!("-{}", old[i]));
+            i += 1;
+        } else {
+            lines.push(format!("+{}", new[j]));
+            j += 1;
+        }
+    }
+
+    lines
+}
@@ -0,0 +1,290 @@
+//! The JSON Schema of sitch's config, generated from the `Sources` types,
+//! and a check of configs against it.
+//!
+//! The schema is meant for editors to check configs as they're written,
+//! so unlike sitch itself, it doesn't allow fields sitch doesn't know
+//! about, which are usually typos.
+
+use crate::sources::Sources;
+use schemars::schema_for;
+use serde_json::{Map, Value};
+
+/// The JSON Schema that a config should match.
+pub fn config_schema() -> Value {
+    let mut schema = serde_json::to_value(schema_for!(Sources)).unwrap();
+    schema["title"] = Value::String("sitch config".to_owned());
+    deny_unknown_fields(&mut schema);
+
+    schema
+}
+
+/// Makes every object with known fields refuse any others, which sitch
+/// keeps in each struct's `extra` field instead.
+fn deny_unknown_fields(schema: &mut Value) {
+    match schema {
+        Value::Object(object) => {
+            if object.contains_key("properties") {
+                object.insert("additionalProperties".to_owned(), Value::Bool(false));
+            }
+            object.values_mut().for_each(deny_unknown_fields);
+        }
+        Value::Array(array) => array.iter_mut().for_each(deny_unknown_fields),
+        _ => {}
+    }
+}
+
+/// Checks a config against the schema, returning each problem found
+/// prefixed with the JSON pointer to where it is, like
+/// `/rss/3/feed: expected a string, found a number`.
+pub fn validate(config: &Value) -> Vec<String> {
+    let schema = config_schema();
+    let mut problems = Vec::new();
+    check(&schema, &schema, config, "", &mut problems);
+
+    problems
+}
+
+/// Checks a value against part of the schema, adding any problems found
+/// to `problems`. Returns whether the value was at least of the right
+/// type, which tells how close it came to matching.
+fn check(
+    root: &Value,
+    schema: &Value,
+    value: &Value,
+    path: &str,
+    problems: &mut Vec<String>,
+) -> bool {
+    // shared types are kept in the schema's definitions
+    if let Some(reference) = schema.get("$ref").and_then(Value::as_str) {
+        return match root.pointer(reference.trim_start_matches('#')) {
+            Some(definition) => check(root, definition, value, path, problems),
+            None => true,
+        };
+    }
+
+    let mut right_type = true;
+    if let Some(parts) = schema.get("allOf").and_then(Value::as_array) {
+        for part in parts {
+            right_type &= check(root, part, value, path, problems);
+        }
+    }
+    // values that can take several forms, like optional values or untagged
+    // enums, are held to the form they come closest to
+    let options = schema
+        .get("anyOf")
+        .or_else(|| schema.get("oneOf"))
+        .and_then(Value::as_array);
+    if let Some(options) = options {
+        let closest = options
+            .iter()
+            .map(|option| {
+                let mut option_problems = Vec::new();
+                let right_type = check(root, option, value, path, &mut option_problems);
+                (right_type, option_problems)
+            })
+            .min_by_key(|(right_type, option_problems)| (!right_type, option_problems.len()));
+        if let Some((option_right_type, option_problems)) = closest {
+            right_type &= option_right_type;
+            problems.extend(option_problems);
+        }
+    }
+
+    if let Some(allowed) = schema.get("enum").and_then(Value::as_array) {
+        if !allowed.contains(value) {
+            let allowed = allowed
+                .iter()
+                .map(Value::to_string)
+                .collect::<Vec<String>>();
+            problems.push(format!(
+                "{}: expected one of {}, found {}",
+                location(path),
+                allowed.join(", "),
+                value
+            ));
+            return false;
+        }
+    }
+
+    if let Some(types) = schema.get("type") {
+        let types = match types {
+            Value::Array(types) => types.iter().filter_map(Value::as_str).collect(),
+            types => types.as_str().into_iter().collect::<Vec<&str>>(),
+        };
+        if !types.iter().any(|kind| is_of_type(value, kind)) {
+            let expected = types
+                .iter()
+                .map(|kind| describe_type(kind))
+                .collect::<Vec<&str>>();
+            problems.push(format!(
+                "{}: expected {}, found {}",
+                location(path),
+                expected.join(" or "),
+                describe_value(value)
+            ));
+            return false;
+        }
+    }
+
+    if let (Some(minimum), Some(number)) = (
+        schema.get("minimum").and_then(Value::as_f64),
+        value.as_f64(),
+    ) {
+        if number < minimum {
+            problems.push(format!(
+                "{}: expected at least {}, found {}",
+                location(path),
+                minimum,
+                number
+            ));
+        }
+    }
+
+    match value {
+        Value::Object(object) => check_object(root, schema, object, path, problems),
+        Value::Array(items) => check_array(root, schema, items, path, problems),
+        _ => {}
+    }
+
+    right_type
+}
+
+/// Checks an object's fields: that the required ones are there, and that
+/// the rest are known and valid.
+fn check_object(
+    root: &Value,
+    schema: &Value,
+    object: &Map<String, Value>,
+    path: &str,
+    problems: &mut Vec<String>,
+) {
+    let empty = Map::new();
+    let properties = schema
+        .get("properties")
+        .and_then(Value::as_object)
+        .unwrap_or(&empty);
+
+    let required = schema
+        .get("required")
+        .and_then(Value::as_array)
+        .map(|required| required.iter().filter_map(Value::as_str).collect())
+        .unwrap_or_else(Vec::new);
+    for field in required {
+        if !object.contains_key(field) {
+            problems.push(format!(
+                "{}: missing required field \"{}\"",
+                location(path),
+                field
+            ));
+        }
+    }
+
+    for (key, field) in object {
+        let field_path = format!("{}/{}", path, key.replace('~', "~0").replace('/', "~1"));
+        match (properties.get(key), schema.get("additionalProperties")) {
+            (Some(field_schema), _) => {
+                check(root, field_schema, field, &field_path, problems);
+            }
+            (None, Some(Value::Bool(false))) => {
+                problems.push(format!("{}: unknown key", field_path));
+            }
+            (None, Some(other_fields)) if other_fields.is_object() => {
+                check(root, other_fields, field, &field_path, problems);
+            }
+            (None, _) => {}
+        }
+    }
+}
+
+/// Checks the items of an array, which either all have the same schema
+/// or, for tuples, one each.
+fn check_array(
+    root: &Value,
+    schema: &Value,
+    items: &[Value],
+    path: &str,
+    problems: &mut Vec<String>,
+) {
+    match schema.get("items") {
+        Some(Value::Array(item_schemas)) => {
+            if items.len() != item_schemas.len() {
+                problems.push(format!(
+                    "{}: expected {} items, found {}",
+                    location(path),
+                    item_schemas.len(),
+                    items.len()
+                ));
+            }
+            for (index, (item, item_schema)) in items.iter().zip(item_schemas).enumerate() {
+                check(
+                    root,
+                    item_schema,
+                    item,
+                    &format!("{}/{}", path, index),
+                    problems,
+                );
+            }
+        }
+        Some(item_schema) => {
+            for (index, item) in items.iter().enumerate() {
+                check(
+                    root,
+                    item_schema,
+                    item,
+                    &format!("{}/{}", path, index),
+                    problems,
+                );
+            }
+        }
+        None => {}
+    }
+}
+
+/// Where a problem is, which is "/" for the config itself.
+fn location(path: &str) -> &str {
+    if path.is_empty() {
+        "/"
+    } else {
+        path
+    }
+}
+
+/// Whether a value is of one of the types JSON Schema knows of.
+fn is_of_type(value: &Value, kind: &str) -> bool {
+    match kind {
+        "null" => value.is_null(),
+        "boolean" => value.is_boolean(),
+        "integer" => value.is_i64() || value.is_u64(),
+        "number" => value.is_number(),
+        "string" => value.is_string(),
+        "array" => value.is_array(),
+        "object" => value.is_object(),
+        _ => true,
+    }
+}
+
+/// A JSON Schema type as it's described in problems.
+fn describe_type(kind: &str) -> &str {
+    match kind {
+        "null" => "null",
+        "boolean" => "true or false",
+        "integer" => "a whole number",
+        "number" => "a number",
+        "string" => "a string",
+        "array" => "a list",
+        "object" => "an object",
+        kind => kind,
+    }
+}
+
+/// The type of a value as it's described in problems.
+fn describe_value(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "true or false",
+        Value::Number(number) if number.is_f64() => "a number",
+        Value::Number(_) => "a whole number",
+        Value::String(_) => "a string",
+        Value::Array(_) => "a list",
+        Value::Object(_) => "an object",
+    }
+}
@@ -0,0 +1,305 @@
+//! A small CSS selector parser and matcher for `select` documents.
+//!
+//! The `select` crate only provides predicates, so this supports the
+//! common subset of CSS that scraping needs: type selectors (`div`),
+//! the universal selector (`*`), ids (`#main`), classes (`.item`),
+//! attributes (`[href]`, `[rel=next]`, `[title="Next page"]`),
+//! descendant (`ul li`) and child (`ul > li`) combinators, and lists
+//! of selectors (`h2, h3`).
+
+use select::document::Document;
+use select::node::Node;
+use std::str::FromStr;
+
+/// A parsed CSS selector.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Selector {
+    /// The comma-separated selectors, any of which can match.
+    alternatives: Vec<Complex>,
+}
+
+/// A chain of compound selectors, e.g. `ul.list > li a`, stored from
+/// left to right with the combinator that comes before each.
+type Complex = Vec<(Combinator, Compound)>;
+
+/// How a compound selector relates to the one before it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Combinator {
+    /// Any ancestor (a space).
+    Descendant,
+    /// The direct parent (`>`).
+    Child,
+}
+
+/// A single element's requirements, e.g. `a.nav[href]`.
+#[derive(Debug, Clone, Default, PartialEq)]
+struct Compound {
+    tag: Option<String>,
+    id: Option<String>,
+    classes: Vec<String>,
+    /// Attributes the element must have, with the value they must
+    /// equal, if any.
+    attrs: Vec<(String, Option<String>)>,
+}
+
+impl FromStr for Selector {
+    type Err = String;
+
+    fn from_str(selector: &str) -> Result<Self, Self::Err> {
+        let alternatives = selector
+            .split(',')
+            .map(|alternative| Self::parse_complex(alternative.trim()))
+            .collect::<Result<Vec<Complex>, String>>()
+            .map_err(|err| format!("\"{}\" isn't a supported CSS selector: {}", selector, err))?;
+
+        Ok(Selector { alternatives })
+    }
+}
+
+impl Selector {
+    /// Finds all nodes in a document that match the selector.
+    pub fn find_in<'a>(&self, document: &'a Document) -> Vec<Node<'a>> {
+        document.find(|node: &Node| self.matches(node)).collect()
+    }
+
+    /// Finds all descendants of a node that match the selector.
+    pub fn find_under<'a>(&self, node: &Node<'a>) -> Vec<Node<'a>> {
+        node.descendants()
+            .filter(|descendant| self.matches(descendant))
+            .collect()
+    }
+
+    /// Whether a node matches the selector.
+    pub fn matches(&self, node: &Node) -> bool {
+        self.alternatives
+            .iter()
+            .any(|complex| Self::matches_from(complex, complex.len() - 1, node))
+    }
+
+    /// Whether a node matches a complex selector up to the compound
+    /// at `index`, checking its ancestors for the compounds before it.
+    fn matches_from(complex: &Complex, index: usize, node: &Node) -> bool {
+        let (combinator, compound) = &complex[index];
+        if !compound.matches(node) {
+            return false;
+        } else if index == 0 {
+            return true;
+        }
+
+        match combinator {
+            Combinator::Child => node
+                .parent()
+                .map(|parent| Self::matches_from(complex, index - 1, &parent))
+                .unwrap_or(false),
+            Combinator::Descendant => {
+                let mut ancestor = node.parent();
+                while let Some(current) = ancestor {
+                    if Self::matches_from(complex, index - 1, &current) {
+                        return true;
+                    }
+                    ancestor = current.parent();
+                }
+                false
+            }
+        }
+    }
+
+    /// Parses a selector without commas, like `ul.list > li a`.
+    fn parse_complex(selector: &str) -> Result<Complex, String> {
+        if selector.is_empty() {
+            return Err("a selector is empty".to_owned());
+        }
+
+        let mut complex = Vec::new();
+        let mut combinator = Combinator::Descendant;
+        let mut chars = selector.chars().peekable();
+        while let Some(&next) = chars.peek() {
+            if next.is_whitespace() {
+                chars.next();
+            } else if next == '>' {
+                if complex.is_empty() || combinator == Combinator::Child {
+                    return Err("\">\" must come between two selectors".to_owned());
+                }
+                combinator = Combinator::Child;
+                chars.next();
+            } else {
+                let mut compound = String::new();
+                // brackets can hold spaces and ">", e.g. [title="a > b"]
+                let mut in_brackets = false;
+                while let Some(&next) = chars.peek() {
+                    if !in_brackets && (next.is_whitespace() || next == '>') {
+                        break;
+                    }
+                    match next {
+                        '[' => in_brackets = true,
+                        ']' => in_brackets = false,
+                        _ => {}
+                    }
+                    compound.push(next);
+                    chars.next();
+                }
+                complex.push((combinator, Compound::parse(&compound)?));
+                combinator = Combinator::Descendant;
+            }
+        }
+
+        if combinator == Combinator::Child {
+            return Err("\">\" must come between two selectors".to_owned());
+        }
+        Ok(complex)
+    }
+}
+
+impl Compound {
+    /// Whether an element meets all of the requirements.
+    fn matches(&self, node: &Node) -> bool {
+        let name = match node.name() {
+            Some(name) => name,
+            // only elements have names
+            None => return false,
+        };
+
+        self.tag
+            .as_ref()
+            .map(|tag| tag.eq_ignore_ascii_case(name))
+            .unwrap_or(true)
+            && self
+                .id
+                .as_ref()
+                .map(|id| node.attr("id") == Some(id.as_str()))
+                .unwrap_or(true)
+            && self.classes.iter().all(|class| {
+                node.attr("class")
+                    .map(|classes| classes.split_whitespace().any(|other| other == class))
+                    .unwrap_or(false)
+            })
+            && self.attrs.iter().all(|(attr, value)| match value {
+                Some(value) => node.attr(attr) == Some(value.as_str()),
+                None => node.attr(attr).is_some(),
+            })
+    }
+
+    /// Parses a compound selector, like `a.nav[href]`.
+    fn parse(selector: &str) -> Result<Self, String> {
+        let mut compound = Compound::default();
+        let mut chars = selector.chars().peekable();
+
+        // the tag name, if any, comes first
+        let tag = Self::take_identifier(&mut chars);
+        if tag.len() > 0 {
+            compound.tag = Some(tag);
+        } else if chars.peek() == Some(&'*') {
+            chars.next();
+        }
+
+        while let Some(next) = chars.next() {
+            match next {
+                '#' => compound.id = Some(Self::require_identifier(&mut chars, "#")?),
+                '.' => compound
+                    .classes
+                    .push(Self::require_identifier(&mut chars, ".")?),
+                '[' => {
+                    let attr = Self::require_identifier(&mut chars, "[")?;
+                    let value = match chars.next() {
+                        Some(']') => None,
+                        Some('=') => {
+                            let mut value = String::new();
+                            loop {
+                                match chars.next() {
+                                    Some(']') => break,
+                                    Some(character) => value.push(character),
+                                    None => return Err("a \"[\" is never closed".to_owned()),
+                                }
+                            }
+                            Some(value.trim_matches(|c| c == '"' || c == '\'').to_owned())
+                        }
+                        _ => return Err(format!("the attribute \"{}\" is malformed", attr)),
+                    };
+                    compound.attrs.push((attr, value));
+                }
+                other => return Err(format!("\"{}\" isn't supported", other)),
+            }
+        }
+
+        Ok(compound)
+    }
+
+    /// Reads an identifier (a tag, id, class, or attribute name).
+    fn take_identifier<I: Iterator<Item = char>>(chars: &mut std::iter::Peekable<I>) -> String {
+        let mut identifier = String::new();
+        while let Some(&next) = chars.peek() {
+            if next.is_alphanumeric() || next == '-' || next == '_' {
+                identifier.push(next);
+                chars.next();
+            } else {
+                break;
+            }
+        }
+        identifier
+    }
+
+    /// Reads an identifier that must follow `after`.
+    fn require_identifier<I: Iterator<Item = char>>(
+        chars: &mut std::iter::Peekable<I>,
+        after: &str,
+    ) -> Result<String, String> {
+        let identifier = Self::take_identifier(chars);
+        if identifier.is_empty() {
+            Err(format!("\"{}\" must be followed by a name", after))
+        } else {
+            Ok(identifier)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PAGE: &str = r#"<html><body>
+        <ul class="posts" id="main">
+            <li class="post new"><a href="/one" title="a > b">One</a></li>
+            <li class="post"><div><a href="/two">Two</a></div></li>
+        </ul>
+        <h2>Heading</h2><h3>Subheading</h3>
+    </body></html>"#;
+
+    /// The text of each node in the page that matches `selector`.
+    fn texts(selector: &str) -> Vec<String> {
+        let document = Document::from(PAGE);
+        let selector: Selector = selector.parse().unwrap();
+        selector
+            .find_in(&document)
+            .iter()
+            .map(|node| node.text())
+            .collect()
+    }
+
+    #[test]
+    fn compound_selectors_need_everything() {
+        assert_eq!(texts("li.post.new a"), vec!["One"]);
+        assert_eq!(texts("ul#main a[href]"), vec!["One", "Two"]);
+        assert_eq!(texts(r#"a[title="a > b"]"#), vec!["One"]);
+        assert_eq!(texts("a[href=/two]"), vec!["Two"]);
+        assert!(texts("li.missing").is_empty());
+    }
+
+    #[test]
+    fn child_combinators_need_the_parent() {
+        assert_eq!(texts("li > a"), vec!["One"]);
+        assert_eq!(texts("li a"), vec!["One", "Two"]);
+    }
+
+    #[test]
+    fn any_alternative_can_match() {
+        assert_eq!(texts("h2, h3"), vec!["Heading", "Subheading"]);
+    }
+
+    #[test]
+    fn unsupported_selectors_are_refused() {
+        assert!("li:first-child".parse::<Selector>().is_err());
+        assert!("li >".parse::<Selector>().is_err());
+        assert!("h2,".parse::<Selector>().is_err());
+        assert!("a[href".parse::<Selector>().is_err());
+    }
+}
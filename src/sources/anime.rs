@@ -1,58 +1,90 @@
 //! The Anime platform for update checking.
 
-use crate::sources::{CheckForUpdates, SourceUpdate};
-use crate::util::readline;
-use chrono::{DateTime, FixedOffset, Local};
-use colored::Colorize;
-use rayon::iter::{IntoParallelRefMutIterator, ParallelIterator};
+use crate::ical::CalendarEvent;
+use crate::network::Api;
+use crate::sources::torrent::TorrentSearch;
+use crate::sources::{
+    check_each_source, deserialize_sources, log_new_items, serialize_sources, validate_field,
+    validate_name, CheckForUpdates, CheckOptions, NamedSource, SourceCheck, SourceSettings,
+    SourceUpdate,
+};
+use crate::state::State;
+use crate::style::{Role, Stylize};
+use crate::util::{access_error, check_status, get, readline, require_interactive, ReadlineResult};
+use chrono::{DateTime, Datelike, FixedOffset, Local, NaiveTime, TimeZone, Utc, Weekday};
+use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
+use serde_json::{Map, Value};
+use std::borrow::Cow;
 
 /// The wrapper type for Bandcamp artists and their last checked times
 /// to implement `CheckForUpdates` on.
-#[derive(Debug, Serialize, Deserialize, Clone, Default)]
-pub struct AnimeList(pub Vec<(Anime, Option<DateTime<Local>>)>);
+#[derive(Debug, Serialize, Deserialize, Clone, Default, JsonSchema)]
+pub struct AnimeList(
+    #[serde(
+        serialize_with = "serialize_sources",
+        deserialize_with = "deserialize_sources"
+    )]
+    #[schemars(with = "Vec<Anime>")]
+    pub Vec<(Anime, Option<DateTime<Local>>)>,
+);
+
+/// The names of the days of the week, starting from Monday.
+const DAY_NAMES: [&str; 7] = [
+    "Monday",
+    "Tuesday",
+    "Wednesday",
+    "Thursday",
+    "Friday",
+    "Saturday",
+    "Sunday",
+];
+
+/// When and whether an anime airs, from its details on Jikan.
+#[derive(Debug, Clone)]
+pub struct AiringSchedule {
+    /// The airing status, e.g. "Currently Airing".
+    pub status: String,
+    /// Whether the anime has finished airing and will never update again.
+    pub finished: bool,
+    /// The local day and time that new episodes air each week, if known.
+    pub broadcast: Option<(Weekday, NaiveTime)>,
+}
 
 /// An anime source struct.
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, Default, JsonSchema)]
 pub struct Anime {
     pub name: String,
     pub id: String,
+    /// The settings that every source has.
+    #[serde(flatten)]
+    pub settings: SourceSettings,
+    /// Any fields sitch doesn't know about, kept so they aren't lost.
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 impl CheckForUpdates for AnimeList {
-    fn check_for_all_updates(
-        &mut self,
-        sitch_last_checked: &Option<DateTime<Local>>,
-    ) -> Vec<(String, Result<Vec<SourceUpdate>, String>)> {
+    fn check_for_all_updates(&mut self, state: &State, options: &CheckOptions) -> Vec<SourceCheck> {
+        check_each_source(
+            "anime",
+            &mut self.0,
+            state,
+            options,
+            |anime, last_checked| {
+                (
+                    anime.name.clone(),
+                    anime.check_for_updates(last_checked, &None),
+                )
+            },
+        )
+    }
+
+    fn diagnose_all(&self) -> Vec<(String, Result<(), String>)> {
         self.0
-            .par_iter_mut()
-            .map(|(anime, last_checked)| {
-                // use the earliest `last_checked` time provided either by sitch generally
-                // or by this source to handle whe the user overrides the `last_checked` time
-                let true_last_checked = if sitch_last_checked.is_some() && last_checked.is_some() {
-                    Some(std::cmp::min(
-                        sitch_last_checked.unwrap(),
-                        last_checked.unwrap(),
-                    ))
-                } else {
-                    last_checked.or(*sitch_last_checked)
-                };
-                let update = anime.check_for_updates(&true_last_checked);
-                // update last_checked if an update occurred
-                if update
-                    .as_ref()
-                    .map(|updates| updates.len() > 0)
-                    .unwrap_or(false)
-                {
-                    *last_checked = Some(Local::now());
-                } else if last_checked.is_none() {
-                    // if this source hasn't been checked yet, but no update was
-                    // found, set it to the "global" `last_checked` time
-                    *last_checked = sitch_last_checked.clone();
-                }
-                (anime.name.clone(), update)
-            })
+            .par_iter()
+            .map(|(anime, _last_checked)| (anime.name.clone(), anime.diagnose()))
             .collect()
     }
 
@@ -61,15 +93,367 @@ impl CheckForUpdates for AnimeList {
     }
 }
 
+/// The anime list along with the torrent search to run for new episodes,
+/// which is set at the top level of the config rather than with the anime.
+pub struct AnimeWithTorrents<'a> {
+    pub list: &'a mut AnimeList,
+    pub torrent_search: &'a Option<TorrentSearch>,
+}
+
+impl<'a> CheckForUpdates for AnimeWithTorrents<'a> {
+    fn check_for_all_updates(&mut self, state: &State, options: &CheckOptions) -> Vec<SourceCheck> {
+        let torrent_search = self.torrent_search;
+        check_each_source(
+            "anime",
+            &mut self.list.0,
+            state,
+            options,
+            |anime, last_checked| {
+                (
+                    anime.name.clone(),
+                    anime.check_for_updates(last_checked, torrent_search),
+                )
+            },
+        )
+    }
+
+    fn diagnose_all(&self) -> Vec<(String, Result<(), String>)> {
+        self.list.diagnose_all()
+    }
+
+    fn type_name(&self) -> &'static str {
+        self.list.type_name()
+    }
+}
+
+impl AnimeList {
+    /// Prints a week view of when each followed anime airs, sorted by
+    /// the local day and time of its broadcast. Anime without a weekly
+    /// broadcast are listed afterwards with their airing status.
+    ///
+    /// Returns the indices of the anime that have finished airing.
+    pub fn print_schedule(&self) -> Vec<usize> {
+        let schedules = self
+            .0
+            .par_iter()
+            .map(|(anime, _last_checked)| anime.schedule())
+            .collect::<Vec<_>>();
+
+        let mut airing = Vec::new();
+        let mut unscheduled = Vec::new();
+        let mut finished = Vec::new();
+        for (index, ((anime, _last_checked), schedule)) in self.0.iter().zip(schedules).enumerate()
+        {
+            match schedule {
+                Ok(AiringSchedule {
+                    broadcast: Some((day, time)),
+                    finished: false,
+                    ..
+                }) => airing.push((day.num_days_from_monday(), time, &anime.name)),
+                Ok(schedule) => {
+                    if schedule.finished {
+                        finished.push(index);
+                        unscheduled.push((&anime.name, "finished".to_owned()));
+                    } else {
+                        unscheduled.push((&anime.name, schedule.status.to_lowercase()));
+                    }
+                }
+                Err(err) => unscheduled.push((&anime.name, err)),
+            }
+        }
+
+        // print each day's anime under a heading for the day
+        airing.sort();
+        let mut current_day = None;
+        for (day, time, name) in airing {
+            if current_day != Some(day) {
+                println!("{}", DAY_NAMES[day as usize].style(Role::Highlight));
+                current_day = Some(day);
+            }
+            println!("  {}  {}", time.format("%H:%M"), name.style(Role::Name));
+        }
+        if unscheduled.len() > 0 {
+            println!("{}", "Not airing weekly".style(Role::Highlight));
+            for (name, status) in unscheduled {
+                println!("  {} [{}]", name.style(Role::Name), status);
+            }
+        }
+
+        finished
+    }
+
+    /// Finds the episodes of each followed anime that will air in the next
+    /// `weeks` weeks as calendar events, or why an anime has none.
+    pub fn calendar_events(&self, weeks: u32) -> Vec<(&Anime, Result<Vec<CalendarEvent>, String>)> {
+        self.0
+            .par_iter()
+            .map(|(anime, _last_checked)| (anime, anime.calendar_events(weeks)))
+            .collect()
+    }
+}
+
+impl NamedSource for Anime {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn name_mut(&mut self) -> &mut String {
+        &mut self.name
+    }
+
+    fn location(&self) -> Cow<'_, str> {
+        Cow::Borrowed(&self.id)
+    }
+
+    fn page(&mut self) -> Result<String, String> {
+        Ok(format!("https://myanimelist.net/anime/{}", self.id))
+    }
+
+    fn validate(&mut self) -> Result<(), String> {
+        validate_name(&mut self.name)?;
+        validate_field("id", &mut self.id)
+    }
+
+    fn settings(&self) -> &SourceSettings {
+        &self.settings
+    }
+
+    fn settings_mut(&mut self) -> &mut SourceSettings {
+        &mut self.settings
+    }
+}
+
 impl Anime {
+    /// Makes an anime from its id or the URL of its page on MyAnimeList,
+    /// like "https://myanimelist.net/anime/1/Cowboy_Bebop", checking that
+    /// it exists and naming it after its title if no name is given.
+    pub fn from_id_or_url(id_or_url: &str, name: Option<String>) -> Result<Self, String> {
+        let id_or_url = id_or_url.trim();
+        // anime pages' URLs have the id after "/anime/"
+        let id = match id_or_url.find("/anime/") {
+            Some(index) => id_or_url[index + "/anime/".len()..]
+                .split(|c| c == '/' || c == '?')
+                .next()
+                .unwrap_or(""),
+            None => id_or_url,
+        };
+
+        let anime = Anime {
+            name: name.clone().unwrap_or_default(),
+            id: id.to_owned(),
+            ..Default::default()
+        };
+        let details = anime.fetch_details()?;
+        let title = details
+            .pointer("/title")
+            .and_then(|title| title.as_str())
+            .ok_or(format!("No anime found with id {}", anime.id))?;
+
+        Ok(Anime {
+            name: name.unwrap_or(title.to_owned()),
+            ..anime
+        })
+    }
+
+    /// Loads the details of the anime from Jikan as JSON.
+    fn fetch_details(&self) -> Result<Value, String> {
+        let query = Api::Jikan.url(&format!("anime/{}", self.id));
+        get(&query)
+            .map_err(|err| access_error(&query, &err))
+            .and_then(check_status)?
+            .json()
+            .map_err(|_err| "Couldn't parse request data as JSON".to_owned())
+    }
+
+    /// Makes sure that an anime exists with this anime's id.
+    pub fn diagnose(&self) -> Result<(), String> {
+        self.fetch_details()?
+            .pointer("/mal_id")
+            .map(|_id| ())
+            .ok_or(format!("No anime found with id {}", self.id))
+    }
+
+    /// Finds out when and whether the anime airs.
+    pub fn schedule(&self) -> Result<AiringSchedule, String> {
+        let data = self.fetch_details()?;
+        let status = data
+            .pointer("/status")
+            .and_then(|status_obj| status_obj.as_str())
+            .ok_or(format!("No anime found with id {}", self.id))?
+            .to_owned();
+        let broadcast = data
+            .pointer("/broadcast")
+            .and_then(|broadcast_obj| broadcast_obj.as_str())
+            .and_then(Self::parse_broadcast);
+
+        Ok(AiringSchedule {
+            finished: status == "Finished Airing",
+            status,
+            broadcast,
+        })
+    }
+
+    /// Parses a broadcast like "Saturdays at 01:00 (JST)" into the local
+    /// day and time that it airs.
+    fn parse_broadcast(broadcast: &str) -> Option<(Weekday, NaiveTime)> {
+        let airs = Self::next_broadcast(broadcast)?.with_timezone(&Local);
+
+        Some((airs.weekday(), airs.time()))
+    }
+
+    /// Parses a broadcast like "Saturdays at 01:00 (JST)" into the next
+    /// time that it airs, in Japan's time zone, which has no daylight
+    /// saving time to account for.
+    fn next_broadcast(broadcast: &str) -> Option<DateTime<FixedOffset>> {
+        let mut parts = broadcast.split_whitespace();
+        let weekday = parts
+            .next()?
+            .trim_end_matches('s')
+            .parse::<Weekday>()
+            .ok()?;
+        let time = match (parts.next(), parts.next(), parts.next()) {
+            (Some("at"), Some(time), Some("(JST)")) => {
+                NaiveTime::parse_from_str(time, "%H:%M").ok()?
+            }
+            _ => return None,
+        };
+
+        // find this week's broadcast in Japan
+        let jst = FixedOffset::east(9 * 60 * 60);
+        let now = Local::now().with_timezone(&jst);
+        let today = now.date();
+        let days_ahead =
+            (7 + weekday.num_days_from_monday() - today.weekday().num_days_from_monday()) % 7;
+        let airs = jst
+            .from_local_date(&today.naive_local())
+            .single()?
+            .and_time(time)?
+            + chrono::Duration::days(days_ahead as i64);
+
+        // one from earlier today has already aired
+        if airs < now {
+            Some(airs + chrono::Duration::weeks(1))
+        } else {
+            Some(airs)
+        }
+    }
+
+    /// Parses how long each episode is, like "24 min per ep" or
+    /// "1 hr 5 min".
+    fn parse_episode_length(duration: &str) -> Option<chrono::Duration> {
+        let words = duration.split_whitespace().collect::<Vec<&str>>();
+        let length = words
+            .windows(2)
+            .filter_map(|pair| {
+                let amount = pair[0].parse::<i64>().ok()?;
+                match pair[1] {
+                    "hr" | "hr." => Some(chrono::Duration::hours(amount)),
+                    "min" | "min." => Some(chrono::Duration::minutes(amount)),
+                    "sec" | "sec." => Some(chrono::Duration::seconds(amount)),
+                    _ => None,
+                }
+            })
+            .fold(chrono::Duration::zero(), |total, part| total + part);
+
+        Some(length).filter(|length| *length > chrono::Duration::zero())
+    }
+
+    /// The number of the latest episode that has aired, or 0 if none have.
+    fn latest_episode(&self) -> Result<u64, String> {
+        let query = Api::Jikan.url(&format!("anime/{}/episodes/1", self.id));
+        let data: Value = get(&query)
+            .map_err(|err| access_error(&query, &err))
+            .and_then(check_status)?
+            .json()
+            .map_err(|_err| "Couldn't parse request data as JSON".to_owned())?;
+        let episodes = data
+            .pointer("/episodes")
+            .and_then(|episodes_obj| episodes_obj.as_array())
+            .ok_or("Could not find episodes in received JSON")?;
+
+        let now = Local::now();
+        Ok(episodes
+            .iter()
+            .filter(|episode| {
+                episode
+                    .pointer("/aired")
+                    .and_then(|date_obj| date_obj.as_str())
+                    .and_then(|date_str| DateTime::<FixedOffset>::parse_from_rfc3339(date_str).ok())
+                    .map(|aired| aired.with_timezone(&Local) <= now)
+                    .unwrap_or(false)
+            })
+            .filter_map(|episode| episode.pointer("/episode_id").and_then(|id| id.as_u64()))
+            .max()
+            .unwrap_or(0))
+    }
+
+    /// Makes a calendar event for each episode of the anime that will air
+    /// in the next `weeks` weeks, going by its weekly broadcast time.
+    ///
+    /// Anime that aren't currently airing or don't have a broadcast time
+    /// have no schedule to go by, so an error saying so is returned.
+    pub fn calendar_events(&self, weeks: u32) -> Result<Vec<CalendarEvent>, String> {
+        let data = self.fetch_details()?;
+        let status = data
+            .pointer("/status")
+            .and_then(|status_obj| status_obj.as_str())
+            .ok_or(format!("No anime found with id {}", self.id))?;
+        if status != "Currently Airing" {
+            return Err(format!("it isn't airing ({})", status.to_lowercase()));
+        }
+        let first_airs = data
+            .pointer("/broadcast")
+            .and_then(|broadcast_obj| broadcast_obj.as_str())
+            .and_then(Self::next_broadcast)
+            .ok_or("it has no weekly broadcast time".to_owned())?
+            .with_timezone(&Utc);
+        let total_episodes = data
+            .pointer("/episodes")
+            .and_then(|episodes_obj| episodes_obj.as_u64());
+        // most episodes run about 24 minutes
+        let length = data
+            .pointer("/duration")
+            .and_then(|duration_obj| duration_obj.as_str())
+            .and_then(Self::parse_episode_length)
+            .unwrap_or(chrono::Duration::minutes(24));
+        let next_episode = self.latest_episode()? + 1;
+
+        Ok((0..weeks as u64)
+            .map(|week| (next_episode + week, week))
+            .take_while(|(number, _week)| {
+                total_episodes
+                    .map(|total_episodes| *number <= total_episodes)
+                    .unwrap_or(true)
+            })
+            .map(|(number, week)| {
+                let start = first_airs + chrono::Duration::weeks(week as i64);
+                CalendarEvent {
+                    // the same episode keeps the same id between calendars
+                    uid: format!("anime-{}-episode-{}@sitch", self.id, number),
+                    summary: format!("{} - Episode {}", self.name, number),
+                    url: Some(format!("https://myanimelist.net/anime/{}", self.id)),
+                    start,
+                    end: start + length,
+                }
+            })
+            .collect())
+    }
+
+    /// Check for episodes of the anime aired since `last_checked`.
+    ///
+    /// If a torrent search is given, each new episode also links to the
+    /// best torrent of it found, if any. A failed search leaves the link
+    /// out rather than failing the check.
     pub fn check_for_updates(
         &self,
         last_checked: &Option<DateTime<Local>>,
+        torrent_search: &Option<TorrentSearch>,
     ) -> Result<Vec<SourceUpdate>, String> {
         // retrieve the API search data as JSON or return an error
-        let query = format!("https://api.jikan.moe/v3/anime/{}/episodes/1", self.id);
-        let data: Value = reqwest::get(&query)
-            .map_err(|_err| format!("Couldn't access {}", query))?
+        let query = Api::Jikan.url(&format!("anime/{}/episodes/1", self.id));
+        let data: Value = get(&query)
+            .map_err(|err| access_error(&query, &err))
+            .and_then(check_status)?
             .json()
             .map_err(|_err| "Couldn't parse request data as JSON".to_owned())?;
 
@@ -79,7 +463,7 @@ impl Anime {
             .and_then(|episodes_obj| episodes_obj.as_array())
             .ok_or("Could not find episodes in received JSON")?;
 
-        Ok(episodes
+        let updates: Vec<SourceUpdate> = episodes
             .iter()
             .filter_map(|episode| {
                 // parse the published date for each episode
@@ -94,54 +478,78 @@ impl Anime {
                             .map(|last_checked| last_checked < *local_date)
                             .unwrap_or(true)
                     })?;
-                // parse episode_id for ther title
+                // parse episode_id and the episode's title, if they exist
                 let episode_number = episode
                     .pointer("/episode_id")
-                    .and_then(|id_obj| id_obj.as_u64())?;
-                let title = format!(
-                    "Episode {} - {}",
-                    episode_number,
-                    episode
-                        .pointer("/title")
-                        .and_then(|title_obj| title_obj.as_str())?
-                );
-                // parse the link for the update
+                    .and_then(|id_obj| id_obj.as_u64());
+                let episode_title = episode
+                    .pointer("/title")
+                    .and_then(|title_obj| title_obj.as_str());
+                let title = match (episode_number, episode_title) {
+                    (Some(number), Some(title)) => format!("Episode {} - {}", number, title),
+                    (Some(number), None) => format!("Episode {}", number),
+                    (None, Some(title)) => title.to_owned(),
+                    (None, None) => "<unnamed>".to_owned(),
+                };
+                // most episodes have no video, so fall back to the forum
+                // thread or the episode's page on MyAnimeList
                 let link = episode
                     .pointer("/video_url")
-                    .and_then(|link_obj| link_obj.as_str())?
-                    .to_owned();
+                    .or(episode.pointer("/forum_url"))
+                    .and_then(|link_obj| link_obj.as_str())
+                    .map(|link| link.to_owned())
+                    .or(episode_number.map(|number| {
+                        format!(
+                            "https://myanimelist.net/anime/{}/_/episode/{}",
+                            self.id, number
+                        )
+                    }))
+                    .unwrap_or("<no link>".to_owned());
 
                 Some(SourceUpdate {
                     title,
                     link,
                     published_date,
+                    thumbnail: None,
+                    description: None,
+                    secondary_link: torrent_search
+                        .as_ref()
+                        .and_then(|search| search.find_episode(&self.name, episode_number?).ok())
+                        .and_then(|torrent| torrent),
+                    also_in: Vec::new(),
                 })
             })
-            .collect())
+            .collect();
+        log_new_items(&self.name, episodes.len(), updates.len());
+
+        Ok(updates)
     }
 
     /// Search interactively for new anime to add to sitch.
     ///
     /// Reads from stdin to take input and asks the user before any
-    /// sources are added.
-    pub fn interactive_search() -> Result<Self, String> {
+    /// sources are added. Returns `None` if the user declined the
+    /// result or quit.
+    pub fn interactive_search() -> Result<Option<Self>, String> {
+        require_interactive("sitch anime add --name <NAME> --id <ID>")?;
         loop {
             // Take a query for input
-            let search_term = readline("Search for an anime by name: ", |search| {
+            let search_term = match readline("Search for an anime by name: ", |search| {
                 if search.len() > 3 {
                     Ok(search)
                 } else {
                     Err("Search term must be longer than 3 characters.".to_owned())
                 }
-            });
+            })? {
+                ReadlineResult::Value(search_term) => search_term,
+                ReadlineResult::Quit => return Ok(None),
+            };
 
             // parse the query's returned data as JSON
-            let query = format!(
-                "https://api.jikan.moe/v3/search/anime?q={}&limit=5",
-                search_term
-            );
-            let data: Value = reqwest::get(&query)
-                .map_err(|_err| format!("Couldn't access {}", query))?
+            let query = Api::Jikan.url(&format!("search/anime?q={}&limit=5", search_term));
+            let data: Value = get(&query)
+                .map_err(|err| access_error(&query, &err))
+                .and_then(check_status)?
                 .json()
                 .map_err(|_err| "Couldn't parse request data as JSON".to_owned())?;
 
@@ -172,7 +580,6 @@ impl Anime {
                 0 => println!("No results found, please try again."),
                 1 => {
                     // if only one was found, ask if they want to add it.
-                    // if they don't, exit from sitch.
                     let (title, id) = search_results.into_iter().next().unwrap();
                     println!("Found 1 result: \"{}\" (id = {})", title, id);
                     let should_add =
@@ -180,11 +587,28 @@ impl Anime {
                             "" | "y" | "Y" | "yes" => Ok(true),
                             "n" | "N" | "no" => Ok(false),
                             _ => Err("Please respond with a yes or no.".to_owned()),
-                        });
-                    if should_add {
-                        return Ok(Self { name: title, id });
-                    } else {
-                        std::process::exit(0);
+                        })?;
+                    match should_add {
+                        ReadlineResult::Value(true) => {
+                            return Ok(Some(Self {
+                                name: title,
+                                id,
+                                ..Default::default()
+                            }))
+                        }
+                        // if they don't, let them search again or stop
+                        ReadlineResult::Value(false) => {
+                            let search_again =
+                                readline("Search again? [Y/n] ", |input| match input.as_str() {
+                                    "" | "y" | "Y" | "yes" => Ok(true),
+                                    "n" | "N" | "no" => Ok(false),
+                                    _ => Err("Please respond with a yes or no.".to_owned()),
+                                })?;
+                            if search_again != ReadlineResult::Value(true) {
+                                return Ok(None);
+                            }
+                        }
+                        ReadlineResult::Quit => return Ok(None),
                     }
                 }
                 num_results => {
@@ -194,12 +618,12 @@ impl Anime {
                     for (index, (title, id)) in search_results.iter().enumerate() {
                         println!(
                             "{}: \"{}\" (id = {})",
-                            (index + 1).to_string().yellow(),
-                            title.green(),
+                            (index + 1).style(Role::Index),
+                            title.style(Role::Name),
                             id
                         );
                     }
-                    let index = readline(
+                    let index = match readline(
                         &format!("Pick a result to add [1 to {}]: ", num_results),
                         |picked| match picked.parse::<usize>() {
                             Ok(index) if (1 <= index && index <= num_results) => Ok(index - 1),
@@ -208,11 +632,204 @@ impl Anime {
                             }
                             Err(_err) => Err("The value wasn't an integer.".to_owned()),
                         },
-                    );
+                    )? {
+                        ReadlineResult::Value(index) => index,
+                        ReadlineResult::Quit => return Ok(None),
+                    };
                     let (name, id) = search_results.into_iter().nth(index).unwrap();
-                    return Ok(Self { name, id });
+                    return Ok(Some(Self {
+                        name,
+                        id,
+                        ..Default::default()
+                    }));
                 }
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::{ApiBases, NetworkConfig};
+    use crate::util::{lock_network, ok_response, serve_in_turn, serve_once};
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn unsuccessful_responses_are_reported() {
+        let _network = lock_network();
+        let (url, server) = serve_once(
+            "127.0.0.81",
+            "HTTP/1.1 503 Service Unavailable\r\nContent-Length: 2\r\n\
+             Connection: close\r\n\r\n{}",
+        );
+        let network = NetworkConfig {
+            api_bases: Some(ApiBases {
+                anime: Some(url),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        network.apply().unwrap();
+
+        let anime = Anime {
+            id: "1".to_owned(),
+            ..Default::default()
+        };
+        let result = anime.diagnose();
+        NetworkConfig::default().apply().unwrap();
+
+        assert!(result
+            .unwrap_err()
+            .ends_with("responded with 503 Service Unavailable"));
+        assert!(server.join().unwrap().starts_with("GET /anime/1 "));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn episodes_without_a_video_still_link_somewhere() {
+        let _network = lock_network();
+        let body = r#"{"episodes": [
+            {"episode_id": 1, "title": "Start", "aired": "2024-03-01T00:00:00+00:00",
+             "video_url": "https://example.com/video/1"},
+            {"episode_id": 2, "aired": "2024-03-08T00:00:00+00:00",
+             "forum_url": "https://example.com/forum/2"},
+            {"episode_id": 3, "title": "Middle", "aired": "2024-03-15T00:00:00+00:00"},
+            {"aired": "2024-03-22T00:00:00+00:00"}
+        ]}"#;
+        let (url, server) = serve_once(
+            "127.0.0.84",
+            &format!(
+                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            ),
+        );
+        let network = NetworkConfig {
+            api_bases: Some(ApiBases {
+                anime: Some(url),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        network.apply().unwrap();
+
+        let anime = Anime {
+            id: "5".to_owned(),
+            ..Default::default()
+        };
+        let updates = anime.check_for_updates(&None, &None);
+        NetworkConfig::default().apply().unwrap();
+        server.join().unwrap();
+
+        let updates = updates.unwrap();
+        let found = updates
+            .iter()
+            .map(|update| (update.title.as_str(), update.link.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            found,
+            vec![
+                ("Episode 1 - Start", "https://example.com/video/1"),
+                ("Episode 2", "https://example.com/forum/2"),
+                (
+                    "Episode 3 - Middle",
+                    "https://myanimelist.net/anime/5/_/episode/3"
+                ),
+                ("<unnamed>", "<no link>"),
+            ]
+        );
+    }
+
+    #[test]
+    fn broadcasts_are_in_japan_time() {
+        let now = Local::now();
+        let airs = Anime::next_broadcast("Saturdays at 01:00 (JST)").unwrap();
+        let airs_here = airs.with_timezone(&Local);
+
+        assert_eq!(airs.offset().local_minus_utc(), 9 * 60 * 60);
+        assert_eq!(airs.weekday(), Weekday::Sat);
+        assert_eq!(airs.time(), NaiveTime::from_hms(1, 0, 0));
+        assert!(airs_here >= now && airs_here < now + chrono::Duration::weeks(1));
+        assert_eq!(Anime::next_broadcast("Saturdays at 01:00 (PST)"), None);
+        assert_eq!(Anime::next_broadcast("Unknown"), None);
+    }
+
+    #[test]
+    fn episode_lengths_add_up() {
+        assert_eq!(
+            Anime::parse_episode_length("1 hr 5 min"),
+            Some(chrono::Duration::minutes(65))
+        );
+        assert_eq!(
+            Anime::parse_episode_length("24 min per ep"),
+            Some(chrono::Duration::minutes(24))
+        );
+        assert_eq!(Anime::parse_episode_length("Unknown"), None);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn calendar_events_are_for_the_next_episodes() {
+        let _network = lock_network();
+        let details = serde_json::json!({
+            "status": "Currently Airing",
+            "broadcast": "Saturdays at 01:00 (JST)",
+            "episodes": 4,
+            "duration": "23 min per ep"
+        });
+        let episodes = serde_json::json!({"episodes": [
+            {"episode_id": 1, "aired": "2024-03-01T00:00:00+09:00"},
+            {"episode_id": 2, "aired": "2024-03-08T00:00:00+09:00"}
+        ]});
+        let (url, server) = serve_in_turn(
+            "127.0.0.88",
+            &[
+                ok_response(&details.to_string()),
+                ok_response(&episodes.to_string()),
+            ],
+        );
+        let network = NetworkConfig {
+            api_bases: Some(ApiBases {
+                anime: Some(url),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        network.apply().unwrap();
+
+        let anime = Anime {
+            name: "Show".to_owned(),
+            id: "7".to_owned(),
+            ..Default::default()
+        };
+        let events = anime.calendar_events(5);
+        NetworkConfig::default().apply().unwrap();
+        server.join().unwrap();
+
+        // only episodes 3 and 4 are left to air
+        let events = events.unwrap();
+        let uids = events
+            .iter()
+            .map(|event| event.uid.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            uids,
+            vec!["anime-7-episode-3@sitch", "anime-7-episode-4@sitch"]
+        );
+        assert_eq!(events[0].summary, "Show - Episode 3");
+        assert_eq!(
+            events[1].start - events[0].start,
+            chrono::Duration::weeks(1)
+        );
+        assert_eq!(
+            events[0].end - events[0].start,
+            chrono::Duration::minutes(23)
+        );
+        let airs_in_japan = events[0]
+            .start
+            .with_timezone(&FixedOffset::east(9 * 60 * 60));
+        assert_eq!(airs_in_japan.weekday(), Weekday::Sat);
+        assert_eq!(airs_in_japan.time(), NaiveTime::from_hms(1, 0, 0));
+    }
+}
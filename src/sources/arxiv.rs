@@ -0,0 +1,266 @@
+//! The arXiv platform for update checking, which reports new papers
+//! matching a search through the arXiv API.
+
+use crate::sources::{
+    check_each_source, deserialize_sources, log_new_items, serialize_sources, validate_field,
+    validate_name, CheckForUpdates, CheckOptions, NamedSource, SourceCheck, SourceSettings,
+    SourceUpdate,
+};
+use crate::state::State;
+use crate::util::{access_error, check_status, get};
+use chrono::{DateTime, FixedOffset, Local};
+use reqwest::Url;
+use schemars::JsonSchema;
+use select::document::Document;
+use select::node::Node;
+use select::predicate::{Attr, Name, Predicate};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::borrow::Cow;
+use std::sync::Mutex;
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+/// The arXiv API endpoint for searching papers.
+const QUERY_URL: &str = "https://export.arxiv.org/api/query";
+
+/// How long arXiv asks API users to wait between requests.
+const REQUEST_INTERVAL: Duration = Duration::from_secs(3);
+
+/// The most papers loaded per query by default.
+const DEFAULT_MAX_RESULTS: usize = 20;
+
+/// The wrapper type for arXiv queries and their last checked times
+/// to implement `CheckForUpdates` on.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, JsonSchema)]
+pub struct ArxivQueries(
+    #[serde(
+        serialize_with = "serialize_sources",
+        deserialize_with = "deserialize_sources"
+    )]
+    #[schemars(with = "Vec<ArxivQuery>")]
+    pub Vec<(ArxivQuery, Option<DateTime<Local>>)>,
+);
+
+/// A search for arXiv papers.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, JsonSchema)]
+pub struct ArxivQuery {
+    pub name: String,
+    /// The search in arXiv's query syntax, like "au:hinton AND cat:cs.LG".
+    pub query: String,
+    /// The most new papers to load per check. Defaults to
+    /// `DEFAULT_MAX_RESULTS`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_results: Option<usize>,
+    /// The settings that every source has.
+    #[serde(flatten)]
+    pub settings: SourceSettings,
+    /// Any fields sitch doesn't know about, kept so they aren't lost.
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// Spaces out requests to the arXiv API across all queries, even
+/// though they are checked in parallel.
+pub struct RateLimiter {
+    last_request: Mutex<Option<Instant>>,
+}
+
+impl RateLimiter {
+    fn new() -> Self {
+        RateLimiter {
+            last_request: Mutex::new(None),
+        }
+    }
+
+    /// Waits until `REQUEST_INTERVAL` has passed since the last request,
+    /// then records a new one.
+    fn wait(&self) {
+        // holding the lock while sleeping makes other queries wait their turn
+        let mut last_request = self.last_request.lock().unwrap();
+        if let Some(last_request) = *last_request {
+            let elapsed = last_request.elapsed();
+            if elapsed < REQUEST_INTERVAL {
+                sleep(REQUEST_INTERVAL - elapsed);
+            }
+        }
+        *last_request = Some(Instant::now());
+    }
+}
+
+impl CheckForUpdates for ArxivQueries {
+    fn check_for_all_updates(&mut self, state: &State, options: &CheckOptions) -> Vec<SourceCheck> {
+        let rate_limiter = RateLimiter::new();
+        check_each_source(
+            "arxiv",
+            &mut self.0,
+            state,
+            options,
+            |query, last_checked| {
+                (
+                    query.name.clone(),
+                    query.check_for_updates(&rate_limiter, last_checked),
+                )
+            },
+        )
+    }
+
+    fn diagnose_all(&self) -> Vec<(String, Result<(), String>)> {
+        // diagnosing runs one query after another to respect the rate limit
+        let rate_limiter = RateLimiter::new();
+        self.0
+            .iter()
+            .map(|(query, _last_checked)| (query.name.clone(), query.diagnose(&rate_limiter)))
+            .collect()
+    }
+
+    fn type_name(&self) -> &'static str {
+        "arXiv"
+    }
+}
+
+impl NamedSource for ArxivQuery {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn name_mut(&mut self) -> &mut String {
+        &mut self.name
+    }
+
+    fn location(&self) -> Cow<'_, str> {
+        Cow::Borrowed(&self.query)
+    }
+
+    fn page(&mut self) -> Result<String, String> {
+        Url::parse_with_params(
+            "https://arxiv.org/search/",
+            &[("query", self.query.as_str()), ("searchtype", "all")],
+        )
+        .map(|url| url.to_string())
+        .map_err(|err| format!("Couldn't make the search's URL: {}", err))
+    }
+
+    fn validate(&mut self) -> Result<(), String> {
+        validate_name(&mut self.name)?;
+        validate_field("query", &mut self.query)
+    }
+
+    fn settings(&self) -> &SourceSettings {
+        &self.settings
+    }
+
+    fn settings_mut(&mut self) -> &mut SourceSettings {
+        &mut self.settings
+    }
+}
+
+impl ArxivQuery {
+    /// Loads the newest papers matching the query, up to `max_results`.
+    fn papers(
+        &self,
+        rate_limiter: &RateLimiter,
+        max_results: usize,
+    ) -> Result<Vec<SourceUpdate>, String> {
+        // the query is passed along as written, only URL-encoded
+        let url = Url::parse_with_params(
+            QUERY_URL,
+            &[
+                ("search_query", self.query.as_str()),
+                ("sortBy", "submittedDate"),
+                ("sortOrder", "descending"),
+                ("max_results", &max_results.to_string()),
+            ],
+        )
+        .map_err(|err| format!("Couldn't build the arXiv API URL: {}", err))?;
+
+        rate_limiter.wait();
+        let response = get(url.as_str()).map_err(|err| access_error(url.as_str(), &err))?;
+        let feed = check_status(response)?
+            .text()
+            .map_err(|_err| "Couldn't read the arXiv API's response".to_owned())?;
+        let document = Document::from(feed.as_str());
+
+        Ok(document
+            .find(Name("entry"))
+            .filter_map(|entry| Self::parse_entry(&entry))
+            .collect())
+    }
+
+    /// Makes sure that the query can be run.
+    pub fn diagnose(&self, rate_limiter: &RateLimiter) -> Result<(), String> {
+        self.papers(rate_limiter, 1).map(|_papers| ())
+    }
+
+    /// Check for papers matching the query published since `last_checked`.
+    pub fn check_for_updates(
+        &self,
+        rate_limiter: &RateLimiter,
+        last_checked: &Option<DateTime<Local>>,
+    ) -> Result<Vec<SourceUpdate>, String> {
+        let papers = self.papers(
+            rate_limiter,
+            self.max_results.unwrap_or(DEFAULT_MAX_RESULTS),
+        )?;
+        let loaded = papers.len();
+        let updates: Vec<SourceUpdate> = papers
+            .into_iter()
+            .filter(|update| {
+                last_checked
+                    .map(|last_checked| last_checked < update.published_date)
+                    .unwrap_or(true)
+            })
+            .collect();
+        log_new_items(&self.name, loaded, updates.len());
+
+        Ok(updates)
+    }
+
+    /// Parses a paper from the Atom feed, skipping it if it has no date.
+    fn parse_entry(entry: &Node) -> Option<SourceUpdate> {
+        // <entry>
+        //   <id>http://arxiv.org/abs/1901.00001v1</id>
+        //   <published>2019-01-01T00:00:00Z</published>
+        //   <title>A Paper's Title</title>
+        //   <summary>The abstract...</summary>
+        //   <author><name>Some One</name></author>
+        //   <link href="http://arxiv.org/abs/1901.00001v1" rel="alternate" type="text/html"/>
+        //   ...
+        // </entry>
+        let text_of = |name: &str| {
+            entry.find(Name(name)).next().map(|node| {
+                node.text()
+                    .split_whitespace()
+                    .collect::<Vec<&str>>()
+                    .join(" ")
+            })
+        };
+        let published_date = text_of("published")
+            .and_then(|date| DateTime::<FixedOffset>::parse_from_rfc3339(&date).ok())
+            .map(|date| date.with_timezone(&Local))?;
+        let authors = entry
+            .find(Name("author").descendant(Name("name")))
+            .map(|name| name.text().trim().to_owned())
+            .collect::<Vec<String>>();
+
+        Some(SourceUpdate {
+            title: text_of("title").unwrap_or("<no title>".to_owned()),
+            link: entry
+                .find(Name("link").and(Attr("rel", "alternate")))
+                .next()
+                .and_then(|link| link.attr("href"))
+                .map(|href| href.to_owned())
+                .or_else(|| text_of("id"))
+                .unwrap_or("<no link>".to_owned()),
+            published_date,
+            thumbnail: None,
+            description: if authors.is_empty() {
+                None
+            } else {
+                Some(authors.join(", "))
+            },
+            secondary_link: None,
+            also_in: Vec::new(),
+        })
+    }
+}
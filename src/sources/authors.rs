@@ -0,0 +1,419 @@
+//! The authors platform for update checking, which reports new books
+//! by authors through their Goodreads feeds.
+
+use crate::sources::{
+    check_each_source_remembering, deserialize_sources, log_new_items, serialize_sources,
+    validate_field, validate_name, CheckForUpdates, CheckOptions, NamedSource, SourceCheck,
+    SourceSettings, SourceUpdate,
+};
+use crate::state::{SourceMemory, State};
+use crate::util::{access_error, check_status, get};
+use chrono::{DateTime, FixedOffset, Local};
+use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+use reqwest::Url;
+use rss::Channel;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::borrow::Cow;
+
+/// The wrapper type for authors and their last checked times
+/// to implement `CheckForUpdates` on.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, JsonSchema)]
+pub struct Authors(
+    #[serde(
+        serialize_with = "serialize_sources",
+        deserialize_with = "deserialize_sources"
+    )]
+    #[schemars(with = "Vec<Author>")]
+    pub Vec<(Author, Option<DateTime<Local>>)>,
+);
+
+/// An author on Goodreads.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, JsonSchema)]
+pub struct Author {
+    pub name: String,
+    /// The author's id on Goodreads, from their page's URL.
+    pub author_id: String,
+    /// The settings that every source has.
+    #[serde(flatten)]
+    pub settings: SourceSettings,
+    /// Any fields sitch doesn't know about, kept so they aren't lost.
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// A book read from an author's feed.
+struct Book {
+    title: String,
+    link: String,
+    date: Option<DateTime<Local>>,
+    /// The ISBN, or the link for books without one.
+    key: String,
+}
+
+impl CheckForUpdates for Authors {
+    fn check_for_all_updates(&mut self, state: &State, options: &CheckOptions) -> Vec<SourceCheck> {
+        check_each_source_remembering(
+            "authors",
+            &mut self.0,
+            state,
+            options,
+            |author, last_checked, memory| {
+                (
+                    author.name.clone(),
+                    author.check_for_updates(last_checked, memory),
+                )
+            },
+        )
+    }
+
+    fn diagnose_all(&self) -> Vec<(String, Result<(), String>)> {
+        self.0
+            .par_iter()
+            .map(|(author, _last_checked)| (author.name.clone(), author.diagnose()))
+            .collect()
+    }
+
+    fn type_name(&self) -> &'static str {
+        "Authors"
+    }
+}
+
+impl NamedSource for Author {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn name_mut(&mut self) -> &mut String {
+        &mut self.name
+    }
+
+    fn location(&self) -> Cow<'_, str> {
+        Cow::Owned(self.author_url())
+    }
+
+    fn validate(&mut self) -> Result<(), String> {
+        validate_name(&mut self.name)?;
+        validate_field("author id", &mut self.author_id)
+    }
+
+    fn settings(&self) -> &SourceSettings {
+        &self.settings
+    }
+
+    fn settings_mut(&mut self) -> &mut SourceSettings {
+        &mut self.settings
+    }
+}
+
+impl Author {
+    /// Makes an author from their id or the URL of their Goodreads page,
+    /// like "https://www.goodreads.com/author/show/1234.Some_Author".
+    ///
+    /// If no name is given, the one in the URL is used, or the author's
+    /// feed is loaded to find it.
+    pub fn from_id_or_url(id_or_url: &str, name: Option<String>) -> Result<Self, String> {
+        let id_or_url = id_or_url.trim();
+        // the last part of the path is "<id>" or "<id>.<Name>"
+        let (id, url_name) = if id_or_url.chars().all(|c| c.is_ascii_digit()) {
+            (id_or_url.to_owned(), None)
+        } else {
+            let url = Url::parse(id_or_url)
+                .map_err(|_err| format!("{} isn't an author id or URL", id_or_url))?;
+            let last_segment = url
+                .path_segments()
+                .and_then(|segments| segments.filter(|segment| !segment.is_empty()).last())
+                .unwrap_or("");
+            let mut parts = last_segment.splitn(2, '.');
+            let id = parts.next().unwrap_or("");
+            if id.is_empty() || !id.chars().all(|c| c.is_ascii_digit()) {
+                return Err(format!("Couldn't find an author id in {}", id_or_url));
+            }
+            let url_name = parts.next().map(|name| name.replace('_', " "));
+            (id.to_owned(), url_name)
+        };
+
+        let mut author = Author {
+            author_id: id,
+            ..Default::default()
+        };
+        author.name = match name.or(url_name) {
+            Some(name) => name,
+            None => author.fetch_channel()?.title().trim().to_owned(),
+        };
+
+        Ok(author)
+    }
+
+    /// The URL of the author's page on Goodreads.
+    pub fn author_url(&self) -> String {
+        format!("https://www.goodreads.com/author/show/{}", self.author_id)
+    }
+
+    /// Loads the author's books feed, returning the raw XML along with it
+    /// to read the fields that the `rss` crate leaves out.
+    fn fetch_feed(&self) -> Result<(Channel, String), String> {
+        let feed_url = format!(
+            "https://www.goodreads.com/author/list_rss/{}",
+            self.author_id
+        );
+        let response = get(&feed_url).map_err(|err| access_error(&feed_url, &err))?;
+        let xml = check_status(response)?
+            .text()
+            .map_err(|_err| "Couldn't read the author's feed".to_owned())?;
+        let channel = xml
+            .parse::<Channel>()
+            .map_err(|err| format!("Couldn't parse the author's feed: {}", err))?;
+
+        Ok((channel, xml))
+    }
+
+    /// Loads just the author's books feed.
+    fn fetch_channel(&self) -> Result<Channel, String> {
+        self.fetch_feed().map(|(channel, _xml)| channel)
+    }
+
+    /// Reads the books in the author's feed (see `fetch_feed`), in feed
+    /// order.
+    fn read_books(&self, channel: &Channel, xml: &str) -> Vec<Book> {
+        // <item>
+        //     <guid><![CDATA[https://www.goodreads.com/book/show/1234]]></guid>
+        //     <pubDate><![CDATA[Tue, 13 Oct 2026 00:00:00 -0700]]></pubDate>
+        //     <title><![CDATA[A Book]]></title>
+        //     <link><![CDATA[https://www.goodreads.com/book/show/1234]]></link>
+        //     <isbn><![CDATA[0123456789]]></isbn>
+        //     ...
+        // </item>
+        // the items' raw XML, in the same order as the parsed items
+        let raw_items = xml.split("<item>").skip(1).collect::<Vec<&str>>();
+
+        channel
+            .items()
+            .iter()
+            .enumerate()
+            .map(|(index, item)| {
+                let link = item
+                    .link()
+                    .or_else(|| item.guid().map(|guid| guid.value()))
+                    .map(|link| link.trim().to_owned())
+                    .unwrap_or(self.author_url());
+                let isbn = raw_items
+                    .get(index)
+                    .and_then(|raw_item| Self::raw_element(raw_item, "isbn"))
+                    .filter(|isbn| !isbn.is_empty());
+
+                Book {
+                    title: item.title().unwrap_or("<untitled>").trim().to_owned(),
+                    date: item
+                        .pub_date()
+                        .and_then(|date| {
+                            DateTime::<FixedOffset>::parse_from_rfc2822(date.trim()).ok()
+                        })
+                        .map(|date| date.with_timezone(&Local)),
+                    key: isbn.unwrap_or(link.clone()),
+                    link,
+                }
+            })
+            .collect()
+    }
+
+    /// Reads the text of an element in an item's raw XML, without any
+    /// CDATA markers around it.
+    fn raw_element(raw_item: &str, name: &str) -> Option<String> {
+        let start = raw_item.find(&format!("<{}>", name))? + name.len() + 2;
+        let length = raw_item[start..].find(&format!("</{}>", name))?;
+        let text = raw_item[start..start + length].trim();
+        let text = text
+            .trim_start_matches("<![CDATA[")
+            .trim_end_matches("]]>")
+            .trim();
+
+        Some(text.to_owned())
+    }
+
+    /// Makes sure that the author's feed loads.
+    pub fn diagnose(&self) -> Result<(), String> {
+        self.fetch_feed().map(|_feed| ())
+    }
+
+    /// Check for books by the author published since `last_checked`.
+    pub fn check_for_updates(
+        &self,
+        last_checked: &Option<DateTime<Local>>,
+        memory: &mut SourceMemory,
+    ) -> Result<Vec<SourceUpdate>, String> {
+        let (channel, xml) = self.fetch_feed()?;
+        let books = self.read_books(&channel, &xml);
+        let count = books.len();
+        let updates = Self::new_books(books, last_checked, &mut memory.seen_books, Local::now());
+        log_new_items(&self.name, count, updates.len());
+
+        Ok(updates)
+    }
+
+    /// Picks out the new books from the ones in the author's feed.
+    ///
+    /// Books with a date are new if they were published after
+    /// `last_checked`. Books without one are new if their ISBN isn't in
+    /// `seen_books` from last time, so the first check only remembers them.
+    fn new_books(
+        books: Vec<Book>,
+        last_checked: &Option<DateTime<Local>>,
+        seen_books: &mut Vec<String>,
+        now: DateTime<Local>,
+    ) -> Vec<SourceUpdate> {
+        let first_check = seen_books.is_empty();
+        let updates = books
+            .iter()
+            .filter_map(|book| {
+                let published_date = match book.date {
+                    Some(date) if last_checked.map(|last| last < date).unwrap_or(true) => date,
+                    Some(_old_date) => return None,
+                    None if !first_check && !seen_books.contains(&book.key) => now,
+                    None => return None,
+                };
+
+                Some(SourceUpdate {
+                    title: book.title.clone(),
+                    link: book.link.clone(),
+                    published_date,
+                    thumbnail: None,
+                    description: None,
+                    secondary_link: None,
+                    also_in: Vec::new(),
+                })
+            })
+            .collect();
+
+        // only the current books are kept so the list doesn't grow forever
+        seen_books.clear();
+        for book in books {
+            if !seen_books.contains(&book.key) {
+                seen_books.push(book.key);
+            }
+        }
+
+        updates
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+
+    // one dated book with an ISBN, then undated books without one and
+    // with an empty one
+    const FEED: &str = "<rss version=\"2.0\"><channel><title>Some Author</title>\
+        <link>https://www.goodreads.com/author/show/1234</link><description></description>\
+        <item><guid><![CDATA[https://www.goodreads.com/book/show/1]]></guid>\
+        <pubDate><![CDATA[Sat, 02 Mar 2024 12:00:00 +0000]]></pubDate>\
+        <title><![CDATA[ First Book ]]></title>\
+        <link><![CDATA[https://www.goodreads.com/book/show/1]]></link>\
+        <isbn><![CDATA[0123456789]]></isbn></item>\
+        <item><title>Second Book</title>\
+        <link>https://www.goodreads.com/book/show/2</link></item>\
+        <item><guid>https://www.goodreads.com/book/show/3</guid>\
+        <title>Third Book</title><isbn><![CDATA[]]></isbn></item>\
+        </channel></rss>";
+
+    fn books(feed: &str) -> Vec<Book> {
+        let author = Author {
+            author_id: "1234".to_owned(),
+            ..Default::default()
+        };
+        let channel = feed.parse::<Channel>().unwrap();
+        author.read_books(&channel, feed)
+    }
+
+    fn book(title: &str, key: &str) -> Book {
+        Book {
+            title: title.to_owned(),
+            link: format!("https://www.goodreads.com/book/show/{}", title),
+            date: None,
+            key: key.to_owned(),
+        }
+    }
+
+    fn titles(updates: &[SourceUpdate]) -> Vec<&str> {
+        updates.iter().map(|update| update.title.as_str()).collect()
+    }
+
+    #[test]
+    fn books_are_read_from_the_feed() {
+        let books = books(FEED);
+
+        let titles = books
+            .iter()
+            .map(|book| book.title.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(titles, vec!["First Book", "Second Book", "Third Book"]);
+        assert_eq!(
+            books[0].date,
+            Some(Utc.ymd(2024, 3, 2).and_hms(12, 0, 0).with_timezone(&Local))
+        );
+        assert_eq!(books[1].date, None);
+        assert_eq!(books[2].link, "https://www.goodreads.com/book/show/3");
+    }
+
+    #[test]
+    fn books_are_known_by_isbn_or_else_link() {
+        let books = books(FEED);
+
+        let keys = books
+            .iter()
+            .map(|book| book.key.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            keys,
+            vec![
+                "0123456789",
+                "https://www.goodreads.com/book/show/2",
+                "https://www.goodreads.com/book/show/3",
+            ]
+        );
+    }
+
+    #[test]
+    fn the_first_check_only_remembers_undated_books() {
+        let mut seen_books = Vec::new();
+        let now = Local.ymd(2024, 3, 1).and_hms(9, 0, 0);
+        let books = vec![book("First", "1"), book("Second", "2")];
+
+        let updates = Author::new_books(books, &Some(now), &mut seen_books, now);
+        assert!(updates.is_empty());
+        assert_eq!(seen_books, vec!["1", "2"]);
+    }
+
+    #[test]
+    fn undated_books_are_new_if_they_werent_seen() {
+        let mut seen_books = vec!["1".to_owned(), "gone".to_owned()];
+        let now = Local.ymd(2024, 3, 1).and_hms(9, 0, 0);
+        let books = vec![book("First", "1"), book("Second", "2")];
+
+        let updates = Author::new_books(books, &Some(now), &mut seen_books, now);
+        assert_eq!(titles(&updates), vec!["Second"]);
+        assert_eq!(updates[0].published_date, now);
+        // books that left the feed are forgotten
+        assert_eq!(seen_books, vec!["1", "2"]);
+    }
+
+    #[test]
+    fn dated_books_are_new_if_published_since_the_last_check() {
+        let mut seen_books = Vec::new();
+        let last_checked = Local.ymd(2024, 3, 1).and_hms(0, 0, 0);
+        let mut old = book("Old", "1");
+        old.date = Some(last_checked - chrono::Duration::days(1));
+        let mut new = book("New", "2");
+        new.date = Some(last_checked + chrono::Duration::days(1));
+
+        let updates = Author::new_books(
+            vec![old, new],
+            &Some(last_checked),
+            &mut seen_books,
+            last_checked,
+        );
+        assert_eq!(titles(&updates), vec!["New"]);
+    }
+}
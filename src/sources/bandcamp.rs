@@ -1,53 +1,119 @@
 //! The Bandcamp platform for update checking.
 
-use crate::sources::{CheckForUpdates, SourceUpdate};
+use crate::sources::{
+    check_each_source, deserialize_sources, log_new_items, serialize_sources, validate_name,
+    validate_url, CheckForUpdates, CheckOptions, NamedSource, SourceCheck, SourceSettings,
+    SourceUpdate,
+};
+use crate::state::State;
+use crate::style::{Role, Stylize};
+use crate::util::{
+    access_error, check_status, get, get_following_redirects, http_client, join_url, log_request,
+    log_response, parse_date, readline, require_interactive, send, ReadlineResult,
+};
 use chrono::{DateTime, Local, TimeZone};
-use rayon::iter::{IntoParallelIterator, IntoParallelRefMutIterator, ParallelIterator};
+use log::debug;
+use rayon::iter::{IntoParallelIterator, IntoParallelRefIterator, ParallelIterator};
+use reqwest::{Client, Url};
+use schemars::JsonSchema;
 use select::document::Document;
 use select::predicate::{Attr, Class, Name, Predicate};
 use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::borrow::Cow;
+
+/// The most albums loaded from an artist's page per check, unless the
+/// artist sets `max_albums`.
+pub const DEFAULT_MAX_ALBUMS: usize = 10;
 
 /// The wrapper type for Bandcamp artists and their last checked times
 /// to implement `CheckForUpdates` on.
-#[derive(Debug, Serialize, Deserialize, Clone, Default)]
-pub struct BandcampArtists(pub Vec<(BandcampArtist, Option<DateTime<Local>>)>);
+#[derive(Debug, Serialize, Deserialize, Clone, Default, JsonSchema)]
+pub struct BandcampArtists(
+    #[serde(
+        serialize_with = "serialize_sources",
+        deserialize_with = "deserialize_sources"
+    )]
+    #[schemars(with = "Vec<BandcampArtist>")]
+    pub Vec<(BandcampArtist, Option<DateTime<Local>>)>,
+);
 
 /// A Bandcamp artist struct.
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, Default, JsonSchema)]
 pub struct BandcampArtist {
     pub name: String,
     pub url: String,
+    /// Where the artist page has permanently moved to, if it has.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resolved_url: Option<String>,
+    /// A user agent to use instead of sitch's for picky hosts.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user_agent: Option<String>,
+    /// The most of the newest albums on the page to load per check, like
+    /// more for a label that releases a lot. Defaults to
+    /// `DEFAULT_MAX_ALBUMS`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_albums: Option<usize>,
+    /// The settings that every source has.
+    #[serde(flatten)]
+    pub settings: SourceSettings,
+    /// Any fields sitch doesn't know about, kept so they aren't lost.
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// A release listed on an artist page, with what the page says about it
+/// besides its link.
+#[derive(Debug, Clone, Default)]
+struct ListedRelease {
+    /// The link to the release's page.
+    link: String,
+    /// The release's title, if the page shows it.
+    title: Option<String>,
+    /// When the release was published, if the page says.
+    published: Option<DateTime<Local>>,
+    /// Whether only the artist's subscribers can open the release.
+    subscriber_only: bool,
+}
+
+impl ListedRelease {
+    /// An update for the release from what the artist page says about it,
+    /// for when its own page can't be loaded, or `None` without a date.
+    fn grid_update(&self, artist: &str) -> Option<SourceUpdate> {
+        Some(SourceUpdate {
+            title: format!(
+                "{} by {} (subscriber-only)",
+                self.title
+                    .as_ref()
+                    .map(String::as_str)
+                    .unwrap_or("<no album name>"),
+                artist
+            ),
+            link: self.link.clone(),
+            published_date: self.published?,
+            thumbnail: None,
+            description: None,
+            secondary_link: None,
+            also_in: Vec::new(),
+        })
+    }
 }
 
 impl CheckForUpdates for BandcampArtists {
-    fn check_for_all_updates(
-        &mut self,
-        sitch_last_checked: &Option<DateTime<Local>>,
-    ) -> Vec<(String, Result<Vec<SourceUpdate>, String>)> {
+    fn check_for_all_updates(&mut self, state: &State, options: &CheckOptions) -> Vec<SourceCheck> {
+        check_each_source(
+            "bandcamp",
+            &mut self.0,
+            state,
+            options,
+            |artist, last_checked| (artist.name.clone(), artist.check_for_updates(last_checked)),
+        )
+    }
+
+    fn diagnose_all(&self) -> Vec<(String, Result<(), String>)> {
         self.0
-            .par_iter_mut()
-            .map(|(artist, last_checked)| {
-                // use the earliest `last_checked` time provided either by sitch generally
-                // or by this source to handle whe the user overrides the `last_checked` time
-                let true_last_checked = if sitch_last_checked.is_some() && last_checked.is_some() {
-                    Some(std::cmp::min(
-                        sitch_last_checked.unwrap(),
-                        last_checked.unwrap(),
-                    ))
-                } else {
-                    last_checked.or(*sitch_last_checked)
-                };
-                let update = artist.check_for_updates(&true_last_checked);
-                // update last_checked if an update occurred
-                if update.as_ref().map(|updates| updates.len()).unwrap_or(0) > 0 {
-                    *last_checked = Some(Local::now());
-                } else if last_checked.is_none() {
-                    // if this source hasn't been checked yet, but no update was
-                    // found, set it to the "global" `last_checked` time
-                    *last_checked = sitch_last_checked.clone();
-                }
-                (artist.name.clone(), update)
-            })
+            .par_iter()
+            .map(|(artist, _last_checked)| (artist.name.clone(), artist.diagnose()))
             .collect()
     }
 
@@ -56,108 +122,776 @@ impl CheckForUpdates for BandcampArtists {
     }
 }
 
+impl NamedSource for BandcampArtist {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn name_mut(&mut self) -> &mut String {
+        &mut self.name
+    }
+
+    fn location(&self) -> Cow<'_, str> {
+        Cow::Borrowed(&self.url)
+    }
+
+    fn validate(&mut self) -> Result<(), String> {
+        validate_name(&mut self.name)?;
+        validate_url("URL", &mut self.url)
+    }
+
+    fn settings(&self) -> &SourceSettings {
+        &self.settings
+    }
+
+    fn settings_mut(&mut self) -> &mut SourceSettings {
+        &mut self.settings
+    }
+}
+
 impl BandcampArtist {
-    /// Check for updates for a BandCamp artist.
+    /// The URL of the artist page, preferring where it has moved to.
+    pub fn page_url(&self) -> &str {
+        self.resolved_url.as_ref().unwrap_or(&self.url)
+    }
+
+    /// Makes an artist from the URL of their page, checking that it has
+    /// albums and naming them after the page if no name is given. The URL
+    /// is made canonical (see `canonical_url`), with a warning if it was
+    /// of a single release.
+    pub fn from_url(url: &str, name: Option<String>) -> Result<Self, String> {
+        let (canonical, was_release) = Self::canonical_url(url)?;
+        if was_release {
+            eprintln!(
+                "{} is a single release, so the artist's page at {} is added instead.",
+                url.trim(),
+                canonical
+            );
+        }
+        let mut artist = BandcampArtist {
+            name: name.clone().unwrap_or_default(),
+            url: canonical,
+            ..Default::default()
+        };
+        let (artist_document, _moved_to) = artist.fetch_artist_page()?;
+        if artist.find_releases(&artist_document, &None).is_empty() {
+            return Err("Couldn't find any albums on the artist page".to_owned());
+        }
+
+        if name.is_none() {
+            // <meta property="og:site_name" content="Artist Name">
+            artist.name = artist_document
+                .find(Name("meta").and(Attr("property", "og:site_name")))
+                .filter_map(|node| node.attr("content"))
+                .map(|site_name| site_name.trim().to_owned())
+                .next()
+                .or_else(|| {
+                    // otherwise use the subdomain, like "artist" in artist.bandcamp.com
+                    let url = Url::parse(&artist.url).ok()?;
+                    Some(url.host_str()?.split('.').next()?.to_owned())
+                })
+                .unwrap_or(artist.url.clone());
+        }
+
+        Ok(artist)
+    }
+
+    /// Makes a pasted URL into the root of the artist's page, like
+    /// `https://artist.bandcamp.com` for `artist.bandcamp.com/`,
+    /// `http://artist.bandcamp.com/music`, or
+    /// `https://artist.bandcamp.com/album/some-album`, returning it along
+    /// with whether the URL was of a single album or track.
+    pub fn canonical_url(url: &str) -> Result<(String, bool), String> {
+        let url = url.trim();
+        let with_scheme = if url.contains("://") {
+            url.to_owned()
+        } else {
+            format!("https://{}", url)
+        };
+        let parsed = Url::parse(&with_scheme)
+            .map_err(|err| format!("\"{}\" isn't a valid URL: {}", url, err))?;
+        if parsed.scheme() != "http" && parsed.scheme() != "https" {
+            return Err(format!("\"{}\" isn't a web page.", url));
+        }
+        let host = parsed
+            .host_str()
+            .ok_or_else(|| format!("\"{}\" isn't a valid URL: it has no host", url))?;
+        if host == "bandcamp.com" || host == "www.bandcamp.com" {
+            return Err(format!(
+                "\"{}\" is on Bandcamp's own site, use an artist's page like https://artist.bandcamp.com instead.",
+                url
+            ));
+        }
+        // <https://artist.bandcamp.com/album/some-album> or </track/some-track>
+        let was_release = parsed
+            .path_segments()
+            .and_then(|mut segments| segments.next())
+            .map(|first| first == "album" || first == "track")
+            .unwrap_or(false);
+
+        Ok((format!("https://{}", host), was_release))
+    }
+
+    /// Makes a URL given when adding an artist canonical (see
+    /// `canonical_url`). When it was of a single release, this asks
+    /// whether to add the artist's page instead if someone's there to
+    /// answer, failing if they say no, and warns that it is otherwise.
+    pub fn offer_canonical_url(url: &str) -> Result<String, String> {
+        let (canonical, was_release) = Self::canonical_url(url)?;
+        if !was_release {
+            return Ok(canonical);
+        }
+
+        if !atty::is(atty::Stream::Stdin) {
+            eprintln!(
+                "{} is a single release, so the artist's page at {} is added instead.",
+                url.trim(),
+                canonical
+            );
+            return Ok(canonical);
+        }
+        println!(
+            "{} is a single release, which never has new ones.",
+            url.trim()
+        );
+        let use_canonical = readline(
+            &format!("Add the artist's page at {} instead? [Y/n] ", canonical),
+            |input| match input.as_str() {
+                "" | "y" | "Y" | "yes" => Ok(true),
+                "n" | "N" | "no" => Ok(false),
+                _ => Err("Please respond with a yes or no.".to_owned()),
+            },
+        )?;
+        if use_canonical == ReadlineResult::Value(true) {
+            Ok(canonical)
+        } else {
+            Err("Only an artist's page can be checked for new releases.".to_owned())
+        }
+    }
+
+    /// Loads the artist page as an HTML document, returning it along with
+    /// where the page has permanently moved to if it has.
+    fn fetch_artist_page(&self) -> Result<(Document, Option<String>), String> {
+        let user_agent = self.user_agent.as_ref().map(String::as_str);
+        let (mut response, moved_to) = get_following_redirects(self.page_url(), user_agent)
+            .map_err(|err| format!("Could not fetch artist page: {}", err))?;
+        let artist_page = response
+            .text()
+            .map_err(|_err| "No html found on artist page".to_owned())?;
+
+        Ok((Document::from(artist_page.as_str()), moved_to))
+    }
+
+    /// The most albums to load from the page per check.
+    fn max_albums(&self) -> usize {
+        self.max_albums.unwrap_or(DEFAULT_MAX_ALBUMS)
+    }
+
+    /// Finds the most recent releases on an artist page, at most
+    /// `max_albums` of them to minimize the number of requests made.
+    /// Releases are albums or, for tracks released on their own, tracks.
     ///
-    /// Quite unfortunately, Bandcamp disabled their general purpose
-    /// API for exactly what sitch would need for all new users, only
-    /// an API for an artist's own albums is available. Thus, we need
-    /// to web-scrape to find updates for artists.
-    pub fn check_for_updates(
+    /// Label pages list their releases with dates, so the newest are
+    /// picked from those, leaving out the ones from well before
+    /// `last_checked` (see `find_label_releases`). Artist pages list their
+    /// newest releases first, and come in two layouts, so both are tried.
+    fn find_releases(
         &self,
+        artist_document: &Document,
         last_checked: &Option<DateTime<Local>>,
-    ) -> Result<Vec<SourceUpdate>, String> {
-        // get the artist page and parse it as an HTML document
-        let artist_page = reqwest::get(&self.url)
-            .map_err(|err| format!("Could not fetch artist page: {}", err))?
-            .text()
-            .map_err(|_err| "No html found on artist page".to_owned())?;
-        let artist_document = Document::from(artist_page.as_str());
+    ) -> Vec<ListedRelease> {
+        let base_url = self.page_url();
+        if let Some(mut releases) = Self::find_label_releases(artist_document, base_url) {
+            // newest first, with the releases that have no date last
+            releases.sort_by(|release, other| other.published.cmp(&release.published));
+            return releases
+                .into_iter()
+                .filter(|release| match (release.published, last_checked) {
+                    // album pages can be dated a little differently, so
+                    // only skip what's a day older than the last check
+                    (Some(date), Some(checked)) => date > *checked - chrono::Duration::days(1),
+                    _ => true,
+                })
+                .take(self.max_albums())
+                .collect();
+        }
 
-        // <li class="music-grid-item square first-four">
-        //     <a href="/album/meat-machine-ep"></a>
+        // <li class="music-grid-item square first-four subscriber-only">
+        //     <a href="/album/meat-machine-ep">
+        //         <p class="title">Meat Machine EP</p>
+        //     </a>
         // </li>
-        // try the first type of artist page parsing to get album links
-        let mut recent_album_links = artist_document
+        // try the first type of artist page parsing to get release links
+        let recent_releases = artist_document
             .find(Name("li").and(Class("music-grid-item")))
             .filter_map(|node| {
-                node.find(Name("a"))
-                    .next()
-                    .and_then(|link_el| link_el.attr("href"))
-                    .map(|album_link| format!("{}{}", self.url, album_link))
+                let link_el = node.find(Name("a")).next()?;
+                Some(ListedRelease {
+                    link: join_url(base_url, link_el.attr("href")?),
+                    // the title can be followed by the artist on another line
+                    title: node.find(Class("title")).next().and_then(|title_el| {
+                        Some(title_el.text().trim().lines().next()?.to_owned())
+                    }),
+                    published: None,
+                    subscriber_only: node.is(Class("subscriber-only")),
+                })
             })
-            // only take 10 max to minimize the number of requests made
-            .take(10)
-            .collect::<Vec<String>>();
+            .take(self.max_albums())
+            .collect::<Vec<ListedRelease>>();
 
         // if no links are found, try parsing the second type of pages
-        if recent_album_links.len() == 0 {
-            recent_album_links = artist_document
+        if recent_releases.len() == 0 {
+            artist_document
                 .find(Name("div").and(Attr("id", "discography").descendant(Class("trackTitle"))))
                 .filter_map(|node| {
-                    node.find(Name("a"))
-                        .next()
-                        .and_then(|link_el| link_el.attr("href"))
-                        .map(|album_link| format!("{}{}", self.url, album_link))
+                    let link_el = node.find(Name("a")).next()?;
+                    Some(ListedRelease {
+                        link: join_url(base_url, link_el.attr("href")?),
+                        title: Some(link_el.text().trim().to_owned()),
+                        ..Default::default()
+                    })
                 })
-                // only take 10 max to minimize the number of requests made
-                .take(10)
-                .collect::<Vec<String>>();
+                .take(self.max_albums())
+                .collect::<Vec<ListedRelease>>()
+        } else {
+            recent_releases
         }
+    }
+
+    /// Finds the releases on a label's page, or `None` if it isn't a
+    /// label's page.
+    ///
+    /// Labels list dozens of artists and hundreds of releases, often with
+    /// old reissues pinned first, so unlike an artist's page, the order
+    /// of their grid says little about what's new.
+    fn find_label_releases(document: &Document, base_url: &str) -> Option<Vec<ListedRelease>> {
+        // <div id="pagedata" data-blob="{&quot;page_type&quot;:&quot;label&quot;, ...}">
+        let page_data = document
+            .find(Attr("id", "pagedata"))
+            .next()?
+            .attr("data-blob")
+            .and_then(|blob| serde_json::from_str::<Value>(blob).ok())?;
+        if page_data["page_type"] != "label" {
+            return None;
+        }
+
+        // <ol id="music-grid" data-client-items="[{&quot;id&quot;:123,
+        //     &quot;type&quot;:&quot;album&quot;, &quot;title&quot;:&quot;X&quot;,
+        //     &quot;page_url&quot;:&quot;/album/x&quot;, &quot;subscriber_only&quot;:false,
+        //     &quot;publish_date&quot;:&quot;05 Apr 2019 00:00:00 GMT&quot;}, ...]">
+        //     <li class="music-grid-item" data-item-id="album-123">
+        //         <a href="/album/x"><p class="title">X</p></a>
+        //     </li>
+        // </ol>
+        let grid = document
+            .find(Name("ol").and(Attr("id", "music-grid")))
+            .next()?;
+        let items = grid
+            .attr("data-client-items")
+            .and_then(|items| serde_json::from_str::<Vec<Value>>(items).ok())
+            .unwrap_or_default();
+        let mut releases = items
+            .iter()
+            .filter_map(|item| {
+                let id = format!("{}-{}", item["type"].as_str()?, item["id"].as_u64()?);
+                let published = item["publish_date"]
+                    .as_str()
+                    .or_else(|| item["release_date"].as_str())
+                    .and_then(|date| {
+                        parse_date(&date.replace("GMT", "+0000"), Some("%d %b %Y %T %z"))
+                    });
+                let release = ListedRelease {
+                    link: join_url(base_url, item["page_url"].as_str()?),
+                    title: item["title"].as_str().map(str::to_owned),
+                    published,
+                    subscriber_only: item["subscriber_only"].as_bool().unwrap_or(false),
+                };
+
+                Some((id, release))
+            })
+            .collect::<Vec<_>>();
+        // the grid items shown on the page may not be in the data, and
+        // have no date if they aren't
+        for node in grid.find(Name("li").and(Attr("data-item-id", ()))) {
+            let id = node.attr("data-item-id").unwrap_or_default();
+            if releases.iter().any(|(known, _release)| known == id) {
+                continue;
+            }
+            if let Some(link) = node
+                .find(Name("a"))
+                .next()
+                .and_then(|link_el| link_el.attr("href"))
+            {
+                let release = ListedRelease {
+                    link: join_url(base_url, link),
+                    title: node.find(Class("title")).next().and_then(|title_el| {
+                        Some(title_el.text().trim().lines().next()?.to_owned())
+                    }),
+                    published: None,
+                    subscriber_only: node.is(Class("subscriber-only")),
+                };
+                releases.push((id.to_owned(), release));
+            }
+        }
+
+        Some(releases.into_iter().map(|(_id, release)| release).collect())
+    }
+
+    /// Makes sure that the artist page loads and has albums where expected.
+    pub fn diagnose(&self) -> Result<(), String> {
+        let (artist_document, _moved_to) = self.fetch_artist_page()?;
+        if self.find_releases(&artist_document, &None).len() > 0 {
+            Ok(())
+        } else {
+            Err("Couldn't find any albums on the artist page".to_owned())
+        }
+    }
+
+    /// Check for updates for a BandCamp artist.
+    ///
+    /// Quite unfortunately, Bandcamp disabled their general purpose
+    /// API for exactly what sitch would need for all new users, only
+    /// an API for an artist's own albums is available. Thus, we need
+    /// to web-scrape to find updates for artists.
+    pub fn check_for_updates(
+        &mut self,
+        last_checked: &Option<DateTime<Local>>,
+    ) -> Result<Vec<SourceUpdate>, String> {
+        // get the artist page and its most recent releases
+        let (artist_document, moved_to) = self.fetch_artist_page()?;
+        // remember where the artist page moved to if it moved permanently
+        if moved_to.is_some() {
+            self.resolved_url = moved_to;
+        }
+        let recent_releases = self.find_releases(&artist_document, last_checked);
+        let user_agent = self.user_agent.as_ref().map(String::as_str);
+
+        let client = http_client(user_agent, true)
+            .map_err(|err| format!("Couldn't build an HTTP client: {}", err))?;
 
         // in parallel, attempt to retrieve, parse, and then filter out
-        // the first 10 albums on an artist's page to find updates
-        recent_album_links
+        // the newest releases on an artist's page to find updates
+        let loaded = recent_releases.len();
+        let artist_name = &self.name;
+        let updates: Vec<SourceUpdate> = recent_releases
             .into_par_iter()
-            .filter_map(|link| {
-                // either load the page or return an error
-                let mut album_page = match reqwest::get(&link) {
-                    Ok(page) => page,
-                    Err(err) => return Some(Err(format!("Could not fetch album page: {}", err))),
-                };
-                // either parse the page into HTML or return an error
-                let album_document = match album_page.text() {
-                    Ok(text) => Document::from(text.as_str()),
-                    Err(_err) => return Some(Err("No html found on album page".to_owned())),
+            .filter_map(|release| {
+                let update = match Self::load_release(&client, &release.link) {
+                    Ok(update) => update,
+                    // subscriber-only releases can't be loaded without
+                    // signing in, so they're reported from the artist page
+                    Err(err) if release.subscriber_only => match release.grid_update(artist_name) {
+                        Some(update) => update,
+                        None => {
+                            debug!(
+                                "{}: skipped a subscriber-only release without a date: {}",
+                                artist_name, err
+                            );
+                            return None;
+                        }
+                    },
+                    Err(err) => return Some(Err(err)),
                 };
 
-                // parse the album name from the `class="trackTitle"` element
-                let album_name = album_document
-                    .find(Class("trackTitle"))
-                    .next()
-                    .map(|name_el| name_el.text().trim().to_owned())
-                    .unwrap_or("<no album name>".to_owned());
-                // parse the artist name from the `itemprop="byArtist"` element
-                let artist = album_document
-                    .find(Attr("itemprop", "byArtist").descendant(Name("a")))
-                    .next()
-                    .map(|artist_el| artist_el.text())
-                    .unwrap_or("<no artist>".to_owned());
-                // parse the published date from the below element, and
-                // return an error if the parsing fails
-                // <meta itemprop="datePublished" content="20190426">
-                let published_date = match album_document
-                    .find(Attr("itemprop", "datePublished"))
+                // only return releases published after the last_checked date if it is given
+                let is_new = last_checked
+                    .map(|checked| checked < update.published_date)
+                    .unwrap_or(true);
+                Some(Ok(update)).filter(|_update| is_new)
+            })
+            .collect::<Result<_, String>>()?;
+        log_new_items(&self.name, loaded, updates.len());
+
+        Ok(updates)
+    }
+
+    /// Loads the page of an album or track into an update. Tracks are
+    /// only listed on their own when they aren't on an album, so they're
+    /// titled as singles.
+    fn load_release(client: &Client, link: &str) -> Result<SourceUpdate, String> {
+        // either load the page or return an error
+        log_request("GET", link);
+        let album_page = send(client, client.get(link));
+        log_response(link, &album_page);
+        let mut album_page = album_page
+            .map_err(|err| access_error(link, &err))
+            .and_then(check_status)
+            .map_err(|err| format!("Could not fetch album page: {}", err))?;
+        // either parse the page into HTML or return an error
+        let album_document = album_page
+            .text()
+            .map(|text| Document::from(text.as_str()))
+            .map_err(|_err| "No html found on album page".to_owned())?;
+
+        // parse the album name from the `class="trackTitle"` element
+        let album_name = album_document
+            .find(Class("trackTitle"))
+            .next()
+            .map(|name_el| name_el.text().trim().to_owned())
+            .unwrap_or("<no album name>".to_owned());
+        // parse the artist name from the `itemprop="byArtist"` element
+        let artist = album_document
+            .find(Attr("itemprop", "byArtist").descendant(Name("a")))
+            .next()
+            .map(|artist_el| artist_el.text())
+            .unwrap_or("<no artist>".to_owned());
+        // parse the published date from the below element, and
+        // return an error if the parsing fails
+        // <meta itemprop="datePublished" content="20190426">
+        let published_date = album_document
+            .find(Attr("itemprop", "datePublished"))
+            .next()
+            .and_then(|date_el| date_el.attr("content"))
+            .and_then(|date_str| {
+                Local
+                    .datetime_from_str(&(date_str.to_owned() + "00:00:00"), "%Y%m%d%T")
+                    .ok()
+            })
+            .ok_or_else(|| format!("No published date on album at {}", link))?;
+        // the cover art and description are in the Open Graph tags
+        // <meta property="og:image" content="https://f4.bcbits.com/img/a1_5.jpg">
+        let open_graph = |property| {
+            album_document
+                .find(Name("meta").and(Attr("property", property)))
+                .next()
+                .and_then(|meta_el| meta_el.attr("content"))
+                .map(|content| content.to_owned())
+        };
+        let single = if link.contains("/track/") {
+            " (single)"
+        } else {
+            ""
+        };
+
+        Ok(SourceUpdate {
+            title: format!("{} by {}{}", album_name, artist, single),
+            link: link.to_owned(),
+            published_date,
+            thumbnail: open_graph("og:image"),
+            description: open_graph("og:description"),
+            secondary_link: None,
+            also_in: Vec::new(),
+        })
+    }
+
+    /// Searches Bandcamp for artists, returning the name, location,
+    /// and URL of each result.
+    fn search(search_term: &str) -> Result<Vec<(String, String, String)>, String> {
+        let query = Url::parse_with_params(
+            "https://bandcamp.com/search",
+            &[("q", search_term), ("item_type", "b")],
+        )
+        .map_err(|err| format!("Couldn't build the search URL: {}", err))?;
+        let search_page = get(query.as_str())
+            .map_err(|err| access_error(query.as_str(), &err))
+            .and_then(check_status)?
+            .text()
+            .map_err(|_err| "No html found on search page".to_owned())?;
+        let search_document = Document::from(search_page.as_str());
+
+        // <li class="searchresult band">
+        //     <div class="heading"><a href="...">Name</a></div>
+        //     <div class="subhead">Location</div>
+        //     <div class="itemurl"><a href="...">https://name.bandcamp.com</a></div>
+        // </li>
+        Ok(search_document
+            .find(Name("li").and(Class("searchresult")))
+            .filter_map(|node| {
+                let name = node.find(Class("heading")).next()?.text().trim().to_owned();
+                let location = node
+                    .find(Class("subhead"))
                     .next()
-                    .and_then(|date_el| date_el.attr("content"))
-                    .and_then(|date_str| {
-                        Local
-                            .datetime_from_str(&(date_str.to_owned() + "00:00:00"), "%Y%m%d%T")
-                            .ok()
-                    }) {
-                    Some(date) => date,
-                    None => return Some(Err(format!("No published date on album at {}", link))),
-                };
+                    .map(|subhead| subhead.text().trim().to_owned())
+                    .unwrap_or_default();
+                let url = node
+                    .find(Class("itemurl"))
+                    .next()?
+                    .text()
+                    .trim()
+                    .trim_end_matches('/')
+                    .to_owned();
 
-                // only return albums published after the last_checked date if it is given
-                Some(Ok(SourceUpdate {
-                    title: format!("{} by {}", album_name, artist),
-                    link,
-                    published_date: Some(published_date).filter(|&date| {
-                        last_checked.map(|checked| checked < date).unwrap_or(true)
-                    })?,
-                }))
+                Some((name, location, url))
             })
+            .take(5)
+            .collect())
+    }
+
+    /// Search interactively for new Bandcamp artists to add to sitch.
+    ///
+    /// Reads from stdin to take input and asks the user before any
+    /// sources are added. Returns `None` if the user declined the
+    /// result or quit.
+    pub fn interactive_search() -> Result<Option<Self>, String> {
+        require_interactive("sitch bandcamp add --name <NAME> --url <URL>")?;
+        loop {
+            // Take a query for input
+            let search_term = match readline("Search for an artist by name: ", |search| {
+                if search.len() > 1 {
+                    Ok(search)
+                } else {
+                    Err("Search term must be longer than 1 character.".to_owned())
+                }
+            })? {
+                ReadlineResult::Value(search_term) => search_term,
+                ReadlineResult::Quit => return Ok(None),
+            };
+            let search_results = Self::search(&search_term)?;
+
+            match search_results.len() {
+                // try again if there were no results found
+                0 => println!("No results found, please try again."),
+                1 => {
+                    // if only one was found, ask if they want to add it.
+                    let (name, location, url) = search_results.into_iter().next().unwrap();
+                    println!("Found 1 result: \"{}\" ({}) {}", name, location, url);
+                    let should_add =
+                        readline("Add it to sitch? [Y/n]", |input| match input.as_str() {
+                            "" | "y" | "Y" | "yes" => Ok(true),
+                            "n" | "N" | "no" => Ok(false),
+                            _ => Err("Please respond with a yes or no.".to_owned()),
+                        })?;
+                    match should_add {
+                        ReadlineResult::Value(true) => {
+                            return Ok(Some(Self {
+                                name,
+                                url,
+                                ..Default::default()
+                            }))
+                        }
+                        // if they don't, let them search again or stop
+                        ReadlineResult::Value(false) => {
+                            let search_again =
+                                readline("Search again? [Y/n] ", |input| match input.as_str() {
+                                    "" | "y" | "Y" | "yes" => Ok(true),
+                                    "n" | "N" | "no" => Ok(false),
+                                    _ => Err("Please respond with a yes or no.".to_owned()),
+                                })?;
+                            if search_again != ReadlineResult::Value(true) {
+                                return Ok(None);
+                            }
+                        }
+                        ReadlineResult::Quit => return Ok(None),
+                    }
+                }
+                num_results => {
+                    // if multiple were found, print how many were found and then
+                    // enumerate them. Let the user choose one of them to add to sitch.
+                    println!("Found {} results:", num_results);
+                    for (index, (name, location, url)) in search_results.iter().enumerate() {
+                        println!(
+                            "{}: \"{}\" ({}) {}",
+                            (index + 1).style(Role::Index),
+                            name.style(Role::Name),
+                            location,
+                            url
+                        );
+                    }
+                    let index = match readline(
+                        &format!("Pick a result to add [1 to {}]: ", num_results),
+                        |picked| match picked.parse::<usize>() {
+                            Ok(index) if (1 <= index && index <= num_results) => Ok(index - 1),
+                            Ok(_bad_index) => {
+                                Err("The specified index was out of bounds.".to_owned())
+                            }
+                            Err(_err) => Err("The value wasn't an integer.".to_owned()),
+                        },
+                    )? {
+                        ReadlineResult::Value(index) => index,
+                        ReadlineResult::Quit => return Ok(None),
+                    };
+                    let (name, _location, url) = search_results.into_iter().nth(index).unwrap();
+                    return Ok(Some(Self {
+                        name,
+                        url,
+                        ..Default::default()
+                    }));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::{lock_network, ok_response, serve_once};
+
+    /// A label's page listing releases as `(type, id, link, publish date)`
+    /// in its grid's data, with the grid showing `shown` besides them.
+    fn label_page(releases: &[(&str, u64, &str, &str)], shown: &str) -> Document {
+        let items = releases
+            .iter()
+            .map(|(kind, id, link, date)| {
+                serde_json::json!({
+                    "type": kind,
+                    "id": id,
+                    "title": link.rsplit('/').next().unwrap(),
+                    "page_url": link,
+                    "publish_date": date,
+                })
+            })
+            .collect::<Vec<Value>>();
+        let escape = |json: String| json.replace('"', "&quot;");
+        let html = format!(
+            "<div id=\"pagedata\" data-blob=\"{}\"></div>\
+             <ol id=\"music-grid\" data-client-items=\"{}\">{}</ol>",
+            escape(serde_json::json!({"page_type": "label"}).to_string()),
+            escape(Value::from(items).to_string()),
+            shown
+        );
+
+        Document::from(html.as_str())
+    }
+
+    fn links(releases: &[ListedRelease]) -> Vec<&str> {
+        releases
+            .iter()
+            .map(|release| release.link.as_str())
             .collect()
     }
+
+    #[test]
+    fn label_releases_are_picked_by_date() {
+        let document = label_page(
+            &[
+                ("album", 1, "/album/reissue", "05 Apr 2009 00:00:00 GMT"),
+                ("album", 2, "/album/newest", "05 Apr 2019 00:00:00 GMT"),
+                ("track", 3, "/track/single", "01 Apr 2019 00:00:00 GMT"),
+                ("album", 4, "/album/older", "01 Mar 2019 00:00:00 GMT"),
+            ],
+            "<li data-item-id=\"album-2\"><a href=\"/album/newest\"></a></li>\
+             <li data-item-id=\"album-5\"><a href=\"/album/undated\"></a></li>",
+        );
+        let label = BandcampArtist {
+            url: "https://label.bandcamp.com".to_owned(),
+            max_albums: Some(3),
+            ..Default::default()
+        };
+
+        let releases = label.find_releases(&document, &None);
+        assert_eq!(
+            links(&releases),
+            vec![
+                "https://label.bandcamp.com/album/newest",
+                "https://label.bandcamp.com/track/single",
+                "https://label.bandcamp.com/album/older",
+            ]
+        );
+
+        // releases from well before the last check are left out
+        let last_checked = Some(Local.ymd(2019, 3, 15).and_hms(0, 0, 0));
+        let releases = label.find_releases(&document, &last_checked);
+        assert_eq!(
+            links(&releases),
+            vec![
+                "https://label.bandcamp.com/album/newest",
+                "https://label.bandcamp.com/track/single",
+                "https://label.bandcamp.com/album/undated",
+            ]
+        );
+    }
+
+    #[test]
+    fn artist_releases_are_taken_in_page_order() {
+        let document = Document::from(
+            "<li class=\"music-grid-item\"><a href=\"/album/new\"><p class=\"title\">New\n\
+             Artist</p></a></li>\
+             <li class=\"music-grid-item subscriber-only\"><a href=\"/track/secret\"></a></li>\
+             <li class=\"music-grid-item\"><a href=\"/album/old\"></a></li>",
+        );
+        let artist = BandcampArtist {
+            url: "https://artist.bandcamp.com".to_owned(),
+            max_albums: Some(2),
+            ..Default::default()
+        };
+
+        let releases = artist.find_releases(&document, &None);
+        assert_eq!(
+            links(&releases),
+            vec![
+                "https://artist.bandcamp.com/album/new",
+                "https://artist.bandcamp.com/track/secret",
+            ]
+        );
+        assert_eq!(releases[0].title, Some("New".to_owned()));
+        assert!(!releases[0].subscriber_only && releases[1].subscriber_only);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn tracks_on_their_own_are_singles() {
+        let _network = lock_network();
+        let page = "<h2 class=\"trackTitle\"> Song </h2>\
+                    <span itemprop=\"byArtist\"><a>Artist</a></span>\
+                    <meta itemprop=\"datePublished\" content=\"20190426\">\
+                    <meta property=\"og:image\" content=\"https://f4.bcbits.com/img/a1_5.jpg\">";
+        let (url, server) = serve_once("127.0.0.89", &ok_response(page));
+        let link = format!("{}/track/song", url);
+
+        let client = http_client(None, true).unwrap();
+        let update = BandcampArtist::load_release(&client, &link).unwrap();
+        server.join().unwrap();
+
+        assert_eq!(update.title, "Song by Artist (single)");
+        assert_eq!(update.link, link);
+        assert_eq!(
+            update.published_date,
+            Local.ymd(2019, 4, 26).and_hms(0, 0, 0)
+        );
+        assert_eq!(
+            update.thumbnail,
+            Some("https://f4.bcbits.com/img/a1_5.jpg".to_owned())
+        );
+    }
+
+    #[test]
+    fn subscriber_only_releases_are_reported_from_the_grid() {
+        let release = ListedRelease {
+            link: "https://artist.bandcamp.com/album/secret".to_owned(),
+            title: Some("Secret".to_owned()),
+            published: Some(Local.ymd(2019, 4, 26).and_hms(0, 0, 0)),
+            subscriber_only: true,
+        };
+
+        let update = release.grid_update("Artist").unwrap();
+        assert_eq!(update.title, "Secret by Artist (subscriber-only)");
+        assert_eq!(update.link, release.link);
+
+        // without a date, it can't be told whether it's new
+        let undated = ListedRelease {
+            published: None,
+            ..release
+        };
+        assert!(undated.grid_update("Artist").is_none());
+    }
+
+    #[test]
+    fn artist_urls_are_made_canonical() {
+        let canonical = |url| BandcampArtist::canonical_url(url).unwrap();
+
+        assert_eq!(
+            canonical("artist.bandcamp.com"),
+            ("https://artist.bandcamp.com".to_owned(), false)
+        );
+        assert_eq!(
+            canonical("  http://artist.bandcamp.com/music "),
+            ("https://artist.bandcamp.com".to_owned(), false)
+        );
+        assert_eq!(
+            canonical("https://music.example.com/album/some-album"),
+            ("https://music.example.com".to_owned(), true)
+        );
+        assert_eq!(
+            canonical("https://artist.bandcamp.com/track/some-track"),
+            ("https://artist.bandcamp.com".to_owned(), true)
+        );
+    }
+
+    #[test]
+    fn bandcamps_own_site_is_not_an_artist() {
+        assert!(BandcampArtist::canonical_url("https://bandcamp.com/discover").is_err());
+        assert!(BandcampArtist::canonical_url("www.bandcamp.com").is_err());
+        assert!(BandcampArtist::canonical_url("ftp://artist.bandcamp.com").is_err());
+    }
 }
@@ -0,0 +1,200 @@
+//! The crates.io platform for update checking, which reports
+//! new releases of Rust crates.
+
+use crate::sources::{
+    check_each_source, deserialize_sources, log_new_items, serialize_sources, validate_field,
+    validate_name, CheckForUpdates, CheckOptions, NamedSource, SourceCheck, SourceSettings,
+    SourceUpdate,
+};
+use crate::state::State;
+use crate::util::{access_error, check_status, get};
+use chrono::{DateTime, FixedOffset, Local};
+use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::borrow::Cow;
+
+/// The wrapper type for crates and their last checked times
+/// to implement `CheckForUpdates` on.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, JsonSchema)]
+pub struct RustCrates(
+    #[serde(
+        serialize_with = "serialize_sources",
+        deserialize_with = "deserialize_sources"
+    )]
+    #[schemars(with = "Vec<RustCrate>")]
+    pub Vec<(RustCrate, Option<DateTime<Local>>)>,
+);
+
+/// A crate published on crates.io.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, JsonSchema)]
+pub struct RustCrate {
+    pub name: String,
+    /// The crate's name on crates.io, like "serde".
+    pub crate_name: String,
+    /// Whether to leave out pre-release versions, like "1.0.0-beta.1".
+    #[serde(default)]
+    pub exclude_prereleases: bool,
+    /// The settings that every source has.
+    #[serde(flatten)]
+    pub settings: SourceSettings,
+    /// Any fields sitch doesn't know about, kept so they aren't lost.
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl CheckForUpdates for RustCrates {
+    fn check_for_all_updates(&mut self, state: &State, options: &CheckOptions) -> Vec<SourceCheck> {
+        check_each_source(
+            "crates",
+            &mut self.0,
+            state,
+            options,
+            |rust_crate, last_checked| {
+                (
+                    rust_crate.name.clone(),
+                    rust_crate.check_for_updates(last_checked),
+                )
+            },
+        )
+    }
+
+    fn diagnose_all(&self) -> Vec<(String, Result<(), String>)> {
+        self.0
+            .par_iter()
+            .map(|(rust_crate, _last_checked)| (rust_crate.name.clone(), rust_crate.diagnose()))
+            .collect()
+    }
+
+    fn type_name(&self) -> &'static str {
+        "crates.io"
+    }
+}
+
+impl NamedSource for RustCrate {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn name_mut(&mut self) -> &mut String {
+        &mut self.name
+    }
+
+    fn location(&self) -> Cow<'_, str> {
+        Cow::Borrowed(&self.crate_name)
+    }
+
+    fn page(&mut self) -> Result<String, String> {
+        Ok(self.crate_url())
+    }
+
+    fn validate(&mut self) -> Result<(), String> {
+        validate_name(&mut self.name)?;
+        validate_field("crate name", &mut self.crate_name)
+    }
+
+    fn settings(&self) -> &SourceSettings {
+        &self.settings
+    }
+
+    fn settings_mut(&mut self) -> &mut SourceSettings {
+        &mut self.settings
+    }
+}
+
+impl RustCrate {
+    /// The URL of the crate's page on crates.io.
+    pub fn crate_url(&self) -> String {
+        format!("https://crates.io/crates/{}", self.crate_name)
+    }
+
+    /// Loads the crate's published versions from the crates.io API.
+    fn versions(&self) -> Result<Vec<Value>, String> {
+        // crates.io rejects requests without a user agent, which `get` sets
+        let url = format!(
+            "https://crates.io/api/v1/crates/{}/versions",
+            self.crate_name
+        );
+        let response = get(&url).map_err(|err| access_error(&url, &err))?;
+        let versions: Value = check_status(response)?
+            .json()
+            .map_err(|_err| "Couldn't parse request data as JSON".to_owned())?;
+
+        versions
+            .pointer("/versions")
+            .and_then(|versions| versions.as_array())
+            .cloned()
+            .ok_or("Couldn't parse received versions as JSON array".to_owned())
+    }
+
+    /// Makes sure that the crate exists on crates.io.
+    pub fn diagnose(&self) -> Result<(), String> {
+        self.versions().map(|_versions| ())
+    }
+
+    /// Check for versions of the crate released since `last_checked`,
+    /// skipping yanked versions and, if asked to, pre-releases.
+    pub fn check_for_updates(
+        &self,
+        last_checked: &Option<DateTime<Local>>,
+    ) -> Result<Vec<SourceUpdate>, String> {
+        let versions = self.versions()?;
+        let updates: Vec<SourceUpdate> = versions
+            .iter()
+            .filter_map(|version| self.parse_version(version))
+            .filter(|update| {
+                last_checked
+                    .map(|last_checked| last_checked < update.published_date)
+                    .unwrap_or(true)
+            })
+            .collect();
+        log_new_items(&self.name, versions.len(), updates.len());
+
+        Ok(updates)
+    }
+
+    /// Parses a version, skipping it if it was yanked, is an excluded
+    /// pre-release, or has no version number or date.
+    fn parse_version(&self, version: &Value) -> Option<SourceUpdate> {
+        // {
+        //     "versions": [
+        //         {
+        //             "num": "1.0.210",
+        //             "yanked": false,
+        //             "created_at": "2024-09-06T21:26:59.017432+00:00",
+        //             ...
+        //         },
+        //         ...
+        //     ]
+        // }
+        let yanked = version
+            .pointer("/yanked")
+            .and_then(|yanked_obj| yanked_obj.as_bool())
+            .unwrap_or(false);
+        let number = version
+            .pointer("/num")
+            .and_then(|num_obj| num_obj.as_str())?;
+        // semver pre-releases are marked with a "-" after the version core
+        let prerelease = number.contains('-');
+        if yanked || (prerelease && self.exclude_prereleases) {
+            return None;
+        }
+
+        let published_date = version
+            .pointer("/created_at")
+            .and_then(|date_obj| date_obj.as_str())
+            .and_then(|date| DateTime::<FixedOffset>::parse_from_rfc3339(date).ok())
+            .map(|date| date.with_timezone(&Local))?;
+
+        Some(SourceUpdate {
+            title: format!("{} {} released", self.crate_name, number),
+            link: format!("{}/{}", self.crate_url(), number),
+            published_date,
+            thumbnail: None,
+            description: None,
+            secondary_link: None,
+            also_in: Vec::new(),
+        })
+    }
+}
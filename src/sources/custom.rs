@@ -0,0 +1,212 @@
+//! The custom platform for update checking, which reads updates out
+//! of any JSON API using JSON pointers.
+
+use crate::sources::{
+    check_each_source, deserialize_sources, log_new_items, serialize_sources, validate_name,
+    validate_url, CheckForUpdates, CheckOptions, NamedSource, SourceCheck, SourceSettings,
+    SourceUpdate,
+};
+use crate::state::State;
+use crate::util::{access_error, check_status, get, parse_date};
+use chrono::{DateTime, Local};
+use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+use reqwest::Url;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::borrow::Cow;
+
+/// The wrapper type for custom sources and their last checked times
+/// to implement `CheckForUpdates` on.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, JsonSchema)]
+pub struct CustomSources(
+    #[serde(
+        serialize_with = "serialize_sources",
+        deserialize_with = "deserialize_sources"
+    )]
+    #[schemars(with = "Vec<CustomSource>")]
+    pub Vec<(CustomSource, Option<DateTime<Local>>)>,
+);
+
+/// A JSON API whose updates are found with JSON pointers, like
+/// "/data/events" (see RFC 6901).
+#[derive(Debug, Serialize, Deserialize, Clone, Default, JsonSchema)]
+pub struct CustomSource {
+    pub name: String,
+    /// The URL of the JSON to check.
+    pub url: String,
+    /// Where the array of items is in the JSON, or "" if it is the root.
+    pub items_pointer: String,
+    /// Where each item's title is, relative to the item.
+    pub title_pointer: String,
+    /// Where each item's link is, relative to the item. Relative links
+    /// are resolved against `url`.
+    pub link_pointer: String,
+    /// Where each item's date is, relative to the item.
+    pub date_pointer: String,
+    /// How dates are formatted: a chrono format string like
+    /// "%Y-%m-%d %H:%M", or "epoch" for seconds since the Unix epoch.
+    /// RFC 3339 and RFC 2822 dates are read if not set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub date_format: Option<String>,
+    /// The settings that every source has.
+    #[serde(flatten)]
+    pub settings: SourceSettings,
+    /// Any fields sitch doesn't know about, kept so they aren't lost.
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl CheckForUpdates for CustomSources {
+    fn check_for_all_updates(&mut self, state: &State, options: &CheckOptions) -> Vec<SourceCheck> {
+        check_each_source(
+            "custom",
+            &mut self.0,
+            state,
+            options,
+            |source, last_checked| (source.name.clone(), source.check_for_updates(last_checked)),
+        )
+    }
+
+    fn diagnose_all(&self) -> Vec<(String, Result<(), String>)> {
+        self.0
+            .par_iter()
+            .map(|(source, _last_checked)| (source.name.clone(), source.diagnose()))
+            .collect()
+    }
+
+    fn type_name(&self) -> &'static str {
+        "Custom"
+    }
+}
+
+impl NamedSource for CustomSource {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn name_mut(&mut self) -> &mut String {
+        &mut self.name
+    }
+
+    fn location(&self) -> Cow<'_, str> {
+        Cow::Borrowed(&self.url)
+    }
+
+    fn validate(&mut self) -> Result<(), String> {
+        validate_name(&mut self.name)?;
+        validate_url("URL", &mut self.url)
+    }
+
+    fn settings(&self) -> &SourceSettings {
+        &self.settings
+    }
+
+    fn settings_mut(&mut self) -> &mut SourceSettings {
+        &mut self.settings
+    }
+}
+
+impl CustomSource {
+    /// Loads the JSON and reads every item in it, failing on the first
+    /// item that one of the pointers doesn't work for.
+    fn updates(&self) -> Result<Vec<SourceUpdate>, String> {
+        let response = get(&self.url).map_err(|err| access_error(&self.url, &err))?;
+        let json: Value = check_status(response)?
+            .json()
+            .map_err(|_err| "Couldn't parse request data as JSON".to_owned())?;
+
+        let items = json
+            .pointer(&self.items_pointer)
+            .ok_or(format!(
+                "Nothing was found at the items_pointer \"{}\"",
+                self.items_pointer
+            ))?
+            .as_array()
+            .ok_or(format!(
+                "The items_pointer \"{}\" doesn't point to an array",
+                self.items_pointer
+            ))?;
+
+        items
+            .iter()
+            .enumerate()
+            .map(|(index, item)| self.parse_item(index, item))
+            .collect()
+    }
+
+    /// Makes sure that the JSON can be loaded and all of its items read.
+    pub fn diagnose(&self) -> Result<(), String> {
+        self.updates().map(|_updates| ())
+    }
+
+    /// Check for items dated after `last_checked`.
+    pub fn check_for_updates(
+        &self,
+        last_checked: &Option<DateTime<Local>>,
+    ) -> Result<Vec<SourceUpdate>, String> {
+        let items = self.updates()?;
+        let loaded = items.len();
+        let updates: Vec<SourceUpdate> = items
+            .into_iter()
+            .filter(|update| {
+                last_checked
+                    .map(|last_checked| last_checked < update.published_date)
+                    .unwrap_or(true)
+            })
+            .collect();
+        log_new_items(&self.name, loaded, updates.len());
+
+        Ok(updates)
+    }
+
+    /// Reads an item with the per-field pointers.
+    fn parse_item(&self, index: usize, item: &Value) -> Result<SourceUpdate, String> {
+        // errors name the full pointer, e.g. "/events/3/title"
+        let field = |field_name: &str, pointer: &str| {
+            item.pointer(pointer).ok_or(format!(
+                "Nothing was found for item {} at the {} \"{}/{}{}\"",
+                index, field_name, self.items_pointer, index, pointer
+            ))
+        };
+
+        let title = Self::as_text(field("title_pointer", &self.title_pointer)?);
+        let link = Self::as_text(field("link_pointer", &self.link_pointer)?);
+        let date = field("date_pointer", &self.date_pointer)?;
+        let published_date = parse_date(
+            &Self::as_text(date),
+            self.date_format.as_ref().map(String::as_str),
+        )
+        .ok_or(format!(
+            "The date of item {} ({}) doesn't match the date_format \"{}\"",
+            index,
+            date,
+            self.date_format
+                .as_ref()
+                .map(String::as_str)
+                .unwrap_or("rfc3339")
+        ))?;
+
+        Ok(SourceUpdate {
+            title,
+            // links relative to the API are made absolute
+            link: Url::parse(&self.url)
+                .and_then(|base| base.join(&link))
+                .map(String::from)
+                .unwrap_or(link),
+            published_date,
+            thumbnail: None,
+            description: None,
+            secondary_link: None,
+            also_in: Vec::new(),
+        })
+    }
+
+    /// The text of a string, or of any other JSON value as written.
+    fn as_text(value: &Value) -> String {
+        match value {
+            Value::String(text) => text.clone(),
+            other => other.to_string(),
+        }
+    }
+}
@@ -0,0 +1,338 @@
+//! The GitHub platform for update checking, which reports new
+//! commits to repositories' branches.
+
+use crate::sources::{
+    check_each_source, deserialize_sources, log_new_items, serialize_sources, validate_field,
+    validate_name, CheckForUpdates, CheckOptions, NamedSource, SourceCheck, SourceSettings,
+    SourceUpdate,
+};
+use crate::state::State;
+use crate::util::{
+    access_error, check_status, http_client, log_request, log_response, send, SentResponse,
+};
+use chrono::{DateTime, FixedOffset, Local, SecondsFormat, TimeZone, Utc};
+use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+use reqwest::header::{ACCEPT, AUTHORIZATION};
+use reqwest::{StatusCode, Url};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::borrow::Cow;
+use std::env;
+
+/// The environment variable a GitHub token is read from if the
+/// config doesn't have one.
+const TOKEN_VAR: &str = "GITHUB_TOKEN";
+
+/// The wrapper type for GitHub repositories and their last checked times
+/// to implement `CheckForUpdates` on.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, JsonSchema)]
+pub struct GitHubRepos {
+    /// A personal access token, which raises the API rate limit from 60
+    /// to 5,000 requests an hour. Falls back to `$GITHUB_TOKEN`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+    #[serde(
+        default,
+        serialize_with = "serialize_sources",
+        deserialize_with = "deserialize_sources"
+    )]
+    #[schemars(with = "Vec<GitHubRepo>")]
+    pub repos: Vec<(GitHubRepo, Option<DateTime<Local>>)>,
+    /// Any fields sitch doesn't know about, kept so they aren't lost.
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// A GitHub repository whose commits are followed.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, JsonSchema)]
+pub struct GitHubRepo {
+    pub name: String,
+    /// The user or organization that owns the repository.
+    pub owner: String,
+    /// The repository's name.
+    pub repo: String,
+    /// The branch to follow, or the default branch if not set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub branch: Option<String>,
+    /// Whether to report all new commits as a single update.
+    #[serde(default)]
+    pub collapse: bool,
+    /// The settings that every source has.
+    #[serde(flatten)]
+    pub settings: SourceSettings,
+    /// Any fields sitch doesn't know about, kept so they aren't lost.
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl GitHubRepos {
+    /// The token to authenticate with, if there is one.
+    fn token(&self) -> Option<String> {
+        self.token
+            .clone()
+            .or_else(|| env::var(TOKEN_VAR).ok())
+            .filter(|token| token.trim().len() > 0)
+    }
+}
+
+impl CheckForUpdates for GitHubRepos {
+    fn check_for_all_updates(&mut self, state: &State, options: &CheckOptions) -> Vec<SourceCheck> {
+        let token = self.token();
+        check_each_source(
+            "github",
+            &mut self.repos,
+            state,
+            options,
+            |repo, last_checked| {
+                (
+                    repo.name.clone(),
+                    repo.check_for_updates(&token, last_checked),
+                )
+            },
+        )
+    }
+
+    fn diagnose_all(&self) -> Vec<(String, Result<(), String>)> {
+        let token = self.token();
+        self.repos
+            .par_iter()
+            .map(|(repo, _last_checked)| (repo.name.clone(), repo.diagnose(&token)))
+            .collect()
+    }
+
+    fn type_name(&self) -> &'static str {
+        "GitHub"
+    }
+}
+
+impl NamedSource for GitHubRepo {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn name_mut(&mut self) -> &mut String {
+        &mut self.name
+    }
+
+    fn location(&self) -> Cow<'_, str> {
+        match &self.branch {
+            Some(branch) => Cow::Owned(format!("{}/{}@{}", self.owner, self.repo, branch)),
+            None => Cow::Owned(format!("{}/{}", self.owner, self.repo)),
+        }
+    }
+
+    fn page(&mut self) -> Result<String, String> {
+        Ok(self.repo_url())
+    }
+
+    fn validate(&mut self) -> Result<(), String> {
+        validate_name(&mut self.name)?;
+        validate_field("owner", &mut self.owner)?;
+        validate_field("repo", &mut self.repo)
+    }
+
+    fn settings(&self) -> &SourceSettings {
+        &self.settings
+    }
+
+    fn settings_mut(&mut self) -> &mut SourceSettings {
+        &mut self.settings
+    }
+}
+
+impl GitHubRepo {
+    /// The URL of the repository on GitHub.
+    pub fn repo_url(&self) -> String {
+        format!("https://github.com/{}/{}", self.owner, self.repo)
+    }
+
+    /// Loads the repository's commits, newest first, optionally only
+    /// those committed since `since`.
+    fn commits(
+        &self,
+        token: &Option<String>,
+        since: &Option<DateTime<Local>>,
+        per_page: usize,
+    ) -> Result<Vec<Value>, String> {
+        let mut params = vec![("per_page", per_page.to_string())];
+        if let Some(branch) = &self.branch {
+            params.push(("sha", branch.clone()));
+        }
+        if let Some(since) = since {
+            params.push((
+                "since",
+                since
+                    .with_timezone(&Utc)
+                    .to_rfc3339_opts(SecondsFormat::Secs, true),
+            ));
+        }
+        let url = Url::parse_with_params(
+            &format!(
+                "https://api.github.com/repos/{}/{}/commits",
+                self.owner, self.repo
+            ),
+            &params,
+        )
+        .map_err(|err| format!("Couldn't build the GitHub API URL: {}", err))?;
+
+        let client =
+            http_client(None, true).map_err(|_err| "Couldn't build an HTTP client".to_owned())?;
+        let mut request = client
+            .get(url.as_str())
+            .header(ACCEPT, "application/vnd.github.v3+json");
+        if let Some(token) = token {
+            request = request.header(AUTHORIZATION, format!("token {}", token));
+        }
+        log_request("GET", url.as_str());
+        let response = send(&client, request);
+        log_response(url.as_str(), &response);
+        let response = response.map_err(|err| access_error(url.as_str(), &err))?;
+        let commits: Value = check_status(Self::check_rate_limit(response)?)?
+            .json()
+            .map_err(|_err| "Couldn't parse request data as JSON".to_owned())?;
+
+        commits
+            .as_array()
+            .cloned()
+            .ok_or("Couldn't parse received commits as JSON array".to_owned())
+    }
+
+    /// Turns a response refused for running out of API requests into an
+    /// error saying so, with when the limit resets.
+    fn check_rate_limit(response: SentResponse) -> Result<SentResponse, String> {
+        let header = |name: &str| {
+            response
+                .headers()
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(|value| value.to_owned())
+        };
+        let remaining = header("x-ratelimit-remaining");
+        let refused = response.status() == StatusCode::FORBIDDEN
+            || response.status() == StatusCode::TOO_MANY_REQUESTS;
+        if !refused || remaining.as_ref().map(String::as_str) != Some("0") {
+            return Ok(response);
+        }
+
+        let resets = header("x-ratelimit-reset")
+            .and_then(|reset| reset.parse::<i64>().ok())
+            .and_then(|reset| Local.timestamp_opt(reset, 0).single())
+            .map(|reset| format!(" until {}", reset.format("%-I:%M %p")))
+            .unwrap_or_default();
+        Err(format!(
+            "The GitHub API rate limit is used up (X-RateLimit-Remaining: 0){}; \
+             set a token in the github section of your config or ${} for a higher limit",
+            resets, TOKEN_VAR
+        ))
+    }
+
+    /// Makes sure that the repository (and branch, if set) exists.
+    pub fn diagnose(&self, token: &Option<String>) -> Result<(), String> {
+        self.commits(token, &None, 1).map(|_commits| ())
+    }
+
+    /// Check for commits to the repository since `last_checked`.
+    ///
+    /// At most 100 commits are loaded per check. If the repository is
+    /// set to `collapse`, several new commits are reported as a single
+    /// update linking to a comparison of them.
+    pub fn check_for_updates(
+        &self,
+        token: &Option<String>,
+        last_checked: &Option<DateTime<Local>>,
+    ) -> Result<Vec<SourceUpdate>, String> {
+        // (sha, update) pairs, newest first
+        let loaded = self.commits(token, last_checked, 100)?;
+        let commits = loaded
+            .iter()
+            .filter_map(|commit| {
+                let sha = commit
+                    .pointer("/sha")
+                    .and_then(|sha_obj| sha_obj.as_str())?;
+                Some((sha.to_owned(), Self::parse_commit(commit)?))
+            })
+            .filter(|(_sha, update)| {
+                last_checked
+                    .map(|last_checked| last_checked < update.published_date)
+                    .unwrap_or(true)
+            })
+            .collect::<Vec<(String, SourceUpdate)>>();
+        log_new_items(&self.name, loaded.len(), commits.len());
+
+        if !self.collapse || commits.len() < 2 {
+            return Ok(commits.into_iter().map(|(_sha, update)| update).collect());
+        }
+
+        let (newest_sha, newest) = &commits[0];
+        let (oldest_sha, _oldest) = &commits[commits.len() - 1];
+        Ok(vec![SourceUpdate {
+            title: format!("{} new commits", commits.len()),
+            // the parent of the oldest commit is included to show its changes too
+            link: format!(
+                "{}/compare/{}^...{}",
+                self.repo_url(),
+                oldest_sha,
+                newest_sha
+            ),
+            published_date: newest.published_date,
+            thumbnail: None,
+            description: Some(
+                commits
+                    .iter()
+                    .map(|(_sha, update)| update.title.clone())
+                    .collect::<Vec<String>>()
+                    .join("\n"),
+            ),
+            secondary_link: None,
+            also_in: Vec::new(),
+        }])
+    }
+
+    /// Parses a commit, skipping it if it has no date.
+    ///
+    /// The title is the first line of the commit message.
+    fn parse_commit(commit: &Value) -> Option<SourceUpdate> {
+        // [
+        //     {
+        //         "sha": "6dcb09b5b57875f334f61aebed695e2e4193db5e",
+        //         "html_url": "https://github.com/owner/repo/commit/6dcb09b...",
+        //         "commit": {
+        //             "message": "Fix all the bugs\n\nMore details",
+        //             "committer": { "date": "2011-04-14T16:00:49Z", ... },
+        //             ...
+        //         },
+        //         ...
+        //     },
+        //     ...
+        // ]
+        let published_date = commit
+            .pointer("/commit/committer/date")
+            .and_then(|date_obj| date_obj.as_str())
+            .and_then(|date| DateTime::<FixedOffset>::parse_from_rfc3339(date).ok())
+            .map(|date| date.with_timezone(&Local))?;
+        let message = commit
+            .pointer("/commit/message")
+            .and_then(|message_obj| message_obj.as_str())
+            .unwrap_or("");
+
+        Some(SourceUpdate {
+            title: message
+                .lines()
+                .next()
+                .filter(|line| line.trim().len() > 0)
+                .unwrap_or("<no message>")
+                .to_owned(),
+            link: commit
+                .pointer("/html_url")
+                .and_then(|url_obj| url_obj.as_str())
+                .unwrap_or("<no link>")
+                .to_owned(),
+            published_date,
+            thumbnail: None,
+            description: None,
+            secondary_link: None,
+            also_in: Vec::new(),
+        })
+    }
+}
@@ -0,0 +1,501 @@
+//! The Gmail platform for update checking, which reports new emails
+//! matching Gmail searches.
+
+use crate::sources::{
+    check_each_source, deserialize_sources, log_new_items, serialize_sources, validate_field,
+    validate_name, CheckForUpdates, CheckOptions, NamedSource, SourceCheck, SourceSettings,
+    SourceUpdate,
+};
+use crate::state::State;
+use crate::util::{
+    access_error, check_status, http_client, log_request, log_response, readline, send,
+    ReadlineResult,
+};
+use atty::Stream;
+use chrono::{DateTime, Local, TimeZone};
+use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+use reqwest::header::AUTHORIZATION;
+use reqwest::{StatusCode, Url};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::borrow::Cow;
+
+/// Where the user is sent to let sitch read their email.
+const AUTH_URL: &str = "https://accounts.google.com/o/oauth2/v2/auth";
+
+/// Where authorization codes and refresh tokens are traded for tokens.
+const TOKEN_URL: &str = "https://oauth2.googleapis.com/token";
+
+/// Where Google sends the user after they let sitch in. Nothing needs
+/// to be listening there, as the user copies the address back to sitch.
+const REDIRECT_URI: &str = "http://localhost";
+
+/// Read-only access to the user's email. The narrower metadata scope
+/// doesn't allow searching.
+const SCOPE: &str = "https://www.googleapis.com/auth/gmail.readonly";
+
+/// The Gmail API endpoint for the user's messages.
+const MESSAGES_URL: &str = "https://gmail.googleapis.com/gmail/v1/users/me/messages";
+
+/// The most new messages loaded per filter per check.
+const MAX_MESSAGES: usize = 50;
+
+/// The wrapper type for Gmail filters and their last checked times
+/// to implement `CheckForUpdates` on.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, JsonSchema)]
+pub struct GmailFilters {
+    /// The OAuth client and sign-in that give sitch access to Gmail.
+    /// Gmail filters are ignored if it isn't set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub oauth: Option<GmailOauth>,
+    #[serde(
+        default,
+        serialize_with = "serialize_sources",
+        deserialize_with = "deserialize_sources"
+    )]
+    #[schemars(with = "Vec<GmailFilter>")]
+    pub filters: Vec<(GmailFilter, Option<DateTime<Local>>)>,
+    /// Any fields sitch doesn't know about, kept so they aren't lost.
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// The OAuth client that the user made for sitch in the Google API
+/// console, along with the refresh token from signing in with it.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, JsonSchema)]
+pub struct GmailOauth {
+    pub client_id: String,
+    pub client_secret: String,
+    /// Used to get a new access token for each check, once signed in.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub refresh_token: Option<String>,
+    /// Any fields sitch doesn't know about, kept so they aren't lost.
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// A Gmail search whose new matching emails are reported.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, JsonSchema)]
+pub struct GmailFilter {
+    pub name: String,
+    /// The search, as typed into Gmail, like "from:someone is:unread".
+    pub filter: String,
+    /// The settings that every source has.
+    #[serde(flatten)]
+    pub settings: SourceSettings,
+    /// Any fields sitch doesn't know about, kept so they aren't lost.
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl CheckForUpdates for GmailFilters {
+    fn check_for_all_updates(&mut self, state: &State, options: &CheckOptions) -> Vec<SourceCheck> {
+        // only check for updates if sitch has access to Gmail
+        let access_token = match (&self.oauth, self.filters.is_empty()) {
+            (Some(oauth), false) => match oauth.access_token() {
+                Ok(access_token) => access_token,
+                Err(err) => return vec![SourceCheck::failed("Authorization".to_owned(), err)],
+            },
+            _ => return Vec::new(),
+        };
+
+        check_each_source(
+            "gmail",
+            &mut self.filters,
+            state,
+            options,
+            |filter, last_checked| {
+                (
+                    filter.name.clone(),
+                    filter.check_for_updates(&access_token, last_checked),
+                )
+            },
+        )
+    }
+
+    fn diagnose_all(&self) -> Vec<(String, Result<(), String>)> {
+        if self.filters.is_empty() {
+            return Vec::new();
+        }
+        let access_token = match self.oauth.as_ref().map(GmailOauth::access_token) {
+            Some(Ok(access_token)) => access_token,
+            Some(Err(err)) => return vec![("Authorization".to_owned(), Err(err))],
+            None => {
+                return vec![(
+                    "Authorization".to_owned(),
+                    Err("No client ID is set, so Gmail filters are ignored".to_owned()),
+                )]
+            }
+        };
+
+        self.filters
+            .par_iter()
+            .map(|(filter, _last_checked)| (filter.name.clone(), filter.diagnose(&access_token)))
+            .collect()
+    }
+
+    fn type_name(&self) -> &'static str {
+        "Gmail"
+    }
+}
+
+impl NamedSource for GmailFilter {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn name_mut(&mut self) -> &mut String {
+        &mut self.name
+    }
+
+    fn location(&self) -> Cow<'_, str> {
+        Cow::Borrowed(&self.filter)
+    }
+
+    fn page(&mut self) -> Result<String, String> {
+        let mut url = Url::parse("https://mail.google.com/mail/u/0/")
+            .map_err(|err| format!("Couldn't make the search's URL: {}", err))?;
+        url.set_fragment(Some(&format!("search/{}", self.filter)));
+
+        Ok(url.to_string())
+    }
+
+    fn validate(&mut self) -> Result<(), String> {
+        validate_name(&mut self.name)?;
+        validate_field("filter", &mut self.filter)
+    }
+
+    fn settings(&self) -> &SourceSettings {
+        &self.settings
+    }
+
+    fn settings_mut(&mut self) -> &mut SourceSettings {
+        &mut self.settings
+    }
+}
+
+impl GmailOauth {
+    /// Reads the client ID and secret from the JSON file that the Google
+    /// API console gives for an OAuth client, which is either for a
+    /// desktop ("installed") or web application.
+    pub fn from_client_json(json: &str) -> Result<Self, String> {
+        let json: Value = serde_json::from_str(json)
+            .map_err(|err| format!("The client ID file isn't valid JSON: {}", err))?;
+        let client = json
+            .get("installed")
+            .or_else(|| json.get("web"))
+            .ok_or("The client ID file has no \"installed\" or \"web\" client in it")?;
+        let field = |name: &str| {
+            client
+                .get(name)
+                .and_then(|value| value.as_str())
+                .map(|value| value.to_owned())
+                .ok_or(format!("The client ID file has no {}", name))
+        };
+
+        Ok(GmailOauth {
+            client_id: field("client_id")?,
+            client_secret: field("client_secret")?,
+            ..Default::default()
+        })
+    }
+
+    /// Whether the user has signed in with the client.
+    pub fn is_signed_in(&self) -> bool {
+        self.refresh_token.is_some()
+    }
+
+    /// Walks the user through letting sitch read their email, keeping
+    /// the refresh token that Google gives for it.
+    ///
+    /// The user opens a link, signs in, and is sent on to `REDIRECT_URI`,
+    /// which won't load. The address it was sent to has the authorization
+    /// code in it, which the user pastes back into sitch.
+    pub fn sign_in(&mut self) -> Result<(), String> {
+        if !atty::is(Stream::Stdin) {
+            return Err(
+                "Signing in to Gmail needs an interactive terminal, but stdin isn't one. \
+                 Run `sitch gmail apikey login` in a terminal instead."
+                    .to_owned(),
+            );
+        }
+
+        let auth_url = Url::parse_with_params(
+            AUTH_URL,
+            &[
+                ("client_id", self.client_id.as_str()),
+                ("redirect_uri", REDIRECT_URI),
+                ("response_type", "code"),
+                ("scope", SCOPE),
+                // ask for a refresh token, even if the user signed in before
+                ("access_type", "offline"),
+                ("prompt", "consent"),
+            ],
+        )
+        .map_err(|err| format!("Couldn't build the sign-in URL: {}", err))?;
+        eprintln!("Open this link in your browser and let sitch read your email:");
+        eprintln!("{}", auth_url);
+        eprintln!(
+            "You'll then be sent to a page on {} that doesn't load. \
+             Copy its address from your browser.",
+            REDIRECT_URI
+        );
+
+        let code = match readline("Paste the address (or just its code): ", |input| {
+            Self::parse_code(&input)
+        })? {
+            ReadlineResult::Value(code) => code,
+            ReadlineResult::Quit => return Err("Stopped signing in to Gmail.".to_owned()),
+        };
+        let tokens = self.request_token(&[
+            ("grant_type", "authorization_code"),
+            ("code", code.as_str()),
+            ("redirect_uri", REDIRECT_URI),
+        ])?;
+        let refresh_token = tokens
+            .get("refresh_token")
+            .and_then(|token| token.as_str())
+            .ok_or("Google didn't give sitch a refresh token")?;
+        self.refresh_token = Some(refresh_token.to_owned());
+
+        Ok(())
+    }
+
+    /// Finds the authorization code in the pasted address, or takes
+    /// the input as the code itself.
+    fn parse_code(input: &str) -> Result<String, String> {
+        let input = input.trim();
+        if input.is_empty() {
+            return Err("Please paste the address or code.".to_owned());
+        }
+        match Url::parse(input) {
+            Ok(url) => {
+                let param = |name: &str| {
+                    url.query_pairs()
+                        .find(|(key, _value)| key == name)
+                        .map(|(_key, value)| value.into_owned())
+                };
+                if let Some(error) = param("error") {
+                    Err(format!("Google didn't let sitch in: {}", error))
+                } else {
+                    param("code").ok_or("That address has no code in it.".to_owned())
+                }
+            }
+            Err(_err) => Ok(input.to_owned()),
+        }
+    }
+
+    /// Gets a fresh access token for checking, as they only last an hour.
+    pub fn access_token(&self) -> Result<String, String> {
+        let refresh_token = self.refresh_token.as_ref().ok_or(
+            "You haven't signed in to Gmail yet. Run `sitch gmail apikey login` to sign in.",
+        )?;
+        let tokens = self.request_token(&[
+            ("grant_type", "refresh_token"),
+            ("refresh_token", refresh_token.as_str()),
+        ])?;
+
+        tokens
+            .get("access_token")
+            .and_then(|token| token.as_str())
+            .map(|token| token.to_owned())
+            .ok_or("Google didn't give sitch an access token".to_owned())
+    }
+
+    /// Trades a grant (like a code or refresh token) for tokens, turning
+    /// Google's errors into ones that say what to do about them.
+    fn request_token(&self, grant: &[(&str, &str)]) -> Result<Value, String> {
+        let mut params = vec![
+            ("client_id", self.client_id.as_str()),
+            ("client_secret", self.client_secret.as_str()),
+        ];
+        params.extend_from_slice(grant);
+
+        log_request("POST", TOKEN_URL);
+        let client =
+            http_client(None, true).map_err(|_err| "Couldn't build an HTTP client".to_owned())?;
+        let response = send(&client, client.post(TOKEN_URL).form(&params));
+        log_response(TOKEN_URL, &response);
+        let mut response = response.map_err(|err| access_error(TOKEN_URL, &err))?;
+        let tokens: Value = response
+            .json()
+            .map_err(|_err| "Couldn't parse Google's response as JSON".to_owned())?;
+
+        // { "error": "invalid_grant", "error_description": "Token has been expired or revoked." }
+        match tokens.get("error").and_then(|error| error.as_str()) {
+            Some("invalid_grant") => Err("Your Gmail sign-in has expired or was revoked. \
+                 Run `sitch gmail apikey login` to sign in again."
+                .to_owned()),
+            Some("invalid_client") | Some("unauthorized_client") => {
+                Err("Google doesn't recognize your Gmail client ID. \
+                 Set it again with `sitch gmail apikey set`."
+                    .to_owned())
+            }
+            Some(error) => Err(format!(
+                "Google refused to sign in to Gmail: {}",
+                tokens
+                    .get("error_description")
+                    .and_then(|description| description.as_str())
+                    .unwrap_or(error)
+            )),
+            None => Ok(tokens),
+        }
+    }
+}
+
+impl GmailFilter {
+    /// Loads JSON from the Gmail API, signed in with `access_token`.
+    fn api_get(url: &Url, access_token: &str) -> Result<Value, String> {
+        log_request("GET", url.as_str());
+        let client =
+            http_client(None, true).map_err(|_err| "Couldn't build an HTTP client".to_owned())?;
+        let response = send(
+            &client,
+            client
+                .get(url.as_str())
+                .header(AUTHORIZATION, format!("Bearer {}", access_token)),
+        );
+        log_response(url.as_str(), &response);
+        let response = response.map_err(|err| access_error(url.as_str(), &err))?;
+        if response.status() == StatusCode::UNAUTHORIZED {
+            return Err("Gmail rejected sitch's access. \
+                        Run `sitch gmail apikey login` to sign in again."
+                .to_owned());
+        }
+
+        check_status(response)?
+            .json()
+            .map_err(|_err| "Couldn't parse request data as JSON".to_owned())
+    }
+
+    /// Finds the ids of the newest messages matching the filter, and
+    /// sent after `after` if given.
+    fn message_ids(
+        &self,
+        access_token: &str,
+        after: &Option<DateTime<Local>>,
+        max_results: usize,
+    ) -> Result<Vec<String>, String> {
+        // Gmail searches take `after:` as seconds since the epoch, too
+        let query = match after {
+            Some(after) => format!("({}) after:{}", self.filter, after.timestamp()),
+            None => self.filter.clone(),
+        };
+        let url = Url::parse_with_params(
+            MESSAGES_URL,
+            &[
+                ("q", query.as_str()),
+                ("maxResults", &max_results.to_string()),
+            ],
+        )
+        .map_err(|err| format!("Couldn't build the Gmail API URL: {}", err))?;
+
+        // { "messages": [{ "id": "16f1...", "threadId": "16f1..." }], "resultSizeEstimate": 1 }
+        // (with no "messages" at all if nothing matched)
+        Ok(Self::api_get(&url, access_token)?
+            .get("messages")
+            .and_then(|messages| messages.as_array())
+            .map(|messages| {
+                messages
+                    .iter()
+                    .filter_map(|message| message.get("id")?.as_str())
+                    .map(|id| id.to_owned())
+                    .collect()
+            })
+            .unwrap_or_default())
+    }
+
+    /// Loads a message's subject, sender, and when it was received.
+    fn message(access_token: &str, id: &str) -> Result<SourceUpdate, String> {
+        let url = Url::parse_with_params(
+            &format!("{}/{}", MESSAGES_URL, id),
+            &[
+                ("format", "metadata"),
+                ("metadataHeaders", "Subject"),
+                ("metadataHeaders", "From"),
+            ],
+        )
+        .map_err(|err| format!("Couldn't build the Gmail API URL: {}", err))?;
+
+        // {
+        //     "id": "16f1...",
+        //     "internalDate": "1571234567000",
+        //     "payload": { "headers": [{ "name": "Subject", "value": "Hello" }, ...] },
+        //     ...
+        // }
+        let message = Self::api_get(&url, access_token)?;
+        let header = |name: &str| {
+            message
+                .pointer("/payload/headers")
+                .and_then(|headers| headers.as_array())
+                .and_then(|headers| {
+                    headers.iter().find(|header| {
+                        header
+                            .get("name")
+                            .and_then(|header_name| header_name.as_str())
+                            .map(|header_name| header_name.eq_ignore_ascii_case(name))
+                            .unwrap_or(false)
+                    })
+                })
+                .and_then(|header| header.get("value")?.as_str())
+                .map(|value| value.to_owned())
+        };
+        let published_date = message
+            .get("internalDate")
+            .and_then(|date| date.as_str())
+            .and_then(|date| date.parse::<i64>().ok())
+            .and_then(|millis| {
+                Local
+                    .timestamp_opt(millis / 1000, (millis % 1000) as u32 * 1_000_000)
+                    .single()
+            })
+            .ok_or(format!("Couldn't tell when message {} was received", id))?;
+
+        Ok(SourceUpdate {
+            title: header("Subject")
+                .filter(|subject| !subject.trim().is_empty())
+                .unwrap_or("<no subject>".to_owned()),
+            link: format!("https://mail.google.com/mail/u/0/#all/{}", id),
+            published_date,
+            thumbnail: None,
+            description: header("From"),
+            secondary_link: None,
+            also_in: Vec::new(),
+        })
+    }
+
+    /// Makes sure that Gmail accepts the filter.
+    pub fn diagnose(&self, access_token: &str) -> Result<(), String> {
+        self.message_ids(access_token, &None, 1).map(|_ids| ())
+    }
+
+    /// Check for emails matching the filter received since `last_checked`.
+    ///
+    /// At most `MAX_MESSAGES` of the newest emails are loaded per check.
+    pub fn check_for_updates(
+        &self,
+        access_token: &str,
+        last_checked: &Option<DateTime<Local>>,
+    ) -> Result<Vec<SourceUpdate>, String> {
+        let ids = self.message_ids(access_token, last_checked, MAX_MESSAGES)?;
+        let messages = ids
+            .par_iter()
+            .map(|id| Self::message(access_token, id))
+            .collect::<Result<Vec<SourceUpdate>, String>>()?;
+
+        // searches aren't exact to the second, so the dates are checked again
+        let loaded = messages.len();
+        let updates: Vec<SourceUpdate> = messages
+            .into_iter()
+            .filter(|update| {
+                last_checked
+                    .map(|last_checked| last_checked < update.published_date)
+                    .unwrap_or(true)
+            })
+            .collect();
+        log_new_items(&self.name, loaded, updates.len());
+
+        Ok(updates)
+    }
+}
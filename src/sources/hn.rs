@@ -0,0 +1,207 @@
+//! The Hacker News platform for update checking, which searches
+//! stories through the Algolia HN Search API.
+
+use crate::sources::{
+    check_each_source, deserialize_sources, log_new_items, serialize_sources, validate_field,
+    validate_name, CheckForUpdates, CheckOptions, NamedSource, SourceCheck, SourceSettings,
+    SourceUpdate,
+};
+use crate::state::State;
+use crate::util::{access_error, check_status, get};
+use chrono::{DateTime, Local, TimeZone};
+use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+use reqwest::Url;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::borrow::Cow;
+
+/// The Algolia endpoint for searching HN stories, newest first.
+const SEARCH_URL: &str = "https://hn.algolia.com/api/v1/search_by_date";
+
+/// The wrapper type for Hacker News watches and their last checked times
+/// to implement `CheckForUpdates` on.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, JsonSchema)]
+pub struct HnWatches(
+    #[serde(
+        serialize_with = "serialize_sources",
+        deserialize_with = "deserialize_sources"
+    )]
+    #[schemars(with = "Vec<HnWatch>")]
+    pub Vec<(HnWatch, Option<DateTime<Local>>)>,
+);
+
+/// A search for Hacker News stories.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, JsonSchema)]
+pub struct HnWatch {
+    pub name: String,
+    /// The search terms stories must match.
+    pub query: String,
+    /// The fewest points a story needs to be reported.
+    #[serde(default)]
+    pub min_points: u64,
+    /// The settings that every source has.
+    #[serde(flatten)]
+    pub settings: SourceSettings,
+    /// Any fields sitch doesn't know about, kept so they aren't lost.
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl CheckForUpdates for HnWatches {
+    fn check_for_all_updates(&mut self, state: &State, options: &CheckOptions) -> Vec<SourceCheck> {
+        check_each_source("hn", &mut self.0, state, options, |watch, last_checked| {
+            (watch.name.clone(), watch.check_for_updates(last_checked))
+        })
+    }
+
+    fn diagnose_all(&self) -> Vec<(String, Result<(), String>)> {
+        self.0
+            .par_iter()
+            .map(|(watch, _last_checked)| (watch.name.clone(), watch.diagnose()))
+            .collect()
+    }
+
+    fn type_name(&self) -> &'static str {
+        "Hacker News"
+    }
+}
+
+impl NamedSource for HnWatch {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn name_mut(&mut self) -> &mut String {
+        &mut self.name
+    }
+
+    fn location(&self) -> Cow<'_, str> {
+        Cow::Borrowed(&self.query)
+    }
+
+    fn page(&mut self) -> Result<String, String> {
+        Url::parse_with_params("https://hn.algolia.com/", &[("q", &self.query)])
+            .map(|url| url.to_string())
+            .map_err(|err| format!("Couldn't make the search's URL: {}", err))
+    }
+
+    fn validate(&mut self) -> Result<(), String> {
+        validate_name(&mut self.name)?;
+        validate_field("query", &mut self.query)
+    }
+
+    fn settings(&self) -> &SourceSettings {
+        &self.settings
+    }
+
+    fn settings_mut(&mut self) -> &mut SourceSettings {
+        &mut self.settings
+    }
+}
+
+impl HnWatch {
+    /// Searches for the watch's stories, at most `hits` of them, that
+    /// have enough points and were posted after `last_checked`.
+    fn search(
+        &self,
+        last_checked: &Option<DateTime<Local>>,
+        hits: usize,
+    ) -> Result<Vec<SourceUpdate>, String> {
+        // Algolia takes numeric filters as a comma-separated list
+        let mut numeric_filters = format!("points>={}", self.min_points);
+        if let Some(last_checked) = last_checked {
+            numeric_filters += &format!(",created_at_i>{}", last_checked.timestamp());
+        }
+        let url = Url::parse_with_params(
+            SEARCH_URL,
+            &[
+                ("query", self.query.as_str()),
+                ("tags", "story"),
+                ("numericFilters", &numeric_filters),
+                ("hitsPerPage", &hits.to_string()),
+            ],
+        )
+        .map_err(|err| format!("Couldn't build the Hacker News search URL: {}", err))?;
+
+        let response = get(url.as_str()).map_err(|err| access_error(url.as_str(), &err))?;
+        let results: Value = check_status(response)?
+            .json()
+            .map_err(|_err| "Couldn't parse request data as JSON".to_owned())?;
+
+        Ok(results
+            .pointer("/hits")
+            .and_then(|hits| hits.as_array())
+            .ok_or("Couldn't parse received stories as JSON array")?
+            .iter()
+            .filter_map(Self::parse_hit)
+            .collect())
+    }
+
+    /// Makes sure that the search can be run.
+    pub fn diagnose(&self) -> Result<(), String> {
+        self.search(&None, 1).map(|_stories| ())
+    }
+
+    /// Check for stories matching the watch since `last_checked`.
+    ///
+    /// Only stories posted after the last check are searched, so a story
+    /// that reaches `min_points` after that is not reported. At most 50
+    /// stories are loaded per check.
+    pub fn check_for_updates(
+        &self,
+        last_checked: &Option<DateTime<Local>>,
+    ) -> Result<Vec<SourceUpdate>, String> {
+        // the search itself leaves out stories from before the last check
+        let updates = self.search(last_checked, 50)?;
+        log_new_items(&self.name, updates.len(), updates.len());
+
+        Ok(updates)
+    }
+
+    /// Parses a story, skipping it if it has no id or date.
+    ///
+    /// Updates link to the story's discussion on HN, with the link
+    /// the story was submitted with as the description.
+    fn parse_hit(hit: &Value) -> Option<SourceUpdate> {
+        // {
+        //     "hits": [
+        //         {
+        //             "title": "Show HN: ...",
+        //             "url": "https://example.com/",
+        //             "points": 120,
+        //             "objectID": "21617016",
+        //             "created_at_i": 1574800000,
+        //             ...
+        //         },
+        //         ...
+        //     ],
+        //     ...
+        // }
+        let id = hit
+            .pointer("/objectID")
+            .and_then(|id_obj| id_obj.as_str())?;
+        let published_date = hit
+            .pointer("/created_at_i")
+            .and_then(|date_obj| date_obj.as_i64())
+            .and_then(|seconds| Local.timestamp_opt(seconds, 0).single())?;
+
+        Some(SourceUpdate {
+            title: hit
+                .pointer("/title")
+                .and_then(|title_obj| title_obj.as_str())
+                .unwrap_or("<no title>")
+                .to_owned(),
+            link: format!("https://news.ycombinator.com/item?id={}", id),
+            published_date,
+            thumbnail: None,
+            description: hit
+                .pointer("/url")
+                .and_then(|url_obj| url_obj.as_str())
+                .filter(|url| !url.is_empty())
+                .map(|url| url.to_owned()),
+            secondary_link: None,
+            also_in: Vec::new(),
+        })
+    }
+}
@@ -0,0 +1,293 @@
+//! The itch.io platform for update checking, which reports new games
+//! and devlog posts from creators.
+
+use crate::sources::{
+    check_each_source, deserialize_sources, log_new_items, serialize_sources, validate_name,
+    validate_url, CheckForUpdates, CheckOptions, NamedSource, SourceCheck, SourceSettings,
+    SourceUpdate,
+};
+use crate::state::State;
+use crate::util::{access_error, check_status, get, join_url, redact_url};
+use chrono::{DateTime, FixedOffset, Local, NaiveDateTime, TimeZone, Utc};
+use rayon::iter::{IntoParallelIterator, IntoParallelRefIterator, ParallelIterator};
+use reqwest::StatusCode;
+use rss::Channel;
+use schemars::JsonSchema;
+use select::document::Document;
+use select::predicate::{Class, Name, Predicate};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::borrow::Cow;
+use std::io::BufReader;
+
+/// The most games checked per creator, to minimize the number of
+/// requests made.
+const MAX_GAMES: usize = 10;
+
+/// The wrapper type for itch.io creators and their last checked times
+/// to implement `CheckForUpdates` on.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, JsonSchema)]
+pub struct ItchCreators(
+    #[serde(
+        serialize_with = "serialize_sources",
+        deserialize_with = "deserialize_sources"
+    )]
+    #[schemars(with = "Vec<ItchCreator>")]
+    pub Vec<(ItchCreator, Option<DateTime<Local>>)>,
+);
+
+/// An itch.io creator struct.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, JsonSchema)]
+pub struct ItchCreator {
+    pub name: String,
+    /// The creator's page, like "https://creator.itch.io".
+    pub url: String,
+    /// The settings that every source has.
+    #[serde(flatten)]
+    pub settings: SourceSettings,
+    /// Any fields sitch doesn't know about, kept so they aren't lost.
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// A game listed on a creator's page.
+struct Game {
+    title: String,
+    url: String,
+}
+
+impl CheckForUpdates for ItchCreators {
+    fn check_for_all_updates(&mut self, state: &State, options: &CheckOptions) -> Vec<SourceCheck> {
+        check_each_source(
+            "itch",
+            &mut self.0,
+            state,
+            options,
+            |creator, last_checked| {
+                (
+                    creator.name.clone(),
+                    creator.check_for_updates(last_checked),
+                )
+            },
+        )
+    }
+
+    fn diagnose_all(&self) -> Vec<(String, Result<(), String>)> {
+        self.0
+            .par_iter()
+            .map(|(creator, _last_checked)| (creator.name.clone(), creator.diagnose()))
+            .collect()
+    }
+
+    fn type_name(&self) -> &'static str {
+        "itch.io"
+    }
+}
+
+impl NamedSource for ItchCreator {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn name_mut(&mut self) -> &mut String {
+        &mut self.name
+    }
+
+    fn location(&self) -> Cow<'_, str> {
+        Cow::Borrowed(&self.url)
+    }
+
+    fn validate(&mut self) -> Result<(), String> {
+        validate_name(&mut self.name)?;
+        validate_url("URL", &mut self.url)
+    }
+
+    fn settings(&self) -> &SourceSettings {
+        &self.settings
+    }
+
+    fn settings_mut(&mut self) -> &mut SourceSettings {
+        &mut self.settings
+    }
+}
+
+impl ItchCreator {
+    /// Loads a page as an HTML document.
+    fn fetch_page(url: &str) -> Result<Document, String> {
+        let response = get(url).map_err(|err| access_error(url, &err))?;
+        let page = check_status(response)?
+            .text()
+            .map_err(|_err| format!("No html found at {}", redact_url(url)))?;
+
+        Ok(Document::from(page.as_str()))
+    }
+
+    /// Finds the first `MAX_GAMES` games in the creator page's grid.
+    fn find_games(&self, creator_document: &Document) -> Vec<Game> {
+        // <div class="game_cell" data-game_id="123">
+        //     <a class="thumb_link game_link" href="https://creator.itch.io/game">...</a>
+        //     <div class="game_cell_data">
+        //         <div class="game_title"><a class="title game_link" href="...">Game</a></div>
+        //     </div>
+        // </div>
+        creator_document
+            .find(Class("game_cell"))
+            .filter_map(|cell| {
+                let link = cell.find(Class("game_link")).next()?;
+                let title = cell
+                    .find(Class("game_title"))
+                    .next()
+                    .map(|title_el| title_el.text().trim().to_owned())
+                    .unwrap_or("<no game title>".to_owned());
+
+                Some(Game {
+                    title,
+                    url: join_url(&self.url, link.attr("href")?),
+                })
+            })
+            .take(MAX_GAMES)
+            .collect()
+    }
+
+    /// Makes sure that the creator page loads and has games where expected.
+    pub fn diagnose(&self) -> Result<(), String> {
+        let creator_document = Self::fetch_page(&self.url)?;
+        if self.find_games(&creator_document).len() > 0 {
+            Ok(())
+        } else {
+            Err("Couldn't find any games on the creator page".to_owned())
+        }
+    }
+
+    /// Check for a creator's new games and devlog posts since `last_checked`.
+    ///
+    /// Like Bandcamp, itch.io has no feed for a creator, so their page is
+    /// scraped for their games. Each game's page then says when it was
+    /// published and updated, and its devlog has an RSS feed.
+    pub fn check_for_updates(
+        &self,
+        last_checked: &Option<DateTime<Local>>,
+    ) -> Result<Vec<SourceUpdate>, String> {
+        let creator_document = Self::fetch_page(&self.url)?;
+        let is_new = |date: &DateTime<Local>| {
+            last_checked
+                .map(|last_checked| last_checked < *date)
+                .unwrap_or(true)
+        };
+
+        // check each game in parallel, failing if any game fails
+        let updates = self
+            .find_games(&creator_document)
+            .into_par_iter()
+            .map(|game| {
+                let game_document = Self::fetch_page(&game.url)?;
+                let published = Self::info_date(&game_document, "Published");
+                let updated = Self::info_date(&game_document, "Updated");
+                let posts = Self::devlog_posts(&game)?;
+                // the game itself counts as an item too
+                let loaded = posts.len() + 1;
+                let mut updates = posts
+                    .into_iter()
+                    .filter(|update| is_new(&update.published_date))
+                    .collect::<Vec<SourceUpdate>>();
+
+                if let Some(published) = published.filter(is_new) {
+                    updates.push(SourceUpdate {
+                        title: format!("New game: {}", game.title),
+                        link: game.url.clone(),
+                        published_date: published,
+                        thumbnail: Self::cover_image(&game_document),
+                        description: None,
+                        secondary_link: None,
+                        also_in: Vec::new(),
+                    });
+                } else if let Some(updated) = updated.filter(is_new) {
+                    // devlog posts already say what changed in an update
+                    if updates.is_empty() {
+                        updates.push(SourceUpdate {
+                            title: format!("{} was updated", game.title),
+                            link: game.url.clone(),
+                            published_date: updated,
+                            thumbnail: Self::cover_image(&game_document),
+                            description: None,
+                            secondary_link: None,
+                            also_in: Vec::new(),
+                        });
+                    }
+                }
+
+                Ok((loaded, updates))
+            })
+            .collect::<Result<Vec<(usize, Vec<SourceUpdate>)>, String>>()?;
+
+        let loaded = updates.iter().map(|(loaded, _updates)| loaded).sum();
+        let updates: Vec<SourceUpdate> = updates
+            .into_iter()
+            .flat_map(|(_loaded, updates)| updates)
+            .collect();
+        log_new_items(&self.name, loaded, updates.len());
+
+        Ok(updates)
+    }
+
+    /// Loads a game's devlog posts, if it has a devlog.
+    fn devlog_posts(game: &Game) -> Result<Vec<SourceUpdate>, String> {
+        let feed_url = format!("{}/devlog.rss", game.url.trim_end_matches('/'));
+        let response = get(&feed_url).map_err(|err| access_error(&feed_url, &err))?;
+        // games without a devlog have no feed
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(vec![]);
+        }
+        let channel = Channel::read_from(BufReader::new(check_status(response)?))
+            .map_err(|err| format!("Couldn't load the devlog of {}: {}", game.title, err))?;
+
+        Ok(channel
+            .items()
+            .iter()
+            .filter_map(|item| {
+                let published_date = DateTime::<FixedOffset>::parse_from_rfc2822(item.pub_date()?)
+                    .ok()?
+                    .with_timezone(&Local);
+
+                Some(SourceUpdate {
+                    title: format!("{}: {}", game.title, item.title().unwrap_or("<unnamed>")),
+                    link: item.link().unwrap_or(&game.url).to_owned(),
+                    published_date,
+                    thumbnail: None,
+                    description: None,
+                    secondary_link: None,
+                    also_in: Vec::new(),
+                })
+            })
+            .collect())
+    }
+
+    /// Reads a date from the "More information" table on a game page.
+    fn info_date(game_document: &Document, row_name: &str) -> Option<DateTime<Local>> {
+        // <div class="game_info_panel_widget"><table><tbody>
+        //     <tr><td>Published</td><td><abbr title="15 October 2026 @ 12:00 UTC">...</abbr></td></tr>
+        // </tbody></table></div>
+        game_document
+            .find(Class("game_info_panel_widget").descendant(Name("tr")))
+            .find(|row| {
+                row.find(Name("td"))
+                    .next()
+                    .map(|cell| cell.text().trim() == row_name)
+                    .unwrap_or(false)
+            })
+            .and_then(|row| row.find(Name("abbr")).next())
+            .and_then(|abbr| abbr.attr("title"))
+            .and_then(|date| NaiveDateTime::parse_from_str(date, "%d %B %Y @ %H:%M UTC").ok())
+            .map(|date| Utc.from_utc_datetime(&date).with_timezone(&Local))
+    }
+
+    /// Finds a game's cover image in its Open Graph tags.
+    fn cover_image(game_document: &Document) -> Option<String> {
+        // <meta property="og:image" content="https://img.itch.zone/...">
+        game_document
+            .find(Name("meta"))
+            .find(|meta| meta.attr("property") == Some("og:image"))
+            .and_then(|meta| meta.attr("content"))
+            .map(|content| content.to_owned())
+    }
+}
@@ -1,54 +1,61 @@
 //! The Manga platform for update checking.
 
-use crate::sources::{CheckForUpdates, SourceUpdate};
-use crate::util::readline;
+use crate::network::Api;
+use crate::sources::{
+    check_each_source, deserialize_sources, log_new_items, serialize_sources, validate_field,
+    validate_name, CheckForUpdates, CheckOptions, NamedSource, SourceCheck, SourceSettings,
+    SourceUpdate,
+};
+use crate::state::State;
+use crate::style::{Role, Stylize};
+use crate::util::{access_error, check_status, get, readline, require_interactive, ReadlineResult};
 use chrono::{DateTime, Local, TimeZone};
-use colored::Colorize;
-use rayon::iter::{IntoParallelRefMutIterator, ParallelIterator};
+use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
+use serde_json::{Map, Value};
+use std::borrow::Cow;
 
 /// The wrapper type for manga and their last checked times
 /// to implement `CheckForUpdates` on.
-#[derive(Debug, Serialize, Deserialize, Clone, Default)]
-pub struct MangaList(pub Vec<(Manga, Option<DateTime<Local>>)>);
+#[derive(Debug, Serialize, Deserialize, Clone, Default, JsonSchema)]
+pub struct MangaList(
+    #[serde(
+        serialize_with = "serialize_sources",
+        deserialize_with = "deserialize_sources"
+    )]
+    #[schemars(with = "Vec<Manga>")]
+    pub Vec<(Manga, Option<DateTime<Local>>)>,
+);
 
 // A manga source struct.
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, Default, JsonSchema)]
 pub struct Manga {
     pub name: String,
     pub id: String,
+    /// The settings that every source has.
+    #[serde(flatten)]
+    pub settings: SourceSettings,
+    /// Any fields sitch doesn't know about, kept so they aren't lost.
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 impl CheckForUpdates for MangaList {
-    fn check_for_all_updates(
-        &mut self,
-        sitch_last_checked: &Option<DateTime<Local>>,
-    ) -> Vec<(String, Result<Vec<SourceUpdate>, String>)> {
+    fn check_for_all_updates(&mut self, state: &State, options: &CheckOptions) -> Vec<SourceCheck> {
+        check_each_source(
+            "manga",
+            &mut self.0,
+            state,
+            options,
+            |manga, last_checked| (manga.name.clone(), manga.check_for_updates(last_checked)),
+        )
+    }
+
+    fn diagnose_all(&self) -> Vec<(String, Result<(), String>)> {
         self.0
-            .par_iter_mut()
-            .map(|(manga, last_checked)| {
-                // use the earliest `last_checked` time provided either by sitch generally
-                // or by this source to handle whe the user overrides the `last_checked` time
-                let true_last_checked = if sitch_last_checked.is_some() && last_checked.is_some() {
-                    Some(std::cmp::min(
-                        sitch_last_checked.unwrap(),
-                        last_checked.unwrap(),
-                    ))
-                } else {
-                    last_checked.or(*sitch_last_checked)
-                };
-                let update = manga.check_for_updates(&true_last_checked);
-                // update last_checked if an update occurred
-                if update.as_ref().map(|updates| updates.len()).unwrap_or(0) > 0 {
-                    *last_checked = Some(Local::now());
-                } else if last_checked.is_none() {
-                    // if this source hasn't been checked yet, but no update was
-                    // found, set it to the "global" `last_checked` time
-                    *last_checked = sitch_last_checked.clone();
-                }
-                (manga.name.clone(), update)
-            })
+            .par_iter()
+            .map(|(manga, _last_checked)| (manga.name.clone(), manga.diagnose()))
             .collect()
     }
 
@@ -57,15 +64,100 @@ impl CheckForUpdates for MangaList {
     }
 }
 
+impl NamedSource for Manga {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn name_mut(&mut self) -> &mut String {
+        &mut self.name
+    }
+
+    fn location(&self) -> Cow<'_, str> {
+        Cow::Borrowed(&self.id)
+    }
+
+    fn page(&mut self) -> Result<String, String> {
+        // Manga Eden's pages are named by an alias that only its API knows
+        let query = Api::MangaEden.url(&format!("manga/{}/", self.id));
+        let data: Value = get(&query)
+            .map_err(|err| access_error(&query, &err))
+            .and_then(check_status)?
+            .json()
+            .map_err(|_err| format!("No manga found with id {}", self.id))?;
+        let alias = data
+            .pointer("/alias")
+            .and_then(Value::as_str)
+            .ok_or(format!(
+                "Couldn't find the page of the manga with id {}",
+                self.id
+            ))?;
+
+        Ok(format!("https://www.mangaeden.com/en/en-manga/{}/", alias))
+    }
+
+    fn validate(&mut self) -> Result<(), String> {
+        validate_name(&mut self.name)?;
+        validate_field("id", &mut self.id)
+    }
+
+    fn settings(&self) -> &SourceSettings {
+        &self.settings
+    }
+
+    fn settings_mut(&mut self) -> &mut SourceSettings {
+        &mut self.settings
+    }
+}
+
 impl Manga {
+    /// Makes a manga from its id, checking that it has chapters and naming
+    /// it after its title if no name is given.
+    pub fn from_id(id: &str, name: Option<String>) -> Result<Self, String> {
+        let id = id.trim();
+        let query = Api::MangaEden.url(&format!("manga/{}/", id));
+        let data: Value = get(&query)
+            .map_err(|err| access_error(&query, &err))
+            .and_then(check_status)?
+            .json()
+            .map_err(|_err| format!("No manga found with id {}", id))?;
+        if data.pointer("/chapters").is_none() {
+            return Err(format!("No chapters found for the manga with id {}", id));
+        }
+
+        Ok(Manga {
+            name: name
+                .or_else(|| Some(data.pointer("/title")?.as_str()?.trim().to_owned()))
+                .unwrap_or(id.to_owned()),
+            id: id.to_owned(),
+            ..Default::default()
+        })
+    }
+
+    /// Makes sure that a manga with chapters exists with this manga's id.
+    pub fn diagnose(&self) -> Result<(), String> {
+        let query = Api::MangaEden.url(&format!("manga/{}/", self.id));
+        let data: Value = get(&query)
+            .map_err(|err| access_error(&query, &err))
+            .and_then(check_status)?
+            .json()
+            .map_err(|_err| format!("No manga found with id {}", self.id))?;
+
+        data.pointer("/chapters").map(|_chapters| ()).ok_or(format!(
+            "No chapters found for the manga with id {}",
+            self.id
+        ))
+    }
+
     pub fn check_for_updates(
         &self,
         last_checked: &Option<DateTime<Local>>,
     ) -> Result<Vec<SourceUpdate>, String> {
         // retrieve the API search data as JSON or return an error
-        let query = format!("https://www.mangaeden.com/api/manga/{}/", self.id);
-        let data: Value = reqwest::get(&query)
-            .map_err(|_err| format!("Couldn't access {}", query))?
+        let query = Api::MangaEden.url(&format!("manga/{}/", self.id));
+        let data: Value = get(&query)
+            .map_err(|err| access_error(&query, &err))
+            .and_then(check_status)?
             .json()
             .map_err(|_err| "Couldn't parse request data as JSON".to_owned())?;
 
@@ -83,7 +175,7 @@ impl Manga {
         //     "A Spiritually Transmitted Cold", - The chapter title
         //     "5bfe41ce719a167a5c3e2c98"        - The id (unused)
         // ],
-        Ok(chapters
+        let updates: Vec<SourceUpdate> = chapters
             .iter()
             .filter_map(|chapter_obj| {
                 let chapter = chapter_obj.as_array()?;
@@ -109,66 +201,136 @@ impl Manga {
                     title,
                     link,
                     published_date,
+                    thumbnail: None,
+                    description: None,
+                    secondary_link: None,
+                    also_in: Vec::new(),
                 })
             })
-            .collect())
+            .collect();
+        log_new_items(&self.name, chapters.len(), updates.len());
+
+        Ok(updates)
+    }
+
+    /// Loads the title, alias, and id of every manga that MangaEden has.
+    ///
+    /// MangaEden has no search endpoint, so searching has to be done
+    /// over the whole catalog.
+    fn fetch_catalog() -> Result<Vec<(String, String, String)>, String> {
+        let query = Api::MangaEden.url("list/0/");
+        let data: Value = get(&query)
+            .map_err(|err| access_error(&query, &err))
+            .and_then(check_status)?
+            .json()
+            .map_err(|_err| "Couldn't parse request data as JSON".to_owned())?;
+
+        data.pointer("/manga")
+            .and_then(|manga_obj| manga_obj.as_array())
+            .ok_or("Couldn't parse received manga as JSON array".to_owned())?
+            .iter()
+            .map(|manga| {
+                let id = manga
+                    .pointer("/i")
+                    .and_then(|id_obj| id_obj.as_str())
+                    .ok_or("No id found in search result".to_owned())?
+                    .to_owned();
+                let title = manga
+                    .pointer("/t")
+                    .and_then(|title_obj| title_obj.as_str())
+                    .ok_or("No title found for search result".to_owned())?
+                    .to_owned();
+                // the alias is an alternate, URL-friendly title
+                let alias = manga
+                    .pointer("/a")
+                    .and_then(|alias_obj| alias_obj.as_str())
+                    .unwrap_or("")
+                    .to_owned();
+
+                Ok((title, alias, id))
+            })
+            .collect()
+    }
+
+    /// Ranks how well a manga matches a lowercased search term, where
+    /// lower is better: exact matches come first, then names starting
+    /// with the term, then names merely containing it. A match on the
+    /// alias ranks just below the same kind of match on the title.
+    /// Returns `None` if neither the title nor the alias contain the term.
+    fn search_rank(search_term: &str, title: &str, alias: &str) -> Option<u8> {
+        // aliases look like "shingeki-no-kyojin"
+        let names = [title.to_lowercase(), alias.to_lowercase().replace('-', " ")];
+
+        names
+            .iter()
+            .zip(0..)
+            .filter_map(|(name, alias_rank)| {
+                let kind_rank = if name == search_term {
+                    0
+                } else if name.starts_with(search_term) {
+                    1
+                } else if name.contains(search_term) {
+                    2
+                } else {
+                    return None;
+                };
+                Some(2 * kind_rank + alias_rank)
+            })
+            .min()
+    }
+
+    /// Finds the five manga in the catalog (see `fetch_catalog`) that best
+    /// match a search term, ignoring case, as their titles and ids. Ties
+    /// go to the shorter title, which is closer to the term.
+    fn search_catalog(
+        catalog: &[(String, String, String)],
+        search_term: &str,
+    ) -> Vec<(String, String)> {
+        let search_term = search_term.trim().to_lowercase();
+        let mut matches = catalog
+            .iter()
+            .filter_map(|(title, alias, id)| {
+                Self::search_rank(&search_term, title, alias).map(|rank| (rank, title, id))
+            })
+            .collect::<Vec<_>>();
+        matches.sort_by_key(|(rank, title, _id)| (*rank, title.len()));
+
+        matches
+            .into_iter()
+            .take(5)
+            .map(|(_rank, title, id)| (title.clone(), id.clone()))
+            .collect()
     }
 
     /// Search interactively for new manga to add to sitch.
     ///
     /// Reads from stdin to take input and asks the user before any
-    /// sources are added.
-    pub fn interactive_search() -> Result<Self, String> {
+    /// sources are added. Returns `None` if the user declined the
+    /// result or quit.
+    pub fn interactive_search() -> Result<Option<Self>, String> {
+        require_interactive("sitch manga add --name <NAME> --id <ID>")?;
+        // the catalog is large, so only load it once for all searches
+        let catalog = Self::fetch_catalog()?;
         loop {
             // Take a query for input
-            let search_term = readline("Search for an manga by name: ", |search| {
+            let search_term = match readline("Search for an manga by name: ", |search| {
                 if search.len() > 3 {
                     Ok(search)
                 } else {
                     Err("Search term must be longer than 3 characters.".to_owned())
                 }
-            });
-
-            // parse the query's returned data as JSON
-            let query = "https://www.mangaeden.com/api/list/0/";
-            let data: Value = reqwest::get(query)
-                .map_err(|_err| format!("Couldn't access {}", query))?
-                .json()
-                .map_err(|_err| "Couldn't parse request data as JSON".to_owned())?;
-
-            // format the results for the user to pick from
-            let search_results = data
-                .pointer("/manga")
-                .and_then(|manga_obj| manga_obj.as_array())
-                .ok_or("Couldn't parse received manga as JSON array".to_owned())?
-                .iter()
-                .map(|search_result| {
-                    let id = search_result
-                        .pointer("/i")
-                        .and_then(|id_obj| id_obj.as_str())
-                        .ok_or("No id found in search result".to_owned())?
-                        .to_string();
-                    let title = search_result
-                        .pointer("/t")
-                        .and_then(|title_obj| title_obj.as_str())
-                        .ok_or("No title found for search result".to_owned())?
-                        .to_owned();
-
-                    Ok((title, id))
-                })
-                .filter(|opt_result| match opt_result {
-                    Ok((title, _id)) => title.to_lowercase().contains(&search_term),
-                    Err(_err) => true,
-                })
-                .take(5)
-                .collect::<Result<Vec<(String, String)>, String>>()?;
+            })? {
+                ReadlineResult::Value(search_term) => search_term,
+                ReadlineResult::Quit => return Ok(None),
+            };
+
+            let search_results = Self::search_catalog(&catalog, &search_term);
 
             match search_results.len() {
                 // try again if there were no results found
                 0 => println!("No results found, please try again."),
                 1 => {
                     // if only one was found, ask if they want to add it.
-                    // if they don't, exit from sitch.
                     let (title, id) = search_results.into_iter().next().unwrap();
                     println!("Found 1 result: \"{}\" (id = {})", title, id);
                     let should_add =
@@ -176,11 +338,28 @@ impl Manga {
                             "" | "y" | "Y" | "yes" => Ok(true),
                             "n" | "N" | "no" => Ok(false),
                             _ => Err("Please respond with a yes or no.".to_owned()),
-                        });
-                    if should_add {
-                        return Ok(Self { name: title, id });
-                    } else {
-                        std::process::exit(0);
+                        })?;
+                    match should_add {
+                        ReadlineResult::Value(true) => {
+                            return Ok(Some(Self {
+                                name: title,
+                                id,
+                                ..Default::default()
+                            }))
+                        }
+                        // if they don't, let them search again or stop
+                        ReadlineResult::Value(false) => {
+                            let search_again =
+                                readline("Search again? [Y/n] ", |input| match input.as_str() {
+                                    "" | "y" | "Y" | "yes" => Ok(true),
+                                    "n" | "N" | "no" => Ok(false),
+                                    _ => Err("Please respond with a yes or no.".to_owned()),
+                                })?;
+                            if search_again != ReadlineResult::Value(true) {
+                                return Ok(None);
+                            }
+                        }
+                        ReadlineResult::Quit => return Ok(None),
                     }
                 }
                 num_results => {
@@ -190,12 +369,12 @@ impl Manga {
                     for (index, (title, id)) in search_results.iter().enumerate() {
                         println!(
                             "{}: \"{}\" (id = {})",
-                            (index + 1).to_string().yellow(),
-                            title.green(),
+                            (index + 1).style(Role::Index),
+                            title.style(Role::Name),
                             id
                         );
                     }
-                    let index = readline(
+                    let index = match readline(
                         &format!("Pick a result to add [1 to {}]: ", num_results),
                         |picked| match picked.parse::<usize>() {
                             Ok(index) if (1 <= index && index <= num_results) => Ok(index - 1),
@@ -204,11 +383,86 @@ impl Manga {
                             }
                             Err(_err) => Err("The value wasn't an integer.".to_owned()),
                         },
-                    );
+                    )? {
+                        ReadlineResult::Value(index) => index,
+                        ReadlineResult::Quit => return Ok(None),
+                    };
                     let (name, id) = search_results.into_iter().nth(index).unwrap();
-                    return Ok(Self { name, id });
+                    return Ok(Some(Self {
+                        name,
+                        id,
+                        ..Default::default()
+                    }));
                 }
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn catalog() -> Vec<(String, String, String)> {
+        [
+            ("One Piece Party", "one-piece-party", "1"),
+            ("Boruto", "boruto", "2"),
+            ("One Piece", "one-piece", "3"),
+            ("Attack on Titan", "shingeki-no-kyojin", "4"),
+            (
+                "Shingeki no Kyojin: Before the Fall",
+                "before-the-fall",
+                "5",
+            ),
+            ("The One Piece Chronicles", "one-piece-chronicles", "6"),
+            ("Naruto", "naruto", "7"),
+        ]
+        .iter()
+        .map(|(title, alias, id)| (title.to_string(), alias.to_string(), id.to_string()))
+        .collect()
+    }
+
+    fn titles(results: &[(String, String)]) -> Vec<&str> {
+        results.iter().map(|(title, _id)| title.as_str()).collect()
+    }
+
+    #[test]
+    fn searches_ignore_case() {
+        let results = Manga::search_catalog(&catalog(), "  NARUTO ");
+        assert_eq!(results, vec![("Naruto".to_owned(), "7".to_owned())]);
+
+        assert_eq!(Manga::search_rank("naruto", "NaRuTo", ""), Some(0));
+        assert_eq!(
+            Manga::search_rank("kyojin", "", "Shingeki-No-Kyojin"),
+            Some(5)
+        );
+    }
+
+    #[test]
+    fn exact_matches_come_before_prefixes_before_substrings() {
+        let results = Manga::search_catalog(&catalog(), "one piece");
+        assert_eq!(
+            titles(&results),
+            vec!["One Piece", "One Piece Party", "The One Piece Chronicles"]
+        );
+
+        assert_eq!(Manga::search_rank("piece", "Piece", ""), Some(0));
+        assert_eq!(Manga::search_rank("piece", "Piece of Cake", ""), Some(2));
+        assert_eq!(Manga::search_rank("piece", "One Piece", ""), Some(4));
+        assert_eq!(Manga::search_rank("piece", "Boruto", "boruto"), None);
+    }
+
+    #[test]
+    fn alias_matches_come_after_title_matches_of_the_same_kind() {
+        // both start with the term, one in its title and one in its alias
+        let results = Manga::search_catalog(&catalog(), "shingeki");
+        assert_eq!(
+            titles(&results),
+            vec!["Shingeki no Kyojin: Before the Fall", "Attack on Titan"]
+        );
+
+        let title_match = Manga::search_rank("boruto", "Boruto", "");
+        let alias_match = Manga::search_rank("boruto", "Next Generations", "boruto");
+        assert!(title_match < alias_match);
+    }
+}
@@ -0,0 +1,357 @@
+//! The Mastodon (and other Fediverse servers with Mastodon's API)
+//! platform for update checking.
+
+use crate::sources::{
+    check_each_source, deserialize_sources, log_new_items, serialize_sources, validate_field,
+    validate_name, CheckForUpdates, CheckOptions, NamedSource, SourceCheck, SourceSettings,
+    SourceUpdate,
+};
+use crate::state::State;
+use crate::util::{access_error, check_status, get};
+use chrono::{DateTime, FixedOffset, Local};
+use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+use reqwest::Url;
+use schemars::JsonSchema;
+use select::document::Document;
+use select::predicate::Name;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::borrow::Cow;
+
+/// The longest a post's title can be before it is cut off.
+const MAX_TITLE_LENGTH: usize = 80;
+
+/// The wrapper type for Mastodon accounts and their last checked times
+/// to implement `CheckForUpdates` on.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, JsonSchema)]
+pub struct MastodonAccounts(
+    #[serde(
+        serialize_with = "serialize_sources",
+        deserialize_with = "deserialize_sources"
+    )]
+    #[schemars(with = "Vec<MastodonAccount>")]
+    pub Vec<(MastodonAccount, Option<DateTime<Local>>)>,
+);
+
+/// A Mastodon account struct.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, JsonSchema)]
+pub struct MastodonAccount {
+    pub name: String,
+    /// The server the account is on, like "mastodon.social".
+    pub instance: String,
+    /// The account's username, without any "@".
+    pub username: String,
+    /// Whether to leave out posts that the account boosted.
+    #[serde(default)]
+    pub exclude_boosts: bool,
+    /// The id of the account on its server, kept after looking it up.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub account_id: Option<String>,
+    /// The settings that every source has.
+    #[serde(flatten)]
+    pub settings: SourceSettings,
+    /// Any fields sitch doesn't know about, kept so they aren't lost.
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl CheckForUpdates for MastodonAccounts {
+    fn check_for_all_updates(&mut self, state: &State, options: &CheckOptions) -> Vec<SourceCheck> {
+        check_each_source(
+            "mastodon",
+            &mut self.0,
+            state,
+            options,
+            |account, last_checked| {
+                (
+                    account.name.clone(),
+                    account.check_for_updates(last_checked),
+                )
+            },
+        )
+    }
+
+    fn diagnose_all(&self) -> Vec<(String, Result<(), String>)> {
+        self.0
+            .par_iter()
+            .map(|(account, _last_checked)| (account.name.clone(), account.diagnose()))
+            .collect()
+    }
+
+    fn type_name(&self) -> &'static str {
+        "Mastodon"
+    }
+}
+
+impl NamedSource for MastodonAccount {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn name_mut(&mut self) -> &mut String {
+        &mut self.name
+    }
+
+    fn location(&self) -> Cow<'_, str> {
+        Cow::Owned(format!("@{}@{}", self.username, self.instance))
+    }
+
+    fn page(&mut self) -> Result<String, String> {
+        Ok(self.profile_url())
+    }
+
+    fn validate(&mut self) -> Result<(), String> {
+        validate_name(&mut self.name)?;
+        validate_field("instance", &mut self.instance)?;
+        validate_field("username", &mut self.username)
+    }
+
+    fn settings(&self) -> &SourceSettings {
+        &self.settings
+    }
+
+    fn settings_mut(&mut self) -> &mut SourceSettings {
+        &mut self.settings
+    }
+}
+
+impl MastodonAccount {
+    /// Splits an account given as a profile URL ("https://instance/@user")
+    /// or a handle ("@user@instance") into its instance and username.
+    pub fn parse_account(account: &str) -> Result<(String, String), String> {
+        let account = account.trim();
+        let invalid = || {
+            format!(
+                "\"{}\" isn't a Mastodon account. Use a profile URL like \
+                 https://mastodon.social/@user or a handle like @user@mastodon.social.",
+                account
+            )
+        };
+
+        if let Ok(url) = Url::parse(account) {
+            let instance = url.host_str().ok_or_else(invalid)?.to_owned();
+            let username = url
+                .path_segments()
+                .and_then(|mut segments| segments.next())
+                .filter(|segment| segment.starts_with('@') && segment.len() > 1)
+                .ok_or_else(invalid)?[1..]
+                .to_owned();
+            Ok((instance, username))
+        } else {
+            let mut parts = account.trim_start_matches('@').splitn(2, '@');
+            match (parts.next(), parts.next()) {
+                (Some(username), Some(instance)) if username.len() > 0 && instance.len() > 0 => {
+                    Ok((instance.to_owned(), username.to_owned()))
+                }
+                _ => Err(invalid()),
+            }
+        }
+    }
+
+    /// The URL of the account's profile page.
+    pub fn profile_url(&self) -> String {
+        format!("https://{}/@{}", self.instance, self.username)
+    }
+
+    /// Fetches JSON from the account's server, failing on unsuccessful responses.
+    fn get_json(&self, url: &Url) -> Result<Value, String> {
+        let response = get(url.as_str()).map_err(|err| access_error(url.as_str(), &err))?;
+        check_status(response)?
+            .json()
+            .map_err(|_err| "Couldn't parse request data as JSON".to_owned())
+    }
+
+    /// Looks up the account's id on its server.
+    fn lookup_id(&self) -> Result<String, String> {
+        let url = Url::parse_with_params(
+            &format!("https://{}/api/v1/accounts/lookup", self.instance),
+            &[("acct", &self.username)],
+        )
+        .map_err(|err| format!("Couldn't build the Mastodon API URL: {}", err))?;
+        let account = self.get_json(&url).map_err(|err| {
+            format!(
+                "Couldn't find @{}@{}: {}",
+                self.username, self.instance, err
+            )
+        })?;
+
+        account
+            .pointer("/id")
+            .and_then(|id_obj| id_obj.as_str())
+            .map(|id| id.to_owned())
+            .ok_or(format!(
+                "No account found for @{}@{}",
+                self.username, self.instance
+            ))
+    }
+
+    /// Makes sure that the account exists on its server.
+    pub fn diagnose(&self) -> Result<(), String> {
+        self.lookup_id().map(|_id| ())
+    }
+
+    /// Check for the account's posts since `last_checked`, leaving out
+    /// replies and, if asked to, boosts.
+    ///
+    /// Only the latest 40 posts are loaded, which is the most that
+    /// Mastodon gives at once.
+    pub fn check_for_updates(
+        &mut self,
+        last_checked: &Option<DateTime<Local>>,
+    ) -> Result<Vec<SourceUpdate>, String> {
+        let account_id = match &self.account_id {
+            Some(account_id) => account_id.clone(),
+            None => {
+                let account_id = self.lookup_id()?;
+                self.account_id = Some(account_id.clone());
+                account_id
+            }
+        };
+
+        let url = Url::parse_with_params(
+            &format!(
+                "https://{}/api/v1/accounts/{}/statuses",
+                self.instance, account_id
+            ),
+            &[
+                ("limit", "40"),
+                ("exclude_replies", "true"),
+                (
+                    "exclude_reblogs",
+                    if self.exclude_boosts { "true" } else { "false" },
+                ),
+            ],
+        )
+        .map_err(|err| format!("Couldn't build the Mastodon API URL: {}", err))?;
+        let statuses = self.get_json(&url)?;
+
+        let statuses = statuses
+            .as_array()
+            .ok_or("Couldn't parse received posts as JSON array")?;
+        let updates: Vec<SourceUpdate> = statuses
+            .iter()
+            .filter_map(Self::parse_status)
+            .filter(|update| {
+                last_checked
+                    .map(|last_checked| last_checked < update.published_date)
+                    .unwrap_or(true)
+            })
+            .collect();
+        log_new_items(&self.name, statuses.len(), updates.len());
+
+        Ok(updates)
+    }
+
+    /// Parses a post, skipping it if it has no date.
+    ///
+    /// The title is the content warning if there is one, and otherwise
+    /// the first line of the post. Boosts are titled with who wrote the
+    /// boosted post, and link to it.
+    fn parse_status(status: &Value) -> Option<SourceUpdate> {
+        // [
+        //     {
+        //         "created_at": "2019-11-26T23:27:31.000Z",
+        //         "url": "https://mastodon.social/@user/103206804533200177",
+        //         "spoiler_text": "",
+        //         "content": "<p>Post text</p>",
+        //         "reblog": null,
+        //         "media_attachments": [{ "preview_url": "https://...", ... }],
+        //         ...
+        //     },
+        //     ...
+        // ]
+        let published_date = status
+            .pointer("/created_at")
+            .and_then(|date_obj| date_obj.as_str())
+            .and_then(|date| DateTime::<FixedOffset>::parse_from_rfc3339(date).ok())
+            .map(|date| date.with_timezone(&Local))?;
+        // boosts hold the post that was boosted
+        let boosted = status
+            .pointer("/reblog")
+            .filter(|reblog| reblog.is_object());
+        let post = boosted.unwrap_or(status);
+
+        let lines = post
+            .pointer("/content")
+            .and_then(|content_obj| content_obj.as_str())
+            .map(Self::text_lines)
+            .unwrap_or_default();
+        let content_warning = post
+            .pointer("/spoiler_text")
+            .and_then(|spoiler_obj| spoiler_obj.as_str())
+            .filter(|spoiler| spoiler.trim().len() > 0)
+            .map(|spoiler| format!("CW: {}", spoiler.trim()));
+        let first_line = content_warning
+            .or_else(|| lines.first().cloned())
+            .unwrap_or("<no text>".to_owned());
+        let title = match boosted.and_then(|post| post.pointer("/account/acct")) {
+            Some(author) => format!(
+                "Boosted @{}: {}",
+                author.as_str().unwrap_or("someone"),
+                first_line
+            ),
+            None => first_line,
+        };
+
+        Some(SourceUpdate {
+            title: Self::shorten(&title),
+            link: post
+                .pointer("/url")
+                .and_then(|url_obj| url_obj.as_str())
+                .unwrap_or("<no link>")
+                .to_owned(),
+            published_date,
+            thumbnail: post
+                .pointer("/media_attachments/0/preview_url")
+                .and_then(|url_obj| url_obj.as_str())
+                .map(|url| url.to_owned()),
+            description: if lines.is_empty() {
+                None
+            } else {
+                Some(lines.join("\n"))
+            },
+            secondary_link: None,
+            also_in: Vec::new(),
+        })
+    }
+
+    /// Strips the HTML from a post, returning its non-empty lines.
+    fn text_lines(content: &str) -> Vec<String> {
+        // posts break lines with <br> inside of <p> paragraphs
+        let content = content
+            .replace("<br>", "\n")
+            .replace("<br/>", "\n")
+            .replace("<br />", "\n");
+        let document = Document::from(content.as_str());
+        let paragraphs = document
+            .find(Name("p"))
+            .map(|paragraph| paragraph.text())
+            .collect::<Vec<String>>();
+        let text = if paragraphs.is_empty() {
+            document
+                .find(Name("body"))
+                .next()
+                .map(|body| body.text())
+                .unwrap_or_default()
+        } else {
+            paragraphs.join("\n")
+        };
+
+        text.lines()
+            .map(|line| line.trim())
+            .filter(|line| line.len() > 0)
+            .map(|line| line.to_owned())
+            .collect()
+    }
+
+    /// Cuts a title off at `MAX_TITLE_LENGTH` characters.
+    fn shorten(title: &str) -> String {
+        if title.chars().count() <= MAX_TITLE_LENGTH {
+            return title.to_owned();
+        }
+
+        let mut short = title.chars().take(MAX_TITLE_LENGTH - 1).collect::<String>();
+        short.push('…');
+        short
+    }
+}
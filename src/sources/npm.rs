@@ -0,0 +1,192 @@
+//! The npm platform for update checking, which reports new
+//! releases of JavaScript packages.
+
+use crate::sources::{
+    check_each_source, deserialize_sources, log_new_items, serialize_sources, validate_field,
+    validate_name, CheckForUpdates, CheckOptions, NamedSource, SourceCheck, SourceSettings,
+    SourceUpdate,
+};
+use crate::state::State;
+use crate::util::{access_error, check_status, get};
+use chrono::{DateTime, FixedOffset, Local};
+use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::borrow::Cow;
+
+/// The wrapper type for npm packages and their last checked times
+/// to implement `CheckForUpdates` on.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, JsonSchema)]
+pub struct NpmPackages(
+    #[serde(
+        serialize_with = "serialize_sources",
+        deserialize_with = "deserialize_sources"
+    )]
+    #[schemars(with = "Vec<NpmPackage>")]
+    pub Vec<(NpmPackage, Option<DateTime<Local>>)>,
+);
+
+/// A package published on npm.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, JsonSchema)]
+pub struct NpmPackage {
+    pub name: String,
+    /// The package's name on npm, like "left-pad" or "@org/pkg".
+    pub package: String,
+    /// Dist-tags, like "next", whose versions are left out.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exclude_tags: Vec<String>,
+    /// The settings that every source has.
+    #[serde(flatten)]
+    pub settings: SourceSettings,
+    /// Any fields sitch doesn't know about, kept so they aren't lost.
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl CheckForUpdates for NpmPackages {
+    fn check_for_all_updates(&mut self, state: &State, options: &CheckOptions) -> Vec<SourceCheck> {
+        check_each_source(
+            "npm",
+            &mut self.0,
+            state,
+            options,
+            |package, last_checked| {
+                (
+                    package.name.clone(),
+                    package.check_for_updates(last_checked),
+                )
+            },
+        )
+    }
+
+    fn diagnose_all(&self) -> Vec<(String, Result<(), String>)> {
+        self.0
+            .par_iter()
+            .map(|(package, _last_checked)| (package.name.clone(), package.diagnose()))
+            .collect()
+    }
+
+    fn type_name(&self) -> &'static str {
+        "npm"
+    }
+}
+
+impl NamedSource for NpmPackage {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn name_mut(&mut self) -> &mut String {
+        &mut self.name
+    }
+
+    fn location(&self) -> Cow<'_, str> {
+        Cow::Borrowed(&self.package)
+    }
+
+    fn page(&mut self) -> Result<String, String> {
+        Ok(self.package_url())
+    }
+
+    fn validate(&mut self) -> Result<(), String> {
+        validate_name(&mut self.name)?;
+        validate_field("package", &mut self.package)
+    }
+
+    fn settings(&self) -> &SourceSettings {
+        &self.settings
+    }
+
+    fn settings_mut(&mut self) -> &mut SourceSettings {
+        &mut self.settings
+    }
+}
+
+impl NpmPackage {
+    /// The URL of the package's page on npm.
+    pub fn package_url(&self) -> String {
+        format!("https://www.npmjs.com/package/{}", self.package)
+    }
+
+    /// Loads the package's metadata from the npm registry.
+    fn metadata(&self) -> Result<Value, String> {
+        // the registry wants the slash in scoped packages encoded
+        let url = format!(
+            "https://registry.npmjs.org/{}",
+            self.package.replace('/', "%2F")
+        );
+        let response = get(&url).map_err(|err| access_error(&url, &err))?;
+        check_status(response)?
+            .json()
+            .map_err(|_err| "Couldn't parse request data as JSON".to_owned())
+    }
+
+    /// Makes sure that the package exists on npm.
+    pub fn diagnose(&self) -> Result<(), String> {
+        self.metadata().map(|_metadata| ())
+    }
+
+    /// Check for versions of the package published since `last_checked`,
+    /// leaving out versions with any of the excluded dist-tags.
+    pub fn check_for_updates(
+        &self,
+        last_checked: &Option<DateTime<Local>>,
+    ) -> Result<Vec<SourceUpdate>, String> {
+        // {
+        //     "dist-tags": { "latest": "1.3.0", "next": "2.0.0-rc.1" },
+        //     "time": {
+        //         "created": "2014-03-14T20:26:43.402Z",
+        //         "modified": "2019-06-01T10:20:30.000Z",
+        //         "1.3.0": "2016-03-22T13:22:55.917Z",
+        //         ...
+        //     },
+        //     ...
+        // }
+        let metadata = self.metadata()?;
+        let excluded_versions = self
+            .exclude_tags
+            .iter()
+            .filter_map(|tag| metadata.pointer(&format!("/dist-tags/{}", tag)))
+            .filter_map(|version| version.as_str())
+            .collect::<Vec<&str>>();
+        let times = metadata
+            .pointer("/time")
+            .and_then(|times| times.as_object())
+            .ok_or("Couldn't find when the package's versions were published")?;
+
+        let updates: Vec<SourceUpdate> = times
+            .iter()
+            .filter(|(version, _time)| {
+                // the map also has when the package was created and modified
+                *version != "created"
+                    && *version != "modified"
+                    && !excluded_versions.contains(&version.as_str())
+            })
+            .filter_map(|(version, time)| {
+                let published_date = time
+                    .as_str()
+                    .and_then(|time| DateTime::<FixedOffset>::parse_from_rfc3339(time).ok())
+                    .map(|date| date.with_timezone(&Local))?;
+
+                Some(SourceUpdate {
+                    title: format!("{} {} released", self.package, version),
+                    link: format!("{}/v/{}", self.package_url(), version),
+                    published_date,
+                    thumbnail: None,
+                    description: None,
+                    secondary_link: None,
+                    also_in: Vec::new(),
+                })
+            })
+            .filter(|update| {
+                last_checked
+                    .map(|last_checked| last_checked < update.published_date)
+                    .unwrap_or(true)
+            })
+            .collect();
+        log_new_items(&self.name, times.len(), updates.len());
+
+        Ok(updates)
+    }
+}
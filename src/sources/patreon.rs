@@ -0,0 +1,305 @@
+//! The Patreon platform for update checking, which reports new posts
+//! by creators, including the ones only their patrons can see.
+
+use crate::sources::{
+    check_each_source, deserialize_sources, log_new_items, serialize_sources, validate_name,
+    validate_url, CheckForUpdates, CheckOptions, NamedSource, SourceCheck, SourceSettings,
+    SourceUpdate,
+};
+use crate::state::State;
+use crate::util::{access_error, check_status, get, redact_url};
+use chrono::{DateTime, FixedOffset, Local};
+use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+use reqwest::Url;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::borrow::Cow;
+
+/// The label put before the titles of posts only patrons can see.
+const PATRONS_LABEL: &str = "[patrons]";
+
+/// Text in the pages Patreon shows instead of the real one when it
+/// suspects a bot.
+const CHALLENGE_MARKERS: &[&str] = &["challenge-platform", "cf-challenge", "Just a moment..."];
+
+/// Where a creator's page mentions the id of their campaign, each
+/// followed by the id.
+const CAMPAIGN_ID_MARKERS: &[&str] = &[
+    "\"campaign\":{\"data\":{\"id\":\"",
+    "/api/campaigns/",
+    "\"campaign_id\":",
+    "campaign_id=",
+];
+
+/// The wrapper type for Patreon creators and their last checked times
+/// to implement `CheckForUpdates` on.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, JsonSchema)]
+pub struct PatreonCreators(
+    #[serde(
+        serialize_with = "serialize_sources",
+        deserialize_with = "deserialize_sources"
+    )]
+    #[schemars(with = "Vec<PatreonCreator>")]
+    pub Vec<(PatreonCreator, Option<DateTime<Local>>)>,
+);
+
+/// A creator on Patreon.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, JsonSchema)]
+pub struct PatreonCreator {
+    pub name: String,
+    /// The creator's page, like "https://www.patreon.com/creator".
+    pub creator_url: String,
+    /// The id of the creator's campaign, found on their page and kept
+    /// so the page doesn't need loading again.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub campaign_id: Option<String>,
+    /// The settings that every source has.
+    #[serde(flatten)]
+    pub settings: SourceSettings,
+    /// Any fields sitch doesn't know about, kept so they aren't lost.
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl CheckForUpdates for PatreonCreators {
+    fn check_for_all_updates(&mut self, state: &State, options: &CheckOptions) -> Vec<SourceCheck> {
+        check_each_source(
+            "patreon",
+            &mut self.0,
+            state,
+            options,
+            |creator, last_checked| {
+                (
+                    creator.name.clone(),
+                    creator.check_for_updates(last_checked),
+                )
+            },
+        )
+    }
+
+    fn diagnose_all(&self) -> Vec<(String, Result<(), String>)> {
+        self.0
+            .par_iter()
+            .map(|(creator, _last_checked)| (creator.name.clone(), creator.diagnose()))
+            .collect()
+    }
+
+    fn type_name(&self) -> &'static str {
+        "Patreon"
+    }
+}
+
+impl NamedSource for PatreonCreator {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn name_mut(&mut self) -> &mut String {
+        &mut self.name
+    }
+
+    fn location(&self) -> Cow<'_, str> {
+        Cow::Borrowed(&self.creator_url)
+    }
+
+    fn validate(&mut self) -> Result<(), String> {
+        validate_name(&mut self.name)?;
+        validate_url("creator URL", &mut self.creator_url)
+    }
+
+    fn settings(&self) -> &SourceSettings {
+        &self.settings
+    }
+
+    fn settings_mut(&mut self) -> &mut SourceSettings {
+        &mut self.settings
+    }
+}
+
+impl PatreonCreator {
+    /// Makes a creator from their page's URL, finding their campaign id
+    /// on it. The name defaults to the last part of the URL.
+    pub fn from_url(creator_url: &str, name: Option<String>) -> Result<Self, String> {
+        let url = Url::parse(creator_url.trim())
+            .map_err(|err| format!("The URL {} is invalid: {}", creator_url, err))?;
+        let mut creator = PatreonCreator {
+            name: name
+                .or_else(|| {
+                    url.path_segments()?
+                        .filter(|segment| !segment.is_empty())
+                        .last()
+                        .map(|segment| segment.to_owned())
+                })
+                .unwrap_or(creator_url.to_owned()),
+            creator_url: url.into(),
+            ..Default::default()
+        };
+        creator.campaign_id = Some(creator.find_campaign_id()?);
+
+        Ok(creator)
+    }
+
+    /// Turns a page that Patreon sent to check for bots into an error
+    /// saying so, instead of a confusing one about what's missing from it.
+    fn check_challenge(text: &str) -> Result<(), String> {
+        if CHALLENGE_MARKERS.iter().any(|marker| text.contains(marker)) {
+            Err("Patreon showed a page checking for bots instead; \
+                 try again later"
+                .to_owned())
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Finds the creator's campaign id on their page.
+    fn find_campaign_id(&self) -> Result<String, String> {
+        let mut response =
+            get(&self.creator_url).map_err(|err| access_error(&self.creator_url, &err))?;
+        let html = response
+            .text()
+            .map_err(|_err| "Couldn't read the creator's page".to_owned())?;
+        // challenge pages are usually sent with an error status
+        Self::check_challenge(&html)?;
+        check_status(response)?;
+
+        CAMPAIGN_ID_MARKERS
+            .iter()
+            .filter_map(|marker| {
+                let start = html.find(marker)? + marker.len();
+                let id = html[start..]
+                    .trim_start_matches(|c: char| c == '"' || c.is_whitespace())
+                    .chars()
+                    .take_while(|c| c.is_ascii_digit())
+                    .collect::<String>();
+                Some(id).filter(|id| !id.is_empty())
+            })
+            .next()
+            .ok_or(format!(
+                "Couldn't find a campaign on {}. Is it a creator's page?",
+                redact_url(&self.creator_url)
+            ))
+    }
+
+    /// Loads the newest posts of the creator's campaign from Patreon's API.
+    fn posts(&self, campaign_id: &str) -> Result<Vec<Value>, String> {
+        let url = Url::parse_with_params(
+            &format!(
+                "https://www.patreon.com/api/campaigns/{}/posts",
+                campaign_id
+            ),
+            &[
+                ("sort", "-published_at"),
+                (
+                    "fields[post]",
+                    "title,url,published_at,is_public,current_user_can_view",
+                ),
+            ],
+        )
+        .map_err(|err| format!("Couldn't build the Patreon API URL: {}", err))?;
+
+        let mut response = get(url.as_str()).map_err(|err| access_error(url.as_str(), &err))?;
+        let text = response
+            .text()
+            .map_err(|_err| "Couldn't read Patreon's response".to_owned())?;
+        Self::check_challenge(&text)?;
+        check_status(response)?;
+        let posts: Value = serde_json::from_str(&text)
+            .map_err(|_err| "Couldn't parse request data as JSON".to_owned())?;
+
+        posts
+            .get("data")
+            .and_then(|data| data.as_array())
+            .cloned()
+            .ok_or("Couldn't find posts in Patreon's response".to_owned())
+    }
+
+    /// Makes sure that the creator's campaign and posts can be found.
+    pub fn diagnose(&self) -> Result<(), String> {
+        let campaign_id = match &self.campaign_id {
+            Some(campaign_id) => campaign_id.clone(),
+            None => self.find_campaign_id()?,
+        };
+        self.posts(&campaign_id).map(|_posts| ())
+    }
+
+    /// Check for posts by the creator published since `last_checked`.
+    ///
+    /// The campaign id is looked up first if it isn't known yet.
+    pub fn check_for_updates(
+        &mut self,
+        last_checked: &Option<DateTime<Local>>,
+    ) -> Result<Vec<SourceUpdate>, String> {
+        let campaign_id = match &self.campaign_id {
+            Some(campaign_id) => campaign_id.clone(),
+            None => {
+                let campaign_id = self.find_campaign_id()?;
+                self.campaign_id = Some(campaign_id.clone());
+                campaign_id
+            }
+        };
+
+        // {
+        //     "data": [{
+        //         "id": "123",
+        //         "type": "post",
+        //         "attributes": {
+        //             "title": "A Post",
+        //             "url": "https://www.patreon.com/posts/a-post-123",
+        //             "published_at": "2026-10-15T12:00:00.000+00:00",
+        //             "is_public": false,
+        //             "current_user_can_view": false
+        //         }
+        //     }, ...]
+        // }
+        let posts = self.posts(&campaign_id)?;
+        let updates: Vec<SourceUpdate> = posts
+            .iter()
+            .filter_map(|post| {
+                let attribute = |name: &str| post.pointer(&format!("/attributes/{}", name));
+                let published_date = attribute("published_at")
+                    .and_then(|date| date.as_str())
+                    .and_then(|date| DateTime::<FixedOffset>::parse_from_rfc3339(date).ok())
+                    .map(|date| date.with_timezone(&Local))?;
+                let title = attribute("title")
+                    .and_then(|title| title.as_str())
+                    .unwrap_or("<untitled>");
+                // posts only patrons can see are listed too, but marked
+                let patrons_only = attribute("is_public")
+                    .or_else(|| attribute("current_user_can_view"))
+                    .and_then(|public| public.as_bool())
+                    .map(|public| !public)
+                    .unwrap_or(false);
+
+                Some(SourceUpdate {
+                    title: if patrons_only {
+                        format!("{} {}", PATRONS_LABEL, title)
+                    } else {
+                        title.to_owned()
+                    },
+                    link: attribute("url")
+                        .and_then(|url| url.as_str())
+                        .map(|url| url.to_owned())
+                        .or_else(|| {
+                            let id = post.get("id")?.as_str()?;
+                            Some(format!("https://www.patreon.com/posts/{}", id))
+                        })
+                        .unwrap_or(self.creator_url.clone()),
+                    published_date,
+                    thumbnail: None,
+                    description: None,
+                    secondary_link: None,
+                    also_in: Vec::new(),
+                })
+            })
+            .filter(|update| {
+                last_checked
+                    .map(|last_checked| last_checked < update.published_date)
+                    .unwrap_or(true)
+            })
+            .collect();
+        log_new_items(&self.name, posts.len(), updates.len());
+
+        Ok(updates)
+    }
+}
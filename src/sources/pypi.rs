@@ -0,0 +1,272 @@
+//! The PyPI platform for update checking, which reports new
+//! releases of Python packages.
+
+use crate::sources::{
+    check_each_source, deserialize_sources, log_new_items, serialize_sources, validate_field,
+    validate_name, CheckForUpdates, CheckOptions, NamedSource, SourceCheck, SourceSettings,
+    SourceUpdate,
+};
+use crate::state::State;
+use crate::util::{access_error, check_status, get};
+use chrono::{DateTime, FixedOffset, Local};
+use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::borrow::Cow;
+
+/// What comes right after the release numbers of pre-release versions,
+/// in any of the spellings PEP 440 allows.
+const PRERELEASE_MARKERS: &[&str] = &["a", "b", "c", "rc", "alpha", "beta", "pre", "preview"];
+
+/// The wrapper type for PyPI packages and their last checked times
+/// to implement `CheckForUpdates` on.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, JsonSchema)]
+pub struct PypiPackages(
+    #[serde(
+        serialize_with = "serialize_sources",
+        deserialize_with = "deserialize_sources"
+    )]
+    #[schemars(with = "Vec<PypiPackage>")]
+    pub Vec<(PypiPackage, Option<DateTime<Local>>)>,
+);
+
+/// A package published on PyPI.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, JsonSchema)]
+pub struct PypiPackage {
+    pub name: String,
+    /// The package's name on PyPI, like "requests".
+    pub package: String,
+    /// Whether to leave out pre-releases and dev releases, like
+    /// "2.0.0rc1" or "1.1.dev3".
+    #[serde(default)]
+    pub exclude_prereleases: bool,
+    /// The settings that every source has.
+    #[serde(flatten)]
+    pub settings: SourceSettings,
+    /// Any fields sitch doesn't know about, kept so they aren't lost.
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl CheckForUpdates for PypiPackages {
+    fn check_for_all_updates(&mut self, state: &State, options: &CheckOptions) -> Vec<SourceCheck> {
+        check_each_source(
+            "pypi",
+            &mut self.0,
+            state,
+            options,
+            |package, last_checked| {
+                (
+                    package.name.clone(),
+                    package.check_for_updates(last_checked),
+                )
+            },
+        )
+    }
+
+    fn diagnose_all(&self) -> Vec<(String, Result<(), String>)> {
+        self.0
+            .par_iter()
+            .map(|(package, _last_checked)| (package.name.clone(), package.diagnose()))
+            .collect()
+    }
+
+    fn type_name(&self) -> &'static str {
+        "PyPI"
+    }
+}
+
+impl NamedSource for PypiPackage {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn name_mut(&mut self) -> &mut String {
+        &mut self.name
+    }
+
+    fn location(&self) -> Cow<'_, str> {
+        Cow::Borrowed(&self.package)
+    }
+
+    fn page(&mut self) -> Result<String, String> {
+        Ok(self.package_url())
+    }
+
+    fn validate(&mut self) -> Result<(), String> {
+        validate_name(&mut self.name)?;
+        validate_field("package", &mut self.package)
+    }
+
+    fn settings(&self) -> &SourceSettings {
+        &self.settings
+    }
+
+    fn settings_mut(&mut self) -> &mut SourceSettings {
+        &mut self.settings
+    }
+}
+
+impl PypiPackage {
+    /// The URL of the package's page on PyPI.
+    pub fn package_url(&self) -> String {
+        format!("https://pypi.org/project/{}/", self.package)
+    }
+
+    /// Loads the package's metadata from PyPI's JSON API.
+    fn metadata(&self) -> Result<Value, String> {
+        let url = format!("https://pypi.org/pypi/{}/json", self.package);
+        let response = get(&url).map_err(|err| access_error(&url, &err))?;
+        check_status(response)?
+            .json()
+            .map_err(|_err| "Couldn't parse request data as JSON".to_owned())
+    }
+
+    /// Whether PEP 440 considers a version a pre-release or dev release,
+    /// like "2.0.0rc1", "1.0-beta.2" or "1.1.post1.dev3".
+    pub fn is_prerelease(version: &str) -> bool {
+        let version = version.trim().to_lowercase();
+        // leave out the local version ("+ubuntu1") and epoch ("1!")
+        let version = version.split('+').next().unwrap_or("");
+        let version = version.rsplit('!').next().unwrap_or("");
+        let version = version.trim_start_matches('v');
+
+        // whatever follows the release numbers, like "rc1" in "2.0.0rc1"
+        let rest = version
+            .trim_start_matches(|c: char| c.is_ascii_digit() || c == '.')
+            .trim_start_matches(|c| c == '.' || c == '-' || c == '_');
+
+        rest.contains("dev")
+            || PRERELEASE_MARKERS.iter().any(|marker| {
+                rest.starts_with(marker)
+                    && !rest[marker.len()..].starts_with(|c: char| c.is_ascii_alphabetic())
+            })
+    }
+
+    /// When a release was uploaded, which is when its first file that
+    /// wasn't yanked was uploaded. Releases with no such files are left
+    /// out.
+    fn upload_time(files: &Value) -> Option<DateTime<Local>> {
+        files
+            .as_array()?
+            .iter()
+            .filter(|file| {
+                !file
+                    .get("yanked")
+                    .and_then(|yanked| yanked.as_bool())
+                    .unwrap_or(false)
+            })
+            .filter_map(|file| {
+                let time = file.get("upload_time_iso_8601")?.as_str()?;
+                DateTime::<FixedOffset>::parse_from_rfc3339(time).ok()
+            })
+            .min()
+            .map(|date| date.with_timezone(&Local))
+    }
+
+    /// Makes sure that the package exists on PyPI.
+    pub fn diagnose(&self) -> Result<(), String> {
+        self.metadata().map(|_metadata| ())
+    }
+
+    /// Check for versions of the package uploaded since `last_checked`,
+    /// leaving out yanked releases, and pre-releases if they're excluded.
+    pub fn check_for_updates(
+        &self,
+        last_checked: &Option<DateTime<Local>>,
+    ) -> Result<Vec<SourceUpdate>, String> {
+        // {
+        //     "info": { "name": "requests", "version": "2.32.0", ... },
+        //     "releases": {
+        //         "2.32.0": [{
+        //             "upload_time_iso_8601": "2024-05-20T15:52:41.123456Z",
+        //             "yanked": false,
+        //             ...
+        //         }, ...],
+        //         ...
+        //     },
+        //     ...
+        // }
+        let metadata = self.metadata()?;
+        let package = metadata
+            .pointer("/info/name")
+            .and_then(|name| name.as_str())
+            .unwrap_or(&self.package);
+        let releases = metadata
+            .pointer("/releases")
+            .and_then(|releases| releases.as_object())
+            .ok_or("Couldn't find the package's releases")?;
+
+        let updates: Vec<SourceUpdate> = releases
+            .iter()
+            .filter(|(version, _files)| !(self.exclude_prereleases && Self::is_prerelease(version)))
+            .filter_map(|(version, files)| {
+                let published_date = Self::upload_time(files)?;
+
+                Some(SourceUpdate {
+                    title: format!("{} {} released", package, version),
+                    link: format!("{}{}/", self.package_url(), version),
+                    published_date,
+                    thumbnail: None,
+                    description: None,
+                    secondary_link: None,
+                    also_in: Vec::new(),
+                })
+            })
+            .filter(|update| {
+                last_checked
+                    .map(|last_checked| last_checked < update.published_date)
+                    .unwrap_or(true)
+            })
+            .collect();
+        log_new_items(&self.name, releases.len(), updates.len());
+
+        Ok(updates)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use serde_json::json;
+
+    #[test]
+    fn prereleases_are_recognized() {
+        for version in &[
+            "2.0.0rc1",
+            "1.0-beta.2",
+            "1.1.post1.dev3",
+            "v3.0a1",
+            "1!2.0b3",
+        ] {
+            assert!(PypiPackage::is_prerelease(version), "{}", version);
+        }
+        for version in &["2.32.0", "1.0.post1", "2.0+rc1", "1.0.final"] {
+            assert!(!PypiPackage::is_prerelease(version), "{}", version);
+        }
+    }
+
+    #[test]
+    fn releases_were_uploaded_with_their_first_file() {
+        let files = json!([
+            {"upload_time_iso_8601": "2024-05-21T00:00:00Z", "yanked": false},
+            {"upload_time_iso_8601": "2024-05-20T00:00:00Z", "yanked": true},
+            {"upload_time_iso_8601": "2024-05-22T00:00:00Z"},
+        ]);
+
+        assert_eq!(
+            PypiPackage::upload_time(&files),
+            Some(Local.timestamp(1_716_249_600, 0))
+        );
+    }
+
+    #[test]
+    fn yanked_releases_are_left_out() {
+        let files = json!([{"upload_time_iso_8601": "2024-05-20T00:00:00Z", "yanked": true}]);
+
+        assert_eq!(PypiPackage::upload_time(&files), None);
+        assert_eq!(PypiPackage::upload_time(&json!([])), None);
+    }
+}
@@ -1,52 +1,106 @@
 //! The RSS feed platform for update checking.
 
-use crate::sources::{CheckForUpdates, SourceUpdate};
+use crate::sources::{
+    check_each_source_remembering, deserialize_sources, log_new_items, serialize_sources,
+    validate_name, validate_url, CheckForUpdates, CheckOptions, NamedSource, SourceCheck,
+    SourceSettings, SourceUpdate,
+};
+use crate::state::{SourceMemory, State};
+use crate::util::{get_following_redirects, redact_url};
 use chrono::{DateTime, FixedOffset, Local};
-use rayon::iter::{IntoParallelRefMutIterator, ParallelIterator};
-use rss::Channel;
+use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+use rss::{Channel, Item};
+use schemars::JsonSchema;
+use select::document::Document;
+use select::predicate::Name;
 use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::borrow::Cow;
+use std::io::BufReader;
 
 /// The wrapper type for RSS feeds and their last checked times
 /// to implement `CheckForUpdates` on.
-#[derive(Debug, Serialize, Deserialize, Clone, Default)]
-pub struct RssSources(pub Vec<(RssSource, Option<DateTime<Local>>)>);
+#[derive(Debug, Serialize, Deserialize, Clone, Default, JsonSchema)]
+pub struct RssSources(
+    #[serde(
+        serialize_with = "serialize_sources",
+        deserialize_with = "deserialize_sources"
+    )]
+    #[schemars(with = "Vec<RssSource>")]
+    pub Vec<(RssSource, Option<DateTime<Local>>)>,
+);
+
+/// Text that Substack and similar newsletters put in feed items that
+/// are cut off for non-subscribers.
+const PAYWALL_MARKERS: &[&str] = &[
+    "class=\"paywall",
+    "Keep reading with a 7-day free trial",
+    "This post is for paid subscribers",
+];
+
+/// The label put before the titles of paywalled newsletter items.
+const PAYWALLED_LABEL: &str = "[paywalled]";
+
+/// The most item GUIDs remembered for each feed, after which the oldest
+/// are forgotten.
+const MAX_SEEN_GUIDS: usize = 500;
+
+/// A browser-like user agent for newsletters that turn away sitch's.
+const BROWSER_USER_AGENT: &str =
+    "Mozilla/5.0 (X11; Linux x86_64; rv:109.0) Gecko/20100101 Firefox/115.0";
 
 /// An RSS feed struct.
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, Default, JsonSchema)]
 pub struct RssSource {
     pub name: String,
     pub feed: String,
+    /// Where the feed has permanently moved to, if it has.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resolved_feed: Option<String>,
+    /// A user agent to use instead of sitch's for picky hosts.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user_agent: Option<String>,
+    /// Whether the feed is a newsletter, whose cut-off items for paid
+    /// subscribers are labeled as paywalled.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub newsletter: bool,
+    /// The page of the site the feed is for, from the feed's link, kept
+    /// once `sitch open` has looked it up.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub site: Option<String>,
+    /// Whether to report items again when they're edited, which some
+    /// feeds show by moving the item's date up. Otherwise, the items seen
+    /// so far are remembered in the state (see `SourceMemory`).
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub report_edits: bool,
+    /// The settings that every source has.
+    #[serde(flatten)]
+    pub settings: SourceSettings,
+    /// Any fields sitch doesn't know about, kept so they aren't lost.
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 impl CheckForUpdates for RssSources {
-    fn check_for_all_updates(
-        &mut self,
-        sitch_last_checked: &Option<DateTime<Local>>,
-    ) -> Vec<(String, Result<Vec<SourceUpdate>, String>)> {
+    fn check_for_all_updates(&mut self, state: &State, options: &CheckOptions) -> Vec<SourceCheck> {
+        check_each_source_remembering(
+            "rss",
+            &mut self.0,
+            state,
+            options,
+            |rss, last_checked, memory| {
+                // everything since a time that was asked for is reported
+                let report_seen = options.since_time;
+                let updates = rss.check_for_updates(last_checked, memory, report_seen);
+                (rss.name.clone(), updates)
+            },
+        )
+    }
+
+    fn diagnose_all(&self) -> Vec<(String, Result<(), String>)> {
         self.0
-            .par_iter_mut()
-            .map(|(rss, last_checked)| {
-                // use the earliest `last_checked` time provided either by sitch generally
-                // or by this source to handle whe the user overrides the `last_checked` time
-                let true_last_checked = if sitch_last_checked.is_some() && last_checked.is_some() {
-                    Some(std::cmp::min(
-                        sitch_last_checked.unwrap(),
-                        last_checked.unwrap(),
-                    ))
-                } else {
-                    last_checked.or(*sitch_last_checked)
-                };
-                let update = rss.check_for_updates(&true_last_checked);
-                // update last_checked if an update occurred
-                if update.as_ref().map(|updates| updates.len()).unwrap_or(0) > 0 {
-                    *last_checked = Some(Local::now());
-                } else if last_checked.is_none() {
-                    // if this source hasn't been checked yet, but no update was
-                    // found, set it to the "global" `last_checked` time
-                    *last_checked = sitch_last_checked.clone();
-                }
-                (rss.name.clone(), update)
-            })
+            .par_iter()
+            .map(|(rss, _last_checked)| (rss.name.clone(), rss.diagnose()))
             .collect()
     }
 
@@ -55,18 +109,191 @@ impl CheckForUpdates for RssSources {
     }
 }
 
+impl NamedSource for RssSource {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn name_mut(&mut self) -> &mut String {
+        &mut self.name
+    }
+
+    fn location(&self) -> Cow<'_, str> {
+        Cow::Borrowed(&self.feed)
+    }
+
+    fn page(&mut self) -> Result<String, String> {
+        if self.site.is_none() {
+            let (channel, _moved_to) = self.fetch_channel()?;
+            let link = channel.link().trim();
+            // feeds without a link are their own page
+            self.site = Some(if link.is_empty() {
+                self.feed_url().to_owned()
+            } else {
+                link.to_owned()
+            });
+        }
+
+        Ok(self.site.clone().unwrap_or_default())
+    }
+
+    fn validate(&mut self) -> Result<(), String> {
+        validate_name(&mut self.name)?;
+        validate_url("feed", &mut self.feed)
+    }
+
+    fn settings(&self) -> &SourceSettings {
+        &self.settings
+    }
+
+    fn settings_mut(&mut self) -> &mut SourceSettings {
+        &mut self.settings
+    }
+}
+
+/// Whether a flag is off, to leave it out of the config.
+fn is_false(flag: &bool) -> bool {
+    !flag
+}
+
 impl RssSource {
+    /// Finds and checks the feed of a newsletter (like a Substack
+    /// publication) from the publication's URL, naming it after the
+    /// feed's title if no name is given.
+    ///
+    /// If the publication turns away sitch's user agent, a browser's
+    /// is tried and kept for the feed.
+    pub fn newsletter(publication_url: &str, name: Option<String>) -> Result<Self, String> {
+        let mut url = publication_url.trim().trim_end_matches('/').to_owned();
+        if !url.contains("://") {
+            url = format!("https://{}", url);
+        }
+        // Substack publications serve their feed at /feed
+        if !url.ends_with("/feed") {
+            url += "/feed";
+        }
+
+        let mut source = RssSource {
+            name: name.clone().unwrap_or_default(),
+            feed: url,
+            newsletter: true,
+            ..Default::default()
+        };
+        let channel = match source.fetch_channel() {
+            Ok((channel, _moved_to)) => channel,
+            Err(err) => {
+                source.user_agent = Some(BROWSER_USER_AGENT.to_owned());
+                match source.fetch_channel() {
+                    Ok((channel, _moved_to)) => channel,
+                    // report the original problem, not the retry's
+                    Err(_retry_err) => {
+                        return Err(format!(
+                            "Couldn't find a newsletter feed at {}: {}",
+                            source.feed, err
+                        ))
+                    }
+                }
+            }
+        };
+        if name.is_none() {
+            source.name = channel.title().trim().to_owned();
+        }
+
+        Ok(source)
+    }
+
+    /// Makes a source from a feed's URL, checking that the feed loads and
+    /// naming it after the feed's title if no name is given.
+    pub fn from_feed(feed: &str, name: Option<String>) -> Result<Self, String> {
+        let mut source = RssSource {
+            name: name.clone().unwrap_or_default(),
+            feed: feed.trim().to_owned(),
+            ..Default::default()
+        };
+        let (channel, _moved_to) = source.fetch_channel()?;
+        if name.is_none() {
+            source.name = channel.title().trim().to_owned();
+        }
+
+        Ok(source)
+    }
+
+    /// Whether a newsletter item is cut off for non-subscribers.
+    fn is_paywalled(item: &Item) -> bool {
+        let content = format!(
+            "{}{}",
+            item.description().unwrap_or(""),
+            item.content().unwrap_or("")
+        );
+        PAYWALL_MARKERS
+            .iter()
+            .any(|marker| content.contains(marker))
+    }
+
+    /// The URL to fetch the feed from, preferring where it has moved to.
+    pub fn feed_url(&self) -> &str {
+        self.resolved_feed.as_ref().unwrap_or(&self.feed)
+    }
+
+    /// Loads and parses the feed, returning it along with where
+    /// it has permanently moved to if it has.
+    fn fetch_channel(&self) -> Result<(Channel, Option<String>), String> {
+        let feed_url = self.feed_url();
+        let (response, moved_to) =
+            get_following_redirects(feed_url, self.user_agent.as_ref().map(String::as_str))?;
+        let channel = Channel::read_from(BufReader::new(response)).map_err(|err| {
+            format!(
+                "Couldn't load RSS feed from {}: {}",
+                redact_url(feed_url),
+                err
+            )
+        })?;
+
+        Ok((channel, moved_to))
+    }
+
+    /// Makes sure that the feed can be loaded and parsed.
+    pub fn diagnose(&self) -> Result<(), String> {
+        self.fetch_channel().map(|_feed| ())
+    }
+
+    /// Checks the feed for items published since it was last checked,
+    /// leaving out the ones `memory` has seen before unless `report_seen`
+    /// is set, and remembering the ones in the feed now.
     pub fn check_for_updates(
-        &self,
+        &mut self,
         last_checked: &Option<DateTime<Local>>,
+        memory: &mut SourceMemory,
+        report_seen: bool,
     ) -> Result<Vec<SourceUpdate>, String> {
         // load the RSS feed items or return an error
-        let channel = Channel::from_url(&self.feed)
-            .map_err(|err| format!("Couldn't load RSS feed from {}: {}", self.feed, err))?;
-        let items = channel.into_items();
+        let (channel, moved_to) = self.fetch_channel()?;
+        // remember where the feed moved to if it moved permanently
+        if moved_to.is_some() {
+            self.resolved_feed = moved_to;
+        }
+
+        Ok(self.updates_in(channel.into_items(), last_checked, memory, report_seen))
+    }
+
+    /// The feed's items that are new since it was last checked, see
+    /// `check_for_updates`.
+    fn updates_in(
+        &self,
+        items: Vec<Item>,
+        last_checked: &Option<DateTime<Local>>,
+        memory: &mut SourceMemory,
+        report_seen: bool,
+    ) -> Vec<SourceUpdate> {
+        let loaded = items.len();
+        let guids = items.iter().map(Self::guid).collect::<Vec<String>>();
+        // items seen before are only new again if edits are reported,
+        // since an edit can move an item's date past the last check
+        let report_seen = report_seen || self.report_edits;
 
-        Ok(items
+        let updates: Vec<SourceUpdate> = items
             .into_iter()
+            .filter(|item| report_seen || !memory.seen_guids.contains(&Self::guid(item)))
             .filter_map(|item| {
                 // parse the feed items and determine which items were published
                 // after the last_checked date if it was provided
@@ -79,11 +306,178 @@ impl RssSource {
                             .unwrap_or(true)
                     })
             })
-            .map(|(item, published_date)| SourceUpdate {
-                title: item.title().unwrap_or("<unnamed>").to_owned(),
-                link: item.link().unwrap_or("<no link>").to_owned(),
-                published_date,
+            .map(|(item, published_date)| {
+                // descriptions are usually HTML, which may include an image
+                let description = item.description().map(Document::from);
+                let title = item.title().unwrap_or("<unnamed>");
+                SourceUpdate {
+                    // the label goes in the title so every output shows it
+                    title: if self.newsletter && Self::is_paywalled(&item) {
+                        format!("{} {}", PAYWALLED_LABEL, title)
+                    } else {
+                        title.to_owned()
+                    },
+                    link: item.link().unwrap_or("<no link>").to_owned(),
+                    published_date,
+                    thumbnail: Self::media_thumbnail(&item).or_else(|| {
+                        description
+                            .as_ref()
+                            .and_then(|document| document.find(Name("img")).next())
+                            .and_then(|img_el| img_el.attr("src"))
+                            .map(|src| src.to_owned())
+                    }),
+                    description: description
+                        .as_ref()
+                        .and_then(|document| document.find(Name("body")).next())
+                        .map(|body_el| body_el.text()),
+                    secondary_link: None,
+                    also_in: Vec::new(),
+                }
+            })
+            .collect();
+        log_new_items(&self.name, loaded, updates.len());
+        Self::remember_guids(&mut memory.seen_guids, guids);
+
+        updates
+    }
+
+    /// The GUID of an item, or its link if it doesn't have one.
+    fn guid(item: &Item) -> String {
+        item.guid()
+            .map(|guid| guid.value())
+            .or_else(|| item.link())
+            .unwrap_or("")
+            .trim()
+            .to_owned()
+    }
+
+    /// Adds the GUIDs of the items in the feed to the ones seen so far,
+    /// forgetting the oldest once there are too many.
+    fn remember_guids(seen_guids: &mut Vec<String>, guids: Vec<String>) {
+        for guid in guids {
+            if !guid.is_empty() && !seen_guids.contains(&guid) {
+                seen_guids.push(guid);
+            }
+        }
+        if seen_guids.len() > MAX_SEEN_GUIDS {
+            let forgotten = seen_guids.len() - MAX_SEEN_GUIDS;
+            seen_guids.drain(..forgotten);
+        }
+    }
+
+    /// Finds the URL of an item's `<media:thumbnail>`, if it has one.
+    fn media_thumbnail(item: &Item) -> Option<String> {
+        item.extensions()
+            .get("media")?
+            .get("thumbnail")?
+            .first()?
+            .attrs()
+            .get("url")
+            .map(|url| url.to_owned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn items(pub_dates: &[(&str, &str)]) -> Vec<Item> {
+        let items = pub_dates
+            .iter()
+            .map(|(guid, pub_date)| {
+                format!(
+                    "<item><title>{0}</title><link>https://example.com/{0}</link>\
+                     <guid>{0}</guid><pubDate>{1}</pubDate></item>",
+                    guid, pub_date
+                )
             })
-            .collect())
+            .collect::<String>();
+        let feed = format!(
+            "<rss version=\"2.0\"><channel><title>Blog</title>\
+             <link>https://example.com</link><description></description>{}\
+             </channel></rss>",
+            items
+        );
+        Channel::read_from(feed.as_bytes()).unwrap().into_items()
+    }
+
+    fn titles(updates: &[SourceUpdate]) -> Vec<&str> {
+        updates.iter().map(|update| update.title.as_str()).collect()
+    }
+
+    #[test]
+    fn new_items_are_reported_and_remembered() {
+        let feed = RssSource::default();
+        let mut memory = SourceMemory::default();
+        let last_checked = Some(Local.ymd(2024, 3, 1).and_hms(0, 0, 0));
+        let items = items(&[
+            ("old", "Thu, 29 Feb 2024 12:00:00 +0000"),
+            ("new", "Sat, 02 Mar 2024 12:00:00 +0000"),
+        ]);
+
+        let updates = feed.updates_in(items, &last_checked, &mut memory, false);
+        assert_eq!(titles(&updates), vec!["new"]);
+        assert_eq!(memory.seen_guids, vec!["old", "new"]);
+    }
+
+    #[test]
+    fn edited_items_arent_reported_again() {
+        let feed = RssSource::default();
+        let mut memory = SourceMemory {
+            seen_guids: vec!["post".to_owned()],
+            ..Default::default()
+        };
+        let last_checked = Some(Local.ymd(2024, 3, 2).and_hms(0, 0, 0));
+        // the edit moved the post's date past the last check
+        let edited = items(&[
+            ("post", "Sun, 03 Mar 2024 12:00:00 +0000"),
+            ("other", "Sun, 03 Mar 2024 13:00:00 +0000"),
+        ]);
+
+        let updates = feed.updates_in(edited, &last_checked, &mut memory, false);
+        assert_eq!(titles(&updates), vec!["other"]);
+    }
+
+    #[test]
+    fn edited_items_are_reported_when_asked_for() {
+        let feed = RssSource {
+            report_edits: true,
+            ..Default::default()
+        };
+        let mut memory = SourceMemory {
+            seen_guids: vec!["post".to_owned()],
+            ..Default::default()
+        };
+        let last_checked = Some(Local.ymd(2024, 3, 2).and_hms(0, 0, 0));
+        let edited = items(&[("post", "Sun, 03 Mar 2024 12:00:00 +0000")]);
+
+        let updates = feed.updates_in(edited, &last_checked, &mut memory, false);
+        assert_eq!(titles(&updates), vec!["post"]);
+    }
+
+    #[test]
+    fn seen_items_are_reported_since_a_given_time() {
+        let feed = RssSource::default();
+        let mut memory = SourceMemory {
+            seen_guids: vec!["post".to_owned()],
+            ..Default::default()
+        };
+        let since = Some(Local.ymd(2024, 2, 1).and_hms(0, 0, 0));
+        let items = items(&[("post", "Fri, 01 Mar 2024 12:00:00 +0000")]);
+
+        let updates = feed.updates_in(items, &since, &mut memory, true);
+        assert_eq!(titles(&updates), vec!["post"]);
+        assert_eq!(memory.seen_guids, vec!["post"]);
+    }
+
+    #[test]
+    fn only_the_newest_guids_are_remembered() {
+        let mut seen_guids = Vec::new();
+        let guids = (0..MAX_SEEN_GUIDS + 2).map(|n| n.to_string()).collect();
+        RssSource::remember_guids(&mut seen_guids, guids);
+
+        assert_eq!(seen_guids.len(), MAX_SEEN_GUIDS);
+        assert_eq!(seen_guids[0], "2");
     }
 }
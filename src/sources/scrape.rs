@@ -0,0 +1,319 @@
+//! The scrape platform for update checking, which reads updates out
+//! of any web page using CSS selectors.
+
+use crate::selector::Selector;
+use crate::sources::{
+    check_each_source, deserialize_sources, log_new_items, serialize_sources, validate_name,
+    validate_url, CheckForUpdates, CheckOptions, NamedSource, SourceCheck, SourceSettings,
+    SourceUpdate,
+};
+use crate::state::State;
+use crate::util::{access_error, check_status, get, parse_date};
+use chrono::{DateTime, Local};
+use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+use reqwest::Url;
+use schemars::JsonSchema;
+use select::document::Document;
+use select::node::Node;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::borrow::Cow;
+
+/// The wrapper type for scraped pages and their last checked times
+/// to implement `CheckForUpdates` on.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, JsonSchema)]
+pub struct ScrapeSources(
+    #[serde(
+        serialize_with = "serialize_sources",
+        deserialize_with = "deserialize_sources"
+    )]
+    #[schemars(with = "Vec<ScrapeSource>")]
+    pub Vec<(ScrapeSource, Option<DateTime<Local>>)>,
+);
+
+/// A web page whose updates are found with CSS selectors.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, JsonSchema)]
+pub struct ScrapeSource {
+    pub name: String,
+    /// The URL of the page to scrape.
+    pub url: String,
+    /// Selects each item on the page.
+    pub item_selector: String,
+    /// Selects the item's title within it, or uses all of the item's
+    /// text if not set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title_selector: Option<String>,
+    /// Selects the element within the item whose `href` is its link, or
+    /// uses the item's first link if not set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub link_selector: Option<String>,
+    /// Selects the item's date within it. A `datetime` attribute (as on
+    /// `<time>`) is preferred over the element's text.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub date_selector: Option<String>,
+    /// How dates are formatted, as for custom sources.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub date_format: Option<String>,
+    /// The links of the items found last time, used to tell which
+    /// undated items are new.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub seen_links: Vec<String>,
+    /// The settings that every source has.
+    #[serde(flatten)]
+    pub settings: SourceSettings,
+    /// Any fields sitch doesn't know about, kept so they aren't lost.
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// An item read from a scraped page.
+pub struct ScrapedItem {
+    pub title: String,
+    pub link: String,
+    /// When the item was posted, if it has a date that could be read.
+    pub date: Option<DateTime<Local>>,
+}
+
+impl CheckForUpdates for ScrapeSources {
+    fn check_for_all_updates(&mut self, state: &State, options: &CheckOptions) -> Vec<SourceCheck> {
+        check_each_source(
+            "scrape",
+            &mut self.0,
+            state,
+            options,
+            |source, last_checked| (source.name.clone(), source.check_for_updates(last_checked)),
+        )
+    }
+
+    fn diagnose_all(&self) -> Vec<(String, Result<(), String>)> {
+        self.0
+            .par_iter()
+            .map(|(source, _last_checked)| (source.name.clone(), source.diagnose()))
+            .collect()
+    }
+
+    fn type_name(&self) -> &'static str {
+        "Scrape"
+    }
+}
+
+impl NamedSource for ScrapeSource {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn name_mut(&mut self) -> &mut String {
+        &mut self.name
+    }
+
+    fn location(&self) -> Cow<'_, str> {
+        Cow::Borrowed(&self.url)
+    }
+
+    fn validate(&mut self) -> Result<(), String> {
+        validate_name(&mut self.name)?;
+        validate_url("URL", &mut self.url)
+    }
+
+    fn settings(&self) -> &SourceSettings {
+        &self.settings
+    }
+
+    fn settings_mut(&mut self) -> &mut SourceSettings {
+        &mut self.settings
+    }
+}
+
+impl ScrapeSource {
+    /// Parses one of the source's selectors, naming it in any error.
+    fn parse_selector(field_name: &str, selector: &str) -> Result<Selector, String> {
+        selector
+            .parse()
+            .map_err(|err| format!("The {} is invalid: {}", field_name, err))
+    }
+
+    /// Parses an optional selector.
+    fn parse_optional_selector(
+        field_name: &str,
+        selector: &Option<String>,
+    ) -> Result<Option<Selector>, String> {
+        match selector {
+            Some(selector) => Self::parse_selector(field_name, selector).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Loads the page and reads the items on it, in page order.
+    pub fn extract(&self) -> Result<Vec<ScrapedItem>, String> {
+        let item_selector = Self::parse_selector("item_selector", &self.item_selector)?;
+        let title_selector = Self::parse_optional_selector("title_selector", &self.title_selector)?;
+        let link_selector = Self::parse_optional_selector("link_selector", &self.link_selector)?;
+        let date_selector = Self::parse_optional_selector("date_selector", &self.date_selector)?;
+        let page_url =
+            Url::parse(&self.url).map_err(|err| format!("The URL is invalid: {}", err))?;
+
+        let response = get(&self.url).map_err(|err| access_error(&self.url, &err))?;
+        let html = check_status(response)?
+            .text()
+            .map_err(|_err| "Couldn't read the page".to_owned())?;
+        let document = Document::from(html.as_str());
+
+        Ok(item_selector
+            .find_in(&document)
+            .iter()
+            .map(|item| {
+                let title = match &title_selector {
+                    Some(selector) => selector.find_under(item).first().map(Self::text_of),
+                    None => None,
+                }
+                .unwrap_or_else(|| Self::text_of(item));
+                let href = match &link_selector {
+                    Some(selector) => selector
+                        .find_under(item)
+                        .first()
+                        .and_then(|node| node.attr("href")),
+                    // use the item itself if it is a link
+                    None => item.attr("href").or_else(|| {
+                        item.descendants()
+                            .filter(|node| node.name() == Some("a"))
+                            .filter_map(|node| node.attr("href"))
+                            .next()
+                    }),
+                };
+                // links are made absolute, falling back to the page itself
+                let link = href
+                    .and_then(|href| page_url.join(href).ok())
+                    .unwrap_or(page_url.clone())
+                    .into();
+                let date = date_selector
+                    .as_ref()
+                    .and_then(|selector| selector.find_under(item).first().cloned())
+                    .and_then(|node| {
+                        let date_format = self.date_format.as_ref().map(String::as_str);
+                        // `datetime` attributes are usually RFC 3339
+                        match node.attr("datetime") {
+                            Some(datetime) => parse_date(datetime, None)
+                                .or_else(|| parse_date(datetime, date_format)),
+                            None => parse_date(&Self::text_of(&node), date_format),
+                        }
+                    });
+
+                ScrapedItem { title, link, date }
+            })
+            .collect())
+    }
+
+    /// An element's text with its whitespace collapsed.
+    fn text_of(node: &Node) -> String {
+        node.text()
+            .split_whitespace()
+            .collect::<Vec<&str>>()
+            .join(" ")
+    }
+
+    /// Makes sure that the page can be loaded and that it has items.
+    pub fn diagnose(&self) -> Result<(), String> {
+        if self.extract()?.is_empty() {
+            Err(format!(
+                "The item_selector \"{}\" didn't match anything",
+                self.item_selector
+            ))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Check for new items on the page.
+    ///
+    /// Items with a date are new if they were posted after `last_checked`.
+    /// Items without one are new if their link wasn't on the page last
+    /// time, so the first check only remembers them.
+    pub fn check_for_updates(
+        &mut self,
+        last_checked: &Option<DateTime<Local>>,
+    ) -> Result<Vec<SourceUpdate>, String> {
+        let items = self.extract()?;
+        let first_check = self.seen_links.is_empty();
+        let now = Local::now();
+
+        let updates: Vec<SourceUpdate> = items
+            .iter()
+            .filter_map(|item| {
+                let published_date = match item.date {
+                    Some(date) if last_checked.map(|last| last < date).unwrap_or(true) => date,
+                    Some(_old_date) => return None,
+                    None if !first_check && !self.seen_links.contains(&item.link) => now,
+                    None => return None,
+                };
+
+                Some(SourceUpdate {
+                    title: item.title.clone(),
+                    link: item.link.clone(),
+                    published_date,
+                    thumbnail: None,
+                    description: None,
+                    secondary_link: None,
+                    also_in: Vec::new(),
+                })
+            })
+            .collect();
+        log_new_items(&self.name, items.len(), updates.len());
+
+        // only the current items are kept so the list doesn't grow forever
+        let mut seen_links = Vec::new();
+        for item in items {
+            if !seen_links.contains(&item.link) {
+                seen_links.push(item.link);
+            }
+        }
+        self.seen_links = seen_links;
+
+        Ok(updates)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::{lock_network, ok_response, serve_in_turn};
+    use chrono::TimeZone;
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn new_items_are_found_by_date_or_by_link() {
+        let _network = lock_network();
+        let first_page = r#"<article><a href="/old">Old</a></article>
+            <article><h2>Dated</h2><a href="/dated"></a>
+                <time datetime="2024-03-05T00:00:00Z">March 5</time></article>"#;
+        let second_page = r#"<article><a href="/new">New</a></article>
+            <article><a href="/old">Old</a></article>"#;
+        let (url, server) = serve_in_turn(
+            "127.0.0.87",
+            &[ok_response(first_page), ok_response(second_page)],
+        );
+        let mut source = ScrapeSource {
+            url: format!("{}/blog/", url),
+            item_selector: "article".to_owned(),
+            title_selector: Some("h2".to_owned()),
+            date_selector: Some("time".to_owned()),
+            ..Default::default()
+        };
+        let last_checked = Some(Local.ymd(2024, 3, 1).and_hms(0, 0, 0));
+
+        // undated items are only remembered the first time
+        let first = source.check_for_updates(&last_checked).unwrap();
+        let second = source.check_for_updates(&last_checked).unwrap();
+        server.join().unwrap();
+
+        assert_eq!(first.len(), 1);
+        assert_eq!(first[0].title, "Dated");
+        assert_eq!(first[0].link, format!("{}/dated", url));
+        assert_eq!(second.len(), 1);
+        assert_eq!(second[0].title, "New");
+        assert_eq!(second[0].link, format!("{}/new", url));
+        assert_eq!(
+            source.seen_links,
+            vec![format!("{}/new", url), format!("{}/old", url)]
+        );
+    }
+}
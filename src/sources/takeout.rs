@@ -0,0 +1,166 @@
+//! Reading the YouTube subscriptions in a Google Takeout export, either
+//! the `subscriptions.csv` Takeout gives now or the OPML file it used to.
+
+use crate::sources::youtube::YouTubeChannel;
+use reqwest::Url;
+
+/// Reads the channels in a Takeout export of YouTube subscriptions,
+/// telling the CSV and OPML formats apart by their first characters.
+///
+/// Each row that should be a channel but isn't comes back as an error
+/// naming its line.
+pub fn read_subscriptions(export: &str) -> Vec<Result<YouTubeChannel, String>> {
+    // the CSV is written with a byte order mark
+    let export = export.trim_start_matches('\u{feff}');
+    if export.trim_start().starts_with('<') {
+        read_opml(export)
+    } else {
+        read_csv(export)
+    }
+}
+
+/// Reads a `subscriptions.csv`, which looks like:
+///
+/// ```text
+/// Channel Id,Channel Url,Channel Title
+/// UC9XtgFNeoDbjISzoJT0Qi9w,http://www.youtube.com/channel/UC9XtgFNeoDbjISzoJT0Qi9w,Shnabubula
+/// ```
+fn read_csv(export: &str) -> Vec<Result<YouTubeChannel, String>> {
+    // the header's names are translated, so columns are found by position
+    // unless the header says otherwise
+    let mut id_column = 0;
+    let mut title_column = 2;
+
+    export
+        .lines()
+        .enumerate()
+        .filter(|(_index, line)| !line.trim().is_empty())
+        .filter_map(|(index, line)| {
+            let fields = csv_fields(line);
+            if index == 0 && !is_channel_id(&fields[0]) {
+                let find = |name: &str| {
+                    fields
+                        .iter()
+                        .position(|field| field.to_lowercase().contains(name))
+                };
+                id_column = find("id").unwrap_or(id_column);
+                title_column = find("title").unwrap_or(title_column);
+                return None;
+            }
+
+            let channel_id = fields.get(id_column).map(|id| id.trim()).unwrap_or("");
+            let title = fields
+                .get(title_column)
+                .map(|title| title.trim())
+                .unwrap_or("");
+            Some(channel(index + 1, channel_id, title))
+        })
+        .collect()
+}
+
+/// Splits a line of CSV into its fields, un-quoting the quoted ones.
+fn csv_fields(line: &str) -> Vec<String> {
+    let mut fields = vec![String::new()];
+    let mut in_quotes = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            // a doubled quote inside quotes is a literal quote
+            '"' if in_quotes && chars.peek() == Some(&'"') => {
+                chars.next();
+                fields.last_mut().unwrap().push('"');
+            }
+            '"' => in_quotes = !in_quotes,
+            ',' if !in_quotes => fields.push(String::new()),
+            c => fields.last_mut().unwrap().push(c),
+        }
+    }
+
+    fields
+}
+
+/// Reads an OPML export, which lists each channel's feed like:
+///
+/// ```text
+/// <outline text="Shnabubula" title="Shnabubula" type="rss"
+///     xmlUrl="https://www.youtube.com/feeds/videos.xml?channel_id=UC9XtgFNeoDbjISzoJT0Qi9w" />
+/// ```
+///
+/// Outlines without a feed, like the one holding all the others, are
+/// skipped.
+fn read_opml(export: &str) -> Vec<Result<YouTubeChannel, String>> {
+    export
+        .match_indices("<outline")
+        .filter_map(|(start, _outline)| {
+            let end = export[start..]
+                .find('>')
+                .map(|end| start + end)
+                .unwrap_or(export.len());
+            let outline = &export[start..end];
+            let line = export[..start].matches('\n').count() + 1;
+
+            let feed_url = xml_attribute(outline, "xmlUrl")?;
+            let channel_id = Url::parse(&feed_url)
+                .ok()
+                .and_then(|url| {
+                    url.query_pairs()
+                        .find(|(key, _value)| key == "channel_id")
+                        .map(|(_key, value)| value.into_owned())
+                })
+                .unwrap_or_default();
+            let title = xml_attribute(outline, "title")
+                .or_else(|| xml_attribute(outline, "text"))
+                .unwrap_or_default();
+            Some(channel(line, &channel_id, &title))
+        })
+        .collect()
+}
+
+/// Reads an attribute of an XML element, like `title="..."`.
+fn xml_attribute(element: &str, name: &str) -> Option<String> {
+    let marker = format!(" {}=", name);
+    let start = element.find(&marker)? + marker.len();
+    let quote = element[start..].chars().next()?;
+    let length = element[start + 1..].find(quote)?;
+
+    Some(
+        element[start + 1..start + 1 + length]
+            .replace("&quot;", "\"")
+            .replace("&apos;", "'")
+            .replace("&#39;", "'")
+            .replace("&lt;", "<")
+            .replace("&gt;", ">")
+            .replace("&amp;", "&"),
+    )
+}
+
+/// Whether some text looks like a channel id, like "UC9XtgFNeoDbjISzoJT0Qi9w".
+fn is_channel_id(text: &str) -> bool {
+    let text = text.trim();
+    text.starts_with("UC")
+        && text.len() == 24
+        && text
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Makes a channel out of a row of an export, or an error if it has no
+/// channel id.
+fn channel(line: usize, channel_id: &str, title: &str) -> Result<YouTubeChannel, String> {
+    if !is_channel_id(channel_id) {
+        return Err(format!(
+            "line {}: \"{}\" isn't a channel id",
+            line, channel_id
+        ));
+    }
+
+    Ok(YouTubeChannel {
+        name: if title.is_empty() {
+            channel_id.to_owned()
+        } else {
+            title.to_owned()
+        },
+        channel_id: channel_id.to_owned(),
+        ..Default::default()
+    })
+}
@@ -0,0 +1,231 @@
+//! Searching a torrent RSS feed for new anime episodes, so that updates
+//! can link to a torrent of the episode. Torrents are only ever linked,
+//! never downloaded.
+
+use crate::util::{access_error, check_status, get};
+use reqwest::Url;
+use rss::{Channel, Item};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::io::BufReader;
+
+/// The search used if none is configured, nyaa.si's RSS feed of
+/// English-translated anime.
+const DEFAULT_URL: &str = "https://nyaa.si/?page=rss&c=1_2&f=0";
+
+/// How to search for torrents of new anime episodes. Having this in the
+/// config opts in to the search.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, JsonSchema)]
+pub struct TorrentSearch {
+    /// The torrent RSS search to use, which gets the search added as a
+    /// `q` parameter. Defaults to `DEFAULT_URL`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    /// Text that a torrent's title must contain, like "1080p".
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quality: Option<String>,
+    /// Whether to only pick torrents marked as trusted (`nyaa:trusted`).
+    #[serde(default)]
+    pub trusted_only: bool,
+    /// Any fields sitch doesn't know about, kept so they aren't lost.
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl TorrentSearch {
+    /// Searches for torrents of an anime's episode, returning the link
+    /// of the best match if there is one.
+    pub fn find_episode(&self, anime_name: &str, episode: u64) -> Result<Option<String>, String> {
+        let query = format!("{} {:02}", anime_name, episode);
+        let mut url = Url::parse(self.url.as_ref().map(String::as_str).unwrap_or(DEFAULT_URL))
+            .map_err(|err| format!("The torrent search URL is invalid: {}", err))?;
+        url.query_pairs_mut().append_pair("q", &query);
+
+        let response = get(url.as_str()).map_err(|err| access_error(url.as_str(), &err))?;
+        let channel = Channel::read_from(BufReader::new(check_status(response)?))
+            .map_err(|err| format!("Couldn't read the torrent search results: {}", err))?;
+
+        Ok(self
+            .best_match(channel.items(), episode)
+            .and_then(Self::torrent_link))
+    }
+
+    /// Picks the search result most likely to be the episode.
+    ///
+    /// Results must have the episode number on its own in their title
+    /// (so "05" matches episode 5, but "1080p" doesn't match 1080) and
+    /// the quality, if one is set. Of those, the one with the most seeders
+    /// wins, preferring trusted torrents in a tie.
+    pub fn best_match<'a>(&self, results: &'a [Item], episode: u64) -> Option<&'a Item> {
+        let quality = self.quality.as_ref().map(|quality| quality.to_lowercase());
+
+        results
+            .iter()
+            .filter(|item| {
+                let title = item.title().unwrap_or("").to_lowercase();
+                quality
+                    .as_ref()
+                    .map(|quality| title.contains(quality.as_str()))
+                    .unwrap_or(true)
+                    && Self::has_episode_number(&title, episode)
+                    && (!self.trusted_only || Self::is_trusted(item))
+            })
+            .max_by_key(|item| (Self::seeders(item), Self::is_trusted(item)))
+    }
+
+    /// Whether a title has the episode number as a whole number, like
+    /// "[Group] Show - 05 [1080p]" or "Show S01E05".
+    fn has_episode_number(title: &str, episode: u64) -> bool {
+        title
+            .split(|c: char| !c.is_ascii_alphanumeric())
+            .flat_map(|word| {
+                // "s01e05" and "e05" hold the number after the "e"
+                let number = match word.rfind('e') {
+                    Some(index) => &word[index + 1..],
+                    None => word,
+                };
+                number.parse::<u64>().ok()
+            })
+            .any(|number| number == episode)
+    }
+
+    /// Reads one of nyaa's extension elements, like `<nyaa:seeders>`.
+    fn nyaa_value<'a>(item: &'a Item, name: &str) -> Option<&'a str> {
+        item.extensions().get("nyaa")?.get(name)?.first()?.value()
+    }
+
+    /// How many seeders a result has, or 0 if the feed doesn't say.
+    fn seeders(item: &Item) -> u64 {
+        Self::nyaa_value(item, "seeders")
+            .and_then(|seeders| seeders.trim().parse().ok())
+            .unwrap_or(0)
+    }
+
+    /// Whether a result is marked as coming from a trusted uploader.
+    fn is_trusted(item: &Item) -> bool {
+        Self::nyaa_value(item, "trusted")
+            .map(|trusted| trusted.trim().eq_ignore_ascii_case("yes"))
+            .unwrap_or(false)
+    }
+
+    /// The page of a result (its GUID on nyaa), falling back to its link,
+    /// which is usually the .torrent file itself.
+    fn torrent_link(item: &Item) -> Option<String> {
+        item.guid()
+            .filter(|guid| guid.is_permalink())
+            .map(|guid| guid.value())
+            .or_else(|| item.link())
+            .map(|link| link.to_owned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A search result feed like nyaa's, from (title, seeders, trusted).
+    fn results(torrents: &[(&str, u64, &str)]) -> Vec<Item> {
+        let items = torrents
+            .iter()
+            .enumerate()
+            .map(|(index, (title, seeders, trusted))| {
+                format!(
+                    "<item><title>{}</title><link>https://nyaa.si/download/{}.torrent</link>\
+                     <guid isPermaLink=\"true\">https://nyaa.si/view/{}</guid>\
+                     <nyaa:seeders>{}</nyaa:seeders><nyaa:trusted>{}</nyaa:trusted></item>",
+                    title, index, index, seeders, trusted
+                )
+            })
+            .collect::<String>();
+        let feed = format!(
+            "<rss version=\"2.0\" xmlns:nyaa=\"https://nyaa.si/xmlns/nyaa\">\
+             <channel><title>Search</title><link>https://nyaa.si</link>\
+             <description>Search</description>{}</channel></rss>",
+            items
+        );
+
+        Channel::read_from(feed.as_bytes())
+            .unwrap()
+            .items()
+            .to_vec()
+    }
+
+    fn best_title(search: &TorrentSearch, results: &[Item], episode: u64) -> Option<String> {
+        search
+            .best_match(results, episode)
+            .and_then(|item| item.title())
+            .map(|title| title.to_owned())
+    }
+
+    #[test]
+    fn episode_numbers_must_stand_alone() {
+        assert!(TorrentSearch::has_episode_number(
+            "[group] show - 05 [1080p]",
+            5
+        ));
+        assert!(TorrentSearch::has_episode_number("show s01e05", 5));
+        assert!(!TorrentSearch::has_episode_number(
+            "[group] show - 15 [1080p]",
+            1080
+        ));
+        assert!(!TorrentSearch::has_episode_number("[group] show - 15", 5));
+    }
+
+    #[test]
+    fn the_most_seeded_match_wins() {
+        let results = results(&[
+            ("[A] Show - 05 [720p]", 500, "No"),
+            ("[B] Show - 05 [1080p]", 100, "No"),
+            ("[C] Show - 05 [1080p]", 300, "No"),
+            ("[D] Show - 06 [1080p]", 900, "No"),
+        ]);
+        let search = TorrentSearch {
+            quality: Some("1080P".to_owned()),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            best_title(&search, &results, 5),
+            Some("[C] Show - 05 [1080p]".to_owned())
+        );
+        assert_eq!(
+            best_title(&TorrentSearch::default(), &results, 5),
+            Some("[A] Show - 05 [720p]".to_owned())
+        );
+        assert_eq!(best_title(&search, &results, 7), None);
+    }
+
+    #[test]
+    fn trusted_torrents_are_preferred() {
+        let results = results(&[
+            ("[A] Show - 05", 300, "No"),
+            ("[B] Show - 05", 300, "Yes"),
+            ("[C] Show - 05", 100, "Yes"),
+            ("[D] Show - 05", 900, "No"),
+        ]);
+        let trusted_only = TorrentSearch {
+            trusted_only: true,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            best_title(&trusted_only, &results, 5),
+            Some("[B] Show - 05".to_owned())
+        );
+        assert_eq!(
+            best_title(&TorrentSearch::default(), &results[..3], 5),
+            Some("[B] Show - 05".to_owned())
+        );
+    }
+
+    #[test]
+    fn torrents_link_to_their_page() {
+        let results = results(&[("[A] Show - 05", 1, "No")]);
+
+        assert_eq!(
+            TorrentSearch::torrent_link(&results[0]),
+            Some("https://nyaa.si/view/0".to_owned())
+        );
+    }
+}
@@ -0,0 +1,235 @@
+//! The watch platform for update checking, which notices when the
+//! content of a web page changes.
+
+use crate::selector::Selector;
+use crate::sources::{
+    check_each_source_remembering, deserialize_sources, serialize_sources, validate_name,
+    validate_url, CheckForUpdates, CheckOptions, NamedSource, SourceCheck, SourceSettings,
+    SourceUpdate,
+};
+use crate::state::{SourceMemory, State};
+use crate::util::{access_error, check_status, get};
+use chrono::{DateTime, Local};
+use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+use schemars::JsonSchema;
+use select::document::Document;
+use select::predicate::Name;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::borrow::Cow;
+
+/// The wrapper type for watched pages and their last checked times
+/// to implement `CheckForUpdates` on.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, JsonSchema)]
+pub struct WatchedPages(
+    #[serde(
+        serialize_with = "serialize_sources",
+        deserialize_with = "deserialize_sources"
+    )]
+    #[schemars(with = "Vec<WatchedPage>")]
+    pub Vec<(WatchedPage, Option<DateTime<Local>>)>,
+);
+
+/// A web page that is checked for any change to its text.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, JsonSchema)]
+pub struct WatchedPage {
+    pub name: String,
+    /// The URL of the page to watch.
+    pub url: String,
+    /// A CSS selector for the part of the page to watch, or the whole
+    /// page's text if not set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub selector: Option<String>,
+    /// The settings that every source has.
+    #[serde(flatten)]
+    pub settings: SourceSettings,
+    /// Any fields sitch doesn't know about, kept so they aren't lost.
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl CheckForUpdates for WatchedPages {
+    fn check_for_all_updates(&mut self, state: &State, options: &CheckOptions) -> Vec<SourceCheck> {
+        check_each_source_remembering(
+            "watch",
+            &mut self.0,
+            state,
+            options,
+            |page, _last_checked, memory| (page.name.clone(), page.check_for_updates(memory)),
+        )
+    }
+
+    fn diagnose_all(&self) -> Vec<(String, Result<(), String>)> {
+        self.0
+            .par_iter()
+            .map(|(page, _last_checked)| (page.name.clone(), page.diagnose()))
+            .collect()
+    }
+
+    fn type_name(&self) -> &'static str {
+        "Watch"
+    }
+}
+
+impl NamedSource for WatchedPage {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn name_mut(&mut self) -> &mut String {
+        &mut self.name
+    }
+
+    fn location(&self) -> Cow<'_, str> {
+        Cow::Borrowed(&self.url)
+    }
+
+    fn validate(&mut self) -> Result<(), String> {
+        validate_name(&mut self.name)?;
+        validate_url("URL", &mut self.url)
+    }
+
+    fn settings(&self) -> &SourceSettings {
+        &self.settings
+    }
+
+    fn settings_mut(&mut self) -> &mut SourceSettings {
+        &mut self.settings
+    }
+}
+
+impl WatchedPage {
+    /// Loads the watched text of the page, with its whitespace collapsed
+    /// so that reformatting doesn't count as a change.
+    fn watched_text(&self) -> Result<String, String> {
+        let selector = match &self.selector {
+            Some(selector) => Some(
+                selector
+                    .parse::<Selector>()
+                    .map_err(|err| format!("The selector is invalid: {}", err))?,
+            ),
+            None => None,
+        };
+
+        let response = get(&self.url).map_err(|err| access_error(&self.url, &err))?;
+        let html = check_status(response)?
+            .text()
+            .map_err(|_err| "Couldn't read the page".to_owned())?;
+        let document = Document::from(html.as_str());
+
+        let text = match selector {
+            Some(selector) => {
+                let nodes = selector.find_in(&document);
+                if nodes.is_empty() {
+                    return Err(format!(
+                        "The selector \"{}\" didn't match anything",
+                        self.selector.as_ref().unwrap()
+                    ));
+                }
+                nodes
+                    .iter()
+                    .map(|node| node.text())
+                    .collect::<Vec<String>>()
+                    .join("\n")
+            }
+            None => document
+                .find(Name("body"))
+                .next()
+                .map(|body| body.text())
+                .unwrap_or(html.clone()),
+        };
+
+        Ok(text.split_whitespace().collect::<Vec<&str>>().join(" "))
+    }
+
+    /// A 64-bit FNV-1a hash of some text as hex, which (unlike the
+    /// standard library's hasher) stays the same between Rust versions.
+    fn hash_text(text: &str) -> String {
+        let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+        for byte in text.bytes() {
+            hash ^= u64::from(byte);
+            hash = hash.wrapping_mul(0x0100_0000_01b3);
+        }
+        format!("{:016x}", hash)
+    }
+
+    /// Makes sure that the page (and selector, if set) can be read.
+    pub fn diagnose(&self) -> Result<(), String> {
+        self.watched_text().map(|_text| ())
+    }
+
+    /// Check whether the page has changed since the last check.
+    ///
+    /// The first check only remembers the page. The hash of the page kept
+    /// in `memory` is only replaced after the page was read, so failed
+    /// checks don't make the next successful one look like a change.
+    pub fn check_for_updates(
+        &self,
+        memory: &mut SourceMemory,
+    ) -> Result<Vec<SourceUpdate>, String> {
+        let text = self.watched_text()?;
+
+        Ok(self.updates_for(&text, memory))
+    }
+
+    /// The update for the watched text if it has changed since the hash
+    /// in `memory`, which is replaced with the text's.
+    fn updates_for(&self, text: &str, memory: &mut SourceMemory) -> Vec<SourceUpdate> {
+        let hash = Self::hash_text(text);
+        let changed = memory
+            .page_hash
+            .as_ref()
+            .map(|old_hash| *old_hash != hash)
+            .unwrap_or(false);
+        memory.page_hash = Some(hash);
+
+        if changed {
+            vec![SourceUpdate {
+                title: "Page changed".to_owned(),
+                link: self.url.clone(),
+                published_date: Local::now(),
+                thumbnail: None,
+                description: None,
+                secondary_link: None,
+                also_in: Vec::new(),
+            }]
+        } else {
+            vec![]
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hashes_are_stable() {
+        assert_eq!(WatchedPage::hash_text(""), "cbf29ce484222325");
+        assert_eq!(WatchedPage::hash_text("a"), "af63dc4c8601ec8c");
+    }
+
+    #[test]
+    fn first_check_only_remembers_the_page() {
+        let page = WatchedPage::default();
+        let mut memory = SourceMemory::default();
+
+        assert!(page.updates_for("Sold out", &mut memory).is_empty());
+        assert_eq!(memory.page_hash, Some(WatchedPage::hash_text("Sold out")));
+    }
+
+    #[test]
+    fn changes_are_reported_once() {
+        let page = WatchedPage {
+            url: "https://example.com/shop".to_owned(),
+            ..Default::default()
+        };
+        let mut memory = SourceMemory::default();
+        page.updates_for("Sold out", &mut memory);
+
+        let updates = page.updates_for("In stock", &mut memory);
+        assert_eq!(updates.len(), 1);
+        assert_eq!(updates[0].link, "https://example.com/shop");
+        assert!(page.updates_for("In stock", &mut memory).is_empty());
+    }
+}
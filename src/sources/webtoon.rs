@@ -0,0 +1,238 @@
+//! The Webtoon platform for update checking, which reports new
+//! episodes of series on LINE Webtoon.
+
+use crate::sources::{
+    check_each_source, deserialize_sources, log_new_items, serialize_sources, validate_field,
+    validate_name, CheckForUpdates, CheckOptions, NamedSource, SourceCheck, SourceSettings,
+    SourceUpdate,
+};
+use crate::state::State;
+use crate::util::{access_error, check_status, get};
+use chrono::{DateTime, FixedOffset, Local};
+use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+use reqwest::Url;
+use rss::Channel;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::borrow::Cow;
+use std::io::BufReader;
+
+/// The language of series whose URLs don't say.
+const DEFAULT_LANGUAGE: &str = "en";
+
+/// The wrapper type for Webtoon series and their last checked times
+/// to implement `CheckForUpdates` on.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, JsonSchema)]
+pub struct WebtoonSeriesList(
+    #[serde(
+        serialize_with = "serialize_sources",
+        deserialize_with = "deserialize_sources"
+    )]
+    #[schemars(with = "Vec<WebtoonSeries>")]
+    pub Vec<(WebtoonSeries, Option<DateTime<Local>>)>,
+);
+
+/// A series on LINE Webtoon.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, JsonSchema)]
+pub struct WebtoonSeries {
+    pub name: String,
+    /// The series' id, from the `title_no` in its URL.
+    pub title_no: u64,
+    /// The genre and slug in the series' URL, like "fantasy/tower-of-god".
+    pub genre_slug: String,
+    /// The language code in the series' URL, or `DEFAULT_LANGUAGE` if
+    /// not set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+    /// The settings that every source has.
+    #[serde(flatten)]
+    pub settings: SourceSettings,
+    /// Any fields sitch doesn't know about, kept so they aren't lost.
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl CheckForUpdates for WebtoonSeriesList {
+    fn check_for_all_updates(&mut self, state: &State, options: &CheckOptions) -> Vec<SourceCheck> {
+        check_each_source(
+            "webtoon",
+            &mut self.0,
+            state,
+            options,
+            |series, last_checked| (series.name.clone(), series.check_for_updates(last_checked)),
+        )
+    }
+
+    fn diagnose_all(&self) -> Vec<(String, Result<(), String>)> {
+        self.0
+            .par_iter()
+            .map(|(series, _last_checked)| (series.name.clone(), series.diagnose()))
+            .collect()
+    }
+
+    fn type_name(&self) -> &'static str {
+        "Webtoon"
+    }
+}
+
+impl NamedSource for WebtoonSeries {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn name_mut(&mut self) -> &mut String {
+        &mut self.name
+    }
+
+    fn location(&self) -> Cow<'_, str> {
+        Cow::Owned(self.series_url())
+    }
+
+    fn validate(&mut self) -> Result<(), String> {
+        validate_name(&mut self.name)?;
+        validate_field("genre", &mut self.genre_slug)
+    }
+
+    fn settings(&self) -> &SourceSettings {
+        &self.settings
+    }
+
+    fn settings_mut(&mut self) -> &mut SourceSettings {
+        &mut self.settings
+    }
+}
+
+impl WebtoonSeries {
+    /// Reads a series from its URL, like
+    /// "https://www.webtoons.com/en/fantasy/tower-of-god/list?title_no=95",
+    /// with an empty name.
+    pub fn from_url(series_url: &str) -> Result<Self, String> {
+        let url = Url::parse(series_url.trim())
+            .map_err(|err| format!("The URL {} is invalid: {}", series_url, err))?;
+        let is_webtoons = url
+            .host_str()
+            .map(|host| host == "webtoons.com" || host.ends_with(".webtoons.com"))
+            .unwrap_or(false);
+        if !is_webtoons {
+            return Err(format!("{} isn't a webtoons.com URL", series_url));
+        }
+
+        let title_no = url
+            .query_pairs()
+            .find(|(key, _value)| key == "title_no")
+            .ok_or(format!(
+                "{} has no title_no, so it isn't a series' URL",
+                series_url
+            ))?
+            .1
+            .parse::<u64>()
+            .map_err(|_err| format!("The title_no in {} isn't a number", series_url))?;
+        // the path is /<language>/<genre>/<slug>/<page>, like /en/fantasy/tower-of-god/list
+        let segments = url
+            .path_segments()
+            .map(|segments| segments.filter(|segment| !segment.is_empty()).collect())
+            .unwrap_or_else(Vec::new);
+        if segments.len() < 3 {
+            return Err(format!(
+                "Couldn't find the genre and name of the series in {}",
+                series_url
+            ));
+        }
+
+        Ok(WebtoonSeries {
+            name: String::new(),
+            title_no,
+            genre_slug: format!("{}/{}", segments[1], segments[2]),
+            language: Some(segments[0].to_owned()).filter(|language| language != DEFAULT_LANGUAGE),
+            settings: SourceSettings::default(),
+            extra: Map::new(),
+        })
+    }
+
+    /// The base URL of the series' pages, without the page or `title_no`.
+    fn base_url(&self) -> String {
+        format!(
+            "https://www.webtoons.com/{}/{}",
+            self.language
+                .as_ref()
+                .map(String::as_str)
+                .unwrap_or(DEFAULT_LANGUAGE),
+            self.genre_slug.trim_matches('/')
+        )
+    }
+
+    /// The URL of the series' list of episodes.
+    pub fn series_url(&self) -> String {
+        format!("{}/list?title_no={}", self.base_url(), self.title_no)
+    }
+
+    /// Loads the series' RSS feed of its newest episodes.
+    pub fn fetch_channel(&self) -> Result<Channel, String> {
+        let feed_url = format!("{}/rss?title_no={}", self.base_url(), self.title_no);
+        let response = get(&feed_url).map_err(|err| access_error(&feed_url, &err))?;
+        Channel::read_from(BufReader::new(check_status(response)?))
+            .map_err(|err| format!("Couldn't load the series' feed: {}", err))
+    }
+
+    /// Makes sure that the series' feed loads.
+    pub fn diagnose(&self) -> Result<(), String> {
+        self.fetch_channel().map(|_channel| ())
+    }
+
+    /// Check for episodes of the series released since `last_checked`.
+    pub fn check_for_updates(
+        &self,
+        last_checked: &Option<DateTime<Local>>,
+    ) -> Result<Vec<SourceUpdate>, String> {
+        // <item>
+        //     <title>Episode 550</title>
+        //     <link>https://www.webtoons.com/en/fantasy/tower-of-god/season-3-ep-133/viewer?title_no=95&amp;episode_no=550</link>
+        //     <pubDate>Sun, 11 Oct 2026 15:00:00 GMT</pubDate>
+        //     ...
+        // </item>
+        let channel = self.fetch_channel()?;
+
+        let updates: Vec<SourceUpdate> = channel
+            .items()
+            .iter()
+            .filter_map(|item| {
+                let published_date = DateTime::<FixedOffset>::parse_from_rfc2822(item.pub_date()?)
+                    .ok()?
+                    .with_timezone(&Local);
+                let link = item.link().unwrap_or("").to_owned();
+                let episode_no = Url::parse(&link).ok().and_then(|url| {
+                    url.query_pairs()
+                        .find(|(key, _value)| key == "episode_no")
+                        .map(|(_key, value)| value.into_owned())
+                });
+                let title = item.title().unwrap_or("<unnamed>").trim();
+
+                Some(SourceUpdate {
+                    title: match episode_no {
+                        Some(episode_no) => format!("#{}: {}", episode_no, title),
+                        None => title.to_owned(),
+                    },
+                    link: if link.is_empty() {
+                        self.series_url()
+                    } else {
+                        link
+                    },
+                    published_date,
+                    thumbnail: None,
+                    description: None,
+                    secondary_link: None,
+                    also_in: Vec::new(),
+                })
+            })
+            .filter(|update| {
+                last_checked
+                    .map(|last_checked| last_checked < update.published_date)
+                    .unwrap_or(true)
+            })
+            .collect();
+        log_new_items(&self.name, channel.items().len(), updates.len());
+
+        Ok(updates)
+    }
+}
@@ -0,0 +1,474 @@
+//! The Wikipedia platform for update checking, which reports new
+//! edits to articles on Wikipedia or any other MediaWiki wiki.
+
+use crate::sources::{
+    check_each_source, deserialize_sources, log_new_items, serialize_sources, validate_field,
+    validate_name, CheckForUpdates, CheckOptions, NamedSource, SourceCheck, SourceSettings,
+    SourceUpdate,
+};
+use crate::state::State;
+use crate::util::{access_error, check_status, get};
+use chrono::{DateTime, FixedOffset, Local, SecondsFormat, Utc};
+use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+use reqwest::Url;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::borrow::Cow;
+
+/// The wiki of articles that don't name one.
+const DEFAULT_WIKI: &str = "en.wikipedia.org";
+
+/// The most revisions loaded per check, which is also the most the API
+/// allows at once without extra permissions.
+const MAX_REVISIONS: usize = 50;
+
+/// The wrapper type for wiki articles and their last checked times
+/// to implement `CheckForUpdates` on.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, JsonSchema)]
+pub struct WikipediaArticles(
+    #[serde(
+        serialize_with = "serialize_sources",
+        deserialize_with = "deserialize_sources"
+    )]
+    #[schemars(with = "Vec<WikipediaArticle>")]
+    pub Vec<(WikipediaArticle, Option<DateTime<Local>>)>,
+);
+
+/// An article on Wikipedia, or on another wiki running MediaWiki.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, JsonSchema)]
+pub struct WikipediaArticle {
+    pub name: String,
+    /// The article's title, like "Rust (programming language)".
+    pub title: String,
+    /// The wiki's domain, like "de.wikipedia.org". Defaults to
+    /// `DEFAULT_WIKI`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub wiki: Option<String>,
+    /// Whether to report all new edits as a single update.
+    #[serde(default)]
+    pub collapse: bool,
+    /// Whether to leave out edits marked as minor.
+    #[serde(default)]
+    pub exclude_minor: bool,
+    /// Whether to leave out edits made by bots.
+    #[serde(default)]
+    pub exclude_bots: bool,
+    /// The settings that every source has.
+    #[serde(flatten)]
+    pub settings: SourceSettings,
+    /// Any fields sitch doesn't know about, kept so they aren't lost.
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// An edit to an article, read from the API.
+struct Revision {
+    id: u64,
+    /// The revision before this one, or 0 if this one created the article.
+    parent_id: u64,
+    user: String,
+    comment: String,
+    minor: bool,
+    date: DateTime<Local>,
+}
+
+impl CheckForUpdates for WikipediaArticles {
+    fn check_for_all_updates(&mut self, state: &State, options: &CheckOptions) -> Vec<SourceCheck> {
+        check_each_source(
+            "wikipedia",
+            &mut self.0,
+            state,
+            options,
+            |article, last_checked| {
+                (
+                    article.name.clone(),
+                    article.check_for_updates(last_checked),
+                )
+            },
+        )
+    }
+
+    fn diagnose_all(&self) -> Vec<(String, Result<(), String>)> {
+        self.0
+            .par_iter()
+            .map(|(article, _last_checked)| (article.name.clone(), article.diagnose()))
+            .collect()
+    }
+
+    fn type_name(&self) -> &'static str {
+        "Wikipedia"
+    }
+}
+
+impl NamedSource for WikipediaArticle {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn name_mut(&mut self) -> &mut String {
+        &mut self.name
+    }
+
+    fn location(&self) -> Cow<'_, str> {
+        Cow::Owned(self.article_url())
+    }
+
+    fn validate(&mut self) -> Result<(), String> {
+        validate_name(&mut self.name)?;
+        validate_field("title", &mut self.title)
+    }
+
+    fn settings(&self) -> &SourceSettings {
+        &self.settings
+    }
+
+    fn settings_mut(&mut self) -> &mut SourceSettings {
+        &mut self.settings
+    }
+}
+
+impl WikipediaArticle {
+    /// The wiki's domain, without any scheme or slashes around it.
+    fn wiki(&self) -> &str {
+        let wiki = self
+            .wiki
+            .as_ref()
+            .map(String::as_str)
+            .unwrap_or(DEFAULT_WIKI);
+        let wiki = wiki.trim();
+        let wiki = wiki
+            .find("://")
+            .map(|index| &wiki[index + 3..])
+            .unwrap_or(wiki);
+
+        wiki.trim_matches('/')
+    }
+
+    /// The URL of one of the wiki's scripts, like "api.php", with the
+    /// given query parameters.
+    fn script_url(&self, script: &str, params: &[(&str, String)]) -> Result<Url, String> {
+        Url::parse_with_params(&format!("https://{}/w/{}", self.wiki(), script), params)
+            .map_err(|err| format!("Couldn't build the URL of {}: {}", self.wiki(), err))
+    }
+
+    /// The URL of the article itself.
+    pub fn article_url(&self) -> String {
+        format!(
+            "https://{}/wiki/{}",
+            self.wiki(),
+            self.title.trim().replace(' ', "_")
+        )
+    }
+
+    /// The URL of the changes between two revisions of the article, or of
+    /// the first revision if `parent_id` is 0.
+    fn diff_url(&self, parent_id: u64, id: u64) -> String {
+        let params = if parent_id == 0 {
+            vec![("oldid", id.to_string())]
+        } else {
+            vec![
+                ("title", self.title.trim().replace(' ', "_")),
+                ("diff", id.to_string()),
+                ("oldid", parent_id.to_string()),
+            ]
+        };
+
+        self.script_url("index.php", &params)
+            .map(String::from)
+            .unwrap_or(self.article_url())
+    }
+
+    /// Makes a request to the wiki's API, returning the `query` object of
+    /// the response.
+    fn query(&self, params: &[(&str, String)]) -> Result<Value, String> {
+        let mut params = params.to_vec();
+        params.push(("action", "query".to_owned()));
+        params.push(("format", "json".to_owned()));
+        params.push(("formatversion", "2".to_owned()));
+        let url = self.script_url("api.php", &params)?;
+
+        let response = get(url.as_str()).map_err(|err| access_error(url.as_str(), &err))?;
+        let response: Value = check_status(response)?
+            .json()
+            .map_err(|_err| "Couldn't parse request data as JSON".to_owned())?;
+        if let Some(info) = response
+            .pointer("/error/info")
+            .and_then(|info| info.as_str())
+        {
+            return Err(format!("The wiki's API returned an error: {}", info));
+        }
+
+        response
+            .get("query")
+            .cloned()
+            .ok_or("Couldn't find the result in the wiki's response".to_owned())
+    }
+
+    /// Loads the article's revisions since `since` (or just the latest
+    /// few), newest first.
+    fn revisions(&self, since: &Option<DateTime<Local>>) -> Result<Vec<Revision>, String> {
+        let mut params = vec![
+            ("prop", "revisions".to_owned()),
+            ("titles", self.title.trim().to_owned()),
+            ("redirects", "1".to_owned()),
+            ("rvprop", "ids|timestamp|comment|user|flags".to_owned()),
+            ("rvlimit", MAX_REVISIONS.to_string()),
+        ];
+        if let Some(since) = since {
+            // revisions are listed newest first, so this is where they stop
+            params.push((
+                "rvend",
+                since
+                    .with_timezone(&Utc)
+                    .to_rfc3339_opts(SecondsFormat::Secs, true),
+            ));
+        }
+
+        // {
+        //     "pages": [{
+        //         "title": "Rust (programming language)",
+        //         "revisions": [{
+        //             "revid": 1250000000,
+        //             "parentid": 1249999999,
+        //             "minor": false,
+        //             "user": "Someone",
+        //             "timestamp": "2026-10-15T12:00:00Z",
+        //             "comment": "/* History */ fix a typo"
+        //         }, ...]
+        //     }]
+        // }
+        let query = self.query(&params)?;
+        let page = query
+            .pointer("/pages/0")
+            .ok_or("Couldn't find the article in the wiki's response")?;
+        if page.get("missing").is_some() || page.get("invalid").is_some() {
+            return Err(format!(
+                "There's no article called \"{}\" on {}",
+                self.title,
+                self.wiki()
+            ));
+        }
+
+        Ok(page
+            .get("revisions")
+            .and_then(|revisions| revisions.as_array())
+            .map(|revisions| revisions.iter().filter_map(Self::parse_revision).collect())
+            .unwrap_or_else(Vec::new))
+    }
+
+    /// Parses a revision, skipping it if it has no id or date.
+    fn parse_revision(revision: &Value) -> Option<Revision> {
+        let text = |name: &str| {
+            revision
+                .get(name)
+                .and_then(|value| value.as_str())
+                .unwrap_or("")
+                .trim()
+                .to_owned()
+        };
+        let date = revision.get("timestamp")?.as_str()?;
+
+        Some(Revision {
+            id: revision.get("revid")?.as_u64()?,
+            parent_id: revision
+                .get("parentid")
+                .and_then(|id| id.as_u64())
+                .unwrap_or(0),
+            user: text("user"),
+            comment: text("comment"),
+            minor: revision
+                .get("minor")
+                .and_then(|minor| minor.as_bool())
+                .unwrap_or(false),
+            date: DateTime::<FixedOffset>::parse_from_rfc3339(date)
+                .ok()?
+                .with_timezone(&Local),
+        })
+    }
+
+    /// Finds which of the given users are bots on the wiki.
+    fn bots(&self, users: &[&str]) -> Result<Vec<String>, String> {
+        if users.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        // {
+        //     "users": [
+        //         { "name": "SomeBot", "groups": ["bot", "*", "user"] },
+        //         { "name": "192.0.2.1", "invalid": true },
+        //         ...
+        //     ]
+        // }
+        let query = self.query(&[
+            ("list", "users".to_owned()),
+            ("ususers", users.join("|")),
+            ("usprop", "groups".to_owned()),
+        ])?;
+
+        Ok(query
+            .get("users")
+            .and_then(|users| users.as_array())
+            .map(|users| {
+                users
+                    .iter()
+                    .filter(|user| {
+                        user.get("groups")
+                            .and_then(|groups| groups.as_array())
+                            .map(|groups| groups.iter().any(|group| group == "bot"))
+                            .unwrap_or(false)
+                    })
+                    .filter_map(|user| user.get("name")?.as_str())
+                    .map(|name| name.to_owned())
+                    .collect()
+            })
+            .unwrap_or_else(Vec::new))
+    }
+
+    /// Makes sure that the article exists on the wiki.
+    pub fn diagnose(&self) -> Result<(), String> {
+        self.revisions(&None).map(|_revisions| ())
+    }
+
+    /// Check for edits to the article made since `last_checked`.
+    ///
+    /// At most 50 edits are loaded per check. Minor edits and edits by
+    /// bots are left out if the article says to. If the article is set to
+    /// `collapse`, several new edits are reported as a single update
+    /// linking to the changes they made together.
+    pub fn check_for_updates(
+        &self,
+        last_checked: &Option<DateTime<Local>>,
+    ) -> Result<Vec<SourceUpdate>, String> {
+        let loaded = self.revisions(last_checked)?;
+        let loaded_count = loaded.len();
+        let mut revisions = loaded
+            .into_iter()
+            .filter(|revision| {
+                last_checked
+                    .map(|last_checked| last_checked < revision.date)
+                    .unwrap_or(true)
+                    && !(self.exclude_minor && revision.minor)
+            })
+            .collect::<Vec<Revision>>();
+        if self.exclude_bots {
+            let mut users = revisions
+                .iter()
+                .map(|revision| revision.user.as_str())
+                .collect::<Vec<&str>>();
+            users.sort();
+            users.dedup();
+            let bots = self.bots(&users)?;
+            revisions.retain(|revision| !bots.contains(&revision.user));
+        }
+        log_new_items(&self.name, loaded_count, revisions.len());
+
+        let updates = revisions
+            .iter()
+            .map(|revision| SourceUpdate {
+                title: if revision.comment.is_empty() {
+                    format!("Edit by {}", revision.user)
+                } else {
+                    revision.comment.clone()
+                },
+                link: self.diff_url(revision.parent_id, revision.id),
+                published_date: revision.date,
+                thumbnail: None,
+                description: Some(format!("by {}", revision.user)),
+                secondary_link: None,
+                also_in: Vec::new(),
+            })
+            .collect::<Vec<SourceUpdate>>();
+
+        if !self.collapse || revisions.len() < 2 {
+            return Ok(updates);
+        }
+
+        let newest = &revisions[0];
+        let oldest = &revisions[revisions.len() - 1];
+        Ok(vec![SourceUpdate {
+            title: format!("{} edits", revisions.len()),
+            // the parent of the oldest edit is compared against to show its
+            // changes too, unless the oldest edit created the article
+            link: if oldest.parent_id == 0 {
+                self.diff_url(oldest.id, newest.id)
+            } else {
+                self.diff_url(oldest.parent_id, newest.id)
+            },
+            published_date: newest.date,
+            thumbnail: None,
+            description: Some(
+                updates
+                    .iter()
+                    .map(|update| update.title.clone())
+                    .collect::<Vec<String>>()
+                    .join("\n"),
+            ),
+            secondary_link: None,
+            also_in: Vec::new(),
+        }])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn article(title: &str, wiki: Option<&str>) -> WikipediaArticle {
+        WikipediaArticle {
+            title: title.to_owned(),
+            wiki: wiki.map(|wiki| wiki.to_owned()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn wikis_can_be_given_as_urls() {
+        assert_eq!(article("Rust", None).wiki(), "en.wikipedia.org");
+        assert_eq!(
+            article("Rust", Some(" https://de.wikipedia.org/ ")).wiki(),
+            "de.wikipedia.org"
+        );
+        assert_eq!(
+            article(" Rust (programming language) ", None).article_url(),
+            "https://en.wikipedia.org/wiki/Rust_(programming_language)"
+        );
+    }
+
+    #[test]
+    fn edits_link_to_their_changes() {
+        let article = article("Rust (programming language)", None);
+
+        assert_eq!(
+            article.diff_url(100, 101),
+            "https://en.wikipedia.org/w/index.php?\
+             title=Rust_%28programming_language%29&diff=101&oldid=100"
+        );
+        assert_eq!(
+            article.diff_url(0, 1),
+            "https://en.wikipedia.org/w/index.php?oldid=1"
+        );
+    }
+
+    #[test]
+    fn revisions_need_an_id_and_a_date() {
+        let revision = WikipediaArticle::parse_revision(&json!({
+            "revid": 101,
+            "parentid": 100,
+            "minor": true,
+            "user": " Someone ",
+            "timestamp": "2024-03-01T12:00:00Z",
+        }))
+        .unwrap();
+        assert_eq!((revision.id, revision.parent_id), (101, 100));
+        assert_eq!(revision.user, "Someone");
+        assert_eq!(revision.comment, "");
+        assert!(revision.minor);
+
+        let undated = json!({"revid": 101, "parentid": 100});
+        assert!(WikipediaArticle::parse_revision(&undated).is_none());
+        let unnumbered = json!({"timestamp": "2024-03-01T12:00:00Z"});
+        assert!(WikipediaArticle::parse_revision(&unnumbered).is_none());
+    }
+}
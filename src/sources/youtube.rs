@@ -1,61 +1,287 @@
 //! The YouTube platform for update checking.
 
-use crate::sources::{CheckForUpdates, SourceUpdate};
-use crate::util::readline;
-use chrono::{DateTime, FixedOffset, Local};
-use colored::Colorize;
-use rayon::iter::{IntoParallelRefMutIterator, ParallelIterator};
-use serde::{Deserialize, Serialize};
-use serde_json::Value;
+use crate::network::Api;
+use crate::sources::{
+    check_each_source, deserialize_sources, log_new_items, serialize_sources, validate_field,
+    validate_name, CheckForUpdates, CheckOptions, NamedSource, SourceCheck, SourceSettings,
+    SourceUpdate,
+};
+use crate::state::State;
+use crate::style::{Role, Stylize};
+use crate::util::{access_error, check_status, get, readline, require_interactive, ReadlineResult};
+use chrono::{DateTime, FixedOffset, Local, NaiveDate, Utc};
+use keyring::Keyring;
+use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+use reqwest::Url;
+use schemars::JsonSchema;
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::{Map, Value};
+use std::borrow::Cow;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
 
 /// The wrapper type for YouTube channels and their last checked times
 /// to implement `CheckForUpdates` on.
-#[derive(Default, Debug, Serialize, Deserialize)]
+#[derive(Default, Debug, Serialize, Deserialize, JsonSchema)]
 pub struct YouTubeChannels {
-    pub api_key: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_api_key")]
+    pub api_key: Option<ApiKey>,
+    #[serde(
+        serialize_with = "serialize_sources",
+        deserialize_with = "deserialize_sources"
+    )]
+    #[schemars(with = "Vec<YouTubeChannel>")]
     pub channels: Vec<(YouTubeChannel, Option<DateTime<Local>>)>,
+    /// The most new videos to load for each channel per check, to bound
+    /// how much API quota a check uses. Defaults to `DEFAULT_MAX_VIDEOS`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_videos: Option<usize>,
+    /// The API quota units available per day, used to warn before they
+    /// run out. Defaults to `DEFAULT_DAILY_QUOTA`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub daily_quota: Option<u64>,
+    /// Any fields sitch doesn't know about, kept so they aren't lost.
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+    /// The API quota used today, which is kept in the state file.
+    #[serde(skip)]
+    pub quota: Mutex<QuotaUsage>,
+    /// Whether the API quota ran out during this run.
+    #[serde(skip)]
+    quota_exhausted: AtomicBool,
+}
+
+/// The most new videos loaded for each channel per check by default.
+const DEFAULT_MAX_VIDEOS: usize = 100;
+
+/// The API quota units a project gets per day by default.
+const DEFAULT_DAILY_QUOTA: u64 = 10_000;
+
+/// The API quota units that a `search.list` request costs.
+const SEARCH_COST: u64 = 100;
+
+/// The API quota units that a `playlistItems.list` or `channels.list`
+/// request costs.
+const LIST_COST: u64 = 1;
+
+/// The error given for channels that weren't checked because the
+/// API quota ran out, which are reported together instead.
+const QUOTA_EXHAUSTED: &str = "The YouTube API quota is used up for today";
+
+/// How many API quota units sitch has used in a day.
+///
+/// YouTube resets quotas at midnight Pacific time, so days are
+/// counted in Pacific Standard Time (ignoring daylight saving time).
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct QuotaUsage {
+    pub day: NaiveDate,
+    pub units: u64,
+}
+
+impl Default for QuotaUsage {
+    fn default() -> Self {
+        QuotaUsage {
+            day: Self::today(),
+            units: 0,
+        }
+    }
+}
+
+impl QuotaUsage {
+    /// The current day as YouTube counts quota usage.
+    fn today() -> NaiveDate {
+        Utc::now()
+            .with_timezone(&FixedOffset::west(8 * 3600))
+            .date()
+            .naive_local()
+    }
+
+    /// Records units used, starting over if the quota has reset since.
+    fn spend(&mut self, units: u64) {
+        let today = Self::today();
+        if self.day != today {
+            *self = QuotaUsage {
+                day: today,
+                units: 0,
+            };
+        }
+        self.units += units;
+    }
+}
+
+/// The service name that the API key is kept under in the OS keyring.
+const KEYRING_SERVICE: &str = "sitch";
+/// The user name that the API key is kept under in the OS keyring.
+const KEYRING_USER: &str = "youtube-api-key";
+
+/// Where the YouTube API key is kept.
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(untagged)]
+pub enum ApiKey {
+    /// The key itself, kept in plaintext in the config.
+    Plaintext(String),
+    /// A marker (`{"keyring": true}`) that the key is kept in the OS keyring.
+    Keyring { keyring: bool },
+}
+
+/// Deserializes the API key, rejecting a keyring marker of `false`, since
+/// the key would still be looked for in the keyring.
+fn deserialize_api_key<'de, D>(deserializer: D) -> Result<Option<ApiKey>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    match Option::<ApiKey>::deserialize(deserializer)? {
+        Some(ApiKey::Keyring { keyring: false }) => Err(D::Error::custom(
+            "the API key can't be {\"keyring\": false}, set it to the key itself or to \
+             {\"keyring\": true} to keep it in your system's keyring",
+        )),
+        api_key => Ok(api_key),
+    }
+}
+
+impl ApiKey {
+    /// Stores a key in the OS keyring, returning the marker to keep in the config.
+    pub fn store_in_keyring(key: &str) -> Result<Self, String> {
+        Keyring::new(KEYRING_SERVICE, KEYRING_USER)
+            .set_password(key)
+            .map_err(Self::keyring_error)?;
+
+        Ok(ApiKey::Keyring { keyring: true })
+    }
+
+    /// The actual key, loading it from the OS keyring if it is kept there.
+    pub fn resolve(&self) -> Result<String, String> {
+        match self {
+            ApiKey::Plaintext(key) => Ok(key.clone()),
+            ApiKey::Keyring { .. } => Keyring::new(KEYRING_SERVICE, KEYRING_USER)
+                .get_password()
+                .map_err(Self::keyring_error),
+        }
+    }
+
+    /// Deletes the key from the OS keyring if it is kept there.
+    pub fn clear(&self) -> Result<(), String> {
+        match self {
+            ApiKey::Plaintext(_key) => Ok(()),
+            ApiKey::Keyring { .. } => Keyring::new(KEYRING_SERVICE, KEYRING_USER)
+                .delete_password()
+                .map_err(Self::keyring_error),
+        }
+    }
+
+    /// A description of the key that doesn't give away the secret.
+    pub fn masked(&self) -> String {
+        match self {
+            ApiKey::Plaintext(key) => format!(
+                "{}{} (kept in your config)",
+                key.chars().take(4).collect::<String>(),
+                "*".repeat(key.chars().count().saturating_sub(4))
+            ),
+            ApiKey::Keyring { .. } => "(kept in your system's keyring)".to_owned(),
+        }
+    }
+
+    fn keyring_error<E: std::fmt::Display>(err: E) -> String {
+        format!(
+            "Couldn't access your system's keyring: {}. If your machine has no keyring, \
+             set the API key without --keyring to keep it in your config instead.",
+            err
+        )
+    }
 }
 
 /// A YouTube channel struct.
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, Default, JsonSchema)]
 pub struct YouTubeChannel {
     pub name: String,
     pub channel_id: String,
+    /// The id of the playlist of the channel's uploads, kept when it
+    /// can't be worked out from the channel id.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub uploads_playlist: Option<String>,
+    /// The settings that every source has.
+    #[serde(flatten)]
+    pub settings: SourceSettings,
+    /// Any fields sitch doesn't know about, kept so they aren't lost.
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 impl CheckForUpdates for YouTubeChannels {
-    fn check_for_all_updates(
-        &mut self,
-        sitch_last_checked: &Option<DateTime<Local>>,
-    ) -> Vec<(String, Result<Vec<SourceUpdate>, String>)> {
+    fn check_for_all_updates(&mut self, state: &State, options: &CheckOptions) -> Vec<SourceCheck> {
         // only check for updates if an API key is provided
+        let max_videos = self.max_videos.unwrap_or(DEFAULT_MAX_VIDEOS);
+        let api_key = match self.api_key.as_ref().map(ApiKey::resolve) {
+            Some(Ok(api_key)) => api_key,
+            Some(Err(err)) => return vec![SourceCheck::failed("API key".to_owned(), err)],
+            None => return Vec::new(),
+        };
+
+        let units_before = self.quota_used_today();
+        let quota = &self.quota;
+        let quota_exhausted = &self.quota_exhausted;
+        let mut results = check_each_source(
+            "youtube",
+            &mut self.channels,
+            state,
+            options,
+            |channel, last_checked| {
+                (
+                    channel.name.clone(),
+                    channel.check_for_updates(
+                        &api_key,
+                        max_videos,
+                        quota,
+                        quota_exhausted,
+                        last_checked,
+                    ),
+                )
+            },
+        );
+
+        // report the channels skipped for lack of quota together
+        let skipped = results
+            .iter()
+            .filter(|check| {
+                check.result.as_ref().err().map(String::as_str) == Some(QUOTA_EXHAUSTED)
+            })
+            .count();
+        if skipped > 0 {
+            results.retain(|check| {
+                check.result.as_ref().err().map(String::as_str) != Some(QUOTA_EXHAUSTED)
+            });
+            results.push(SourceCheck::failed(
+                "API quota".to_owned(),
+                format!(
+                    "The daily YouTube API quota ran out, so {} channel{} weren't checked. \
+                     It resets at midnight Pacific time.",
+                    skipped,
+                    if skipped == 1 { "" } else { "s" }
+                ),
+            ));
+        } else if !options.quiet && !options.notify && !options.porcelain {
+            self.warn_if_quota_low(self.quota_used_today().saturating_sub(units_before));
+        }
+
+        results
+    }
+
+    fn diagnose_all(&self) -> Vec<(String, Result<(), String>)> {
         if let Some(api_key) = &self.api_key {
+            let api_key = match api_key.resolve() {
+                Ok(api_key) => api_key,
+                Err(err) => return vec![("API key".to_owned(), Err(err))],
+            };
             self.channels
-                .par_iter_mut()
-                .map(|(channel, last_checked)| {
-                    // use the earliest `last_checked` time provided either by sitch generally
-                    // or by this source to handle whe the user overrides the `last_checked` time
-                    let true_last_checked =
-                        if sitch_last_checked.is_some() && last_checked.is_some() {
-                            Some(std::cmp::min(
-                                sitch_last_checked.unwrap(),
-                                last_checked.unwrap(),
-                            ))
-                        } else {
-                            last_checked.or(*sitch_last_checked)
-                        };
-                    let update = channel.check_for_updates(api_key, &true_last_checked);
-                    // update last_checked if an update occurred
-                    if update.as_ref().map(|updates| updates.len()).unwrap_or(0) > 0 {
-                        *last_checked = Some(Local::now());
-                    } else if last_checked.is_none() {
-                        // if this source hasn't been checked yet, but no update was
-                        // found, set it to the "global" `last_checked` time
-                        *last_checked = sitch_last_checked.clone();
-                    }
-                    (channel.name.clone(), update)
-                })
+                .par_iter()
+                .map(|(channel, _last_checked)| (channel.name.clone(), channel.diagnose(&api_key)))
                 .collect()
+        } else if self.channels.len() > 0 {
+            vec![(
+                "API key".to_owned(),
+                Err("No API key is set, so YouTube channels are ignored".to_owned()),
+            )]
         } else {
             Vec::new()
         }
@@ -66,105 +292,371 @@ impl CheckForUpdates for YouTubeChannels {
     }
 }
 
+impl NamedSource for YouTubeChannel {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn name_mut(&mut self) -> &mut String {
+        &mut self.name
+    }
+
+    fn location(&self) -> Cow<'_, str> {
+        Cow::Borrowed(&self.channel_id)
+    }
+
+    fn page(&mut self) -> Result<String, String> {
+        Ok(format!(
+            "https://www.youtube.com/channel/{}",
+            self.channel_id
+        ))
+    }
+
+    fn validate(&mut self) -> Result<(), String> {
+        validate_name(&mut self.name)?;
+        validate_field("channel id", &mut self.channel_id)
+    }
+
+    fn settings(&self) -> &SourceSettings {
+        &self.settings
+    }
+
+    fn settings_mut(&mut self) -> &mut SourceSettings {
+        &mut self.settings
+    }
+}
+
 impl YouTubeChannel {
+    /// Makes sure that the API key works and that the channel exists.
+    pub fn diagnose(&self, api_key: &str) -> Result<(), String> {
+        let query = Api::YouTube.url(&format!(
+            "channels?part=id&maxResults=1&id={}&key={}",
+            self.channel_id, api_key
+        ));
+        let data: Value = fetch_json(&query)?;
+
+        if let Some(message) = data
+            .pointer("/error/message")
+            .and_then(|message_obj| message_obj.as_str())
+        {
+            Err(format!("The YouTube API rejected the request: {}", message))
+        } else if data
+            .pointer("/items")
+            .and_then(|items_obj| items_obj.as_array())
+            .map(|items| items.len() > 0)
+            .unwrap_or(false)
+        {
+            Ok(())
+        } else {
+            Err(format!("No channel found with id {}", self.channel_id))
+        }
+    }
+
+    /// The id of the playlist holding the channel's uploads.
+    ///
+    /// Channel ids start with "UC" and their uploads playlists' ids are the
+    /// same but start with "UU". Channels with other ids have theirs looked
+    /// up once and then kept in the config.
+    fn uploads_playlist(
+        &mut self,
+        api_key: &str,
+        quota: &Mutex<QuotaUsage>,
+        quota_exhausted: &AtomicBool,
+    ) -> Result<String, String> {
+        if let Some(uploads_playlist) = &self.uploads_playlist {
+            return Ok(uploads_playlist.clone());
+        } else if self.channel_id.starts_with("UC") {
+            return Ok(format!("UU{}", &self.channel_id[2..]));
+        }
+
+        let query = Url::parse_with_params(
+            &Api::YouTube.url("channels"),
+            &[
+                ("part", "contentDetails"),
+                ("id", &self.channel_id),
+                ("key", api_key),
+            ],
+        )
+        .map_err(|err| format!("Couldn't build the YouTube API URL: {}", err))?;
+        let data = api_get(&query, LIST_COST, quota, quota_exhausted)?;
+        let uploads_playlist = data
+            .pointer("/items/0/contentDetails/relatedPlaylists/uploads")
+            .and_then(|playlist_obj| playlist_obj.as_str())
+            .ok_or(format!("No channel found with id {}", self.channel_id))?
+            .to_owned();
+        self.uploads_playlist = Some(uploads_playlist.clone());
+
+        Ok(uploads_playlist)
+    }
+
+    /// Check for videos published since `last_checked`, loading at most
+    /// `max_videos` of them.
+    ///
+    /// Videos are loaded from the channel's uploads playlist, which lists
+    /// them newest first a page at a time, so pages are followed until
+    /// they reach videos from before `last_checked`, run out, or reach
+    /// `max_videos`. Each request is counted against the `quota`, and once
+    /// the API says that the quota is used up, `quota_exhausted` is set so
+    /// that no more requests are made this run.
     pub fn check_for_updates(
-        &self,
+        &mut self,
         api_key: &str,
+        max_videos: usize,
+        quota: &Mutex<QuotaUsage>,
+        quota_exhausted: &AtomicBool,
         last_checked: &Option<DateTime<Local>>,
     ) -> Result<Vec<SourceUpdate>, String> {
-        // query YouTube's v3 API for videos from the given channel
-        let base_url = "https://www.googleapis.com/youtube/v3/search";
-        let published_after = last_checked
-            .map(|date| date.to_rfc3339())
-            .unwrap_or("1970-01-01T00:00:00Z".to_owned());
-        let params = vec![
-            ("part", "snippet"),
-            ("channelId", &self.channel_id),
-            ("maxResults", "25"),
-            ("order", "date"),
-            ("type", "video"),
-            ("key", api_key),
-            ("publishedAfter", &published_after),
-        ];
-        let query = format!(
-            "{}?{}",
-            base_url,
-            params
-                .into_iter()
-                .map(|(key, value)| format!("{}={}", key, value))
-                .collect::<Vec<_>>()
-                .join("&")
-        );
+        let playlist_id = self.uploads_playlist(api_key, quota, quota_exhausted)?;
+        let mut updates = Vec::new();
+        let mut loaded = 0;
+        let mut page_token: Option<String> = None;
 
-        // retrieve the API search data as JSON
-        let data: Value = reqwest::get(&query)
-            .map_err(|_err| format!("Couldn't access {}", query))?
-            .json()
-            .map_err(|_err| "Couldn't parse request data as JSON".to_owned())?;
+        loop {
+            // query YouTube's v3 API for the newest videos in the playlist
+            let mut params = vec![
+                ("part", "snippet,contentDetails"),
+                ("playlistId", &playlist_id),
+                ("maxResults", "50"),
+                ("key", api_key),
+            ];
+            if let Some(page_token) = &page_token {
+                params.push(("pageToken", page_token));
+            }
+            let query = Url::parse_with_params(&Api::YouTube.url("playlistItems"), &params)
+                .map_err(|err| format!("Couldn't build the YouTube API URL: {}", err))?;
+            let data = api_get(&query, LIST_COST, quota, quota_exhausted)?;
 
-        let items: &Vec<Value> = data
-            .pointer("/items")
-            .and_then(|obj| obj.as_array())
-            .ok_or("YouTube API JSON data wasn't an object")?;
-
-        Ok(items
-            .into_iter()
-            .filter_map(|item| {
-                // parse the published_date
-                let pub_date_str = item
-                    .pointer("/snippet/publishedAt")
-                    .and_then(|date_obj| date_obj.as_str())?;
-                let published_date = DateTime::<FixedOffset>::parse_from_rfc3339(pub_date_str)
-                    .map(|date| date.with_timezone(&Local))
-                    .ok()?;
-                // parse the title of the video
-                let title = item
-                    .pointer("/snippet/title")
-                    .and_then(|title_obj| title_obj.as_str())
-                    .map(|title| title)
-                    .unwrap_or("<unnamed>")
-                    .to_owned();
-                // parse the link to the video
-                let link = item
-                    .pointer("/id/videoId")
-                    .and_then(|id_obj| id_obj.as_str())
-                    .map(|id| format!("https://www.youtube.com/watch?v={}", id))
-                    .unwrap_or("<no link>".to_owned());
-
-                Some(SourceUpdate {
-                    title,
-                    link,
-                    published_date,
-                })
+            let items: &Vec<Value> = data
+                .pointer("/items")
+                .and_then(|obj| obj.as_array())
+                .ok_or("YouTube API JSON data wasn't an object")?;
+            loaded += items.len();
+            let mut reached_old_videos = false;
+            for update in items.iter().filter_map(Self::parse_video) {
+                if last_checked
+                    .map(|last_checked| last_checked < update.published_date)
+                    .unwrap_or(true)
+                {
+                    updates.push(update);
+                } else {
+                    reached_old_videos = true;
+                }
+            }
+
+            page_token = data
+                .pointer("/nextPageToken")
+                .and_then(|token_obj| token_obj.as_str())
+                .map(|token| token.to_owned());
+            if reached_old_videos
+                || page_token.is_none()
+                || items.len() == 0
+                || updates.len() >= max_videos
+            {
+                break;
+            }
+        }
+
+        // keep the newest videos, which are put in order by `check_each_source`
+        updates.sort_by(|a, b| b.published_date.cmp(&a.published_date));
+        updates.truncate(max_videos);
+        log_new_items(&self.name, loaded, updates.len());
+
+        Ok(updates)
+    }
+
+    /// Parses a video from an uploads playlist item, skipping it if it
+    /// has no published date, like private and deleted videos.
+    fn parse_video(item: &Value) -> Option<SourceUpdate> {
+        // parse the published_date
+        let pub_date_str = item
+            .pointer("/contentDetails/videoPublishedAt")
+            .and_then(|date_obj| date_obj.as_str())?;
+        let published_date = DateTime::<FixedOffset>::parse_from_rfc3339(pub_date_str)
+            .map(|date| date.with_timezone(&Local))
+            .ok()?;
+        // parse the title of the video
+        let title = item
+            .pointer("/snippet/title")
+            .and_then(|title_obj| title_obj.as_str())
+            .unwrap_or("<unnamed>")
+            .to_owned();
+        // parse the link to the video
+        let link = item
+            .pointer("/snippet/resourceId/videoId")
+            .and_then(|id_obj| id_obj.as_str())
+            .map(|id| format!("https://www.youtube.com/watch?v={}", id))
+            .unwrap_or("<no link>".to_owned());
+        // use the largest thumbnail that is still a reasonable size
+        let thumbnail = ["medium", "default", "high"]
+            .iter()
+            .filter_map(|size| {
+                item.pointer(&format!("/snippet/thumbnails/{}/url", size))
+                    .and_then(|url_obj| url_obj.as_str())
             })
-            .collect())
+            .next()
+            .map(|url| url.to_owned());
+        let description = item
+            .pointer("/snippet/description")
+            .and_then(|description_obj| description_obj.as_str())
+            .map(|description| description.to_owned());
+
+        Some(SourceUpdate {
+            title,
+            link,
+            published_date,
+            thumbnail,
+            description,
+            secondary_link: None,
+            also_in: Vec::new(),
+        })
+    }
+}
+
+/// Fetches JSON from the YouTube API. The API explains why it refused a
+/// request in the body, so those bodies are returned for the caller to
+/// report instead of just the response's status.
+fn fetch_json(query: &str) -> Result<Value, String> {
+    let mut response = get(query).map_err(|err| access_error(query, &err))?;
+    match response.json::<Value>() {
+        Ok(data) if data.pointer("/error").is_some() => Ok(data),
+        data => {
+            check_status(response)?;
+            data.map_err(|_err| "Couldn't parse request data as JSON".to_owned())
+        }
+    }
+}
+
+/// Sends a request to the YouTube API, counting its cost against the quota.
+///
+/// Fails without sending anything if the quota already ran out this run,
+/// and marks it as run out if the API says so.
+fn api_get(
+    query: &Url,
+    cost: u64,
+    quota: &Mutex<QuotaUsage>,
+    quota_exhausted: &AtomicBool,
+) -> Result<Value, String> {
+    if quota_exhausted.load(Ordering::SeqCst) {
+        return Err(QUOTA_EXHAUSTED.to_owned());
+    }
+
+    quota.lock().unwrap().spend(cost);
+    let data: Value = fetch_json(query.as_str())?;
+
+    if data
+        .pointer("/error/errors/0/reason")
+        .and_then(|reason| reason.as_str())
+        == Some("quotaExceeded")
+    {
+        quota_exhausted.store(true, Ordering::SeqCst);
+        Err(QUOTA_EXHAUSTED.to_owned())
+    } else if let Some(message) = data
+        .pointer("/error/message")
+        .and_then(|message_obj| message_obj.as_str())
+    {
+        Err(format!("The YouTube API rejected the request: {}", message))
+    } else {
+        Ok(data)
     }
 }
 
 impl YouTubeChannels {
+    /// The API quota units sitch has used today.
+    pub fn quota_used_today(&self) -> u64 {
+        let quota = self.quota.lock().unwrap();
+        if quota.day == QuotaUsage::today() {
+            quota.units
+        } else {
+            0
+        }
+    }
+
+    /// Warns when fewer channel checks are left in today's quota than
+    /// it would take to run a check like this one twice more.
+    fn warn_if_quota_low(&self, units_this_run: u64) {
+        let daily_quota = self.daily_quota.unwrap_or(DEFAULT_DAILY_QUOTA);
+        let remaining = daily_quota.saturating_sub(self.quota_used_today());
+        if units_this_run > 0 && remaining < units_this_run * 2 {
+            eprintln!(
+                "Warning: about {} of {} YouTube API quota units are left today \
+                 (~{} channel checks).",
+                remaining,
+                daily_quota,
+                remaining / LIST_COST
+            );
+        }
+    }
+
+    /// Looks up a channel by its id or the URL of its page, like
+    /// "https://www.youtube.com/channel/UC...", naming it after the
+    /// channel's title if no name is given.
+    pub fn resolve_channel(
+        &self,
+        id_or_url: &str,
+        name: Option<String>,
+    ) -> Result<YouTubeChannel, String> {
+        let api_key = self
+            .api_key
+            .as_ref()
+            .ok_or("Must have API key set to look up YouTube channels.".to_owned())?
+            .resolve()?;
+        let id_or_url = id_or_url.trim();
+        // channel pages' URLs have the id after "/channel/"
+        let channel_id = match id_or_url.find("/channel/") {
+            Some(index) => id_or_url[index + "/channel/".len()..]
+                .split(|c| c == '/' || c == '?')
+                .next()
+                .unwrap_or(""),
+            None => id_or_url,
+        };
+
+        let query = Url::parse_with_params(
+            &Api::YouTube.url("channels"),
+            &[("part", "snippet"), ("id", channel_id), ("key", &api_key)],
+        )
+        .map_err(|err| format!("Couldn't build the YouTube API URL: {}", err))?;
+        let data = api_get(&query, LIST_COST, &self.quota, &self.quota_exhausted)?;
+        let title = data
+            .pointer("/items/0/snippet/title")
+            .and_then(|title_obj| title_obj.as_str())
+            .ok_or(format!("No channel found with id {}", channel_id))?;
+
+        Ok(YouTubeChannel {
+            name: name.unwrap_or(title.to_owned()),
+            channel_id: channel_id.to_owned(),
+            ..Default::default()
+        })
+    }
+
     /// Search interactively for new YouTube channels to add to sitch.
     ///
     /// Reads from stdin to take input and asks the user before any
-    /// channels are added.
-    pub fn interactive_search(&self) -> Result<YouTubeChannel, String> {
+    /// channels are added. Returns `None` if the user declined the
+    /// result or quit.
+    pub fn interactive_search(&self) -> Result<Option<YouTubeChannel>, String> {
+        require_interactive("sitch youtube add --name <NAME> --id <CHANNEL ID>")?;
         // only run if an API key is provided
-        if self.api_key.is_none() {
-            return Err("Must have API key set to search for YouTube channels.".to_owned());
-        }
+        let api_key = self
+            .api_key
+            .as_ref()
+            .ok_or("Must have API key set to search for YouTube channels.".to_owned())?
+            .resolve()?;
 
         loop {
             // Take a query for input
-            let search_term = readline("Search for an channel by name: ", |search| {
+            let search_term = match readline("Search for an channel by name: ", |search| {
                 if search.len() > 3 {
                     Ok(search)
                 } else {
                     Err("Search term must be longer than 3 characters.".to_owned())
                 }
-            });
+            })? {
+                ReadlineResult::Value(search_term) => search_term,
+                ReadlineResult::Quit => return Ok(None),
+            };
 
             // query YouTube's v3 API for relevant channels
-            let api_key = self.api_key.clone().unwrap();
-            let base_url = "https://content.googleapis.com/youtube/v3/search";
+            let base_url = Api::YouTube.url("search");
             let params = vec![
                 ("part", "snippet"),
                 ("maxResults", "5"),
@@ -183,10 +675,8 @@ impl YouTubeChannels {
             );
 
             // parse the query's returned data as JSON
-            let data: Value = reqwest::get(&query)
-                .map_err(|_err| format!("Couldn't access {}", query))?
-                .json()
-                .map_err(|_err| "Couldn't parse request data as JSON".to_owned())?;
+            self.quota.lock().unwrap().spend(SEARCH_COST);
+            let data: Value = fetch_json(&query)?;
 
             // {
             //     ...
@@ -228,7 +718,6 @@ impl YouTubeChannels {
                 0 => println!("No results found, please try again."),
                 1 => {
                     // if only one was found, ask if they want to add it.
-                    // if they don't, exit from sitch.
                     let (channel_id, name) = search_results.into_iter().next().unwrap();
                     println!("Found 1 result: \"{}\" (id = {})", name, channel_id);
                     let should_add =
@@ -236,11 +725,28 @@ impl YouTubeChannels {
                             "" | "y" | "Y" | "yes" => Ok(true),
                             "n" | "N" | "no" => Ok(false),
                             _ => Err("Please respond with a yes or no.".to_owned()),
-                        });
-                    if should_add {
-                        return Ok(YouTubeChannel { name, channel_id });
-                    } else {
-                        std::process::exit(0);
+                        })?;
+                    match should_add {
+                        ReadlineResult::Value(true) => {
+                            return Ok(Some(YouTubeChannel {
+                                name,
+                                channel_id,
+                                ..Default::default()
+                            }))
+                        }
+                        // if they don't, let them search again or stop
+                        ReadlineResult::Value(false) => {
+                            let search_again =
+                                readline("Search again? [Y/n] ", |input| match input.as_str() {
+                                    "" | "y" | "Y" | "yes" => Ok(true),
+                                    "n" | "N" | "no" => Ok(false),
+                                    _ => Err("Please respond with a yes or no.".to_owned()),
+                                })?;
+                            if search_again != ReadlineResult::Value(true) {
+                                return Ok(None);
+                            }
+                        }
+                        ReadlineResult::Quit => return Ok(None),
                     }
                 }
                 num_results => {
@@ -250,12 +756,12 @@ impl YouTubeChannels {
                     for (index, (channel_id, name)) in search_results.iter().enumerate() {
                         println!(
                             "{}: \"{}\" (id = {})",
-                            (index + 1).to_string().yellow(),
-                            name.green(),
+                            (index + 1).style(Role::Index),
+                            name.style(Role::Name),
                             channel_id
                         );
                     }
-                    let index = readline(
+                    let index = match readline(
                         &format!("Pick a result to add [1 to {}]: ", num_results),
                         |picked| match picked.parse::<usize>() {
                             Ok(index) if (1 <= index && index <= num_results) => Ok(index - 1),
@@ -264,11 +770,197 @@ impl YouTubeChannels {
                             }
                             Err(_err) => Err("The value wasn't an integer.".to_owned()),
                         },
-                    );
+                    )? {
+                        ReadlineResult::Value(index) => index,
+                        ReadlineResult::Quit => return Ok(None),
+                    };
                     let (channel_id, name) = search_results.into_iter().nth(index).unwrap();
-                    return Ok(YouTubeChannel { name, channel_id });
+                    return Ok(Some(YouTubeChannel {
+                        name,
+                        channel_id,
+                        ..Default::default()
+                    }));
                 }
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::{ApiBases, NetworkConfig};
+    use crate::util::{lock_network, ok_response, serve_in_turn, serve_once};
+    use chrono::TimeZone;
+
+    /// Diagnoses a channel with the YouTube API answering with `response`.
+    fn diagnose_with(ip: &str, response: &str) -> Result<(), String> {
+        let (url, server) = serve_once(ip, response);
+        let network = NetworkConfig {
+            api_bases: Some(ApiBases {
+                youtube: Some(url),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        network.apply().unwrap();
+
+        let channel = YouTubeChannel {
+            channel_id: "UC123".to_owned(),
+            ..Default::default()
+        };
+        let result = channel.diagnose("key");
+        NetworkConfig::default().apply().unwrap();
+        server.join().unwrap();
+
+        result
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn api_errors_are_reported_from_the_body() {
+        let _network = lock_network();
+        let body = r#"{"error":{"message":"API key not valid."}}"#;
+        let response = format!(
+            "HTTP/1.1 400 Bad Request\r\nContent-Length: {}\r\n\
+             Connection: close\r\n\r\n{}",
+            body.len(),
+            body
+        );
+
+        assert_eq!(
+            diagnose_with("127.0.0.82", &response),
+            Err("The YouTube API rejected the request: API key not valid.".to_owned())
+        );
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn other_unsuccessful_responses_are_reported() {
+        let _network = lock_network();
+        let response = "HTTP/1.1 502 Bad Gateway\r\nContent-Length: 8\r\n\
+                        Connection: close\r\n\r\nupstream";
+
+        let err = diagnose_with("127.0.0.83", response).unwrap_err();
+        assert!(err.ends_with("responded with 502 Bad Gateway"), "{}", err);
+    }
+
+    fn video(id: &str, published: &str) -> Value {
+        serde_json::json!({
+            "snippet": {"title": id, "resourceId": {"videoId": id}},
+            "contentDetails": {"videoPublishedAt": published}
+        })
+    }
+
+    /// Checks a channel for videos with the YouTube API answering with
+    /// each of `pages`, returning the videos found and the requests sent.
+    fn check_with(
+        ip: &str,
+        pages: &[Value],
+        max_videos: usize,
+        last_checked: DateTime<Local>,
+    ) -> (Vec<String>, Vec<String>) {
+        let responses = pages
+            .iter()
+            .map(|page| ok_response(&page.to_string()))
+            .collect::<Vec<_>>();
+        let (url, server) = serve_in_turn(ip, &responses);
+        let network = NetworkConfig {
+            api_bases: Some(ApiBases {
+                youtube: Some(url),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        network.apply().unwrap();
+
+        let mut channel = YouTubeChannel {
+            channel_id: "UC123".to_owned(),
+            ..Default::default()
+        };
+        let updates = channel.check_for_updates(
+            "key",
+            max_videos,
+            &Mutex::new(QuotaUsage::default()),
+            &AtomicBool::new(false),
+            &Some(last_checked),
+        );
+        NetworkConfig::default().apply().unwrap();
+        let requests = server.join().unwrap();
+
+        let titles = updates.unwrap().into_iter().map(|update| update.title);
+        (titles.collect(), requests)
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn pages_are_followed_until_old_videos() {
+        let _network = lock_network();
+        let pages = [
+            serde_json::json!({
+                "items": [
+                    video("c", "2024-03-03T00:00:00Z"),
+                    video("b", "2024-03-02T12:00:00Z"),
+                ],
+                "nextPageToken": "second"
+            }),
+            serde_json::json!({
+                "items": [
+                    video("a", "2024-03-02T06:00:00Z"),
+                    video("old", "2024-02-28T00:00:00Z"),
+                ],
+                "nextPageToken": "third"
+            }),
+        ];
+        let last_checked = Local.ymd(2024, 3, 1).and_hms(0, 0, 0);
+
+        let (titles, requests) = check_with("127.0.0.85", &pages, 100, last_checked);
+
+        assert_eq!(titles, vec!["c", "b", "a"]);
+        assert!(requests[0].contains("playlistId=UU123"));
+        assert!(!requests[0].contains("pageToken"));
+        assert!(requests[1].contains("pageToken=second"));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn pages_stop_at_the_most_videos() {
+        let _network = lock_network();
+        let pages = [serde_json::json!({
+            "items": [
+                video("c", "2024-03-03T00:00:00Z"),
+                video("b", "2024-03-02T12:00:00Z"),
+                video("a", "2024-03-02T06:00:00Z"),
+            ],
+            "nextPageToken": "second"
+        })];
+        let last_checked = Local.ymd(2024, 3, 1).and_hms(0, 0, 0);
+
+        let (titles, requests) = check_with("127.0.0.86", &pages, 2, last_checked);
+
+        assert_eq!(titles, vec!["c", "b"]);
+        assert_eq!(requests.len(), 1);
+    }
+
+    #[test]
+    fn the_keyring_marker_has_to_be_true() {
+        let parse = |youtube| serde_json::from_value::<YouTubeChannels>(youtube);
+
+        let plaintext = parse(serde_json::json!({ "api_key": "abc", "channels": [] })).unwrap();
+        assert!(matches!(plaintext.api_key, Some(ApiKey::Plaintext(ref key)) if key == "abc"));
+        let keyring =
+            parse(serde_json::json!({ "api_key": { "keyring": true }, "channels": [] })).unwrap();
+        assert!(matches!(
+            keyring.api_key,
+            Some(ApiKey::Keyring { keyring: true })
+        ));
+        assert!(parse(serde_json::json!({ "channels": [] }))
+            .unwrap()
+            .api_key
+            .is_none());
+
+        let err = parse(serde_json::json!({ "api_key": { "keyring": false }, "channels": [] }))
+            .unwrap_err();
+        assert!(err.to_string().contains("{\"keyring\": false}"));
+    }
+}
@@ -0,0 +1,357 @@
+//! The volatile state that sitch keeps between runs.
+//!
+//! When sitch last checked for updates (both generally and for each
+//! source) changes on nearly every run, so it is kept in a state file
+//! separate from the config to keep the config stable for syncing.
+//! The updates that haven't been read yet are kept there too, as is
+//! a history of the updates found for each source. What the last check
+//! found is kept next to it, so that `--replay` can report it again.
+
+use crate::sources::youtube::QuotaUsage;
+use crate::sources::{NamedSource, SourceUpdate};
+use crate::util::{expand_tilde, write_whole_file};
+use chrono::{DateTime, Local};
+use dirs::{data_local_dir, home_dir};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::env;
+use std::fs::{create_dir_all, read_to_string};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// The most unread updates kept, after which the oldest are dropped.
+pub const MAX_UNREAD: usize = 1000;
+
+/// The most updates kept in each source's history, after which the
+/// oldest are dropped.
+pub const MAX_HISTORY: usize = 500;
+
+/// When sitch and each of its sources were last checked.
+#[derive(Serialize, Deserialize, Default, Debug)]
+pub struct State {
+    /// The last time sitch found an update for any source.
+    pub last_checked: Option<DateTime<Local>>,
+    /// The last checked time of each source, keyed by `State::key`.
+    #[serde(default)]
+    pub sources: BTreeMap<String, DateTime<Local>>,
+    /// When each source was last fetched, whether or not it had updates,
+    /// keyed by `State::key`, for skipping the sources checked more
+    /// recently than their `check_interval`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub fetched: BTreeMap<String, DateTime<Local>>,
+    /// How much of the YouTube API quota sitch has used today.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub youtube_quota: Option<QuotaUsage>,
+    /// The updates found that haven't been marked as read, oldest first.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub unread: Vec<UnreadUpdate>,
+    /// The updates found for each source, oldest first, keyed by
+    /// `State::key`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub history: BTreeMap<String, Vec<HistoryEntry>>,
+    /// What each source remembers about what it has reported, keyed by
+    /// `State::key`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub memory: BTreeMap<String, SourceMemory>,
+}
+
+/// What a source remembers between checks about the updates it has
+/// reported, so they aren't reported again.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct SourceMemory {
+    /// The source's future-dated updates that have been reported, which
+    /// are left out of later checks until their dates are older than the
+    /// source's last check.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub future_links: Vec<FutureLink>,
+    /// The GUIDs (or links, for items without one) of the items in an RSS
+    /// feed so far, oldest first, so that items whose dates move up when
+    /// they're edited aren't reported again.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub seen_guids: Vec<String>,
+    /// The hash of a watched page's text as of its last successful check.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub page_hash: Option<String>,
+    /// The ISBNs (or links, for books without one) of the books in an
+    /// author's feed last time, used to tell which undated books are new.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub seen_books: Vec<String>,
+}
+
+impl SourceMemory {
+    /// Whether there's nothing to remember, in which case the source is
+    /// left out of the state.
+    pub fn is_empty(&self) -> bool {
+        self.future_links.is_empty()
+            && self.seen_guids.is_empty()
+            && self.page_hash.is_none()
+            && self.seen_books.is_empty()
+    }
+}
+
+/// A future-dated update that has been reported.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct FutureLink {
+    /// The link to the update.
+    pub link: String,
+    /// When the update said it was published.
+    pub published_date: DateTime<Local>,
+}
+
+/// What a check changed about a source, kept apart from the source until
+/// the check is saved, so that a check that can't be saved changes
+/// nothing (see `Sources::save`).
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SourceChanges {
+    /// The source's new last checked time, if it has one.
+    pub last_checked: Option<DateTime<Local>>,
+    /// When the source was fetched.
+    pub fetched_at: Option<DateTime<Local>>,
+    /// What the source remembers after the check, if it was checked.
+    pub memory: Option<SourceMemory>,
+}
+
+/// What a check changed, to be applied to the state once it's saved.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CheckChanges {
+    /// The new general last checked time, if any update was found.
+    pub last_checked: Option<DateTime<Local>>,
+    /// What changed about each source that was checked, keyed by
+    /// `State::key`.
+    pub sources: BTreeMap<String, SourceChanges>,
+}
+
+/// An update that was found, as kept in its source's history.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct HistoryEntry {
+    /// The title of the update.
+    pub title: String,
+    /// A link to the update.
+    pub link: String,
+    /// When the update was published.
+    pub published_date: DateTime<Local>,
+    /// When sitch found the update.
+    pub discovered_at: DateTime<Local>,
+}
+
+/// An update that was reported but hasn't been marked as read yet.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct UnreadUpdate {
+    /// The name of the platform the update is from, like "RSS".
+    pub platform: String,
+    /// The name of the source the update is from.
+    pub source: String,
+    /// The title of the update.
+    pub title: String,
+    /// A link to the update.
+    pub link: String,
+    /// When the update was published.
+    pub published_date: DateTime<Local>,
+    /// Another link for the update, like a torrent of an anime episode.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub secondary_link: Option<String>,
+}
+
+/// What checking a source found: its platform, by the name it's reported
+/// with (like "RSS"), its name, its updates or why it couldn't be checked,
+/// and how long that took.
+pub type CheckResult<P> = (P, String, Result<Vec<SourceUpdate>, String>, Duration);
+
+/// What a check found, kept until the next one so that it can be reported
+/// again with `--replay`, like in another format, without checking
+/// anything.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct LastRun {
+    /// When the check was run.
+    pub checked_at: DateTime<Local>,
+    /// When the check looked for updates since, if ever.
+    pub since: Option<DateTime<Local>>,
+    /// What checking each source found.
+    pub results: Vec<CheckResult<String>>,
+}
+
+impl LastRun {
+    /// Where the last check is kept, next to the state at `state_path`.
+    pub fn path(state_path: &Path) -> PathBuf {
+        state_path.with_extension("last-run.json")
+    }
+
+    /// The results of the check for the platforms given, by the names
+    /// they're reported with. Results from platforms sitch no longer has
+    /// are left out.
+    pub fn results(&self, platforms: &[&'static str]) -> Vec<CheckResult<&'static str>> {
+        self.results
+            .iter()
+            .filter_map(|(platform, source, result, duration)| {
+                let platform = platforms.iter().find(|name| *name == platform)?;
+                Some((*platform, source.clone(), result.clone(), *duration))
+            })
+            .collect()
+    }
+
+    /// Loads the last check kept next to the state at `state_path`,
+    /// failing if there hasn't been one.
+    pub fn load(state_path: &Path) -> Result<Self, String> {
+        let path = Self::path(state_path);
+        let contents = read_to_string(&path).map_err(|_err| {
+            "There's no check to replay yet, run `sitch` to check for updates first.".to_owned()
+        })?;
+
+        serde_json::from_str(&contents).map_err(|err| {
+            format!(
+                "Couldn't parse the last check at {}: {}",
+                path.to_string_lossy(),
+                err
+            )
+        })
+    }
+
+    /// Keeps this check next to the state at `state_path`, in place of
+    /// the last one.
+    pub fn save(&self, state_path: &Path) -> Result<(), String> {
+        let path = Self::path(state_path);
+        let contents = serde_json::to_string(self).unwrap();
+        write_whole_file(&path, &format!("{}\n", contents))
+            .map_err(|err| format!("couldn't write to {}: {}", path.to_string_lossy(), err))
+    }
+}
+
+impl State {
+    /// The stable identifier for a source on a platform, made from the
+    /// platform's name and the source's URL or id.
+    pub fn key<T: NamedSource>(platform: &str, source: &T) -> String {
+        format!("{}/{}", platform, source.location())
+    }
+
+    /// Records the last checked and fetched times of a platform's sources.
+    pub fn store<T: NamedSource>(
+        &mut self,
+        platform: &str,
+        sources: &[(T, Option<DateTime<Local>>)],
+    ) {
+        for (source, last_checked) in sources {
+            if let Some(last_checked) = last_checked {
+                self.sources
+                    .insert(Self::key(platform, source), last_checked.clone());
+            }
+            if let Some(fetched_at) = source.settings().fetched_at {
+                self.fetched.insert(Self::key(platform, source), fetched_at);
+            }
+        }
+    }
+
+    /// Applies the changes a check made, as they'll be saved.
+    pub fn apply(&mut self, changes: &CheckChanges) {
+        if changes.last_checked.is_some() {
+            self.last_checked = changes.last_checked;
+        }
+        for (key, source) in &changes.sources {
+            if let Some(last_checked) = source.last_checked {
+                self.sources.insert(key.clone(), last_checked);
+            }
+            if let Some(fetched_at) = source.fetched_at {
+                self.fetched.insert(key.clone(), fetched_at);
+            }
+            match &source.memory {
+                Some(memory) if memory.is_empty() => {
+                    self.memory.remove(key);
+                }
+                Some(memory) => {
+                    self.memory.insert(key.clone(), memory.clone());
+                }
+                None => {}
+            }
+        }
+    }
+
+    /// Fills in the last checked and fetched times of a platform's sources
+    /// from the state, leaving sources the state doesn't know about as they
+    /// are.
+    pub fn restore<T: NamedSource>(
+        &self,
+        platform: &str,
+        sources: &mut [(T, Option<DateTime<Local>>)],
+    ) {
+        for (source, last_checked) in sources {
+            let key = Self::key(platform, source);
+            if let Some(stored) = self.sources.get(&key) {
+                *last_checked = Some(stored.clone());
+            }
+            if let Some(fetched_at) = self.fetched.get(&key) {
+                source.settings_mut().fetched_at = Some(*fetched_at);
+            }
+        }
+    }
+
+    /// Attempts to load the state file, if it exists.
+    pub fn load(path: &Path) -> Result<Option<Self>, String> {
+        match read_to_string(path) {
+            Ok(contents) => serde_json::from_str(&contents).map(Some).map_err(|err| {
+                format!(
+                    "Couldn't parse the state file at {}: {}",
+                    path.to_string_lossy(),
+                    err
+                )
+            }),
+            Err(_err) => Ok(None),
+        }
+    }
+
+    /// Saves the state as JSON to the state file.
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        if let Some(parent) = path.parent() {
+            create_dir_all(parent).ok();
+        }
+        let contents = serde_json::to_string_pretty(self).unwrap();
+        write_whole_file(path, &format!("{}\n", contents)).map_err(|err| {
+            format!(
+                "Could not write to the state file at {}: {}",
+                path.to_string_lossy(),
+                err
+            )
+        })
+    }
+
+    /// Determines the state path for sitch to use.
+    ///
+    /// If one is provided, that is used. If only a config path is chosen,
+    /// the state is kept next to the config (`config.json` gets a
+    /// `config.state.json`) so that separate configs don't share state.
+    /// Otherwise, `$XDG_STATE_HOME/sitch/state.json` is used, where the
+    /// state directory defaults to `~/.local/state`, or
+    /// `$XDG_STATE_HOME/sitch/<profile>.state.json` for a profile.
+    pub fn path(
+        state_path: Option<PathBuf>,
+        config_path: &Option<PathBuf>,
+        profile: &Option<String>,
+    ) -> Result<PathBuf, String> {
+        if let Some(state_path) = state_path {
+            return Ok(expand_tilde(state_path));
+        } else if let Some(config_path) = config_path {
+            let stem = config_path
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+                .unwrap_or("config".to_owned());
+            return Ok(config_path.with_file_name(format!("{}.state.json", stem)));
+        }
+
+        env::var_os("XDG_STATE_HOME")
+            .map(PathBuf::from)
+            .or_else(|| {
+                if cfg!(unix) {
+                    home_dir().map(|home| home.join(".local/state"))
+                } else {
+                    data_local_dir()
+                }
+            })
+            .map(|dir| match profile {
+                Some(profile) => dir.join("sitch").join(format!("{}.state.json", profile)),
+                None => dir.join("sitch/state.json"),
+            })
+            .ok_or(
+                "Could not find your system's state directory. \
+                 Please specify a location for your state file."
+                    .to_owned(),
+            )
+    }
+}
@@ -0,0 +1,341 @@
+//! Statistics over the history of updates sitch has found.
+//!
+//! Every update found is kept per source in the state file (see
+//! `State::history`), which is enough to tell how active each source is,
+//! and which ones have gone quiet.
+
+use crate::digest::Digest;
+use crate::sources::NamedSource;
+use crate::state::{HistoryEntry, State, MAX_HISTORY};
+use chrono::{DateTime, Duration, Local};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// How active a single source has been.
+#[derive(Serialize, Clone, Debug)]
+pub struct SourceStats {
+    /// The name of the platform the source is on, like "RSS".
+    pub platform: &'static str,
+    /// The user's name for the source.
+    pub name: String,
+    /// The source's key in the state file (see `State::key`).
+    #[serde(skip)]
+    pub key: String,
+    /// How many updates have been found in total.
+    pub total: usize,
+    /// How many updates were published in the last 7 days.
+    pub last_7_days: usize,
+    /// How many updates were published in the last 30 days.
+    pub last_30_days: usize,
+    /// When the most recent update was published, if there was one.
+    pub most_recent: Option<DateTime<Local>>,
+    /// The average number of days between updates, if there were at least two.
+    pub average_days_between: Option<f64>,
+}
+
+/// How active all of the sources on a platform have been.
+#[derive(Serialize, Clone, Debug)]
+pub struct PlatformStats {
+    /// The name of the platform, like "RSS".
+    pub platform: &'static str,
+    /// How many sources the platform has.
+    pub sources: usize,
+    /// How many updates have been found in total.
+    pub total: usize,
+    /// How many updates were published in the last 7 days.
+    pub last_7_days: usize,
+    /// How many updates were published in the last 30 days.
+    pub last_30_days: usize,
+    /// When the most recent update was published, if there was one.
+    pub most_recent: Option<DateTime<Local>>,
+}
+
+/// Adds a platform's updates in a digest to each of its sources'
+/// histories, keeping at most `MAX_HISTORY` of the newest.
+pub fn record_history<T: NamedSource>(
+    history: &mut BTreeMap<String, Vec<HistoryEntry>>,
+    platform: &str,
+    type_name: &str,
+    sources: &[(T, Option<DateTime<Local>>)],
+    digest: &Digest,
+) {
+    let platform_updates = match digest
+        .platforms
+        .iter()
+        .find(|(name, _sources)| *name == type_name)
+    {
+        Some((_name, platform_updates)) => platform_updates,
+        None => return,
+    };
+
+    for (source, _last_checked) in sources {
+        let updates = platform_updates
+            .iter()
+            .find(|(source_name, _updates)| source_name == source.name());
+        if let Some((_source_name, updates)) = updates {
+            let entries = history.entry(State::key(platform, source)).or_default();
+            for update in updates {
+                // the same update is found again when checking from an earlier time
+                if entries.iter().any(|entry| {
+                    entry.link == update.link && entry.published_date == update.published_date
+                }) {
+                    continue;
+                }
+                entries.push(HistoryEntry {
+                    title: update.title.clone(),
+                    link: update.link.clone(),
+                    published_date: update.published_date.clone(),
+                    discovered_at: digest.checked_at.clone(),
+                });
+            }
+            entries.sort_by_key(|entry| entry.published_date);
+            if entries.len() > MAX_HISTORY {
+                let excess = entries.len() - MAX_HISTORY;
+                entries.drain(..excess);
+            }
+        }
+    }
+}
+
+/// Adds the stats of each of a platform's sources to `stats`.
+pub fn collect_stats<T: NamedSource>(
+    history: &BTreeMap<String, Vec<HistoryEntry>>,
+    platform: &str,
+    type_name: &'static str,
+    sources: &[(T, Option<DateTime<Local>>)],
+    stats: &mut Vec<SourceStats>,
+) {
+    let now = Local::now();
+    for (source, _last_checked) in sources {
+        let key = State::key(platform, source);
+        let dates = history
+            .get(&key)
+            .map(|entries| {
+                entries
+                    .iter()
+                    .map(|entry| entry.published_date.clone())
+                    .collect()
+            })
+            .unwrap_or_else(Vec::new);
+        let since = |days| {
+            dates
+                .iter()
+                .filter(|date| **date >= now - Duration::days(days))
+                .count()
+        };
+        let average_days_between = match (dates.first(), dates.last()) {
+            (Some(first), Some(last)) if dates.len() > 1 => Some(
+                (*last - *first).num_seconds() as f64
+                    / (dates.len() - 1) as f64
+                    / Duration::days(1).num_seconds() as f64,
+            ),
+            _ => None,
+        };
+
+        stats.push(SourceStats {
+            platform: type_name,
+            name: source.name().to_owned(),
+            key,
+            total: dates.len(),
+            last_7_days: since(7),
+            last_30_days: since(30),
+            most_recent: dates.last().cloned(),
+            average_days_between,
+        });
+    }
+}
+
+/// Sorts sources with the most active first: by updates in the last 30
+/// days, then in total, then by how recently they last updated.
+pub fn sort_by_activity(stats: &mut [SourceStats]) {
+    stats.sort_by(|a, b| {
+        (b.last_30_days, b.total, b.most_recent).cmp(&(a.last_30_days, a.total, a.most_recent))
+    });
+}
+
+/// Totals the stats of each platform's sources, in the order the
+/// platforms first appear in.
+pub fn platform_totals(stats: &[SourceStats]) -> Vec<PlatformStats> {
+    let mut platforms: Vec<PlatformStats> = Vec::new();
+    for source in stats {
+        let index = match platforms
+            .iter()
+            .position(|platform| platform.platform == source.platform)
+        {
+            Some(index) => index,
+            None => {
+                platforms.push(PlatformStats {
+                    platform: source.platform,
+                    sources: 0,
+                    total: 0,
+                    last_7_days: 0,
+                    last_30_days: 0,
+                    most_recent: None,
+                });
+                platforms.len() - 1
+            }
+        };
+        let platform = &mut platforms[index];
+        platform.sources += 1;
+        platform.total += source.total;
+        platform.last_7_days += source.last_7_days;
+        platform.last_30_days += source.last_30_days;
+        platform.most_recent = platform.most_recent.max(source.most_recent);
+    }
+
+    platforms
+}
+
+/// Whether a source hasn't had an update in the given length of time.
+pub fn is_stale(stats: &SourceStats, window: Duration) -> bool {
+    stats
+        .most_recent
+        .map(|most_recent| most_recent < Local::now() - window)
+        .unwrap_or(true)
+}
+
+/// Renders a table of the platforms' and then the sources' stats, leaving
+/// out the platforms' table if there are none.
+pub fn render_table(platforms: &[PlatformStats], sources: &[SourceStats]) -> String {
+    let mut platform_rows = vec![row(&[
+        "Platform", "Sources", "Total", "7 days", "30 days", "Latest",
+    ])];
+    platform_rows.extend(platforms.iter().map(|platform| {
+        vec![
+            platform.platform.to_owned(),
+            platform.sources.to_string(),
+            platform.total.to_string(),
+            platform.last_7_days.to_string(),
+            platform.last_30_days.to_string(),
+            describe_most_recent(platform.most_recent),
+        ]
+    }));
+
+    let mut source_rows = vec![row(&[
+        "Platform", "Source", "Total", "7 days", "30 days", "Latest", "Every",
+    ])];
+    source_rows.extend(sources.iter().map(|source| {
+        vec![
+            source.platform.to_owned(),
+            source.name.clone(),
+            source.total.to_string(),
+            source.last_7_days.to_string(),
+            source.last_30_days.to_string(),
+            describe_most_recent(source.most_recent),
+            source
+                .average_days_between
+                .map(describe_days)
+                .unwrap_or("-".to_owned()),
+        ]
+    }));
+
+    if platforms.is_empty() {
+        align(&source_rows)
+    } else {
+        format!("{}\n{}", align(&platform_rows), align(&source_rows))
+    }
+}
+
+/// Makes a table row out of some text.
+fn row(cells: &[&str]) -> Vec<String> {
+    cells.iter().map(|cell| cell.to_string()).collect()
+}
+
+/// Lines up the cells of a table into columns, with the first row as
+/// the header.
+fn align(rows: &[Vec<String>]) -> String {
+    let columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+    let widths = (0..columns)
+        .map(|column| {
+            rows.iter()
+                .filter_map(|row| row.get(column))
+                .map(|cell| cell.chars().count())
+                .max()
+                .unwrap_or(0)
+        })
+        .collect::<Vec<usize>>();
+
+    rows.iter()
+        .map(|row| {
+            let line = row
+                .iter()
+                .zip(&widths)
+                .map(|(cell, width)| {
+                    format!("{}{}", cell, " ".repeat(width - cell.chars().count()))
+                })
+                .collect::<Vec<String>>()
+                .join("  ");
+            format!("{}\n", line.trim_end())
+        })
+        .collect()
+}
+
+/// Describes how long ago the most recent update was, like "3 days ago".
+pub fn describe_most_recent(most_recent: Option<DateTime<Local>>) -> String {
+    match most_recent {
+        Some(most_recent) => {
+            let days = (Local::now() - most_recent).num_seconds() as f64
+                / Duration::days(1).num_seconds() as f64;
+            format!("{} ago", describe_days(days.max(0.0)))
+        }
+        None => "never".to_owned(),
+    }
+}
+
+/// Describes a number of days in the largest unit that fits, like
+/// "5 hours", "3 days", or "14 months".
+fn describe_days(days: f64) -> String {
+    let (amount, unit) = if days < 1.0 {
+        ((days * 24.0).round(), "hour")
+    } else if days < 60.0 {
+        (days.round(), "day")
+    } else if days < 730.0 {
+        ((days / 30.44).round(), "month")
+    } else {
+        ((days / 365.25).round(), "year")
+    };
+
+    format!(
+        "{} {}{}",
+        amount,
+        unit,
+        if amount == 1.0 { "" } else { "s" }
+    )
+}
+
+/// What to do with a source that's gone quiet.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StaleAction {
+    /// Remove the source entirely.
+    Remove,
+    /// Snooze the source until a date (see `SourceSettings`).
+    Snooze(DateTime<Local>),
+}
+
+/// Acts on the source on a platform with the given key in the state file,
+/// if it's on that platform, returning whether it was.
+pub fn act_on_stale<T: NamedSource>(
+    platform: &str,
+    sources: &mut Vec<(T, Option<DateTime<Local>>)>,
+    key: &str,
+    action: StaleAction,
+) -> bool {
+    let index = match sources
+        .iter()
+        .position(|(source, _last_checked)| State::key(platform, source) == key)
+    {
+        Some(index) => index,
+        None => return false,
+    };
+
+    match action {
+        StaleAction::Remove => {
+            sources.remove(index);
+        }
+        StaleAction::Snooze(until) => {
+            sources[index].0.settings_mut().snoozed_until = Some(until);
+        }
+    }
+
+    true
+}
@@ -0,0 +1,309 @@
+//! Coloring sitch's output, with the colors for each part of it (like
+//! source names or links) picked by the `theme` block of the config.
+//!
+//! Colors are only shown when `colored` would show them, so `--color`,
+//! `NO_COLOR`, and piped output are respected the same as before.
+
+use colored::control::SHOULD_COLORIZE;
+use colored::Color;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::fmt;
+use std::sync::Mutex;
+
+/// The theme every part of the output is colored with, which is the dark
+/// preset until the config's is applied.
+static THEME: Mutex<Theme> = Mutex::new(DARK);
+
+/// The default colors, which are the ones sitch has always used, for
+/// terminals with a dark background.
+const DARK: Theme = Theme {
+    name: Style::color(Paint::Named(Color::Green)),
+    link: Style::color(Paint::Named(Color::BrightBlue)),
+    timing: Style::color(Paint::Named(Color::Magenta)),
+    error: Style::color(Paint::Named(Color::Red)),
+    header: Style::bold(None),
+    index: Style::color(Paint::Named(Color::Yellow)),
+    highlight: Style::color(Paint::Named(Color::Yellow)),
+    success: Style::color(Paint::Named(Color::Green)),
+};
+
+/// Darker colors for terminals with a light background, where bright and
+/// yellow text is hard to read.
+const LIGHT: Theme = Theme {
+    name: Style::color(Paint::Indexed(28)),
+    link: Style::color(Paint::Indexed(25)),
+    timing: Style::color(Paint::Indexed(90)),
+    error: Style::color(Paint::Indexed(124)),
+    header: Style::bold(None),
+    index: Style::color(Paint::Indexed(130)),
+    highlight: Style::color(Paint::Indexed(130)),
+    success: Style::color(Paint::Indexed(28)),
+};
+
+/// What a piece of the output is, which decides its color.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Role {
+    /// A source's name, or the platform it's on.
+    Name,
+    /// A link, or whatever else a source checks.
+    Link,
+    /// How long something took, or when it happened.
+    Timing,
+    /// An error, or what it came from.
+    Error,
+    /// The header above a group of sources or errors.
+    Header,
+    /// The number of an item in a list to pick from.
+    Index,
+    /// Something to notice, like a high priority source.
+    Highlight,
+    /// Something that worked, or was added.
+    Success,
+}
+
+/// A color in the config, which is either the name of one of the 16
+/// terminal colors, like "green" or "bright blue", or the index of one of
+/// the 256 extended ones.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, JsonSchema)]
+#[serde(untagged)]
+pub enum ThemeColor {
+    /// An extended color's index, from 0 to 255.
+    Index(u64),
+    /// A terminal color's name.
+    Name(String),
+}
+
+impl ThemeColor {
+    /// Finds the color this names, failing with the offending value if
+    /// there isn't one.
+    fn parse(&self) -> Result<Paint, String> {
+        match self {
+            ThemeColor::Index(index) if *index <= 255 => Ok(Paint::Indexed(*index as u8)),
+            ThemeColor::Index(index) => Err(format!("{} isn't a color index from 0 to 255", index)),
+            ThemeColor::Name(name) => name
+                .trim()
+                .replace(['_', '-'], " ")
+                .parse::<Color>()
+                .map(Paint::Named)
+                .map_err(|()| {
+                    format!(
+                        "\"{}\" isn't a color, like \"green\" or \"bright blue\"",
+                        name
+                    )
+                }),
+        }
+    }
+}
+
+/// The colors to use, from the `theme` block of the config.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, JsonSchema)]
+pub struct ThemeConfig {
+    /// The colors to start from, which are "dark" (the default) for
+    /// terminals with a dark background, or "light" for ones with a
+    /// light background. The colors below replace the preset's.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub preset: Option<String>,
+    /// The color of source and platform names.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<ThemeColor>,
+    /// The color of links.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub link: Option<ThemeColor>,
+    /// The color of how long checks took, and when updates came out.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timing: Option<ThemeColor>,
+    /// The color of errors.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<ThemeColor>,
+    /// The color of the headers above each platform's updates, which are
+    /// bold either way.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub header: Option<ThemeColor>,
+    /// The color of the numbers in lists to pick from.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub index: Option<ThemeColor>,
+    /// The color of high priority sources and changed lines.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub highlight: Option<ThemeColor>,
+    /// The color of sources that work, and added lines.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub success: Option<ThemeColor>,
+    /// Any fields sitch doesn't know about, kept so they aren't lost.
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl ThemeConfig {
+    /// Colors all output from now on with this theme, failing with the
+    /// offending value if a preset or color doesn't exist.
+    pub fn apply(&self) -> Result<(), String> {
+        let invalid = |field: &str, err: String| {
+            format!(
+                "The {} color in the theme block of your config is invalid: {}",
+                field, err
+            )
+        };
+
+        let mut theme = match self.preset.as_ref().map(|preset| preset.trim()) {
+            None | Some("dark") => DARK,
+            Some("light") => LIGHT,
+            Some(preset) => {
+                return Err(format!(
+                    "The theme preset \"{}\" in your config doesn't exist, it has to be \"dark\" or \"light\".",
+                    preset
+                ))
+            }
+        };
+        let overrides = [
+            ("name", &self.name, &mut theme.name),
+            ("link", &self.link, &mut theme.link),
+            ("timing", &self.timing, &mut theme.timing),
+            ("error", &self.error, &mut theme.error),
+            ("header", &self.header, &mut theme.header),
+            ("index", &self.index, &mut theme.index),
+            ("highlight", &self.highlight, &mut theme.highlight),
+            ("success", &self.success, &mut theme.success),
+        ];
+        for (field, color, style) in overrides {
+            if let Some(color) = color {
+                style.color = Some(color.parse().map_err(|err| invalid(field, err))?);
+            }
+        }
+        *THEME.lock().unwrap() = theme;
+
+        Ok(())
+    }
+}
+
+/// The style of each role.
+#[derive(Clone, Copy, Debug)]
+struct Theme {
+    name: Style,
+    link: Style,
+    timing: Style,
+    error: Style,
+    header: Style,
+    index: Style,
+    highlight: Style,
+    success: Style,
+}
+
+impl Theme {
+    fn style(&self, role: Role) -> Style {
+        match role {
+            Role::Name => self.name,
+            Role::Link => self.link,
+            Role::Timing => self.timing,
+            Role::Error => self.error,
+            Role::Header => self.header,
+            Role::Index => self.index,
+            Role::Highlight => self.highlight,
+            Role::Success => self.success,
+        }
+    }
+}
+
+/// A color that can be shown.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Paint {
+    Named(Color),
+    Indexed(u8),
+}
+
+/// How to show some text.
+#[derive(Clone, Copy, Debug)]
+struct Style {
+    color: Option<Paint>,
+    bold: bool,
+    dimmed: bool,
+}
+
+impl Style {
+    const fn color(color: Paint) -> Self {
+        Style {
+            color: Some(color),
+            bold: false,
+            dimmed: false,
+        }
+    }
+
+    const fn bold(color: Option<Paint>) -> Self {
+        Style {
+            color,
+            bold: true,
+            dimmed: false,
+        }
+    }
+
+    /// The escape codes that start this style, if it has any.
+    fn codes(&self) -> Option<String> {
+        let mut codes = Vec::new();
+        if self.bold {
+            codes.push("1".to_owned());
+        }
+        if self.dimmed {
+            codes.push("2".to_owned());
+        }
+        match self.color {
+            Some(Paint::Named(color)) => codes.push(color.to_fg_str().to_owned()),
+            Some(Paint::Indexed(index)) => codes.push(format!("38;5;{}", index)),
+            None => {}
+        }
+
+        if codes.is_empty() {
+            None
+        } else {
+            Some(codes.join(";"))
+        }
+    }
+}
+
+/// Text colored by the theme for its role, which is shown with the
+/// theme's colors when displayed.
+#[derive(Clone, Debug)]
+pub struct Styled {
+    text: String,
+    style: Style,
+}
+
+impl Styled {
+    /// Makes the text bold too.
+    pub fn bold(mut self) -> Self {
+        self.style.bold = true;
+        self
+    }
+
+    /// Makes the text dimmed too.
+    pub fn dimmed(mut self) -> Self {
+        self.style.dimmed = true;
+        self
+    }
+}
+
+impl fmt::Display for Styled {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.style.codes() {
+            Some(codes) if SHOULD_COLORIZE.should_colorize() => {
+                write!(f, "\x1b[{}m{}\x1b[0m", codes, self.text)
+            }
+            _ => write!(f, "{}", self.text),
+        }
+    }
+}
+
+/// Coloring text by what it is, which is done for anything displayable.
+pub trait Stylize {
+    /// Colors this with the theme's style for a role.
+    fn style(&self, role: Role) -> Styled;
+}
+
+impl<T: fmt::Display + ?Sized> Stylize for T {
+    fn style(&self, role: Role) -> Styled {
+        Styled {
+            text: self.to_string(),
+            style: THEME.lock().unwrap().style(role),
+        }
+    }
+}
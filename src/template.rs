@@ -0,0 +1,358 @@
+//! The template strings each printed update's line is made from, like
+//! `{platform}\t{source}\t{title}\t{link}` given with `--format`.
+//!
+//! Placeholders are names in braces, and dates can be given a chrono
+//! format after a colon, like `{date:%Y-%m-%d}`. Braces are written by
+//! doubling them, and `\t`, `\n`, and `\\` stand for a tab, a newline,
+//! and a backslash. sitch's own formats are templates too, so updates
+//! are always printed the same way.
+
+use crate::sources::{Priority, SourceUpdate};
+use crate::style::{Role, Stylize};
+use crate::util::shorten_title;
+use chrono::format::{Item, StrftimeItems};
+
+/// How dates are shown when a placeholder doesn't say.
+const DATE_FORMAT: &str = "%B %-e, %Y at %-l:%M %p";
+
+/// The format of `Template::normal`.
+const NORMAL: &str =
+    "{platform} - {source}: {summary} was \"{title}\" released on {date}, found here: {link}{also}{also_in} [{took}]";
+
+/// The format of `Template::grouped`, which leaves out the platform
+/// since its section already says it.
+const GROUPED: &str =
+    "{source}: {summary} was \"{title}\" released on {date}, found here: {link}{also}{also_in} [{took}]";
+
+/// The format of `Template::quiet`.
+const QUIET: &str = "{source}: \"{title}\" {link}";
+
+/// The placeholders templates can use, for error messages.
+const FIELD_NAMES: &str =
+    "platform, source, title, link, also, also_in, date, count, summary, and took";
+
+/// A parsed template.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Template {
+    parts: Vec<Part>,
+}
+
+/// A piece of a template.
+#[derive(Clone, Debug, PartialEq)]
+enum Part {
+    /// Text that's printed as is.
+    Text(String),
+    /// A placeholder that's filled in for each update.
+    Field(Field),
+}
+
+/// What a placeholder is filled in with.
+#[derive(Clone, Debug, PartialEq)]
+enum Field {
+    /// The platform, like "RSS".
+    Platform,
+    /// The source's name.
+    Source,
+    /// The update's title.
+    Title,
+    /// The update's link.
+    Link,
+    /// The update's second link, like " (also: <link>)", or nothing if it
+    /// has none.
+    Also,
+    /// The other sources that had the update, like " (also in <names>)",
+    /// or nothing if none did.
+    AlsoIn,
+    /// When the update was published, in a chrono format.
+    Date(String),
+    /// How many updates the source has.
+    Count,
+    /// How many updates the source has in a sentence, like "There have
+    /// been 3 updates, the earliest".
+    Summary,
+    /// How long checking the source took.
+    Took,
+}
+
+/// An update to fill a template in with, and what's known about it.
+pub struct Line<'a> {
+    /// The platform it's from, like "RSS".
+    pub platform: &'a str,
+    /// The name of its source.
+    pub source: &'a str,
+    /// Its source's priority, which colors its name.
+    pub priority: Priority,
+    /// The update itself.
+    pub update: &'a SourceUpdate,
+    /// How many updates its source has.
+    pub count: usize,
+    /// Whether the update is its source's newest instead of its oldest.
+    pub newest_first: bool,
+    /// How long checking its source took, if it's known.
+    pub took: &'a str,
+}
+
+impl Template {
+    /// The line printed for each source as its updates come in.
+    pub fn normal() -> Self {
+        Self::parse(NORMAL).expect("the normal template is valid")
+    }
+
+    /// The line printed for each source in a platform's section.
+    pub fn grouped() -> Self {
+        Self::parse(GROUPED).expect("the grouped template is valid")
+    }
+
+    /// The line printed for each source with `--quiet`.
+    pub fn quiet() -> Self {
+        Self::parse(QUIET).expect("the quiet template is valid")
+    }
+
+    /// Parses a template, failing if a placeholder doesn't exist, a date
+    /// format is invalid, or a brace isn't matched.
+    pub fn parse(template: &str) -> Result<Self, String> {
+        let mut parts = Vec::new();
+        let mut text = String::new();
+        let mut chars = template.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    text.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    text.push('}');
+                }
+                '}' => {
+                    return Err(
+                        "there's a \"}\" without a \"{\" before it, write \"}}\" for a brace"
+                            .to_owned(),
+                    )
+                }
+                '\\' => match chars.next() {
+                    Some('t') => text.push('\t'),
+                    Some('n') => text.push('\n'),
+                    Some('\\') => text.push('\\'),
+                    Some(other) => {
+                        text.push('\\');
+                        text.push(other);
+                    }
+                    None => text.push('\\'),
+                },
+                '{' => {
+                    let mut placeholder = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) => placeholder.push(c),
+                            None => {
+                                return Err(format!(
+                                    "the placeholder \"{{{}\" is never closed, write \"{{{{\" for a brace",
+                                    placeholder
+                                ))
+                            }
+                        }
+                    }
+                    if !text.is_empty() {
+                        parts.push(Part::Text(text.split_off(0)));
+                    }
+                    parts.push(Part::Field(Field::parse(&placeholder)?));
+                }
+                c => text.push(c),
+            }
+        }
+        if !text.is_empty() {
+            parts.push(Part::Text(text));
+        }
+
+        Ok(Template { parts })
+    }
+
+    /// Fills the template in for an update, coloring the placeholders if
+    /// `color` is set.
+    pub fn render(&self, line: &Line, color: bool) -> String {
+        self.parts
+            .iter()
+            .map(|part| match part {
+                Part::Text(text) => text.clone(),
+                Part::Field(field) => field.render(line, color),
+            })
+            .collect()
+    }
+}
+
+impl Field {
+    /// Parses what's between a placeholder's braces.
+    fn parse(placeholder: &str) -> Result<Self, String> {
+        let (name, spec) = match placeholder.find(':') {
+            Some(colon) => (&placeholder[..colon], Some(&placeholder[colon + 1..])),
+            None => (placeholder, None),
+        };
+        let field = match name.trim() {
+            "platform" => Field::Platform,
+            "source" => Field::Source,
+            "title" => Field::Title,
+            "link" => Field::Link,
+            "also" => Field::Also,
+            "also_in" => Field::AlsoIn,
+            "date" => {
+                let spec = spec.unwrap_or(DATE_FORMAT);
+                if StrftimeItems::new(spec).any(|item| item == Item::Error) {
+                    return Err(format!(
+                        "the date format \"{}\" in \"{{{}}}\" is invalid",
+                        spec, placeholder
+                    ));
+                }
+                return Ok(Field::Date(spec.to_owned()));
+            }
+            "count" => Field::Count,
+            "summary" => Field::Summary,
+            "took" => Field::Took,
+            _ => {
+                return Err(format!(
+                    "there's no placeholder \"{{{}}}\", the placeholders are {}",
+                    placeholder, FIELD_NAMES
+                ))
+            }
+        };
+
+        match spec {
+            Some(_spec) => Err(format!(
+                "only dates can be given a format, not \"{{{}}}\"",
+                placeholder
+            )),
+            None => Ok(field),
+        }
+    }
+
+    /// Fills the placeholder in for an update.
+    fn render(&self, line: &Line, color: bool) -> String {
+        let update = line.update;
+        match self {
+            Field::Platform if color => line.priority.highlight(line.platform).to_string(),
+            Field::Platform => line.platform.to_owned(),
+            Field::Source if color => line.priority.highlight(line.source).to_string(),
+            Field::Source => line.source.to_owned(),
+            Field::Title => shorten_title(&update.title),
+            Field::Link if color => update.link.style(Role::Link).to_string(),
+            Field::Link => update.link.clone(),
+            Field::Also => match &update.secondary_link {
+                Some(link) if color => format!(" (also: {})", link.style(Role::Link)),
+                Some(link) => format!(" (also: {})", link),
+                None => String::new(),
+            },
+            Field::AlsoIn if update.also_in.is_empty() => String::new(),
+            Field::AlsoIn => format!(" (also in {})", update.also_in.join(", ")),
+            Field::Date(format) => update.published_date.format(format).to_string(),
+            Field::Count => line.count.to_string(),
+            Field::Summary => {
+                if line.count == 1 {
+                    "There has been 1 update, it".to_owned()
+                } else {
+                    format!(
+                        "There have been {} updates, the {}",
+                        line.count,
+                        if line.newest_first {
+                            "latest"
+                        } else {
+                            "earliest"
+                        }
+                    )
+                }
+            }
+            Field::Took if color => line.took.style(Role::Timing).to_string(),
+            Field::Took => line.took.to_owned(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Local, TimeZone};
+
+    fn update() -> SourceUpdate {
+        SourceUpdate {
+            title: "New post".to_owned(),
+            link: "https://example.com/post".to_owned(),
+            published_date: Local.ymd(2024, 3, 1).and_hms(15, 4, 0),
+            thumbnail: None,
+            description: None,
+            secondary_link: None,
+            also_in: Vec::new(),
+        }
+    }
+
+    fn render(template: &str, update: &SourceUpdate, count: usize) -> String {
+        let line = Line {
+            platform: "RSS",
+            source: "Blog",
+            priority: Priority::Normal,
+            update,
+            count,
+            newest_first: false,
+            took: "1.2 seconds",
+        };
+        Template::parse(template).unwrap().render(&line, false)
+    }
+
+    #[test]
+    fn placeholders_are_filled_in() {
+        assert_eq!(
+            render(
+                "{platform}\\t{source}\\t{title}\\t{link}\\t{date:%Y-%m-%d %H:%M}\\n",
+                &update(),
+                1
+            ),
+            "RSS\tBlog\tNew post\thttps://example.com/post\t2024-03-01 15:04\n"
+        );
+        assert_eq!(render("{{{count}}} \\\\ \\x", &update(), 3), "{3} \\ \\x");
+    }
+
+    #[test]
+    fn optional_parts_are_left_out_when_missing() {
+        let mut also = update();
+        also.secondary_link = Some("https://example.com/torrent".to_owned());
+        also.also_in = vec!["Feed".to_owned(), "Other".to_owned()];
+
+        assert_eq!(render("{link}{also}{also_in}", &update(), 1), update().link);
+        assert_eq!(
+            render("{also}{also_in}", &also, 1),
+            " (also: https://example.com/torrent) (also in Feed, Other)"
+        );
+    }
+
+    #[test]
+    fn summaries_count_the_updates() {
+        assert_eq!(
+            render("{summary}", &update(), 1),
+            "There has been 1 update, it"
+        );
+        assert_eq!(
+            render("{summary}", &update(), 3),
+            "There have been 3 updates, the earliest"
+        );
+    }
+
+    #[test]
+    fn sitchs_own_templates_are_valid() {
+        let line = render(NORMAL, &update(), 1);
+
+        assert_eq!(
+            line,
+            "RSS - Blog: There has been 1 update, it was \"New post\" released on \
+             March 1, 2024 at 3:04 PM, found here: https://example.com/post [1.2 seconds]"
+        );
+        Template::grouped();
+        Template::quiet();
+    }
+
+    #[test]
+    fn broken_templates_are_refused() {
+        for template in &["{name}", "{title", "title}", "{date:%Q}", "{title:%Y}"] {
+            assert!(Template::parse(template).is_err(), "{}", template);
+        }
+    }
+}
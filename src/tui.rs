@@ -0,0 +1,338 @@
+//! Browsing the updates a check found in the terminal, with
+//! `--interactive`.
+//!
+//! Updates are listed under their sources, and can be opened in the
+//! browser, marked read, or have their source snoozed. Everything done
+//! here is saved along with the rest of the check once the list is quit.
+
+use crate::digest::Digest;
+use crate::sources::{SourceUpdate, Sources, SNOOZE_DAYS};
+use crate::style::{Role, Stylize};
+use atty::Stream;
+use chrono::{Duration, Local};
+use crossterm::cursor::{Hide, MoveTo, Show};
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::style::{Attribute, Print, SetAttribute};
+use crossterm::terminal::{
+    self, disable_raw_mode, enable_raw_mode, Clear, ClearType, EnterAlternateScreen,
+    LeaveAlternateScreen,
+};
+use crossterm::{execute, queue};
+use std::io::{self, Stdout, Write};
+
+/// The keys, shown at the top of the screen.
+const HELP: &str = "↑/↓ move  enter open  m mark read  p snooze source  q quit";
+
+/// A line in the list.
+enum Row<'a> {
+    /// A source with updates, on a platform named like "RSS".
+    Source {
+        platform: &'static str,
+        source: &'a str,
+    },
+    /// One of a source's updates, and whether it's been read.
+    Update {
+        platform: &'static str,
+        source: &'a str,
+        update: &'a SourceUpdate,
+        read: bool,
+    },
+}
+
+/// What's shown, and what's been done so far.
+struct Browser<'a> {
+    /// Every source and update, in the order they're listed.
+    rows: Vec<Row<'a>>,
+    /// The selected row, which is always an update.
+    selected: usize,
+    /// The first row on screen.
+    scroll: usize,
+    /// What the last key did, shown until the next one is pressed.
+    status: Option<String>,
+    /// The sources snoozed so far, by platform and name.
+    snoozed: Vec<(&'static str, &'a str)>,
+}
+
+/// Keeps the terminal in raw mode on its alternate screen, and puts it
+/// back the way it was when dropped, even if browsing fails.
+struct Screen(Stdout);
+
+impl Screen {
+    fn enter() -> Result<Self, String> {
+        enable_raw_mode().map_err(|err| format!("Couldn't set up the terminal: {}", err))?;
+        let mut stdout = io::stdout();
+        execute!(stdout, EnterAlternateScreen, Hide)
+            .map_err(|err| format!("Couldn't set up the terminal: {}", err))?;
+
+        Ok(Screen(stdout))
+    }
+}
+
+impl Drop for Screen {
+    fn drop(&mut self) {
+        execute!(self.0, Show, LeaveAlternateScreen).ok();
+        disable_raw_mode().ok();
+    }
+}
+
+/// Makes sure that sitch is running in a terminal before checking, since
+/// `--interactive` can't browse the updates otherwise.
+pub fn require_terminal() -> Result<(), String> {
+    if atty::is(Stream::Stdout) && atty::is(Stream::Stdin) {
+        Ok(())
+    } else {
+        Err(
+            "--interactive needs a terminal, but sitch's input or output isn't one. \
+             Use `sitch unread` to go through the updates instead."
+                .to_owned(),
+        )
+    }
+}
+
+/// Lists the updates in a digest until `q` is pressed, opening them,
+/// marking them read, and snoozing their sources along the way.
+pub fn browse(sources: &mut Sources, digest: &Digest) -> Result<(), String> {
+    let mut browser = Browser::new(digest);
+    if browser.rows.is_empty() {
+        return Ok(());
+    }
+
+    let mut screen = Screen::enter()?;
+    loop {
+        let (width, height) =
+            terminal::size().map_err(|err| format!("Couldn't get the terminal's size: {}", err))?;
+        browser
+            .draw(&mut screen.0, width as usize, height as usize)
+            .map_err(|err| format!("Couldn't draw the updates: {}", err))?;
+
+        // resizing just draws everything again
+        match event::read().map_err(|err| format!("Couldn't read a key: {}", err))? {
+            Event::Key(key)
+                if key.kind != KeyEventKind::Release
+                    && !browser.press(key, height as usize, sources) =>
+            {
+                break;
+            }
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
+
+impl<'a> Browser<'a> {
+    fn new(digest: &'a Digest) -> Self {
+        let mut rows = Vec::new();
+        for (platform, platform_sources) in &digest.platforms {
+            for (source, updates) in platform_sources {
+                rows.push(Row::Source { platform, source });
+                rows.extend(updates.iter().map(|update| Row::Update {
+                    platform,
+                    source,
+                    update,
+                    read: false,
+                }));
+            }
+        }
+
+        Browser {
+            rows,
+            // the first row is always a source, followed by its updates
+            selected: 1,
+            scroll: 0,
+            status: None,
+            snoozed: Vec::new(),
+        }
+    }
+
+    /// Does what a key says, returning whether to keep browsing.
+    fn press(&mut self, key: KeyEvent, height: usize, sources: &mut Sources) -> bool {
+        self.status = None;
+        let page = height.saturating_sub(3).max(1);
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return false,
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => return false,
+            KeyCode::Up | KeyCode::Char('k') => self.move_by(-1),
+            KeyCode::Down | KeyCode::Char('j') => self.move_by(1),
+            KeyCode::PageUp => self.move_by(-(page as isize)),
+            KeyCode::PageDown => self.move_by(page as isize),
+            KeyCode::Home | KeyCode::Char('g') => self.move_by(-(self.rows.len() as isize)),
+            KeyCode::End | KeyCode::Char('G') => self.move_by(self.rows.len() as isize),
+            KeyCode::Enter => self.open(sources),
+            KeyCode::Char('m') => self.toggle_read(sources),
+            KeyCode::Char('p') => self.toggle_snooze(sources),
+            _ => {}
+        }
+
+        true
+    }
+
+    /// Moves the selection by a number of updates, stopping at the first
+    /// and last ones.
+    fn move_by(&mut self, updates: isize) {
+        let step = updates.signum();
+        for _ in 0..updates.abs() {
+            let mut next = self.selected as isize + step;
+            while next >= 0
+                && (next as usize) < self.rows.len()
+                && matches!(self.rows[next as usize], Row::Source { .. })
+            {
+                next += step;
+            }
+            if next < 0 || next as usize >= self.rows.len() {
+                break;
+            }
+            self.selected = next as usize;
+        }
+    }
+
+    /// Opens the selected update's link in the browser, marking it read.
+    fn open(&mut self, sources: &mut Sources) {
+        if let Row::Update {
+            platform,
+            source,
+            update,
+            read,
+        } = &mut self.rows[self.selected]
+        {
+            match webbrowser::open(&update.link) {
+                Ok(_output) => {
+                    sources.mark_update_read(platform, source, update);
+                    *read = true;
+                    self.status = Some(format!("Opened {}", update.link));
+                }
+                Err(err) => self.status = Some(format!("Couldn't open {}: {}", update.link, err)),
+            }
+        }
+    }
+
+    /// Marks the selected update read, or unread again if it already is.
+    fn toggle_read(&mut self, sources: &mut Sources) {
+        if let Row::Update {
+            platform,
+            source,
+            update,
+            read,
+        } = &mut self.rows[self.selected]
+        {
+            if *read {
+                sources.mark_unread(platform, source, update);
+                self.status = Some("Marked the update unread.".to_owned());
+            } else {
+                sources.mark_update_read(platform, source, update);
+                self.status = Some("Marked the update read.".to_owned());
+            }
+            *read = !*read;
+        }
+    }
+
+    /// Snoozes the selected update's source for a week, or wakes it up
+    /// again if it was snoozed here.
+    fn toggle_snooze(&mut self, sources: &mut Sources) {
+        let (platform, source) = match self.rows[self.selected] {
+            Row::Update {
+                platform, source, ..
+            } => (platform, source),
+            Row::Source { .. } => return,
+        };
+
+        if let Some(index) = self
+            .snoozed
+            .iter()
+            .position(|snoozed| *snoozed == (platform, source))
+        {
+            sources.snooze(platform, source, None);
+            self.snoozed.remove(index);
+            self.status = Some(format!("Unsnoozed {}.", source));
+        } else {
+            let until = Local::now() + Duration::days(SNOOZE_DAYS);
+            sources.snooze(platform, source, Some(until));
+            self.snoozed.push((platform, source));
+            self.status = Some(format!(
+                "Snoozed {} until {}.",
+                source,
+                until.format("%T %D")
+            ));
+        }
+    }
+
+    /// Draws the help, the rows that fit on screen, and the status or the
+    /// selected update's link at the bottom.
+    fn draw(&mut self, out: &mut Stdout, width: usize, height: usize) -> io::Result<()> {
+        let list_height = height.saturating_sub(2).max(1);
+        // keep the selection on screen, along with its source if it's
+        // the source's first update
+        let top = match self.selected.checked_sub(1) {
+            Some(above) if matches!(self.rows[above], Row::Source { .. }) => above,
+            _ => self.selected,
+        };
+        if top < self.scroll {
+            self.scroll = top;
+        } else if self.selected >= self.scroll + list_height {
+            self.scroll = self.selected + 1 - list_height;
+        }
+
+        queue!(out, Clear(ClearType::All), MoveTo(0, 0))?;
+        queue!(out, Print(fit(HELP, width).style(Role::Header)))?;
+
+        let shown = self
+            .rows
+            .iter()
+            .enumerate()
+            .skip(self.scroll)
+            .take(list_height);
+        for (line, (index, row)) in shown.enumerate() {
+            queue!(out, MoveTo(0, line as u16 + 1))?;
+            match row {
+                Row::Source { platform, source } => {
+                    let snoozed = if self.snoozed.contains(&(*platform, *source)) {
+                        " (snoozed)"
+                    } else {
+                        ""
+                    };
+                    let text = format!("{} - {}{}", platform, source, snoozed);
+                    queue!(out, Print(fit(&text, width).style(Role::Name)))?;
+                }
+                Row::Update { update, read, .. } => {
+                    let date = update.published_date.format("%Y-%m-%d").to_string();
+                    let marker = if index == self.selected { ">" } else { " " };
+                    let title = fit(
+                        &update.title,
+                        width.saturating_sub(date.chars().count() + 4),
+                    );
+                    queue!(out, Print(format!("{} ", marker)))?;
+                    queue!(out, Print(date.style(Role::Timing)), Print(" "))?;
+                    if index == self.selected {
+                        queue!(out, SetAttribute(Attribute::Reverse))?;
+                    }
+                    if *read {
+                        queue!(out, SetAttribute(Attribute::Dim))?;
+                    }
+                    queue!(out, Print(title), SetAttribute(Attribute::Reset))?;
+                }
+            }
+        }
+
+        let bottom = match (&self.status, &self.rows[self.selected]) {
+            (Some(status), _) => status.clone(),
+            (None, Row::Update { update, .. }) => update.link.clone(),
+            (None, Row::Source { .. }) => String::new(),
+        };
+        queue!(
+            out,
+            MoveTo(0, height.saturating_sub(1) as u16),
+            Print(fit(&bottom, width).style(Role::Link))
+        )?;
+
+        out.flush()
+    }
+}
+
+/// Cuts text down to a width, putting spaces in place of tabs and
+/// newlines so each row stays on its line.
+fn fit(text: &str, width: usize) -> String {
+    text.chars()
+        .map(|c| if c.is_control() { ' ' } else { c })
+        .take(width)
+        .collect()
+}
@@ -1,103 +1,1413 @@
 //! Some miscellaneous utility functions used throughout sitch.
 
+use crate::config_format::ConfigFormat;
+use crate::network::{describe_proxy, proxy_for, Failure};
+use crate::style::{Role, Stylize};
+use atty::Stream;
+use chrono::{DateTime, Duration, Local, NaiveDate, NaiveDateTime, TimeZone};
+use dirs::home_dir;
+use log::debug;
+use reqwest::header::{HeaderMap, HeaderValue, LOCATION, USER_AGENT};
+use reqwest::redirect::Policy;
+use reqwest::{Client, ClientBuilder, Proxy, RequestBuilder, Response, StatusCode, Url};
+use serde::de::DeserializeOwned;
 use serde::Serialize;
-use serde_json::Value;
+use serde_json::{json, Value};
+use std::collections::hash_map::DefaultHasher;
 use std::env::temp_dir;
-use std::fs::{read_to_string, OpenOptions};
-use std::io::{BufRead, Write};
+use std::fs::{metadata, read_to_string, remove_file, rename, set_permissions, File, OpenOptions};
+use std::hash::{Hash, Hasher};
+use std::io::{self, BufRead, Read, Write};
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
 use std::process;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, OnceLock, PoisonError};
+use std::time::Duration as StdDuration;
+use tokio::runtime::{Builder, Runtime};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore, SemaphorePermit};
+use unicode_segmentation::UnicodeSegmentation;
 
-/// Opens a JSON temp file in the user's preferred editor and on save and
+/// The field holding what's being edited when it isn't a table, and so
+/// can't be a TOML document by itself.
+pub const TOML_ITEMS_KEY: &str = "items";
+
+/// Opens a temp file in the user's preferred editor and on save and
 /// close, runs a callback with the result.
 ///
 /// The EDITOR environment variable stores the executable of the user's
-/// preferred editor, which is called on a temp JSON file created in the
-/// user's system temporary directory. When the user saves and exits,
-/// if the file is still valid JSON, the callback `on_save` is called with
-/// the new JSON object, otherwise an error is returned.
-pub fn edit_as_json<T, F>(val: &T, mut on_save: F) -> Result<(), String>
+/// preferred editor, which is called on a temp file created in the
+/// user's system temporary directory, written in the given `format` (the
+/// config's, so editing feels like editing the config). When the user
+/// saves and exits, if the file can still be parsed, the callback `on_save`
+/// is called with the new JSON value. If either the parsing or `on_save`
+/// fails, the error is reported and the user is asked whether to edit
+/// again, which reopens the editor with their edits intact, or to give up,
+/// which returns an error without saving anything.
+///
+/// TOML documents have to be tables, so in TOML anything else, like a list
+/// of sources, is edited as the `TOML_ITEMS_KEY` field of one. Fields left
+/// empty are named in a comment at the top, since TOML can't show them.
+pub fn edit_as<T, F>(format: ConfigFormat, val: &T, mut on_save: F) -> Result<(), String>
 where
     T: Serialize + ?Sized,
     F: FnMut(Value) -> Result<(), String>,
 {
-    // Create a temp file called `sitch.json`
+    // Create a temp file called `sitch.json` or `sitch.toml`
     let mut temp_file_name = temp_dir();
-    temp_file_name.push("sitch.json");
-    let mut temp_file = OpenOptions::new()
-        .read(true)
-        .write(true)
-        .create(true)
-        .open(&temp_file_name)
-        .map_err(|_err| {
-            "Could not make a temporary file. Please make sure that the \
-             current user has edit access to the system's config directory."
+    temp_file_name.push(format!("sitch.{}", format.extension()));
+    let json = serde_json::to_value(val).unwrap();
+    let wrapped = format == ConfigFormat::Toml && !json.is_object();
+    let mut contents = match format {
+        ConfigFormat::Json => format.to_string(val)?,
+        ConfigFormat::Toml if wrapped => format.to_string(&json!({ TOML_ITEMS_KEY: json }))?,
+        ConfigFormat::Toml => {
+            let empty_fields = json
+                .as_object()
+                .into_iter()
+                .flatten()
+                .filter(|(_key, value)| value.is_null())
+                .map(|(key, _value)| key.as_str())
+                .collect::<Vec<&str>>();
+            let mut contents = format.to_string(&json)?;
+            if !empty_fields.is_empty() {
+                contents = format!("# to fill in: {}\n{}", empty_fields.join(", "), contents);
+            }
+            contents
+        }
+    };
+
+    loop {
+        let mut temp_file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .open(&temp_file_name)
+            .map_err(|_err| {
+                "Could not make a temporary file. Please make sure that the \
+                 current user has edit access to the system's config directory."
+                    .to_owned()
+            })?;
+
+        // Clear the file in case it already exists
+        temp_file
+            .set_len(0)
+            .map_err(|_err| "Could not empty the temp file.".to_owned())?;
+        // Save the object to the file
+        temp_file.write_all(contents.as_bytes()).unwrap();
+        // Edit the object in the user's preferred editor
+        let editor = std::env::var("EDITOR").map_err(|_err| {
+            "Could not find your preferred editor. Please set your \
+             EDITOR environment variable when editing text."
                 .to_owned()
         })?;
+        process::Command::new(editor)
+            .arg(&temp_file_name)
+            .output()
+            .map_err(|err| format!("An error occurred while editing the object: {}", err))?;
+
+        // if the edited object is still valid,
+        let edited = read_to_string(&temp_file_name).map_err(|_| {
+            "Could not read temp file after editing. Did it get deleted?".to_owned()
+        })?;
+        let parsed = format.parse(&edited).and_then(|parsed| {
+            if wrapped {
+                parsed
+                    .get(TOML_ITEMS_KEY)
+                    .cloned()
+                    .ok_or(format!("the \"{}\" field is missing", TOML_ITEMS_KEY))
+            } else {
+                Ok(parsed)
+            }
+        });
+        let saved = match parsed {
+            //  run `on_save` on it
+            Ok(json) => on_save(json),
+            Err(err) => Err(format!(
+                "The edited object could not be parsed as {}: {}.",
+                format.name(),
+                err
+            )),
+        };
+
+        match saved {
+            Ok(()) => return Ok(()),
+            Err(err) => {
+                // otherwise, let the user fix their edits or give up
+                eprintln!("{}", err);
+                let edit_again = readline("Edit it again? [Y/n] ", |input| match input.as_str() {
+                    "" | "y" | "Y" | "yes" => Ok(true),
+                    "n" | "N" | "no" => Ok(false),
+                    _ => Err("Please respond with a yes or no.".to_owned()),
+                })?;
+                if edit_again.value() != Some(true) {
+                    return Err("Stopped editing, nothing was changed.".to_owned());
+                }
+                contents = edited;
+            }
+        }
+    }
+}
+
+/// Expands a leading `~` in a path to the user's home directory.
+///
+/// Paths that don't start with `~`, or that start with `~user`,
+/// are left as they are, as are all paths if there is no home directory.
+pub fn expand_tilde(path: PathBuf) -> PathBuf {
+    match (path.strip_prefix("~"), home_dir()) {
+        (Ok(rest), Some(home)) => home.join(rest),
+        _ => path,
+    }
+}
+
+/// Replaces a file's contents all at once, by writing them next to it
+/// and then moving them over it, so that a full disk or a crash can't
+/// leave it half written. A symlinked file's target is the one replaced,
+/// and it keeps its permissions.
+///
+/// The contents are never readable by more users than the file's, since
+/// the file next to it is made with the file's permissions, or as private
+/// to its owner if there's no file yet.
+pub fn write_whole_file(path: &Path, contents: &str) -> io::Result<()> {
+    let path = path.canonicalize().unwrap_or_else(|_err| path.to_owned());
+    let mut temp_name = path.file_name().unwrap_or_default().to_owned();
+    temp_name.push(".tmp");
+    let temp_path = path.with_file_name(temp_name);
+    let existing = metadata(&path).ok();
+
+    // a file left over from a crash would keep its own permissions
+    remove_file(&temp_path).ok();
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+
+        options.mode(
+            existing
+                .as_ref()
+                .map(|existing| existing.permissions().mode() & 0o777)
+                .unwrap_or(0o600),
+        );
+    }
+    options
+        .open(&temp_path)
+        .and_then(|mut temp_file| temp_file.write_all(contents.as_bytes()))?;
+    if let Some(existing) = existing {
+        set_permissions(&temp_path, existing.permissions())?;
+    }
+    rename(&temp_path, &path).inspect_err(|_err| {
+        remove_file(&temp_path).ok();
+    })
+}
 
-    // Clear the file in case it already exists
-    temp_file
-        .set_len(0)
-        .map_err(|_err| "Could not empty the temp file.".to_owned())?;
-    let contents = format!("{}\n", serde_json::to_string_pretty(val).unwrap());
-    // Save the input JSON object to the file
-    temp_file.write_all(contents.as_bytes()).unwrap();
-    // Edit the object in the user's preferred editor
-    let editor = std::env::var("EDITOR").map_err(|_err| {
-        "Could not find your preferred editor. Please set your \
-         EDITOR environment variable when editing text."
-            .to_owned()
-    })?;
-    process::Command::new(editor)
-        .arg(&temp_file_name)
-        .output()
-        .map_err(|err| format!("An error occurred while editing the JSON object: {}", err))?;
-
-    // if the edited JSON is still valid,
-    let edited_json = read_to_string(&temp_file_name)
-        .map_err(|_| "Could not read temp file after editing. Did it get deleted?".to_owned())?;
-    let json = serde_json::from_str(&edited_json).map_err(|_| {
-        "The edited object could not be parsed as JSON. Please try again.".to_owned()
-    })?;
-
-    //  run `on_save` on it
-    on_save(json)
+/// The outcome of asking the user for input with `readline`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReadlineResult<T> {
+    /// The user entered a valid value.
+    Value(T),
+    /// The user asked to quit by entering "q" or "quit".
+    Quit,
+}
+
+impl<T> ReadlineResult<T> {
+    /// The entered value, or `None` if the user quit.
+    pub fn value(self) -> Option<T> {
+        match self {
+            ReadlineResult::Value(val) => Some(val),
+            ReadlineResult::Quit => None,
+        }
+    }
 }
 
 /// Reads input from stdin intelligently.
 ///
-/// This will send a prompt to stdout and then await
-/// some input. On input, if the provided value is either
-/// "q" or "quit", the program exits. Otherwise, the input
+/// This will send a prompt to stderr (so that stdout stays clean for
+/// piping) and then await some input. On input, if the provided value
+/// is either "q" or "quit", `ReadlineResult::Quit` is returned so the
+/// caller can stop what it was doing. Otherwise, the input
 /// (even an empty line) is passed to the `validate` callback
 /// which either returns the parsed value or an error, which
 /// is printed to stderr and then the prompt is asked again.
-pub fn readline<T, F>(prompt: &str, mut validate: F) -> T
+///
+/// If stdin is closed or can't be read, an error is returned instead.
+pub fn readline<T, F>(prompt: &str, mut validate: F) -> Result<ReadlineResult<T>, String>
 where
     F: FnMut(String) -> Result<T, String>,
 {
     let stdin = std::io::stdin();
     loop {
-        print!("{}", prompt);
-        std::io::stdout().flush().unwrap();
-        let input = stdin
-            .lock()
-            .lines()
-            .next()
-            .unwrap_or_else(|| {
-                // If an interrupt is sent, makes sure that an
-                // extra line is sent.
-                println!("");
-                std::process::exit(0);
-            })
-            .unwrap();
+        eprint!("{}", prompt);
+        std::io::stderr().flush().ok();
+        let input = match stdin.lock().lines().next() {
+            Some(Ok(input)) => input,
+            Some(Err(err)) => return Err(format!("Couldn't read from stdin: {}", err)),
+            None => {
+                // finish the prompt's line before reporting the error
+                eprintln!("");
+                return Err("stdin closed — aborting.".to_owned());
+            }
+        };
         // handle quitting
         if &input == "q" || &input == "quit" {
-            std::process::exit(0);
+            return Ok(ReadlineResult::Quit);
         } else {
             match validate(input) {
-                Ok(val) => return val,
+                Ok(val) => return Ok(ReadlineResult::Value(val)),
                 Err(err) => eprintln!("{}", err),
             }
         }
     }
 }
+
+/// Makes sure that stdin is a terminal before searching interactively,
+/// suggesting the non-interactive `alternative` otherwise.
+pub fn require_interactive(alternative: &str) -> Result<(), String> {
+    if atty::is(Stream::Stdin) {
+        Ok(())
+    } else {
+        Err(format!(
+            "Searching needs an interactive terminal, but stdin isn't one. \
+             Use `{}` instead.",
+            alternative
+        ))
+    }
+}
+
+/// Whether names only find sources with exactly that name, set by
+/// `--exact` (see `find_by_name`).
+static EXACT_NAMES: AtomicBool = AtomicBool::new(false);
+
+/// The most names listed to pick from when none match.
+const MAX_SUGGESTIONS: usize = 10;
+
+/// Sets whether names only find sources with exactly that name.
+pub fn set_exact_names(exact: bool) {
+    EXACT_NAMES.store(exact, Ordering::Relaxed);
+}
+
+/// Finds the source a name given on the command line means, among
+/// candidates given as where each is (like its platform or URL) and its
+/// name, returning its index.
+///
+/// A name matches exactly first, then ignoring case, then as the start
+/// of only one source's name, and then as part of only one. Otherwise,
+/// the user is asked to pick from the sources it could mean, closest
+/// first, which includes names that are a typo or two away. With
+/// `--exact`, only exact matches count.
+pub fn find_by_name(query: &str, candidates: &[(&str, &str)]) -> Result<usize, String> {
+    let exact = candidates
+        .iter()
+        .enumerate()
+        .filter(|(_index, (_location, name))| *name == query)
+        .map(|(index, _candidate)| index)
+        .collect::<Vec<usize>>();
+    if exact.len() == 1 {
+        return Ok(exact[0]);
+    } else if !exact.is_empty() {
+        let prompt = format!("Found {} sources named \"{}\":", exact.len(), query);
+        return pick_by_name(&prompt, &exact, candidates);
+    } else if EXACT_NAMES.load(Ordering::Relaxed) {
+        return Err(format!("No source is named exactly \"{}\".", query));
+    }
+
+    // every other candidate is ranked by how well it matches, best first
+    let lowercase = query.to_lowercase();
+    let mut ranked = candidates
+        .iter()
+        .enumerate()
+        .filter_map(|(index, (_location, name))| {
+            name_match(&lowercase, &name.to_lowercase()).map(|rank| (rank, index))
+        })
+        .collect::<Vec<((u8, usize), usize)>>();
+    ranked.sort();
+
+    let best = match ranked.first() {
+        Some(((tier, _distance), _index)) => *tier,
+        None => return Err(format!("No source is named \"{}\".", query)),
+    };
+    let best_matches = ranked
+        .iter()
+        .filter(|((tier, _distance), _index)| *tier == best)
+        .map(|(_rank, index)| *index)
+        .collect::<Vec<usize>>();
+    // a typo is never picked without asking
+    if best_matches.len() == 1 && best <= 2 {
+        return Ok(best_matches[0]);
+    }
+
+    let indices = if best == 0 {
+        best_matches
+    } else {
+        ranked
+            .iter()
+            .take(MAX_SUGGESTIONS)
+            .map(|(_rank, index)| *index)
+            .collect()
+    };
+    let prompt = if best == 0 {
+        format!("Found {} sources named \"{}\":", indices.len(), query)
+    } else {
+        format!(
+            "No source is named \"{}\", but {} could be what you meant:",
+            query,
+            if indices.len() == 1 { "this" } else { "these" }
+        )
+    };
+    pick_by_name(&prompt, &indices, candidates)
+}
+
+/// How well a name matches what was asked for, both lowercase, as a tier
+/// (the same name, the start of the name, part of the name, and a typo
+/// away from either the name or its start) and then the number of
+/// typos, or `None` if it doesn't match.
+fn name_match(query: &str, name: &str) -> Option<(u8, usize)> {
+    let extra = name.chars().count().saturating_sub(query.chars().count());
+    if name == query {
+        return Some((0, 0));
+    } else if name.starts_with(query) {
+        return Some((1, extra));
+    } else if name.contains(query) {
+        return Some((2, extra));
+    }
+
+    let start = name.chars().take(query.chars().count()).collect::<String>();
+    let typos = edit_distance(query, name).min(edit_distance(query, &start));
+    // about one typo is allowed for every four letters
+    if typos <= (query.chars().count() / 4).max(1) {
+        Some((3, typos))
+    } else {
+        None
+    }
+}
+
+/// The number of letters that have to be added, removed, or changed to
+/// turn one string into another (their Levenshtein distance).
+fn edit_distance(from: &str, to: &str) -> usize {
+    let to = to.chars().collect::<Vec<char>>();
+    let mut previous = (0..=to.len()).collect::<Vec<usize>>();
+    for (i, from_char) in from.chars().enumerate() {
+        let mut current = vec![i + 1; to.len() + 1];
+        for (j, to_char) in to.iter().enumerate() {
+            let changed = previous[j] + if from_char == *to_char { 0 } else { 1 };
+            current[j + 1] = changed.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+
+    previous[to.len()]
+}
+
+/// Lists some candidates (see `find_by_name`) and asks the user to pick
+/// one, returning its index among all of them. Without a terminal to ask
+/// in, this fails with the names instead.
+fn pick_by_name(
+    prompt: &str,
+    indices: &[usize],
+    candidates: &[(&str, &str)],
+) -> Result<usize, String> {
+    if !atty::is(Stream::Stdin) {
+        let names = indices
+            .iter()
+            .map(|index| format!("\"{}\" ({})", candidates[*index].1, candidates[*index].0))
+            .collect::<Vec<String>>();
+        return Err(format!("{} {}", prompt, names.join(", ")));
+    }
+
+    println!("{}", prompt);
+    for (number, index) in indices.iter().enumerate() {
+        let (location, name) = candidates[*index];
+        println!(
+            "{}: \"{}\" ({})",
+            (number + 1).style(Role::Index),
+            name.style(Role::Name),
+            location
+        );
+    }
+    let count = indices.len();
+    let picked = readline(
+        &format!("Pick a source [1 to {}]: ", count),
+        |picked| match picked.parse::<usize>() {
+            Ok(number) if (1 <= number && number <= count) => Ok(number - 1),
+            Ok(_bad_number) => Err("The specified index was out of bounds.".to_owned()),
+            Err(_err) => Err("The value wasn't an integer.".to_owned()),
+        },
+    )?
+    .value()
+    .ok_or("Stopped without picking a source.".to_owned())?;
+
+    Ok(indices[picked])
+}
+
+/// Downloads a file (like a notification thumbnail) to a temporary file,
+/// returning its path. The caller should remove the file when done with it.
+pub fn download_to_temp_file(url: &str) -> Result<PathBuf, String> {
+    let mut response = get(url)
+        .map_err(|err| access_error(url, &err))
+        .and_then(check_status)?;
+
+    // name the file after the URL so that concurrent downloads don't clash
+    let mut hasher = DefaultHasher::new();
+    url.hash(&mut hasher);
+    let path = temp_dir().join(format!("sitch-{}-{:x}", process::id(), hasher.finish()));
+    let mut file = File::create(&path)
+        .map_err(|err| format!("Couldn't create {}: {}", path.to_string_lossy(), err))?;
+    response
+        .copy_to(&mut file)
+        .map_err(|err| format!("Couldn't download {}: {}", redact_url(url), err))?;
+
+    Ok(path)
+}
+
+/// Query parameters whose values are secrets, like API keys.
+const SENSITIVE_PARAMS: &[&str] = &[
+    "key",
+    "api_key",
+    "apikey",
+    "token",
+    "access_token",
+    "client_secret",
+    "password",
+];
+
+/// Parses a length of time made of numbers followed by units, like "90d",
+/// "12h" or "1w 3d". The units are "m" (minutes), "h" (hours), "d" (days)
+/// and "w" (weeks), and a bare "0" is no time at all.
+pub fn parse_duration(text: &str) -> Result<Duration, String> {
+    let text = text.trim();
+    if text == "0" {
+        return Ok(Duration::zero());
+    }
+
+    let mut total = Duration::zero();
+    let mut number = String::new();
+    for c in text.chars().filter(|c| !c.is_whitespace()) {
+        if c.is_ascii_digit() {
+            number.push(c);
+            continue;
+        }
+
+        let amount = number
+            .parse::<u32>()
+            .map_err(|_err| format!("Expected a number before \"{}\"", c))?;
+        let amount = i64::from(amount);
+        number.clear();
+        let part = match c {
+            'm' => Duration::minutes(amount),
+            'h' => Duration::hours(amount),
+            'd' => Duration::days(amount),
+            'w' => Duration::weeks(amount),
+            _ => {
+                return Err(format!(
+                    "\"{}\" isn't a unit of time, expected m, h, d or w",
+                    c
+                ))
+            }
+        };
+        total = total
+            .checked_add(&part)
+            .ok_or(format!("\"{}\" is too long", text))?;
+    }
+    if text.is_empty() || !number.is_empty() {
+        return Err("Expected a number and unit of time, like \"90d\"".to_owned());
+    }
+
+    Ok(total)
+}
+
+/// The date format for dates given as seconds since the Unix epoch.
+pub const EPOCH_FORMAT: &str = "epoch";
+
+/// Parses a date with a chrono format string, or `EPOCH_FORMAT` for
+/// seconds since the Unix epoch. RFC 3339 and RFC 2822 dates are read
+/// if no format is given.
+///
+/// Dates without a time zone are taken to be in local time, and
+/// dates without a time to be at midnight.
+pub fn parse_date(text: &str, format: Option<&str>) -> Option<DateTime<Local>> {
+    let text = text.trim();
+    match format {
+        Some(EPOCH_FORMAT) => text
+            .parse::<i64>()
+            .ok()
+            .or_else(|| text.parse::<f64>().ok().map(|seconds| seconds as i64))
+            .and_then(|seconds| Local.timestamp_opt(seconds, 0).single()),
+        Some(format) => DateTime::parse_from_str(text, format)
+            .map(|date| date.with_timezone(&Local))
+            .ok()
+            .or_else(|| {
+                NaiveDateTime::parse_from_str(text, format)
+                    .ok()
+                    .and_then(|date| Local.from_local_datetime(&date).earliest())
+            })
+            .or_else(|| {
+                NaiveDate::parse_from_str(text, format)
+                    .ok()
+                    .and_then(|date| Local.from_local_datetime(&date.and_hms(0, 0, 0)).earliest())
+            }),
+        None => DateTime::parse_from_rfc3339(text)
+            .or_else(|_err| DateTime::parse_from_rfc2822(text))
+            .map(|date| date.with_timezone(&Local))
+            .ok(),
+    }
+}
+
+/// How many characters titles are cut down to when shown by default.
+pub const DEFAULT_TITLE_WIDTH: usize = 120;
+
+/// How many characters titles are cut down to when shown, set by the
+/// config's `title_width`, where 0 means they're never cut down.
+static TITLE_WIDTH: AtomicUsize = AtomicUsize::new(DEFAULT_TITLE_WIDTH);
+
+/// Sets how many characters titles are cut down to when shown.
+pub fn set_title_width(width: usize) {
+    TITLE_WIDTH.store(width, Ordering::Relaxed);
+}
+
+/// Makes a title safe to print on one line: terminal escape sequences
+/// and other control characters are taken out, and runs of whitespace,
+/// including newlines, become single spaces.
+pub fn sanitize_title(title: &str) -> String {
+    let mut kept = String::with_capacity(title.len());
+    let mut chars = title.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            // a control sequence, like a color, ends with a letter or
+            // another character from '@' to '~'
+            '\u{1b}' if chars.peek() == Some(&'[') => {
+                chars.next();
+                for c in chars.by_ref() {
+                    if ('@'..='~').contains(&c) {
+                        break;
+                    }
+                }
+            }
+            '\u{9b}' => {
+                for c in chars.by_ref() {
+                    if ('@'..='~').contains(&c) {
+                        break;
+                    }
+                }
+            }
+            // an operating system command, like setting the window's
+            // title, ends with a bell or an escape and a backslash
+            '\u{1b}' if chars.peek() == Some(&']') => {
+                while let Some(c) = chars.next() {
+                    if c == '\u{7}' {
+                        break;
+                    } else if c == '\u{1b}' && chars.peek() == Some(&'\\') {
+                        chars.next();
+                        break;
+                    }
+                }
+            }
+            // every other escape sequence is the escape and one character
+            '\u{1b}' => {
+                chars.next();
+            }
+            c if c.is_whitespace() => kept.push(' '),
+            c if c.is_control() => {}
+            c => kept.push(c),
+        }
+    }
+
+    kept.split_whitespace().collect::<Vec<&str>>().join(" ")
+}
+
+/// Cuts a title down to the configured width for showing it, with an
+/// ellipsis at the end if anything was cut. Characters that are shown
+/// as one, like an emoji and its skin tone, are never split up.
+pub fn shorten_title(title: &str) -> String {
+    shorten_title_to(title, TITLE_WIDTH.load(Ordering::Relaxed))
+}
+
+/// Cuts a title down to `width` characters like `shorten_title`, where a
+/// width of 0 leaves it whole.
+fn shorten_title_to(title: &str, width: usize) -> String {
+    let title = sanitize_title(title);
+    if width == 0 || title.graphemes(true).nth(width).is_none() {
+        return title;
+    }
+
+    let mut short = title
+        .graphemes(true)
+        .take(width.saturating_sub(1))
+        .collect::<String>()
+        .trim_end()
+        .to_owned();
+    short.push('…');
+
+    short
+}
+
+/// Resolves a link found on a page against the page's URL, like a
+/// browser would, so that relative and root-relative links both work.
+pub fn join_url(base_url: &str, link: &str) -> String {
+    Url::parse(base_url)
+        .and_then(|base| base.join(link))
+        .map(String::from)
+        .unwrap_or_else(|_err| format!("{}{}", base_url, link))
+}
+
+/// Hides the values of sensitive query parameters in a URL (e.g. `key=***`)
+/// so that it can be shown in errors and logs without giving away secrets.
+pub fn redact_url(url: &str) -> String {
+    let (rest, fragment) = match url.find('#') {
+        Some(index) => url.split_at(index),
+        None => (url, ""),
+    };
+    let (base, query) = match rest.find('?') {
+        Some(index) => rest.split_at(index + 1),
+        None => return url.to_owned(),
+    };
+    let query = query
+        .split('&')
+        .map(|param| {
+            let name = param.split('=').next().unwrap_or("");
+            if SENSITIVE_PARAMS.contains(&name.to_lowercase().as_str()) {
+                format!("{}=***", name)
+            } else {
+                param.to_owned()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("&");
+
+    format!("{}{}{}", base, query, fragment)
+}
+
+/// Describes a request that couldn't be sent and why (see `Failure`),
+/// along with the proxy it went through if there was one, since that's
+/// often why.
+pub fn access_error(url: &str, err: &reqwest::Error) -> String {
+    let failure = Failure::classify(err);
+    match Url::parse(url).ok().as_ref().and_then(proxy_for) {
+        Some(proxy) => format!(
+            "Couldn't access {} through the proxy {}: {}",
+            redact_url(url),
+            describe_proxy(&proxy),
+            failure
+        ),
+        None => format!("Couldn't access {}: {}", redact_url(url), failure),
+    }
+}
+
+/// The most redirects sitch will follow for a single request.
+const MAX_REDIRECTS: usize = 10;
+
+/// The user agent sitch identifies itself with, unless a source overrides it.
+pub const SITCH_USER_AGENT: &str = concat!(
+    "sitch/",
+    env!("CARGO_PKG_VERSION"),
+    " (+https://github.com/smores56/sitch)"
+);
+
+/// How many seconds requests wait by default before giving up.
+pub const DEFAULT_TIMEOUT_SECS: u64 = 30;
+
+/// How many seconds requests wait before giving up, set by `--timeout`.
+static TIMEOUT_SECS: AtomicU64 = AtomicU64::new(DEFAULT_TIMEOUT_SECS);
+
+/// The most requests sitch sends at once, across every host, which is
+/// also how many sources are checked at once.
+pub const MAX_REQUESTS: usize = 8;
+
+/// The most requests sitch sends to one host at once, so that checking
+/// many sources on the same site doesn't overwhelm it.
+const MAX_REQUESTS_PER_HOST: usize = 4;
+
+/// How many threads the runtime that sends requests has. Requests spend
+/// nearly all their time waiting on the network, so a couple is plenty.
+const RUNTIME_THREADS: usize = 2;
+
+/// The HTTP clients made so far, by their user agent and whether they
+/// follow redirects. Clients keep their connections open for the next
+/// request, so they're shared instead of being made for each request.
+static CLIENTS: Mutex<Vec<(Option<String>, bool, Client)>> = Mutex::new(Vec::new());
+
+/// The runtime every request is sent on, which is started the first time
+/// one's sent.
+static RUNTIME: OnceLock<Runtime> = OnceLock::new();
+
+/// Room for the `MAX_REQUESTS` requests that can be sent at once.
+static REQUESTS: Semaphore = Semaphore::const_new(MAX_REQUESTS);
+
+/// Room for the `MAX_REQUESTS_PER_HOST` requests that can be sent to each
+/// host at once, by the hosts requests have been sent to so far.
+static HOST_REQUESTS: Mutex<Vec<(String, Arc<Semaphore>)>> = Mutex::new(Vec::new());
+
+/// Sets how many seconds every request waits before giving up.
+pub fn set_timeout(seconds: u64) {
+    TIMEOUT_SECS.store(seconds, Ordering::Relaxed);
+    // the clients made so far still wait as long as before
+    CLIENTS
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clear();
+}
+
+/// Starts building an HTTP client that identifies itself as sitch,
+/// or with the given user agent if one is provided, gives up on
+/// requests after the timeout set by `set_timeout`, and sends them
+/// through a proxy if one is set up (see `proxy_for`).
+fn http_client_builder(user_agent: Option<&str>) -> ClientBuilder {
+    let mut headers = HeaderMap::new();
+    let user_agent = user_agent
+        .and_then(|agent| HeaderValue::from_str(agent).ok())
+        .unwrap_or(HeaderValue::from_static(SITCH_USER_AGENT));
+    headers.insert(USER_AGENT, user_agent);
+
+    Client::builder()
+        .default_headers(headers)
+        .timeout(StdDuration::from_secs(TIMEOUT_SECS.load(Ordering::Relaxed)))
+        .proxy(Proxy::custom(proxy_for))
+}
+
+/// The HTTP client for a user agent (see `http_client_builder`), which is
+/// made the first time it's needed and shared from then on. Clients that
+/// don't follow redirects are for following them by hand.
+pub fn http_client(user_agent: Option<&str>, follow_redirects: bool) -> reqwest::Result<Client> {
+    let mut clients = CLIENTS.lock().unwrap_or_else(PoisonError::into_inner);
+    let existing = clients.iter().find(|(agent, follows, _client)| {
+        agent.as_ref().map(String::as_str) == user_agent && *follows == follow_redirects
+    });
+    if let Some((_agent, _follows, client)) = existing {
+        return Ok(client.clone());
+    }
+
+    let mut builder = http_client_builder(user_agent);
+    if !follow_redirects {
+        builder = builder.redirect(Policy::none());
+    }
+    let client = builder.build()?;
+    clients.push((
+        user_agent.map(str::to_owned),
+        follow_redirects,
+        client.clone(),
+    ));
+
+    Ok(client)
+}
+
+/// The runtime requests are sent on (see `RUNTIME`). Each of the threads
+/// sources are checked on waits for its own requests on it, so that only
+/// the runtime's few threads are ever waiting on the network.
+fn runtime() -> &'static Runtime {
+    RUNTIME.get_or_init(|| {
+        Builder::new_multi_thread()
+            .worker_threads(RUNTIME_THREADS)
+            .thread_name("sitch-http")
+            .enable_all()
+            .build()
+            .expect("Couldn't start the runtime requests are sent on")
+    })
+}
+
+/// The room for requests to a host (see `HOST_REQUESTS`), which is made
+/// the first time a request is sent to it.
+fn host_requests(host: &str) -> Arc<Semaphore> {
+    let mut hosts = HOST_REQUESTS.lock().unwrap_or_else(PoisonError::into_inner);
+    if let Some((_host, requests)) = hosts.iter().find(|(other, _requests)| other == host) {
+        return Arc::clone(requests);
+    }
+
+    let requests = Arc::new(Semaphore::new(MAX_REQUESTS_PER_HOST));
+    hosts.push((host.to_owned(), Arc::clone(&requests)));
+    requests
+}
+
+/// A request's place among the ones being sent right now, which is kept
+/// until its response has been read or dropped.
+struct RequestSlot {
+    _request: SemaphorePermit<'static>,
+    _host_request: OwnedSemaphorePermit,
+}
+
+impl RequestSlot {
+    /// Waits until there's room for a request to the host, since only
+    /// `MAX_REQUESTS` are sent at once, and only `MAX_REQUESTS_PER_HOST`
+    /// to the same host, then takes it.
+    async fn take(host: &str) -> Self {
+        // wait on the host first, so that requests to a busy host don't
+        // hold up the ones to every other host
+        let host_request = host_requests(host)
+            .acquire_owned()
+            .await
+            .expect("the room for requests to a host is never closed");
+        let request = REQUESTS
+            .acquire()
+            .await
+            .expect("the room for requests is never closed");
+
+        RequestSlot {
+            _request: request,
+            _host_request: host_request,
+        }
+    }
+}
+
+/// A response from `send`, which counts as in flight until its body has
+/// been read or it's dropped, since the body is still being downloaded
+/// until then.
+pub struct SentResponse {
+    response: Response,
+    /// The body, once it's been downloaded, and how much of it has been
+    /// read so far.
+    body: Option<io::Cursor<Vec<u8>>>,
+    slot: Option<RequestSlot>,
+}
+
+impl SentResponse {
+    /// Downloads the whole body if that hasn't been done yet, then makes
+    /// room for the next request.
+    fn body(&mut self) -> Result<&mut io::Cursor<Vec<u8>>, String> {
+        if self.body.is_none() {
+            let response = &mut self.response;
+            let body = runtime().block_on(async {
+                let mut body = Vec::new();
+                while let Some(chunk) = response.chunk().await? {
+                    body.extend_from_slice(&chunk);
+                }
+                Ok::<_, reqwest::Error>(body)
+            });
+            self.slot = None;
+            self.body = Some(io::Cursor::new(body.map_err(|err| {
+                format!(
+                    "Couldn't read the response from {}: {}",
+                    redact_url(self.response.url().as_str()),
+                    Failure::classify(&err)
+                )
+            })?));
+        }
+
+        Ok(self.body.get_or_insert_with(Default::default))
+    }
+
+    /// Reads the body as text, replacing anything that isn't UTF-8.
+    pub fn text(&mut self) -> Result<String, String> {
+        let body = self.body()?;
+        Ok(String::from_utf8_lossy(body.get_ref()).into_owned())
+    }
+
+    /// Reads the body as JSON.
+    pub fn json<T: DeserializeOwned>(&mut self) -> Result<T, String> {
+        let body = self.body()?;
+        serde_json::from_slice(body.get_ref()).map_err(|err| err.to_string())
+    }
+
+    /// Copies the body to a writer.
+    pub fn copy_to<W: Write + ?Sized>(&mut self, writer: &mut W) -> Result<u64, String> {
+        let body = self.body()?;
+        io::copy(body, writer).map_err(|err| err.to_string())
+    }
+}
+
+impl Deref for SentResponse {
+    type Target = Response;
+
+    fn deref(&self) -> &Response {
+        &self.response
+    }
+}
+
+impl Read for SentResponse {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.body().map_err(io::Error::other)?.read(buf)
+    }
+}
+
+/// Sends a request with a client from `http_client` once there's room for
+/// it (see `RequestSlot`), waiting on the runtime requests are sent on.
+/// The request counts as in flight until its response's body has been read.
+pub fn send(client: &Client, request: RequestBuilder) -> reqwest::Result<SentResponse> {
+    let request = request.build()?;
+    let host = request.url().host_str().unwrap_or_default().to_owned();
+
+    runtime().block_on(async {
+        let slot = RequestSlot::take(&host).await;
+        let response = client.execute(request).await?;
+
+        Ok(SentResponse {
+            response,
+            body: None,
+            slot: Some(slot),
+        })
+    })
+}
+
+/// Sends a GET request with sitch's user agent, just like `reqwest::get`.
+pub fn get(url: &str) -> reqwest::Result<SentResponse> {
+    log_request("GET", url);
+    let client = http_client(None, true)?;
+    let response = send(&client, client.get(url));
+    log_response(url, &response);
+    response
+}
+
+/// Logs a request about to be sent, with its URL redacted.
+pub fn log_request(method: &str, url: &str) {
+    debug!("{} {}", method, redact_url(url));
+}
+
+/// Logs the status a request was responded with, or why it failed.
+pub fn log_response(url: &str, response: &reqwest::Result<SentResponse>) {
+    match response {
+        Ok(response) => debug!("{} responded with {}", redact_url(url), response.status()),
+        Err(err) => debug!("{}", access_error(url, err)),
+    }
+}
+
+/// Turns an unsuccessful response into a descriptive error.
+///
+/// Sites that block automated requests usually respond with a 403 or a
+/// 429, so those come with a hint that waiting might help.
+pub fn check_status(response: SentResponse) -> Result<SentResponse, String> {
+    let status = response.status();
+    if status == StatusCode::FORBIDDEN || status == StatusCode::TOO_MANY_REQUESTS {
+        Err(format!(
+            "{} responded with {}: the site may be blocking automated requests; \
+             try again later",
+            redact_url(response.url().as_str()),
+            status
+        ))
+    } else if !status.is_success() {
+        Err(format!(
+            "{} responded with {}",
+            redact_url(response.url().as_str()),
+            status
+        ))
+    } else {
+        Ok(response)
+    }
+}
+
+/// Fetches a URL while keeping track of permanent redirects.
+///
+/// Redirects are followed manually so that sitch can tell when a source
+/// has moved for good. Along with the final response, this returns the
+/// URL that the requested one has permanently moved to, which is only
+/// provided if every redirect along the way was permanent (301 or 308).
+/// Redirect loops and redirects from https to http are rejected with
+/// an error instead of being followed, as are unsuccessful responses.
+pub fn get_following_redirects(
+    url: &str,
+    user_agent: Option<&str>,
+) -> Result<(SentResponse, Option<String>), String> {
+    let client = http_client(user_agent, false)
+        .map_err(|err| format!("Couldn't build an HTTP client: {}", err))?;
+    let mut current =
+        Url::parse(url).map_err(|err| format!("Invalid URL {}: {}", redact_url(url), err))?;
+    let mut visited = vec![current.clone()];
+    let mut moved_to = None;
+    let mut all_permanent = true;
+
+    loop {
+        log_request("GET", current.as_str());
+        let response = send(&client, client.get(current.clone()));
+        log_response(current.as_str(), &response);
+        let response = response.map_err(|err| access_error(current.as_str(), &err))?;
+        let status = response.status();
+        // anything but a redirect is the final response
+        if !status.is_redirection() {
+            return check_status(response).map(|response| (response, moved_to));
+        }
+
+        // resolve the next location relative to the current one
+        let location = response
+            .headers()
+            .get(LOCATION)
+            .and_then(|location| location.to_str().ok())
+            .ok_or(format!(
+                "{} redirected without a location",
+                redact_url(current.as_str())
+            ))?;
+        let next = current.join(location).map_err(|err| {
+            format!(
+                "{} redirected to an invalid URL: {}",
+                redact_url(current.as_str()),
+                err
+            )
+        })?;
+
+        if current.scheme() == "https" && next.scheme() == "http" {
+            return Err(format!(
+                "Refusing to follow the redirect from {} to the insecure {}",
+                redact_url(current.as_str()),
+                redact_url(next.as_str())
+            ));
+        } else if visited.contains(&next) {
+            return Err(format!(
+                "Found a redirect loop at {}",
+                redact_url(next.as_str())
+            ));
+        } else if visited.len() > MAX_REDIRECTS {
+            return Err(format!(
+                "Too many redirects starting from {}",
+                redact_url(url)
+            ));
+        }
+
+        // only remember the new location if every redirect so far was permanent
+        all_permanent = all_permanent
+            && (status == StatusCode::MOVED_PERMANENTLY
+                || status == StatusCode::PERMANENT_REDIRECT);
+        if all_permanent {
+            moved_to = Some(next.to_string());
+        }
+
+        visited.push(next.clone());
+        current = next;
+    }
+}
+
+/// A fresh directory for a test to write files in, named after the test.
+#[cfg(test)]
+pub fn test_dir(name: &str) -> PathBuf {
+    let dir = temp_dir().join(format!("sitch-test-{}-{}", process::id(), name));
+    std::fs::remove_dir_all(&dir).ok();
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// Held by tests that send requests, since the proxy and timeout they're
+/// sent with are shared by every test.
+#[cfg(test)]
+static NETWORK_TEST: Mutex<()> = Mutex::new(());
+
+/// Keeps other tests from sending requests until it's dropped, for tests
+/// that send requests or change how they're sent.
+#[cfg(test)]
+pub fn lock_network() -> std::sync::MutexGuard<'static, ()> {
+    NETWORK_TEST.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Answers a single request at `ip` with `response`, returning the URL
+/// to send it to and a thread that ends with the request it got.
+#[cfg(test)]
+pub fn serve_once(ip: &str, response: &str) -> (String, std::thread::JoinHandle<String>) {
+    let (url, server) = serve_in_turn(ip, &[response.to_owned()]);
+    let server = std::thread::spawn(move || server.join().unwrap().remove(0));
+
+    (url, server)
+}
+
+/// Answers a request at `ip` with each of `responses` in turn, returning
+/// the URL to send them to and a thread that ends with the requests it got.
+#[cfg(test)]
+pub fn serve_in_turn(
+    ip: &str,
+    responses: &[String],
+) -> (String, std::thread::JoinHandle<Vec<String>>) {
+    use std::net::TcpListener;
+
+    let listener = TcpListener::bind((ip, 0)).unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let responses = responses.to_vec();
+    let server = std::thread::spawn(move || {
+        responses
+            .iter()
+            .map(|response| {
+                let (stream, _address) = listener.accept().unwrap();
+                let mut reader = io::BufReader::new(stream);
+                let mut request = String::new();
+                while !request.ends_with("\r\n\r\n") {
+                    if reader.read_line(&mut request).unwrap() == 0 {
+                        break;
+                    }
+                }
+                reader.into_inner().write_all(response.as_bytes()).unwrap();
+                request
+            })
+            .collect()
+    });
+
+    (url, server)
+}
+
+/// A successful response with `body`, for `serve_once` and `serve_in_turn`.
+#[cfg(test)]
+pub fn ok_response(body: &str) -> String {
+    format!(
+        "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        body.len(),
+        body
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    fn mode(path: &Path) -> u32 {
+        use std::os::unix::fs::PermissionsExt;
+
+        metadata(path).unwrap().permissions().mode() & 0o777
+    }
+
+    #[test]
+    fn write_whole_file_replaces_contents() {
+        let path = test_dir("replace").join("config.json");
+        write_whole_file(&path, "old").unwrap();
+        write_whole_file(&path, "new").unwrap();
+
+        assert_eq!(read_to_string(&path).unwrap(), "new");
+        assert!(!path.with_file_name("config.json.tmp").exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn write_whole_file_keeps_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let path = test_dir("keep-mode").join("config.json");
+        std::fs::write(&path, "old").unwrap();
+        set_permissions(&path, std::fs::Permissions::from_mode(0o640)).unwrap();
+        write_whole_file(&path, "new").unwrap();
+
+        assert_eq!(mode(&path), 0o640);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn write_whole_file_makes_new_files_private() {
+        let path = test_dir("new-mode").join("state.json");
+        write_whole_file(&path, "{}").unwrap();
+
+        assert_eq!(mode(&path), 0o600);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn write_whole_file_ignores_leftover_temp_file() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = test_dir("leftover");
+        let path = dir.join("config.json");
+        let temp_path = dir.join("config.json.tmp");
+        std::fs::write(&temp_path, "partial").unwrap();
+        set_permissions(&temp_path, std::fs::Permissions::from_mode(0o666)).unwrap();
+        write_whole_file(&path, "secret").unwrap();
+
+        assert_eq!(read_to_string(&path).unwrap(), "secret");
+        assert_eq!(mode(&path), 0o600);
+    }
+
+    fn in_flight_to(host: &str) -> usize {
+        MAX_REQUESTS_PER_HOST - host_requests(host).available_permits()
+    }
+
+    // every 127.x.x.x address is the loopback on Linux, so these tests
+    // have hosts to themselves
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn requests_are_in_flight_until_their_body_is_read() {
+        let _network = lock_network();
+        let (url, server) = serve_once(
+            "127.0.0.71",
+            "HTTP/1.1 200 OK\r\nContent-Length: 5\r\nConnection: close\r\n\r\nhello",
+        );
+
+        let mut response = get(&url).unwrap();
+        assert_eq!(in_flight_to("127.0.0.71"), 1);
+        assert_eq!(response.text().unwrap(), "hello");
+        assert_eq!(in_flight_to("127.0.0.71"), 0);
+        server.join().unwrap();
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn dropped_responses_are_done() {
+        let _network = lock_network();
+        let (url, server) = serve_once(
+            "127.0.0.72",
+            "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+        );
+
+        let response = get(&url).unwrap();
+        assert!(check_status(response).is_err());
+        assert_eq!(in_flight_to("127.0.0.72"), 0);
+        server.join().unwrap();
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn only_so_many_requests_are_sent_at_once() {
+        use std::net::TcpListener;
+        use std::thread;
+
+        let _network = lock_network();
+        let hosts = ["127.0.0.92", "127.0.0.93", "127.0.0.94"];
+        let per_host = 2 * MAX_REQUESTS_PER_HOST;
+        // how many requests are being answered right now and the most
+        // there ever were, in all and then for each host
+        let counts = Arc::new(Mutex::new(vec![(0, 0); hosts.len() + 1]));
+        let urls = hosts
+            .iter()
+            .enumerate()
+            .map(|(index, ip)| {
+                let listener = TcpListener::bind((*ip, 0)).unwrap();
+                let url = format!("http://{}", listener.local_addr().unwrap());
+                let counts = Arc::clone(&counts);
+                thread::spawn(move || {
+                    for stream in listener.incoming().take(per_host) {
+                        let mut stream = stream.unwrap();
+                        let counts = Arc::clone(&counts);
+                        thread::spawn(move || {
+                            let mut reader = io::BufReader::new(stream.try_clone().unwrap());
+                            let mut line = String::new();
+                            while reader.read_line(&mut line).unwrap() > 0 && line != "\r\n" {
+                                line.clear();
+                            }
+                            for &count in &[0, index + 1] {
+                                let mut counts = counts.lock().unwrap();
+                                counts[count].0 += 1;
+                                counts[count].1 = counts[count].1.max(counts[count].0);
+                            }
+                            thread::sleep(StdDuration::from_millis(200));
+                            // done before answering, so the next request
+                            // can't be counted before this one's let go
+                            for &count in &[0, index + 1] {
+                                counts.lock().unwrap()[count].0 -= 1;
+                            }
+                            stream.write_all(ok_response("").as_bytes()).unwrap();
+                        });
+                    }
+                });
+                url
+            })
+            .collect::<Vec<String>>();
+
+        let requests = urls
+            .iter()
+            .flat_map(|url| vec![url.clone(); per_host])
+            .map(|url| thread::spawn(move || get(&url).unwrap().text().unwrap()))
+            .collect::<Vec<_>>();
+        for request in requests {
+            request.join().unwrap();
+        }
+
+        let counts = counts.lock().unwrap();
+        assert_eq!(counts[0].1, MAX_REQUESTS);
+        for (_in_flight, most) in &counts[1..] {
+            assert!(*most <= MAX_REQUESTS_PER_HOST, "{:?}", counts);
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn requests_identify_as_sitch() {
+        let _network = lock_network();
+        let (url, server) = serve_once(
+            "127.0.0.73",
+            "HTTP/1.1 200 OK\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+        );
+
+        get(&url).unwrap();
+        let request = server.join().unwrap().to_lowercase();
+        let user_agent = format!("user-agent: {}\r\n", SITCH_USER_AGENT.to_lowercase());
+        assert!(request.contains(&user_agent), "{}", request);
+    }
+
+    #[test]
+    fn secrets_are_redacted_from_urls() {
+        assert_eq!(
+            redact_url("https://example.com/feed?Key=abc&page=2&token=def#top"),
+            "https://example.com/feed?Key=***&page=2&token=***#top"
+        );
+        assert_eq!(
+            redact_url("https://example.com/feed?keyword=abc"),
+            "https://example.com/feed?keyword=abc"
+        );
+        assert_eq!(
+            redact_url("https://example.com/#key=abc"),
+            "https://example.com/#key=abc"
+        );
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn status_errors_hide_secrets() {
+        let _network = lock_network();
+        let (url, server) = serve_once(
+            "127.0.0.74",
+            "HTTP/1.1 403 Forbidden\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+        );
+
+        let response = get(&format!("{}/videos?api_key=secret", url)).unwrap();
+        let err = check_status(response).err().unwrap();
+        server.join().unwrap();
+
+        assert!(err.contains("/videos?api_key=***"), "{}", err);
+        assert!(!err.contains("secret"), "{}", err);
+    }
+
+    const SOURCES: &[(&str, &str)] = &[
+        ("rss", "Rust Blog"),
+        ("rss", "This Week in Rust"),
+        ("youtube", "Rustacean Station"),
+        ("anime", "Frieren"),
+    ];
+
+    // names that could mean more than one source ask the user to pick, so
+    // these only cover names that find a single one
+    #[test]
+    fn names_are_found_from_closest_to_loosest() {
+        assert_eq!(find_by_name("Frieren", SOURCES), Ok(3));
+        assert_eq!(find_by_name("rust blog", SOURCES), Ok(0));
+        assert_eq!(find_by_name("rustacean", SOURCES), Ok(2));
+        assert_eq!(find_by_name("week", SOURCES), Ok(1));
+        assert_eq!(
+            find_by_name("Bandcamp", SOURCES),
+            Err("No source is named \"Bandcamp\".".to_owned())
+        );
+
+        set_exact_names(true);
+        let exactly = find_by_name("frieren", SOURCES);
+        set_exact_names(false);
+        assert_eq!(
+            exactly,
+            Err("No source is named exactly \"frieren\".".to_owned())
+        );
+    }
+
+    #[test]
+    fn names_are_ranked_by_how_they_match() {
+        assert_eq!(name_match("frieren", "frieren"), Some((0, 0)));
+        assert_eq!(name_match("rust", "rust blog"), Some((1, 5)));
+        assert_eq!(name_match("blog", "rust blog"), Some((2, 5)));
+        assert_eq!(name_match("frieen", "frieren"), Some((3, 1)));
+        assert_eq!(name_match("freiren", "frieren"), None);
+        assert_eq!(name_match("rusk", "rust blog"), Some((3, 1)));
+        assert_eq!(name_match("anime", "frieren"), None);
+    }
+
+    #[test]
+    fn edit_distance_counts_changed_letters() {
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(edit_distance("naïve", "naive"), 1);
+    }
+
+    #[test]
+    fn titles_are_made_safe_to_print() {
+        assert_eq!(
+            sanitize_title("\u{1b}[31mRed\u{1b}[0m\u{1b}]0;title\u{7} and\n\tmore\u{0}"),
+            "Red and more"
+        );
+    }
+
+    #[test]
+    fn titles_are_cut_between_graphemes() {
+        assert_eq!(shorten_title_to("Short title", 20), "Short title");
+        assert_eq!(shorten_title_to("A longer title", 10), "A longer…");
+        // the family is one grapheme made of several characters
+        let family = "\u{1f468}\u{200d}\u{1f469}\u{200d}\u{1f467}";
+        assert_eq!(
+            shorten_title_to(&family.repeat(4), 3),
+            format!("{}{}…", family, family)
+        );
+        assert_eq!(shorten_title_to("e\u{301}e\u{301}e\u{301}", 2), "e\u{301}…");
+        assert_eq!(shorten_title_to("A longer title", 0), "A longer title");
+    }
+}